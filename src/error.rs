@@ -0,0 +1,225 @@
+use std::{fmt, time::Duration};
+
+use hyper::StatusCode;
+use serde::Deserialize;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The request could not be sent or the response could not be read.
+    Transport(hyper::Error),
+    /// The request could not be built, e.g. because of an invalid url.
+    InvalidRequest(hyper::http::Error),
+    /// A non-success status whose body is not a Binance error payload.
+    Http { status: StatusCode, body: String },
+    /// Binance answered with `{"code": ..., "msg": ...}`.
+    Api(ApiError),
+    /// No response within the client's configured timeout.
+    Timeout,
+    /// Sending now would exceed a rate limit (or a 429/418 ban is active) for longer than the
+    /// client is configured to wait.
+    RateLimited { retry_after: Duration },
+    /// A signed endpoint was called on a client built without credentials.
+    MissingCredentials,
+    Signing(String),
+    InvalidParameter(String),
+    Deserialize(serde_json::Error),
+    /// A cassette could not be read or written, or has no response for a replayed request.
+    Cassette(String),
+    /// The websocket connection failed or was closed abnormally.
+    WebSocket(Box<tungstenite::Error>),
+    /// A request with side effects may or may not have taken effect, and checking did not tell.
+    OutcomeUnknown(String),
+}
+
+impl Error {
+    /// Binance error code, if the error came from an API error body.
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            Error::Api(err) => Some(err.code),
+            _ => None,
+        }
+    }
+
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Error::Http { status, .. } => Some(*status),
+            Error::Api(err) => Some(err.status),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Transport(err) => write!(f, "transport error: {}", err),
+            Error::InvalidRequest(err) => write!(f, "invalid request: {}", err),
+            Error::Http { status, body } => write!(f, "http error {}: {}", status, body),
+            Error::Api(err) => write!(f, "{}", err),
+            Error::Timeout => write!(f, "request timed out"),
+            Error::RateLimited { retry_after } => write!(f, "rate limited, retry after {:?}", retry_after),
+            Error::MissingCredentials => write!(f, "api credentials are not configured"),
+            Error::Signing(msg) => write!(f, "signing error: {}", msg),
+            Error::InvalidParameter(msg) => write!(f, "invalid parameter: {}", msg),
+            Error::Deserialize(err) => write!(f, "deserialize error: {}", err),
+            Error::Cassette(msg) => write!(f, "cassette error: {}", msg),
+            Error::WebSocket(err) => write!(f, "websocket error: {}", err),
+            Error::OutcomeUnknown(msg) => write!(f, "outcome unknown: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Transport(err) => Some(err),
+            Error::InvalidRequest(err) => Some(err),
+            Error::Deserialize(err) => Some(err),
+            Error::WebSocket(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<hyper::Error> for Error {
+    fn from(err: hyper::Error) -> Self {
+        Error::Transport(err)
+    }
+}
+
+impl From<hyper::http::Error> for Error {
+    fn from(err: hyper::http::Error) -> Self {
+        Error::InvalidRequest(err)
+    }
+}
+
+impl From<tungstenite::Error> for Error {
+    fn from(err: tungstenite::Error) -> Self {
+        Error::WebSocket(Box::new(err))
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Deserialize(err)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub code: ErrorCode,
+    pub msg: String,
+    /// The `data` some errors come with, e.g. the outcome of both halves of a failed cancel-replace.
+    pub data: Option<serde_json::Value>,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "api error {} ({:?}): {}", self.code.code(), self.code, self.msg)
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiErrorBody {
+    code: i32,
+    msg: String,
+    #[serde(default)]
+    data: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    /// Parses a Binance error payload, returning `None` if the body is something else.
+    pub(crate) fn from_body(status: StatusCode, body: &[u8]) -> Option<Self> {
+        let body: ApiErrorBody = serde_json::from_slice(body).ok()?;
+        Some(ApiError {
+            status,
+            code: ErrorCode::from(body.code),
+            msg: body.msg,
+            data: body.data,
+        })
+    }
+}
+
+macro_rules! error_codes {
+    ($($name:ident = $code:literal,)*) => {
+        /// Binance API error codes, see
+        /// <https://binance-docs.github.io/apidocs/spot/en/#error-codes>
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum ErrorCode {
+            $($name,)*
+            Other(i32),
+        }
+
+        impl ErrorCode {
+            pub fn code(&self) -> i32 {
+                match self {
+                    $(ErrorCode::$name => $code,)*
+                    ErrorCode::Other(code) => *code,
+                }
+            }
+        }
+
+        impl From<i32> for ErrorCode {
+            fn from(code: i32) -> Self {
+                match code {
+                    $($code => ErrorCode::$name,)*
+                    _ => ErrorCode::Other(code),
+                }
+            }
+        }
+    };
+}
+
+error_codes! {
+    Unknown = -1000,
+    Disconnected = -1001,
+    Unauthorized = -1002,
+    TooManyRequests = -1003,
+    UnexpectedResponse = -1006,
+    Timeout = -1007,
+    ServerBusy = -1008,
+    InvalidMessage = -1013,
+    UnknownOrderComposition = -1014,
+    TooManyOrders = -1015,
+    ServiceShuttingDown = -1016,
+    UnsupportedOperation = -1020,
+    InvalidTimestamp = -1021,
+    InvalidSignature = -1022,
+    IllegalChars = -1100,
+    TooManyParameters = -1101,
+    MandatoryParamEmptyOrMalformed = -1102,
+    UnknownParam = -1103,
+    UnreadParameters = -1104,
+    ParamEmpty = -1105,
+    ParamNotRequired = -1106,
+    ParamOverflow = -1108,
+    BadPrecision = -1111,
+    NoDepth = -1112,
+    TifNotRequired = -1114,
+    InvalidTif = -1115,
+    InvalidOrderType = -1116,
+    InvalidSide = -1117,
+    EmptyNewClOrdId = -1118,
+    EmptyOrgClOrdId = -1119,
+    BadInterval = -1120,
+    BadSymbol = -1121,
+    InvalidSymbolStatus = -1122,
+    InvalidListenKey = -1125,
+    MoreThanXxHours = -1127,
+    OptionalParamsBadCombo = -1128,
+    InvalidParameter = -1130,
+    BadRecvWindow = -1131,
+    NewOrderRejected = -2010,
+    CancelRejected = -2011,
+    NoSuchOrder = -2013,
+    BadApiKeyFormat = -2014,
+    RejectedMbxKey = -2015,
+    NoTradingWindow = -2016,
+    CancelReplacePartiallyFailed = -2021,
+    CancelReplaceFailed = -2022,
+    AmendQuantityIncrease = -2038,
+    OrderArchived = -2026,
+}
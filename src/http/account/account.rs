@@ -0,0 +1,257 @@
+use hyper::{body::Bytes, Method};
+
+use crate::error::{Error, Result};
+use crate::http::account::model::{AccountCommission, AccountInformation, AccountTrade, OrderRateLimit, PreventedMatch};
+use crate::http::client::{Client, RequestParam};
+
+const URL_ACCOUNT: &str = "/api/v3/account";
+const URL_MY_TRADES: &str = "/api/v3/myTrades";
+const URL_RATE_LIMIT_ORDER: &str = "/api/v3/rateLimit/order";
+const URL_MY_PREVENTED_MATCHES: &str = "/api/v3/myPreventedMatches";
+const URL_ACCOUNT_COMMISSION: &str = "/api/v3/account/commission";
+
+const MY_TRADES_MAX_LIMIT: u32 = 1000;
+// longest startTime..endTime span `myTrades` accepts
+const MY_TRADES_MAX_WINDOW_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Live balances, commission rates and permissions of the spot account.
+pub async fn account(client: &Client, omit_zero_balances: Option<bool>) -> Result<AccountInformation> {
+    let body = account_raw(client, omit_zero_balances).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn account_raw(client: &Client, omit_zero_balances: Option<bool>) -> Result<Bytes> {
+    let mut param = vec![];
+
+    if let Some(omit_zero_balances) = omit_zero_balances {
+        param.push(RequestParam{key: String::from("omitZeroBalances"), value: omit_zero_balances.to_string()});
+    }
+
+    client.send_signed(Method::GET, URL_ACCOUNT, &param).await
+}
+
+/// One page of the account's trades on `symbol`. With `from_id` or `start_time` the oldest
+/// matching trades are returned, otherwise the most recent ones; see `MyTradesPager` to get
+/// all of them.
+#[allow(clippy::too_many_arguments)]
+pub async fn my_trades(
+    client: &Client,
+    symbol: &str,
+    order_id: Option<u64>,
+    start_time: Option<u64>,
+    end_time: Option<u64>,
+    from_id: Option<u64>,
+    limit: Option<u32>
+) -> Result<Vec<AccountTrade>> {
+    let body = my_trades_raw(client, symbol, order_id, start_time, end_time, from_id, limit).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn my_trades_raw(
+    client: &Client,
+    symbol: &str,
+    order_id: Option<u64>,
+    start_time: Option<u64>,
+    end_time: Option<u64>,
+    from_id: Option<u64>,
+    limit: Option<u32>
+) -> Result<Bytes> {
+    let mut param = vec![
+        RequestParam{key: String::from("symbol"), value: String::from(symbol)},
+    ];
+
+    if let Some(order_id) = order_id {
+        param.push(RequestParam{key: String::from("orderId"), value: order_id.to_string()});
+    }
+    if let Some(start_time) = start_time {
+        param.push(RequestParam{key: String::from("startTime"), value: start_time.to_string()});
+    }
+    if let Some(end_time) = end_time {
+        param.push(RequestParam{key: String::from("endTime"), value: end_time.to_string()});
+    }
+    if let Some(from_id) = from_id {
+        param.push(RequestParam{key: String::from("fromId"), value: from_id.to_string()});
+    }
+    if let Some(limit) = limit {
+        param.push(RequestParam{key: String::from("limit"), value: limit.to_string()});
+    }
+
+    client.send_signed(Method::GET, URL_MY_TRADES, &param).await
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Cursor {
+    FromId(u64),
+    Window { start_time: u64 },
+}
+
+/// Pages through `myTrades`, oldest trades first.
+///
+/// `from_id` follows the trade ids. `between` queries the time range in windows of at most
+/// 24 hours, as Binance requires, and continues by id once a window has more than a page.
+#[derive(Debug)]
+pub struct MyTradesPager<'a> {
+    client: &'a Client,
+    symbol: String,
+    cursor: Option<Cursor>,
+    end_time: Option<u64>,
+    limit: u32,
+}
+
+impl<'a> MyTradesPager<'a> {
+    pub fn from_id(client: &'a Client, symbol: impl Into<String>, from_id: u64) -> Self {
+        MyTradesPager {
+            client,
+            symbol: symbol.into(),
+            cursor: Some(Cursor::FromId(from_id)),
+            end_time: None,
+            limit: MY_TRADES_MAX_LIMIT,
+        }
+    }
+
+    /// Trades from `start_time` to `end_time`, both inclusive.
+    pub fn between(client: &'a Client, symbol: impl Into<String>, start_time: u64, end_time: u64) -> Self {
+        MyTradesPager {
+            client,
+            symbol: symbol.into(),
+            cursor: if start_time <= end_time { Some(Cursor::Window { start_time }) } else { None },
+            end_time: Some(end_time),
+            limit: MY_TRADES_MAX_LIMIT,
+        }
+    }
+
+    /// Trades per request, 1000 by default.
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = limit.clamp(1, MY_TRADES_MAX_LIMIT);
+        self
+    }
+
+    /// The next non-empty page, `None` once every trade was returned.
+    pub async fn next_page(&mut self) -> Result<Option<Vec<AccountTrade>>> {
+        loop {
+            let cursor = match self.cursor {
+                Some(cursor) => cursor,
+                None => {
+                    return Ok(None);
+                },
+            };
+
+            match cursor {
+                Cursor::FromId(from_id) => {
+                    let mut trades = my_trades(self.client, &self.symbol, None, None, None, Some(from_id), Some(self.limit)).await?;
+                    let mut more = trades.len() as u32 == self.limit;
+                    if let Some(end_time) = self.end_time {
+                        let fetched = trades.len();
+                        trades.retain(|t| t.time <= end_time);
+                        more &= trades.len() == fetched;
+                    }
+
+                    self.cursor = match trades.last() {
+                        Some(last) if more => Some(Cursor::FromId(last.id + 1)),
+                        _ => None,
+                    };
+                    return Ok(Some(trades).filter(|trades| !trades.is_empty()));
+                },
+                Cursor::Window { start_time } => {
+                    let end_time = self.end_time.unwrap_or(u64::MAX);
+                    let window_end = end_time.min(start_time.saturating_add(MY_TRADES_MAX_WINDOW_MS - 1));
+                    let trades = my_trades(self.client, &self.symbol, None, Some(start_time), Some(window_end), None, Some(self.limit)).await?;
+
+                    self.cursor = match trades.last() {
+                        // the rest of the window and everything after it, by id
+                        Some(last) if trades.len() as u32 == self.limit => Some(Cursor::FromId(last.id + 1)),
+                        _ if window_end < end_time => Some(Cursor::Window { start_time: window_end + 1 }),
+                        _ => None,
+                    };
+                    if !trades.is_empty() {
+                        return Ok(Some(trades));
+                    }
+                },
+            }
+        }
+    }
+
+    /// All remaining trades.
+    pub async fn collect(mut self) -> Result<Vec<AccountTrade>> {
+        let mut trades = vec![];
+        while let Some(mut page) = self.next_page().await? {
+            trades.append(&mut page);
+        }
+        Ok(trades)
+    }
+}
+
+/// Current usage of the account's order rate limits.
+pub async fn rate_limit_order(client: &Client) -> Result<Vec<OrderRateLimit>> {
+    let body = rate_limit_order_raw(client).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn rate_limit_order_raw(client: &Client) -> Result<Bytes> {
+    client.send_signed(Method::GET, URL_RATE_LIMIT_ORDER, &[]).await
+}
+
+/// Matches prevented by self-trade prevention, either one by `prevented_match_id` or those of
+/// the taker order `order_id`, which can be paged with `from_prevented_match_id` and `limit`.
+pub async fn my_prevented_matches(
+    client: &Client,
+    symbol: &str,
+    prevented_match_id: Option<u64>,
+    order_id: Option<u64>,
+    from_prevented_match_id: Option<u64>,
+    limit: Option<u32>
+) -> Result<Vec<PreventedMatch>> {
+    let body = my_prevented_matches_raw(client, symbol, prevented_match_id, order_id, from_prevented_match_id, limit).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn my_prevented_matches_raw(
+    client: &Client,
+    symbol: &str,
+    prevented_match_id: Option<u64>,
+    order_id: Option<u64>,
+    from_prevented_match_id: Option<u64>,
+    limit: Option<u32>
+) -> Result<Bytes> {
+    let mut param = vec![
+        RequestParam{key: String::from("symbol"), value: String::from(symbol)},
+    ];
+
+    match (prevented_match_id, order_id) {
+        (Some(prevented_match_id), None) => {
+            if from_prevented_match_id.is_some() || limit.is_some() {
+                return Err(Error::InvalidParameter(String::from("fromPreventedMatchId and limit need an orderId")));
+            }
+            param.push(RequestParam{key: String::from("preventedMatchId"), value: prevented_match_id.to_string()});
+        },
+        (None, Some(order_id)) => {
+            param.push(RequestParam{key: String::from("orderId"), value: order_id.to_string()});
+        },
+        _ => {
+            return Err(Error::InvalidParameter(String::from("exactly one of preventedMatchId and orderId is required")));
+        },
+    }
+    if let Some(from_prevented_match_id) = from_prevented_match_id {
+        param.push(RequestParam{key: String::from("fromPreventedMatchId"), value: from_prevented_match_id.to_string()});
+    }
+    if let Some(limit) = limit {
+        param.push(RequestParam{key: String::from("limit"), value: limit.to_string()});
+    }
+
+    client.send_signed(Method::GET, URL_MY_PREVENTED_MATCHES, &param).await
+}
+
+/// Commission rates of `symbol` for the account, including tax and BNB discount.
+pub async fn account_commission(client: &Client, symbol: &str) -> Result<AccountCommission> {
+    let body = account_commission_raw(client, symbol).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn account_commission_raw(client: &Client, symbol: &str) -> Result<Bytes> {
+    let param = vec![
+        RequestParam{key: String::from("symbol"), value: String::from(symbol)},
+    ];
+
+    client.send_signed(Method::GET, URL_ACCOUNT_COMMISSION, &param).await
+}
@@ -0,0 +1,120 @@
+use serde::Deserialize;
+
+use crate::http::market::model::{RateLimitInterval, RateLimitType};
+use crate::http::trade::trade::ESelfTradePreventionMode;
+use crate::Decimal;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommissionRates {
+    pub maker: Decimal,
+    pub taker: Decimal,
+    pub buyer: Decimal,
+    pub seller: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Balance {
+    pub asset: String,
+    pub free: Decimal,
+    pub locked: Decimal,
+}
+
+/// Answer to `account`. The integer commissions are in basis points, `commission_rates` has the
+/// same as fractions.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountInformation {
+    pub maker_commission: u32,
+    pub taker_commission: u32,
+    pub buyer_commission: u32,
+    pub seller_commission: u32,
+    pub commission_rates: CommissionRates,
+    pub can_trade: bool,
+    pub can_withdraw: bool,
+    pub can_deposit: bool,
+    #[serde(default)]
+    pub brokered: bool,
+    #[serde(default)]
+    pub require_self_trade_prevention: bool,
+    #[serde(default)]
+    pub prevent_sor: bool,
+    pub update_time: u64,
+    pub account_type: String,
+    pub balances: Vec<Balance>,
+    pub permissions: Vec<String>,
+    #[serde(default)]
+    pub uid: Option<u64>,
+}
+
+impl AccountInformation {
+    pub fn balance(&self, asset: &str) -> Option<&Balance> {
+        self.balances.iter().find(|b| b.asset == asset)
+    }
+}
+
+/// A fill of one of the account's orders.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountTrade {
+    pub symbol: String,
+    pub id: u64,
+    pub order_id: u64,
+    pub order_list_id: i64,
+    pub price: Decimal,
+    pub qty: Decimal,
+    pub quote_qty: Decimal,
+    pub commission: Decimal,
+    pub commission_asset: String,
+    pub time: u64,
+    pub is_buyer: bool,
+    pub is_maker: bool,
+    pub is_best_match: bool,
+}
+
+/// Usage of an order rate limit, as `rate_limit_order` reports it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderRateLimit {
+    pub rate_limit_type: RateLimitType,
+    pub interval: RateLimitInterval,
+    pub interval_num: u32,
+    pub limit: u32,
+    pub count: u32,
+}
+
+/// An order that expired instead of trading against another order of the account.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreventedMatch {
+    pub symbol: String,
+    pub prevented_match_id: u64,
+    pub taker_order_id: u64,
+    pub maker_symbol: String,
+    pub maker_order_id: u64,
+    pub trade_group_id: u64,
+    pub self_trade_prevention_mode: ESelfTradePreventionMode,
+    pub price: Decimal,
+    pub maker_prevented_quantity: Decimal,
+    pub transact_time: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommissionDiscount {
+    pub enabled_for_account: bool,
+    pub enabled_for_symbol: bool,
+    pub discount_asset: String,
+    pub discount: Decimal,
+}
+
+/// Answer to `account_commission`: the standard and tax rates add up to what a trade is charged.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountCommission {
+    pub symbol: String,
+    pub standard_commission: CommissionRates,
+    pub tax_commission: CommissionRates,
+    pub discount: CommissionDiscount,
+}
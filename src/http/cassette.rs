@@ -0,0 +1,221 @@
+use async_trait::async_trait;
+use hyper::{body::Bytes, Request, Response, StatusCode};
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+
+use std::{collections::{BTreeMap, HashMap}, fs, path::{Path, PathBuf}, sync::{Arc, Mutex}};
+
+use crate::error::{Error, Result};
+use crate::http::transport::Transport;
+
+// per request values, left out of cassettes and ignored when matching
+const VOLATILE_PARAMS: [&str; 2] = ["timestamp", "signature"];
+
+/// Recorded request/response pairs, stored as pretty printed JSON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: CassetteRequest,
+    pub response: CassetteResponse,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CassetteRequest {
+    pub method: String,
+    pub path: String,
+    /// Query and form params sorted by key, without `timestamp` and `signature`.
+    pub params: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CassetteResponse {
+    pub status: u16,
+    /// Rate limit and content headers; everything else is dropped.
+    pub headers: BTreeMap<String, String>,
+    /// The body exactly as received, so replays are byte for byte the same.
+    pub body: String,
+}
+
+impl Cassette {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(err) => {
+                return Err(Error::Cassette(format!("cannot read {}: {}", path.display(), err)));
+            },
+        };
+        match serde_json::from_slice(&data) {
+            Ok(cassette) => Ok(cassette),
+            Err(err) => Err(Error::Cassette(format!("invalid cassette {}: {}", path.display(), err))),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let data = serde_json::to_vec_pretty(self)?;
+        match fs::write(path, data) {
+            Ok(()) => Ok(()),
+            Err(err) => Err(Error::Cassette(format!("cannot write {}: {}", path.display(), err))),
+        }
+    }
+
+    async fn save_async(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_vec_pretty(self)?;
+        match tokio::fs::write(path, data).await {
+            Ok(()) => Ok(()),
+            Err(err) => Err(Error::Cassette(format!("cannot write {}: {}", path.display(), err))),
+        }
+    }
+}
+
+impl CassetteRequest {
+    fn from_request(req: &Request<Bytes>) -> Self {
+        let query = req.uri().query().unwrap_or("");
+        let body = String::from_utf8_lossy(req.body());
+        CassetteRequest {
+            method: req.method().to_string(),
+            path: req.uri().path().to_string(),
+            params: normalize_params(query, &body),
+        }
+    }
+}
+
+impl CassetteResponse {
+    fn from_response(resp: &Response<Bytes>) -> Self {
+        let mut headers = BTreeMap::new();
+        for (name, value) in resp.headers().iter() {
+            let name = name.as_str();
+            let keep = name == "content-type" || name == "retry-after" || name.starts_with("x-mbx-") || name.starts_with("x-sapi-");
+            if let (true, Ok(value)) = (keep, value.to_str()) {
+                headers.insert(name.to_string(), value.to_string());
+            }
+        }
+
+        let body = String::from_utf8_lossy(resp.body()).into_owned();
+        CassetteResponse { status: resp.status().as_u16(), headers, body }
+    }
+
+    fn to_response(&self) -> Result<Response<Bytes>> {
+        let status = match StatusCode::from_u16(self.status) {
+            Ok(status) => status,
+            Err(_) => {
+                return Err(Error::Cassette(format!("invalid status {}", self.status)));
+            },
+        };
+
+        let mut builder = Response::builder().status(status);
+        for (name, value) in self.headers.iter() {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        Ok(builder.body(Bytes::from(self.body.clone()))?)
+    }
+}
+
+/// `a=1&b=2` from the query and form body, decoded, sorted and without volatile params.
+fn normalize_params(query: &str, body: &str) -> String {
+    let mut params: Vec<(String, String)> = query.split('&')
+        .chain(body.split('&'))
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let decode = |s: &str| percent_decode_str(s).decode_utf8_lossy().into_owned();
+            (decode(key), decode(value))
+        })
+        .filter(|(key, _)| !VOLATILE_PARAMS.contains(&key.as_str()))
+        .collect();
+    params.sort();
+
+    params.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>().join("&")
+}
+
+/// Passes requests on to another transport and appends every exchange to a cassette file.
+///
+/// The api key header is never written and `timestamp`/`signature` are stripped from the params.
+/// The file is rewritten after each response so nothing is lost if the process stops.
+#[derive(Debug)]
+pub struct RecordingTransport {
+    inner: Arc<dyn Transport>,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+    // one write at a time, each of the cassette as of when it started
+    writing: tokio::sync::Mutex<()>,
+}
+
+impl RecordingTransport {
+    /// Starts an empty cassette at `path`, replacing any existing file on the first response.
+    pub fn new(inner: impl Transport + 'static, path: impl Into<PathBuf>) -> Self {
+        RecordingTransport::from_arc(Arc::new(inner), path.into())
+    }
+
+    pub(crate) fn from_arc(inner: Arc<dyn Transport>, path: PathBuf) -> Self {
+        RecordingTransport { inner, path, cassette: Mutex::new(Cassette::default()), writing: tokio::sync::Mutex::new(()) }
+    }
+
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Transport for RecordingTransport {
+    async fn send(&self, req: Request<Bytes>) -> Result<Response<Bytes>> {
+        let request = CassetteRequest::from_request(&req);
+        let resp = self.inner.send(req).await?;
+
+        let response = CassetteResponse::from_response(&resp);
+        self.cassette.lock().unwrap().interactions.push(Interaction { request, response });
+
+        let _writing = self.writing.lock().await;
+        let cassette = self.cassette();
+        cassette.save_async(&self.path).await?;
+        Ok(resp)
+    }
+}
+
+/// Answers requests from a cassette without touching the network.
+///
+/// Requests match on method, path and normalized params. Repeated requests get the recorded
+/// responses in order, the last one is served again once they run out.
+#[derive(Debug)]
+pub struct ReplayTransport {
+    responses: HashMap<CassetteRequest, Vec<CassetteResponse>>,
+    served: Mutex<HashMap<CassetteRequest, usize>>,
+}
+
+impl ReplayTransport {
+    pub fn new(cassette: Cassette) -> Self {
+        let mut responses: HashMap<CassetteRequest, Vec<CassetteResponse>> = HashMap::new();
+        for interaction in cassette.interactions {
+            responses.entry(interaction.request).or_default().push(interaction.response);
+        }
+        ReplayTransport { responses, served: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(ReplayTransport::new(Cassette::load(path)?))
+    }
+}
+
+#[async_trait]
+impl Transport for ReplayTransport {
+    async fn send(&self, req: Request<Bytes>) -> Result<Response<Bytes>> {
+        let request = CassetteRequest::from_request(&req);
+        let responses = match self.responses.get(&request) {
+            Some(responses) => responses,
+            None => {
+                return Err(Error::Cassette(format!("no recorded response for {} {}?{}", request.method, request.path, request.params)));
+            },
+        };
+
+        let mut served = self.served.lock().unwrap();
+        let count = served.entry(request).or_insert(0);
+        let response = &responses[(*count).min(responses.len() - 1)];
+        *count += 1;
+        response.to_response()
+    }
+}
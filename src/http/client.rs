@@ -1,10 +1,28 @@
-use hyper_proxy::{Proxy, Intercept, ProxyConnector};
-use hyper_tls::HttpsConnector;
-use log::info;
+use log::{debug, info, warn};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 
-use hyper::{Client as HttpClient, client::HttpConnector, Method, Request, Response, Body, Uri};
+use hyper::{body::Bytes, http::request::Builder, Method, Request, Response};
 
-use std::{env, str::FromStr};
+use std::{env, path::PathBuf, sync::Arc, time::Duration};
+
+use crate::error::{ApiError, Error, ErrorCode, Result};
+use crate::http::cassette::{RecordingTransport, ReplayTransport};
+use crate::http::market::market;
+use crate::http::rate_limit::{request_cost, RateLimiter, RequestCost, Usage, DEFAULT_MAX_WAIT};
+use crate::http::retry::RetryPolicy;
+use crate::http::signer::{Ed25519Signer, HmacSigner, RsaSigner, Signer};
+use crate::http::time_sync::{local_timestamp, TimeOffset, TimeSync, DEFAULT_TIME_SYNC_INTERVAL};
+use crate::http::transport::{HyperTransport, Transport};
+
+pub const BASE_URL: &str = "https://api.binance.com";
+pub const BASE_URL_API1: &str = "https://api1.binance.com";
+pub const BASE_URL_API2: &str = "https://api2.binance.com";
+pub const BASE_URL_API3: &str = "https://api3.binance.com";
+pub const BASE_URL_API4: &str = "https://api4.binance.com";
+pub const BASE_URL_TESTNET: &str = "https://testnet.binance.vision";
+pub const BASE_URL_US: &str = "https://api.binance.us";
+
+const DEFAULT_USER_AGENT: &str = concat!("binance-sdk-rs/", env!("CARGO_PKG_VERSION"));
 
 #[derive(Debug)]
 pub struct RequestParam {
@@ -12,255 +30,473 @@ pub struct RequestParam {
     pub value: String,
 }
 
-#[derive(Debug)]
-enum EClient {
-    Client(HttpClient<HttpsConnector<HttpConnector>>),
-    ProxyClient(HttpClient<ProxyConnector<HttpsConnector<HttpConnector>>>),
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    api_key: String,
+    signer: Arc<dyn Signer>,
+}
+
+impl Credentials {
+    /// HMAC-SHA256 api key.
+    pub fn new(api_key: impl Into<String>, secret_key: impl Into<String>) -> Self {
+        Credentials::with_signer(api_key, HmacSigner::new(secret_key))
+    }
+
+    /// RSA api key, `private_key_pem` is the PKCS#8 PEM private key.
+    pub fn rsa(api_key: impl Into<String>, private_key_pem: &str) -> Result<Self> {
+        Ok(Credentials::with_signer(api_key, RsaSigner::from_pkcs8_pem(private_key_pem)?))
+    }
+
+    /// Ed25519 api key, `private_key_pem` is the PKCS#8 PEM private key.
+    pub fn ed25519(api_key: impl Into<String>, private_key_pem: &str) -> Result<Self> {
+        Ok(Credentials::with_signer(api_key, Ed25519Signer::from_pkcs8_pem(private_key_pem)?))
+    }
+
+    pub fn with_signer(api_key: impl Into<String>, signer: impl Signer + 'static) -> Self {
+        Credentials {
+            api_key: api_key.into(),
+            signer: Arc::new(signer),
+        }
+    }
+
+    /// Reads `APIKEY` and `SECRETKEY` from the environment.
+    pub fn from_env() -> Option<Self> {
+        info!("Trying to get APIKEY and SECRETKEY from env");
+
+        match (env::var("APIKEY"), env::var("SECRETKEY")) {
+            (Ok(api_key), Ok(secret_key)) => Some(Credentials::new(api_key, secret_key)),
+            _ => None,
+        }
+    }
+
+    pub fn api_key(&self) -> &str {
+        &self.api_key
+    }
+
+    /// Signs `payload` with the key's signer, as signed requests are.
+    pub fn sign(&self, payload: &str) -> Result<String> {
+        self.signer.sign(payload.as_bytes())
+    }
 }
 
 #[derive(Debug)]
 pub struct Client {
-    api_key: String,
-    secret_key: String,
+    credentials: Option<Credentials>,
+
+    base_url: String,
+    timeout: Option<Duration>,
+    user_agent: String,
+    proxy_uri: Option<String>,
+    recv_window: Option<u64>,
+    time_sync: TimeSync,
+    rate_limiter: RateLimiter,
+    retry_policy: RetryPolicy,
+
+    transport: Arc<dyn Transport>,
+}
 
-    base_url: &'static str,
+#[derive(Debug)]
+pub struct ClientBuilder {
+    base_url: String,
+    credentials: Option<Credentials>,
+    timeout: Option<Duration>,
+    user_agent: String,
+    proxy_uri: Option<String>,
+    recv_window: Option<u64>,
+    time_sync_interval: Option<Duration>,
+    rate_limit_max_wait: Duration,
+    retry_policy: RetryPolicy,
+    transport: Option<Arc<dyn Transport>>,
+    cassette: Option<ECassetteMode>,
+}
 
-    http_client: EClient,
-    proxy: Option<ProxyConnector<HttpsConnector<HttpConnector>>>,
+#[derive(Debug)]
+enum ECassetteMode {
+    Record(PathBuf),
+    Replay(PathBuf),
 }
 
-impl Client {
-    pub fn get_secret_key(&self) -> &String {
-        &self.secret_key
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        ClientBuilder {
+            base_url: BASE_URL.to_string(),
+            credentials: None,
+            timeout: None,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            proxy_uri: None,
+            recv_window: None,
+            time_sync_interval: Some(DEFAULT_TIME_SYNC_INTERVAL),
+            rate_limit_max_wait: DEFAULT_MAX_WAIT,
+            retry_policy: RetryPolicy::default(),
+            transport: None,
+            cassette: None,
+        }
     }
+}
 
-    pub fn new() -> Result<Self, String> {
-        env_logger::init();
+impl ClientBuilder {
+    pub fn new() -> Self {
+        ClientBuilder::default()
+    }
 
-        let http_client = HttpClient::builder().build::<_, hyper::Body>(HttpsConnector::new());
+    /// e.g. one of the `BASE_URL_*` constants, or the address of a local mock server.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
 
-        let mut client = Client {
-            api_key: "".to_string(),
-            secret_key: "".to_string(),
-            base_url: &"https://api.binance.com",
-            http_client: EClient::Client(http_client),
-            proxy: None,
-        };
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
 
-        match client.get_api_key() {
-            Ok(_) => {return Ok(client);},
-            Err(e) => Err(e),
-        }
+    /// Uses `APIKEY`/`SECRETKEY` from the environment if both are set.
+    pub fn credentials_from_env(mut self) -> Self {
+        self.credentials = Credentials::from_env();
+        self
     }
 
-    pub fn with_proxy(proxy_uri: String) -> Result<Self, String> {
-        let proxy = {
-            let proxy = Proxy::new(Intercept::All, proxy_uri.parse().unwrap());
-            let connector = HttpsConnector::new();
-            let proxy_connector = ProxyConnector::from_proxy(connector, proxy).unwrap();
-            proxy_connector
-        };
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
 
-        let http_client = HttpClient::builder().build::<_, hyper::Body>(proxy.clone());
-        let mut client = Client {
-            api_key: "".to_string(),
-            secret_key: "".to_string(),
-            base_url: &"https://api.binance.com",
-            http_client: EClient::ProxyClient(http_client),
-            proxy: Some(proxy),
-        };
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
 
-        match client.get_api_key() {
-            Ok(_) => {return Ok(client);},
-            Err(e) => Err(e),
-        }
+    pub fn proxy(mut self, proxy_uri: impl Into<String>) -> Self {
+        self.proxy_uri = Some(proxy_uri.into());
+        self
     }
 
-    fn get_api_key(&mut self) -> Result<(), String> {
-        let api_key = self.get_api_key_from_env();
-        if let Ok(k) = api_key {
-            self.api_key = k;
-        } else {
-            return Err("APIKEY not found".to_string());
-        }
+    /// Default `recvWindow` in milliseconds sent with signed requests.
+    pub fn recv_window(mut self, recv_window: u64) -> Self {
+        self.recv_window = Some(recv_window);
+        self
+    }
 
-        let secret_key = self.get_secret_key_from_env();
-        if let Ok(k) = secret_key {
-            self.secret_key = k;
-        } else {
-            return Err("SECRETKEY not found".to_string());
-        }
+    /// How often signed requests refresh the server time offset, 30 minutes by default.
+    pub fn time_sync_interval(mut self, interval: Duration) -> Self {
+        self.time_sync_interval = Some(interval);
+        self
+    }
+
+    /// Sign requests with the local clock only.
+    pub fn disable_time_sync(mut self) -> Self {
+        self.time_sync_interval = None;
+        self
+    }
 
-        Ok(())
+    /// Longest the client sleeps for a rate limit window before failing with
+    /// `Error::RateLimited` instead, 60 seconds by default.
+    pub fn rate_limit_max_wait(mut self, max_wait: Duration) -> Self {
+        self.rate_limit_max_wait = max_wait;
+        self
     }
 
-    fn get_api_key_from_env(&self) -> Result<String, String> {
-        info!("Trying to get APIKEY from env");
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
 
-        let env_apikey = env::var("APIKEY");
+    /// Replaces the default hyper transport; `proxy` is ignored then.
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
 
-        if let Ok(k) = env_apikey {
-            Ok(k)
-        } else {
-            Err("APIKEY not found".to_string())
-        }
+    /// Records every request/response pair to a JSON cassette at `path`, see
+    /// [`RecordingTransport`]. Requests still go through the configured transport.
+    pub fn record_to(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cassette = Some(ECassetteMode::Record(path.into()));
+        self
     }
 
-    fn get_secret_key_from_env(&self) -> Result<String, String> {
-        info!("Trying to get SECRETKEY from env");
+    /// Serves responses from a cassette written by `record_to`, without any network access.
+    pub fn replay_from(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cassette = Some(ECassetteMode::Replay(path.into()));
+        self
+    }
 
-        let env_secretkey = env::var("SECRETKEY");
+    pub fn build(self) -> Result<Client> {
+        let transport: Arc<dyn Transport> = match (self.transport, &self.proxy_uri) {
+            (Some(transport), _) => transport,
+            (None, Some(proxy_uri)) => Arc::new(HyperTransport::with_proxy(proxy_uri)?),
+            (None, None) => Arc::new(HyperTransport::new()),
+        };
+        let transport: Arc<dyn Transport> = match self.cassette {
+            Some(ECassetteMode::Record(path)) => Arc::new(RecordingTransport::from_arc(transport, path)),
+            Some(ECassetteMode::Replay(path)) => Arc::new(ReplayTransport::from_file(path)?),
+            None => transport,
+        };
 
-        if let Ok(k) = env_secretkey {
-            Ok(k)
-        } else {
-            Err("SECRETKEY not found".to_string())
+        Ok(Client {
+            credentials: self.credentials,
+            base_url: self.base_url,
+            timeout: self.timeout,
+            user_agent: self.user_agent,
+            proxy_uri: self.proxy_uri,
+            recv_window: self.recv_window,
+            time_sync: TimeSync::new(self.time_sync_interval),
+            rate_limiter: RateLimiter::new(self.rate_limit_max_wait),
+            retry_policy: self.retry_policy,
+            transport,
+        })
+    }
+}
+
+impl Client {
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// Client for `api.binance.com`, with credentials from the environment if present.
+    pub fn new() -> Result<Self> {
+        Client::builder().credentials_from_env().build()
+    }
+
+    pub fn with_proxy(proxy_uri: String) -> Result<Self> {
+        Client::builder().credentials_from_env().proxy(proxy_uri).build()
+    }
+
+    /// Signs `payload` with the configured credentials.
+    pub fn sign(&self, payload: &str) -> Result<String> {
+        match &self.credentials {
+            Some(credentials) => credentials.sign(payload),
+            None => Err(Error::MissingCredentials),
         }
     }
 
+    pub fn credentials(&self) -> Option<&Credentials> {
+        self.credentials.as_ref()
+    }
+
     pub fn set_api_key(&mut self, api_key: String, secret_key: String) {
-        self.api_key = api_key;
-        self.secret_key = secret_key;
+        self.credentials = Some(Credentials::new(api_key, secret_key));
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn recv_window(&self) -> Option<u64> {
+        self.recv_window
+    }
+
+    /// The http proxy the client was built with, also used by `ws::client::Client::from_http`.
+    pub fn proxy_uri(&self) -> Option<&str> {
+        self.proxy_uri.as_deref()
     }
 }
 
 impl Client {
-    pub async fn get(&self, uri: &str) -> Result<Response<Body>, String> {
-        let mut req = match Request::builder()
-            .method(Method::GET)
-            .uri(format!("{}{}", self.base_url, uri))
-            .header("X-MBX-APIKEY", &self.api_key)
-            .body(Body::empty())
-        {
-            Ok(req) => req,
-            Err(err) => {
-                return Err(err.to_string());
-            },
-        };
+    /// Sends an unsigned request. The api key header is still attached when credentials are set.
+    /// GET requests are retried according to the client's `RetryPolicy`.
+    pub async fn send(&self, method: Method, path: &str, param: &[RequestParam]) -> Result<Bytes> {
+        let query = encode_params(param);
+        let cost = request_cost(&method, path, param);
+        let mut attempt = 0;
+        loop {
+            match self.dispatch(method.clone(), path, query.clone(), cost).await {
+                Err(err) if method == Method::GET && self.retry_policy.should_retry(&err, attempt) => {
+                    self.backoff(&err, attempt).await;
+                    attempt += 1;
+                },
+                resp => {
+                    return resp;
+                },
+            }
+        }
+    }
+
+    /// Sends a request that carries the api key but no signature, like the `userDataStream`
+    /// endpoints. Fails without credentials. Besides GET, PUT and DELETE are retried too, they only
+    /// keep alive or close a listen key there.
+    pub async fn send_with_api_key(&self, method: Method, path: &str, param: &[RequestParam]) -> Result<Bytes> {
+        if self.credentials.is_none() {
+            return Err(Error::MissingCredentials);
+        }
 
-        info!("req: {:?}", &req);
-        if let Some(proxy) = &self.proxy {
-            if let Some(headers) = proxy.http_headers(&Uri::from_str(uri).unwrap()) {
-                req.headers_mut().extend(headers.clone().into_iter());
+        let query = encode_params(param);
+        let cost = request_cost(&method, path, param);
+        let idempotent = method == Method::GET || method == Method::PUT || method == Method::DELETE;
+        let mut attempt = 0;
+        loop {
+            match self.dispatch(method.clone(), path, query.clone(), cost).await {
+                Err(err) if idempotent && self.retry_policy.should_retry(&err, attempt) => {
+                    self.backoff(&err, attempt).await;
+                    attempt += 1;
+                },
+                resp => {
+                    return resp;
+                },
             }
         }
+    }
 
-        match &self.http_client {
-            EClient::Client(client) => {
-                let resp = client.request(req).await;
-                if let Ok(r) = resp {
-                    Ok(r)
-                } else {
-                    Err("Request Error".to_string())
-                }
-            },
-            EClient::ProxyClient(client) => {
-                let resp = client.request(req).await;
-                if let Ok(r) = resp {
-                    Ok(r)
-                } else {
-                    Err("Request Error".to_string())
+    /// Appends `recvWindow` and `timestamp`, signs the encoded params and sends them.
+    /// On a -1021 timestamp error the server time is resynced and the request is re-signed once.
+    /// GET requests are retried according to the client's `RetryPolicy`, every attempt is signed
+    /// with a fresh timestamp.
+    pub async fn send_signed(&self, method: Method, path: &str, param: &[RequestParam]) -> Result<Bytes> {
+        if self.time_sync.needs_sync() {
+            // one request syncs, the others wait for its offset
+            let _syncing = self.time_sync.lock().await;
+            if self.time_sync.needs_sync() {
+                if let Err(err) = self.sync_time().await {
+                    warn!("server time sync failed, using local clock: {}", err);
                 }
             }
         }
+
+        let cost = request_cost(&method, path, param);
+        let mut resynced = false;
+        let mut attempt = 0;
+        loop {
+            let query = self.signed_query(param)?;
+            match self.dispatch(method.clone(), path, query, cost).await {
+                Err(err) if err.code() == Some(ErrorCode::InvalidTimestamp) && self.time_sync.enabled() && !resynced => {
+                    warn!("{}, resyncing server time", err);
+                    self.sync_time().await?;
+                    resynced = true;
+                },
+                Err(err) if method == Method::GET && self.retry_policy.should_retry(&err, attempt) => {
+                    self.backoff(&err, attempt).await;
+                    attempt += 1;
+                },
+                resp => {
+                    return resp;
+                },
+            }
+        }
+    }
+
+    async fn backoff(&self, err: &Error, attempt: u32) {
+        let delay = self.retry_policy.delay(attempt);
+        warn!("request failed: {}, retrying in {:?}", err, delay);
+        tokio::time::sleep(delay).await;
     }
 
-    pub async fn get_with_param(&self, uri: &str, param: &Vec<RequestParam>) -> Result<Response<Body>, String> {
-        let mut param_str = String::new();
-        for p in param {
-            param_str.push_str(&format!("&{}={}", &p.key, &p.value));
+    fn signed_query(&self, param: &[RequestParam]) -> Result<String> {
+        let mut query = encode_params(param);
+        if let Some(recv_window) = self.recv_window {
+            push_query(&mut query, "recvWindow", &recv_window.to_string());
         }
+        push_query(&mut query, "timestamp", &self.time_sync.timestamp().to_string());
 
-        param_str.remove(0);
+        let signature = self.sign(&query)?;
+        push_query(&mut query, "signature", &signature);
+        Ok(query)
+    }
 
-        println!("request param: {}", &param_str);
+    /// Measures the offset between the local clock and Binance server time.
+    pub async fn sync_time(&self) -> Result<TimeOffset> {
+        let sent = local_timestamp();
+        let server_time = market::time(self).await.inspect_err(|_| self.time_sync.record_failure())?;
+        let received = local_timestamp();
 
-        let mut req = match Request::builder()
-            .method(Method::GET)
-            .uri(&format!("{}{}?{}", self.base_url, uri, param_str))
-            .header("X-MBX-APIKEY", &self.api_key)
-            .body(Body::empty())
-        {
-            Ok(req) => req,
-            Err(err) => {
-                return Err(err.to_string());
-            }
-        };
+        let offset = self.time_sync.record(server_time.server_time, sent, received);
+        debug!("server time offset {}ms, round trip {}ms", offset.offset_ms, offset.round_trip_ms);
+        Ok(offset)
+    }
 
-        info!("req: {:?}", &req);
-        if let Some(proxy) = &self.proxy {
-            if let Some(headers) = proxy.http_headers(&Uri::from_str(uri).unwrap()) {
-                req.headers_mut().extend(headers.clone().into_iter());
-            }
-        }
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
 
-        match &self.http_client {
-            EClient::Client(client) => {
-                let resp = client.request(req).await;
-                if let Ok(r) = resp {
-                    Ok(r)
-                } else {
-                    Err("Request Error".to_string())
-                }
-            },
-            EClient::ProxyClient(client) => {
-                let resp = client.request(req).await;
-                if let Ok(r) = resp {
-                    Ok(r)
-                } else {
-                    Err("Request Error".to_string())
-                }
-            }
-        }
+    pub fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
     }
 
-    pub async fn post(&self, uri: &str, param: &Vec<RequestParam>) -> Result<Response<Body>, String> {
-        let mut param_str = String::new();
-        for p in param {
-            param_str.push_str(&format!("&{}={}", &p.key, &p.value));
-        }
+    /// Current usage of every tracked rate limit window.
+    pub fn rate_limit_usage(&self) -> Vec<Usage> {
+        self.rate_limiter.usage(self.time_sync.timestamp())
+    }
+
+    /// Current time in milliseconds, corrected by the server time offset.
+    pub fn timestamp(&self) -> u64 {
+        self.time_sync.timestamp()
+    }
 
-        param_str.remove(0);
+    /// Last measured server time offset, `None` before the first sync.
+    pub fn time_offset(&self) -> Option<TimeOffset> {
+        self.time_sync.offset()
+    }
 
-        println!("request param: {}", &param_str);
+    async fn dispatch(&self, method: Method, path: &str, query: String, cost: RequestCost) -> Result<Bytes> {
+        self.rate_limiter.acquire(cost, || self.time_sync.timestamp()).await?;
 
-        let mut req = match Request::builder()
-            .method(Method::POST)
-            .uri(format!("{}{}", self.base_url, uri))
-            .header("X-MBX-APIKEY", &self.api_key)
-            .body(Body::from(param_str))
-        {
-            Ok(req) => req,
-            Err(err) => {
-                return Err(err.to_string());
-            },
+        let req = if method == Method::POST || method == Method::PUT {
+            self.request_builder(method, format!("{}{}", self.base_url, path))
+                .header(hyper::header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+                .body(Bytes::from(query))?
+        } else if query.is_empty() {
+            self.request_builder(method, format!("{}{}", self.base_url, path))
+                .body(Bytes::new())?
+        } else {
+            self.request_builder(method, format!("{}{}?{}", self.base_url, path, query))
+                .body(Bytes::new())?
         };
 
-        info!("req: {:?}", &req);
-        if let Some(proxy) = &self.proxy {
-            if let Some(headers) = proxy.http_headers(&Uri::from_str(uri).unwrap()) {
-                req.headers_mut().extend(headers.clone().into_iter());
-            }
+        match self.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, self.execute(req)).await {
+                Ok(body) => body,
+                Err(_) => Err(Error::Timeout),
+            },
+            None => self.execute(req).await,
         }
+    }
 
-        match &self.http_client {
-            EClient::Client(client) => {
-                let resp = client.request(req).await;
-                if let Ok(r) = resp {
-                    Ok(r)
-                } else {
-                    Err("Request Error".to_string())
-                }
-            },
-            EClient::ProxyClient(client) => {
-                let resp = client.request(req).await;
-                if let Ok(r) = resp {
-                    Ok(r)
-                } else {
-                    Err("Request Error".to_string())
-                }
-            }
+    fn request_builder(&self, method: Method, url: String) -> Builder {
+        let mut builder = Request::builder()
+            .method(method)
+            .uri(url)
+            .header(hyper::header::USER_AGENT, &self.user_agent);
+        if let Some(credentials) = &self.credentials {
+            builder = builder.header("X-MBX-APIKEY", &credentials.api_key);
         }
+        builder
+    }
+
+    async fn execute(&self, req: Request<Bytes>) -> Result<Bytes> {
+        let resp = self.transport.send(req).await?;
+        self.rate_limiter.update(resp.status(), resp.headers(), self.time_sync.timestamp());
+        body_bytes(resp)
+    }
+}
+
+// everything except the unreserved characters of RFC 3986
+const PARAM_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+fn push_query(query: &mut String, key: &str, value: &str) {
+    if !query.is_empty() {
+        query.push('&');
+    }
+    query.push_str(&utf8_percent_encode(key, PARAM_ENCODE_SET).to_string());
+    query.push('=');
+    query.push_str(&utf8_percent_encode(value, PARAM_ENCODE_SET).to_string());
+}
+
+fn encode_params(param: &[RequestParam]) -> String {
+    let mut query = String::new();
+    for p in param {
+        push_query(&mut query, &p.key, &p.value);
+    }
+    query
+}
+
+/// Takes the response body, turning non-success statuses into errors.
+fn body_bytes(resp: Response<Bytes>) -> Result<Bytes> {
+    let status = resp.status();
+    let body = resp.into_body();
+    if status.is_success() {
+        return Ok(body);
+    }
 
+    match ApiError::from_body(status, &body) {
+        Some(err) => Err(Error::Api(err)),
+        None => Err(Error::Http { status, body: String::from_utf8_lossy(&body).into_owned() }),
     }
 }
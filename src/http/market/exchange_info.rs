@@ -0,0 +1,169 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use log::{debug, warn};
+use tokio::task::JoinHandle;
+
+use crate::error::{Error, Result};
+use crate::http::client::Client;
+use crate::http::market::filters::{OrderContext, SymbolFilters};
+use crate::http::market::market;
+use crate::http::market::model::{ExchangeInfo, SymbolInfo};
+use crate::http::trade::trade::NewOrder;
+
+/// Symbols and their filters change rarely, Binance announces changes ahead.
+pub const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug)]
+struct Snapshot {
+    exchange_info: Arc<ExchangeInfo>,
+    by_symbol: HashMap<String, usize>,
+    by_assets: HashMap<(String, String), usize>,
+    filters: Vec<SymbolFilters>,
+}
+
+impl Snapshot {
+    fn new(exchange_info: ExchangeInfo) -> Self {
+        let symbols = &exchange_info.symbols;
+        let by_symbol = symbols.iter().enumerate().map(|(i, info)| (info.symbol.clone(), i)).collect();
+        let by_assets = symbols.iter().enumerate()
+            .map(|(i, info)| ((info.base_asset.clone(), info.quote_asset.clone()), i))
+            .collect();
+        let filters = symbols.iter().map(SymbolFilters::from).collect();
+        Snapshot { exchange_info: Arc::new(exchange_info), by_symbol, by_assets, filters }
+    }
+
+    fn index(&self, symbol: &str) -> Result<usize> {
+        self.by_symbol.get(&symbol.to_uppercase()).copied()
+            .ok_or_else(|| Error::InvalidParameter(format!("unknown symbol {}", symbol)))
+    }
+}
+
+/// The exchange info, loaded once and refreshed in the background every `refresh_interval`.
+///
+/// Orders can be checked against their symbol's filters with `validate` and moved onto its tick
+/// and step with `round` before they are sent. A failed refresh is logged and retried with the
+/// client's retry policy, then at the next interval; lookups keep answering from the last
+/// exchange info meanwhile.
+/// Dropping the cache stops the task.
+#[derive(Debug)]
+pub struct ExchangeInfoCache {
+    client: Arc<Client>,
+    snapshot: Arc<RwLock<Arc<Snapshot>>>,
+    task: JoinHandle<()>,
+}
+
+impl ExchangeInfoCache {
+    /// Loads the exchange info and starts refreshing it.
+    pub async fn start(client: Arc<Client>, refresh_interval: Duration) -> Result<Self> {
+        let snapshot = Arc::new(RwLock::new(Arc::new(load(&client).await?)));
+        let task = tokio::spawn(refresh_periodically(client.clone(), refresh_interval, snapshot.clone()));
+        Ok(ExchangeInfoCache { client, snapshot, task })
+    }
+
+    /// Loads the exchange info now, e.g. after an order was rejected with -1013 anyway.
+    pub async fn refresh(&self) -> Result<()> {
+        let snapshot = load(&self.client).await?;
+        *self.snapshot.write().unwrap() = Arc::new(snapshot);
+        Ok(())
+    }
+
+    /// The last exchange info loaded.
+    pub fn exchange_info(&self) -> Arc<ExchangeInfo> {
+        self.snapshot().exchange_info.clone()
+    }
+
+    /// Looks a symbol up by name, in any case.
+    pub fn symbol(&self, symbol: &str) -> Option<SymbolInfo> {
+        let snapshot = self.snapshot();
+        let i = snapshot.index(symbol).ok()?;
+        Some(snapshot.exchange_info.symbols[i].clone())
+    }
+
+    /// Looks a symbol up by its base and quote asset, e.g. `("BTC", "USDT")`.
+    pub fn symbol_by_assets(&self, base_asset: &str, quote_asset: &str) -> Option<SymbolInfo> {
+        let snapshot = self.snapshot();
+        let i = *snapshot.by_assets.get(&(base_asset.to_uppercase(), quote_asset.to_uppercase()))?;
+        Some(snapshot.exchange_info.symbols[i].clone())
+    }
+
+    pub fn filters(&self, symbol: &str) -> Option<SymbolFilters> {
+        let snapshot = self.snapshot();
+        let i = snapshot.index(symbol).ok()?;
+        Some(snapshot.filters[i].clone())
+    }
+
+    /// Checks that the symbol trades, takes the order type and that the order passes its filters.
+    pub fn validate(&self, order: &NewOrder, context: &OrderContext) -> Result<()> {
+        let snapshot = self.snapshot();
+        let i = snapshot.index(&order.symbol)?;
+        let info = &snapshot.exchange_info.symbols[i];
+        if info.status != "TRADING" {
+            return Err(Error::InvalidParameter(format!("{} is not trading: {}", info.symbol, info.status)));
+        }
+        let order_type = order.order_type.to_string();
+        if !info.order_types.is_empty() && !info.order_types.contains(&order_type) {
+            return Err(Error::InvalidParameter(format!("{} does not take {} orders", info.symbol, order_type)));
+        }
+        snapshot.filters[i].validate(order, context)
+    }
+
+    /// `order` on its symbol's tick and step, see `SymbolFilters::round`.
+    pub fn round(&self, order: &NewOrder) -> Result<NewOrder> {
+        let snapshot = self.snapshot();
+        let i = snapshot.index(&order.symbol)?;
+        Ok(snapshot.filters[i].round(order))
+    }
+
+    /// Rounds `order` and validates the result, ready to send.
+    pub fn prepare(&self, order: &NewOrder, context: &OrderContext) -> Result<NewOrder> {
+        let order = self.round(order)?;
+        self.validate(&order, context)?;
+        Ok(order)
+    }
+
+    fn snapshot(&self) -> Arc<Snapshot> {
+        self.snapshot.read().unwrap().clone()
+    }
+}
+
+impl Drop for ExchangeInfoCache {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn load(client: &Client) -> Result<Snapshot> {
+    let exchange_info = market::exchange_info(client).await?;
+    debug!("exchange info with {} symbols loaded", exchange_info.symbols.len());
+    Ok(Snapshot::new(exchange_info))
+}
+
+async fn refresh_periodically(client: Arc<Client>, refresh_interval: Duration, snapshot: Arc<RwLock<Arc<Snapshot>>>) {
+    let mut delay = refresh_interval;
+    let mut attempt = 0;
+    loop {
+        tokio::time::sleep(delay).await;
+        match load(&client).await {
+            Ok(loaded) => {
+                *snapshot.write().unwrap() = Arc::new(loaded);
+                attempt = 0;
+                delay = refresh_interval;
+            },
+            // after the last retry, the next interval tries again
+            Err(err) if attempt >= client.retry_policy().max_retries => {
+                attempt = 0;
+                delay = refresh_interval;
+                warn!("refreshing the exchange info failed: {}, next attempt in {:?}", err, delay);
+            },
+            Err(err) => {
+                delay = client.retry_policy().delay(attempt);
+                attempt += 1;
+                warn!("refreshing the exchange info failed: {}, retrying in {:?}", err, delay);
+            },
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use rust_decimal::RoundingStrategy;
+
+use crate::error::{Error, Result};
+use crate::http::market::model::{LotSize, MinNotional, Notional, PercentPriceBySide, PriceFilter, SymbolFilter, SymbolInfo};
+use crate::http::trade::trade::{EOrderType, ESide, NewOrder};
+use crate::Decimal;
+
+/// What an order is checked against besides the filters. Checks that need a value left `None`
+/// are skipped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OrderContext {
+    /// Average price over the filters' `avg_price_mins`, see `market::avg_price`. Market orders
+    /// are valued at it, and limit prices have to stay within PERCENT_PRICE_BY_SIDE of it.
+    pub avg_price: Option<Decimal>,
+    /// Orders open on the symbol, for MAX_NUM_ORDERS.
+    pub open_orders: Option<u32>,
+}
+
+/// The filters of one symbol that orders are checked against.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolFilters {
+    pub price_filter: Option<PriceFilter>,
+    pub lot_size: Option<LotSize>,
+    pub market_lot_size: Option<LotSize>,
+    pub min_notional: Option<MinNotional>,
+    pub notional: Option<Notional>,
+    pub percent_price_by_side: Option<PercentPriceBySide>,
+    pub max_num_orders: Option<u32>,
+    pub iceberg_parts: Option<u32>,
+}
+
+impl From<&SymbolInfo> for SymbolFilters {
+    fn from(info: &SymbolInfo) -> Self {
+        let mut filters = SymbolFilters::default();
+        for filter in info.symbol_filters() {
+            match filter {
+                SymbolFilter::PriceFilter(filter) => filters.price_filter = Some(filter),
+                SymbolFilter::LotSize(filter) => filters.lot_size = Some(filter),
+                SymbolFilter::MarketLotSize(filter) => filters.market_lot_size = Some(filter),
+                SymbolFilter::MinNotional(filter) => filters.min_notional = Some(filter),
+                SymbolFilter::Notional(filter) => filters.notional = Some(filter),
+                SymbolFilter::PercentPriceBySide(filter) => filters.percent_price_by_side = Some(filter),
+                SymbolFilter::MaxNumOrders { max_num_orders } => filters.max_num_orders = Some(max_num_orders),
+                SymbolFilter::IcebergParts { limit } => filters.iceberg_parts = Some(limit),
+            }
+        }
+        filters
+    }
+}
+
+impl SymbolFilters {
+    /// Checks `order` the way the matching engine does, so it is not rejected with -1013.
+    /// Failures are `Error::InvalidParameter` naming the filter like Binance does.
+    pub fn validate(&self, order: &NewOrder, context: &OrderContext) -> Result<()> {
+        let market = order.order_type == EOrderType::MARKET;
+
+        if let Some(filter) = &self.price_filter {
+            for price in order.price.iter().chain(order.stop_price.iter()) {
+                check_price(filter, *price)?;
+            }
+        }
+        if let Some(filter) = &self.lot_size {
+            for qty in order.quantity.iter().chain(order.iceberg_qty.iter()) {
+                check_lot("LOT_SIZE", filter, *qty)?;
+            }
+        }
+        if let (Some(filter), Some(qty), true) = (&self.market_lot_size, order.quantity, market) {
+            check_lot("MARKET_LOT_SIZE", filter, qty)?;
+        }
+        if let (Some(limit), Some(iceberg_qty)) = (self.iceberg_parts, order.iceberg_qty) {
+            let qty = order.quantity.unwrap_or_default();
+            if iceberg_qty.is_zero() || (qty / iceberg_qty).ceil() > Decimal::from(limit) {
+                return failure("ICEBERG_PARTS", format!("{} splits into more than {} parts", qty, limit));
+            }
+        }
+
+        if let (Some(filter), Some(price), Some(avg_price)) = (&self.percent_price_by_side, order.price, context.avg_price) {
+            let (up, down) = match order.side {
+                ESide::BUY => (filter.bid_multiplier_up, filter.bid_multiplier_down),
+                ESide::SELL => (filter.ask_multiplier_up, filter.ask_multiplier_down),
+            };
+            if price > avg_price * up || price < avg_price * down {
+                return failure("PERCENT_PRICE_BY_SIDE", format!("price {} too far from the average price {}", price, avg_price));
+            }
+        }
+
+        if let Some(notional) = notional(order, context) {
+            if let Some(filter) = &self.min_notional {
+                if (!market || filter.apply_to_market) && notional < filter.min_notional {
+                    return failure("MIN_NOTIONAL", format!("notional {} below {}", notional, filter.min_notional));
+                }
+            }
+            if let Some(filter) = &self.notional {
+                if (!market || filter.apply_min_to_market) && notional < filter.min_notional {
+                    return failure("NOTIONAL", format!("notional {} below {}", notional, filter.min_notional));
+                }
+                if (!market || filter.apply_max_to_market) && notional > filter.max_notional {
+                    return failure("NOTIONAL", format!("notional {} above {}", notional, filter.max_notional));
+                }
+            }
+        }
+
+        if let (Some(max_num_orders), Some(open_orders)) = (self.max_num_orders, context.open_orders) {
+            if open_orders >= max_num_orders {
+                return failure("MAX_NUM_ORDERS", format!("{} orders open already", open_orders));
+            }
+        }
+        Ok(())
+    }
+
+    /// `order` with its prices on the tick and its quantities on the step.
+    ///
+    /// Prices round in the order's favour, down for buys and up for sells, stop prices the same
+    /// way. Quantities round down. The result can still fail `validate`, e.g. when
+    /// the quantity rounds below the minimum.
+    pub fn round(&self, order: &NewOrder) -> NewOrder {
+        let mut order = order.clone();
+        if let Some(filter) = &self.price_filter {
+            let round = match order.side {
+                ESide::BUY => RoundingStrategy::ToNegativeInfinity,
+                ESide::SELL => RoundingStrategy::ToPositiveInfinity,
+            };
+            order.price = order.price.map(|price| snap(price, filter.min_price, filter.tick_size, round));
+            order.stop_price = order.stop_price.map(|price| snap(price, filter.min_price, filter.tick_size, round));
+        }
+        if let Some(filter) = &self.lot_size {
+            order.quantity = order.quantity.map(|qty| snap(qty, filter.min_qty, filter.step_size, RoundingStrategy::ToNegativeInfinity));
+            order.iceberg_qty = order.iceberg_qty.map(|qty| snap(qty, filter.min_qty, filter.step_size, RoundingStrategy::ToNegativeInfinity));
+        }
+        if let (Some(filter), EOrderType::MARKET) = (&self.market_lot_size, order.order_type) {
+            order.quantity = order.quantity.map(|qty| snap(qty, filter.min_qty, filter.step_size, RoundingStrategy::ToNegativeInfinity));
+        }
+        order
+    }
+}
+
+// price times quantity, market orders at the average price
+fn notional(order: &NewOrder, context: &OrderContext) -> Option<Decimal> {
+    if let Some(quote_order_qty) = order.quote_order_qty {
+        return Some(quote_order_qty);
+    }
+    let price = order.price.or(order.stop_price).or(context.avg_price)?;
+    Some(price * order.quantity?)
+}
+
+fn failure(filter: &str, msg: String) -> Result<()> {
+    Err(Error::InvalidParameter(format!("Filter failure: {}: {}", filter, msg)))
+}
+
+fn check_price(filter: &PriceFilter, price: Decimal) -> Result<()> {
+    if !filter.min_price.is_zero() && price < filter.min_price {
+        return failure("PRICE_FILTER", format!("price {} below {}", price, filter.min_price));
+    }
+    if !filter.max_price.is_zero() && price > filter.max_price {
+        return failure("PRICE_FILTER", format!("price {} above {}", price, filter.max_price));
+    }
+    if !filter.tick_size.is_zero() && !((price - filter.min_price) % filter.tick_size).is_zero() {
+        return failure("PRICE_FILTER", format!("price {} is not on the tick size {}", price, filter.tick_size));
+    }
+    Ok(())
+}
+
+fn check_lot(name: &str, filter: &LotSize, qty: Decimal) -> Result<()> {
+    if qty < filter.min_qty {
+        return failure(name, format!("quantity {} below {}", qty, filter.min_qty));
+    }
+    if !filter.max_qty.is_zero() && qty > filter.max_qty {
+        return failure(name, format!("quantity {} above {}", qty, filter.max_qty));
+    }
+    if !filter.step_size.is_zero() && !((qty - filter.min_qty) % filter.step_size).is_zero() {
+        return failure(name, format!("quantity {} is not on the step size {}", qty, filter.step_size));
+    }
+    Ok(())
+}
+
+// `value` on the grid of `min + n * step`, `round` picks n; values below `min` are left alone
+fn snap(value: Decimal, min: Decimal, step: Decimal, round: RoundingStrategy) -> Decimal {
+    if step.is_zero() || value <= min {
+        return value;
+    }
+    (((value - min) / step).round_dp_with_strategy(0, round) * step + min).normalize()
+}
@@ -1,6 +1,10 @@
-use hyper::body::Bytes;
+use hyper::{body::Bytes, Method};
 
+use crate::error::{Error, Result};
 use crate::http::client::{Client, RequestParam};
+use crate::http::market::model::{
+    AggTrade, AvgPrice, BookTicker, ExchangeInfo, Kline, OneOrMany, OrderBookSnapshot, ServerTime, Ticker24hr, TickerPrice, Trade,
+};
 
 
 const URL_PING: &str = "/api/v3/ping";
@@ -17,111 +21,104 @@ const URL_TICKER_PRICE: &str = "/api/v3/ticker/price";
 const URL_TICKER_BOOK: &str = "/api/v3/ticker/bookTicker";
 
 
-pub async fn ping(client: &Client) -> Result<Bytes, String> {
-    let resp = client.get(URL_PING).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        }
-    };
-    Ok(body_bytes)
+pub async fn ping(client: &Client) -> Result<()> {
+    ping_raw(client).await?;
+    Ok(())
 }
 
-pub async fn time(client: &Client) -> Result<Bytes, String> {
-    let resp = client.get(URL_TIME).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        }
-    };
-    Ok(body_bytes)
+pub async fn ping_raw(client: &Client) -> Result<Bytes> {
+    client.send(Method::GET, URL_PING, &[]).await
 }
 
-pub async fn exchange_info(client: &Client) -> Result<Bytes, String> {
-    let resp = client.get(URL_EXCHANGE_INFO).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        }
-    };
-    Ok(body_bytes)
+pub async fn time(client: &Client) -> Result<ServerTime> {
+    let body = time_raw(client).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn time_raw(client: &Client) -> Result<Bytes> {
+    client.send(Method::GET, URL_TIME, &[]).await
+}
+
+pub async fn exchange_info(client: &Client) -> Result<ExchangeInfo> {
+    let body = exchange_info_raw(client).await?;
+    let exchange_info: ExchangeInfo = serde_json::from_slice(&body)?;
+    client.rate_limiter().set_limits(&exchange_info.rate_limits);
+    Ok(exchange_info)
+}
+
+pub async fn exchange_info_raw(client: &Client) -> Result<Bytes> {
+    client.send(Method::GET, URL_EXCHANGE_INFO, &[]).await
 }
 
-pub async fn exchange_info_symbol(client: &Client, symbol: &String) -> Result<Bytes, String> {
+pub async fn exchange_info_symbol(client: &Client, symbol: &String) -> Result<ExchangeInfo> {
+    let body = exchange_info_symbol_raw(client, symbol).await?;
+    let exchange_info: ExchangeInfo = serde_json::from_slice(&body)?;
+    client.rate_limiter().set_limits(&exchange_info.rate_limits);
+    Ok(exchange_info)
+}
+
+pub async fn exchange_info_symbol_raw(client: &Client, symbol: &String) -> Result<Bytes> {
     let param = vec![RequestParam{key: String::from("symbol"), value: String::from(symbol)}];
-    let resp = client.get_with_param(URL_EXCHANGE_INFO, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-    Ok(body_bytes)
+    client.send(Method::GET, URL_EXCHANGE_INFO, &param).await
 }
 
-pub async fn exchange_info_symbols(client: &Client, symbols: &Vec<String>) -> Result<Bytes, String> {
-    let mut symbols_str = String::from("[");
-    for s in symbols.iter() {
-        symbols_str.push_str(s.as_str());
-    }
-    symbols_str.push_str(&"]");
-
-    let param = vec![RequestParam{key: String::from("symbol"), value: symbols_str}];
-    let resp = client.get_with_param(URL_EXCHANGE_INFO, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-    Ok(body_bytes)
+pub async fn exchange_info_symbols(client: &Client, symbols: &[String]) -> Result<ExchangeInfo> {
+    let body = exchange_info_symbols_raw(client, symbols).await?;
+    let exchange_info: ExchangeInfo = serde_json::from_slice(&body)?;
+    client.rate_limiter().set_limits(&exchange_info.rate_limits);
+    Ok(exchange_info)
+}
+
+pub async fn exchange_info_symbols_raw(client: &Client, symbols: &[String]) -> Result<Bytes> {
+    let symbols_str = serde_json::to_string(symbols)?;
+
+    let param = vec![RequestParam{key: String::from("symbols"), value: symbols_str}];
+    client.send(Method::GET, URL_EXCHANGE_INFO, &param).await
+}
+
+pub async fn depth(client: &Client, symbol: &String, limit: u32) -> Result<OrderBookSnapshot> {
+    let body = depth_raw(client, symbol, limit).await?;
+    Ok(serde_json::from_slice(&body)?)
 }
 
-pub async fn depth(client: &Client, symbol: &String, limit: u32) -> Result<Bytes, String> {
+pub async fn depth_raw(client: &Client, symbol: &String, limit: u32) -> Result<Bytes> {
     let limit_options: [u32; 8] = [5, 10, 20, 50, 100, 500, 1000, 5000];
-    if let None = limit_options.iter().find(|&&x| x == limit) {
-        return Err(format!("limit must be one of {:?}", &limit_options));
+    if !limit_options.contains(&limit) {
+        return Err(Error::InvalidParameter(format!("limit must be one of {:?}", &limit_options)));
     }
 
     let param = vec![
         RequestParam{key: String::from("symbol"), value: String::from(symbol)},
         RequestParam{key: String::from("limit"), value: limit.to_string()},
     ];
-    let resp = client.get_with_param(URL_DEPTH, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-    Ok(body_bytes)
+    client.send(Method::GET, URL_DEPTH, &param).await
 }
 
-pub async fn trades(client: &Client, symbol: &String, limit: u32) -> Result<Bytes, String> {
+pub async fn trades(client: &Client, symbol: &String, limit: u32) -> Result<Vec<Trade>> {
+    let body = trades_raw(client, symbol, limit).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn trades_raw(client: &Client, symbol: &String, limit: u32) -> Result<Bytes> {
     if limit > 1000 {
-        return Err("limit must be less than or equal to 1000".to_string());
+        return Err(Error::InvalidParameter("limit must be less than or equal to 1000".to_string()));
     }
 
     let param = vec![
         RequestParam{key: String::from("symbol"), value: String::from(symbol)},
         RequestParam{key: String::from("limit"), value: limit.to_string()},
     ];
-    let resp = client.get_with_param(URL_TRADES, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-    Ok(body_bytes)
+    client.send(Method::GET, URL_TRADES, &param).await
+}
+
+pub async fn historical_trades(client: &Client, symbol: &String, limit: u32, from_id: Option<u64>) -> Result<Vec<Trade>> {
+    let body = historical_trades_raw(client, symbol, limit, from_id).await?;
+    Ok(serde_json::from_slice(&body)?)
 }
 
-pub async fn historical_trades(client: &Client, symbol: &String, limit: u32, from_id: Option<u64>) -> Result<Bytes, String> {
+pub async fn historical_trades_raw(client: &Client, symbol: &String, limit: u32, from_id: Option<u64>) -> Result<Bytes> {
     if limit > 1000 {
-        return Err("limit must be less than or equal to 1000".to_string());
+        return Err(Error::InvalidParameter("limit must be less than or equal to 1000".to_string()));
     }
 
     let mut param = vec![
@@ -132,17 +129,15 @@ pub async fn historical_trades(client: &Client, symbol: &String, limit: u32, fro
         param.push(RequestParam{key: String::from("fromId"), value: from_id.to_string()});
     }
 
-    let resp = client.get_with_param(URL_TRADES_HISTORY, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-    Ok(body_bytes)
+    client.send(Method::GET, URL_TRADES_HISTORY, &param).await
 }
 
-pub async fn agg_trades(client: &Client, symbol: &String, from_id: Option<u64>, start_time: Option<u64>, end_time: Option<u64>, limit: Option<u32>) -> Result<Bytes, String> {
+pub async fn agg_trades(client: &Client, symbol: &String, from_id: Option<u64>, start_time: Option<u64>, end_time: Option<u64>, limit: Option<u32>) -> Result<Vec<AggTrade>> {
+    let body = agg_trades_raw(client, symbol, from_id, start_time, end_time, limit).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn agg_trades_raw(client: &Client, symbol: &String, from_id: Option<u64>, start_time: Option<u64>, end_time: Option<u64>, limit: Option<u32>) -> Result<Bytes> {
     let mut param = vec![
         RequestParam{key: String::from("symbol"), value: String::from(symbol)},
     ];
@@ -162,17 +157,15 @@ pub async fn agg_trades(client: &Client, symbol: &String, from_id: Option<u64>,
         param.push(RequestParam{key: String::from("limit"), value: limit.to_string()});
     }
 
-    let resp = client.get_with_param(URL_TRADES_AGG, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-    Ok(body_bytes)
+    client.send(Method::GET, URL_TRADES_AGG, &param).await
+}
+
+pub async fn klines(client: &Client, symbol: &String, interval: &String, start_time: Option<u64>, end_time: Option<u64>, limit: Option<u32>) -> Result<Vec<Kline>> {
+    let body = klines_raw(client, symbol, interval, start_time, end_time, limit).await?;
+    Ok(serde_json::from_slice(&body)?)
 }
 
-pub async fn klines(client: &Client, symbol: &String, interval: &String, start_time: Option<u64>, end_time: Option<u64>, limit: Option<u32>) -> Result<Bytes, String> {
+pub async fn klines_raw(client: &Client, symbol: &String, interval: &String, start_time: Option<u64>, end_time: Option<u64>, limit: Option<u32>) -> Result<Bytes> {
     let mut param = vec![
         RequestParam{key: String::from("symbol"), value: String::from(symbol)},
         RequestParam{key: String::from("interval"), value: String::from(interval)},
@@ -190,72 +183,60 @@ pub async fn klines(client: &Client, symbol: &String, interval: &String, start_t
         param.push(RequestParam{key: String::from("limit"), value: limit.to_string()});
     }
 
-    let resp = client.get_with_param(URL_KLINES, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-    Ok(body_bytes)
+    client.send(Method::GET, URL_KLINES, &param).await
 }
 
-pub async fn avg_price(client: &Client, symbol: &String) -> Result<Bytes, String> {
+pub async fn avg_price(client: &Client, symbol: &String) -> Result<AvgPrice> {
+    let body = avg_price_raw(client, symbol).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn avg_price_raw(client: &Client, symbol: &String) -> Result<Bytes> {
     let param = vec![RequestParam{key: String::from("symbol"), value: String::from(symbol)}];
-    let resp = client.get_with_param(URL_PRICE_AVG, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-    Ok(body_bytes)
+    client.send(Method::GET, URL_PRICE_AVG, &param).await
+}
+
+pub async fn ticker_24hr(client: &Client, symbol: &Option<String>) -> Result<Vec<Ticker24hr>> {
+    let body = ticker_24hr_raw(client, symbol).await?;
+    let tickers: OneOrMany<_> = serde_json::from_slice(&body)?;
+    Ok(tickers.into())
 }
 
-pub async fn ticker_24hr(client: &Client, symbol: &Option<String>) -> Result<Bytes, String> {
+pub async fn ticker_24hr_raw(client: &Client, symbol: &Option<String>) -> Result<Bytes> {
     let mut param = vec![];
     if let Some(symbol) = symbol {
         param.push(RequestParam{key: String::from("symbol"), value: String::from(symbol)});
     }
 
-    let resp = client.get_with_param(URL_TICKER_24HR, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-    Ok(body_bytes)
+    client.send(Method::GET, URL_TICKER_24HR, &param).await
 }
 
-pub async fn ticker_price(client: &Client, symbol: &Option<String>) -> Result<Bytes, String> {
+pub async fn ticker_price(client: &Client, symbol: &Option<String>) -> Result<Vec<TickerPrice>> {
+    let body = ticker_price_raw(client, symbol).await?;
+    let tickers: OneOrMany<_> = serde_json::from_slice(&body)?;
+    Ok(tickers.into())
+}
+
+pub async fn ticker_price_raw(client: &Client, symbol: &Option<String>) -> Result<Bytes> {
     let mut param = vec![];
     if let Some(symbol) = symbol {
         param.push(RequestParam{key: String::from("symbol"), value: String::from(symbol)});
     }
 
-    let resp = client.get_with_param(URL_TICKER_PRICE, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-    Ok(body_bytes)
+    client.send(Method::GET, URL_TICKER_PRICE, &param).await
+}
+
+pub async fn ticker_book(client: &Client, symbol: &Option<String>) -> Result<Vec<BookTicker>> {
+    let body = ticker_book_raw(client, symbol).await?;
+    let tickers: OneOrMany<_> = serde_json::from_slice(&body)?;
+    Ok(tickers.into())
 }
 
-pub async fn ticker_book(client: &Client, symbol: &Option<String>) -> Result<Bytes, String> {
+pub async fn ticker_book_raw(client: &Client, symbol: &Option<String>) -> Result<Bytes> {
     let mut param = vec![];
     if let Some(symbol) = symbol {
         param.push(RequestParam{key: String::from("symbol"), value: String::from(symbol)});
     }
 
-    let resp = client.get_with_param(URL_TICKER_BOOK, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-    Ok(body_bytes)
+    client.send(Method::GET, URL_TICKER_BOOK, &param).await
 }
@@ -1 +1,5 @@
+pub mod exchange_info;
+pub mod filters;
+#[allow(clippy::module_inception)]
 pub mod market;
+pub mod model;
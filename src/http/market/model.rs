@@ -0,0 +1,323 @@
+use std::fmt;
+
+use serde::{de::{self, IgnoredAny, SeqAccess, Visitor}, Deserialize, Deserializer};
+
+use crate::Decimal;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerTime {
+    pub server_time: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RateLimitType {
+    RequestWeight,
+    Orders,
+    RawRequests,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RateLimitInterval {
+    Second,
+    Minute,
+    Day,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimit {
+    pub rate_limit_type: RateLimitType,
+    pub interval: RateLimitInterval,
+    pub interval_num: u32,
+    pub limit: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeInfo {
+    pub timezone: String,
+    pub server_time: u64,
+    pub rate_limits: Vec<RateLimit>,
+    #[serde(default)]
+    pub exchange_filters: Vec<serde_json::Value>,
+    pub symbols: Vec<SymbolInfo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolInfo {
+    pub symbol: String,
+    pub status: String,
+    pub base_asset: String,
+    pub base_asset_precision: u32,
+    pub quote_asset: String,
+    #[serde(default)]
+    pub quote_asset_precision: u32,
+    #[serde(default)]
+    pub base_commission_precision: u32,
+    #[serde(default)]
+    pub quote_commission_precision: u32,
+    #[serde(default)]
+    pub order_types: Vec<String>,
+    #[serde(default)]
+    pub iceberg_allowed: bool,
+    #[serde(default)]
+    pub oco_allowed: bool,
+    #[serde(default)]
+    pub quote_order_qty_market_allowed: bool,
+    #[serde(default)]
+    pub allow_trailing_stop: bool,
+    #[serde(default)]
+    pub cancel_replace_allowed: bool,
+    #[serde(default)]
+    pub is_spot_trading_allowed: bool,
+    #[serde(default)]
+    pub is_margin_trading_allowed: bool,
+    #[serde(default)]
+    pub filters: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+}
+
+impl SymbolInfo {
+    /// The filters `SymbolFilter` knows, the others are skipped.
+    pub fn symbol_filters(&self) -> Vec<SymbolFilter> {
+        self.filters.iter().filter_map(|filter| serde_json::from_value(filter.clone()).ok()).collect()
+    }
+}
+
+/// A trading rule of a symbol, see
+/// <https://developers.binance.com/docs/binance-spot-api-docs/filters>
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "filterType", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SymbolFilter {
+    PriceFilter(PriceFilter),
+    LotSize(LotSize),
+    MarketLotSize(LotSize),
+    MinNotional(MinNotional),
+    Notional(Notional),
+    PercentPriceBySide(PercentPriceBySide),
+    #[serde(rename_all = "camelCase")]
+    MaxNumOrders { max_num_orders: u32 },
+    IcebergParts { limit: u32 },
+}
+
+/// Price and stop price range and tick; a zero disables that check.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceFilter {
+    pub min_price: Decimal,
+    pub max_price: Decimal,
+    pub tick_size: Decimal,
+}
+
+/// Quantity range and step; a zero step disables the step check.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LotSize {
+    pub min_qty: Decimal,
+    pub max_qty: Decimal,
+    pub step_size: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MinNotional {
+    pub min_notional: Decimal,
+    pub apply_to_market: bool,
+    pub avg_price_mins: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Notional {
+    pub min_notional: Decimal,
+    pub apply_min_to_market: bool,
+    pub max_notional: Decimal,
+    pub apply_max_to_market: bool,
+    pub avg_price_mins: u32,
+}
+
+/// How far from the average price of the last `avg_price_mins` minutes buys and sells may be priced.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PercentPriceBySide {
+    pub bid_multiplier_up: Decimal,
+    pub bid_multiplier_down: Decimal,
+    pub ask_multiplier_up: Decimal,
+    pub ask_multiplier_down: Decimal,
+    pub avg_price_mins: u32,
+}
+
+/// A `[price, qty]` pair of the order book.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PriceLevel {
+    pub price: Decimal,
+    pub qty: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderBookSnapshot {
+    pub last_update_id: u64,
+    pub bids: Vec<PriceLevel>,
+    pub asks: Vec<PriceLevel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trade {
+    pub id: u64,
+    pub price: Decimal,
+    pub qty: Decimal,
+    pub quote_qty: Decimal,
+    pub time: u64,
+    pub is_buyer_maker: bool,
+    pub is_best_match: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AggTrade {
+    #[serde(rename = "a")]
+    pub agg_trade_id: u64,
+    #[serde(rename = "p")]
+    pub price: Decimal,
+    #[serde(rename = "q")]
+    pub qty: Decimal,
+    #[serde(rename = "f")]
+    pub first_trade_id: u64,
+    #[serde(rename = "l")]
+    pub last_trade_id: u64,
+    #[serde(rename = "T")]
+    pub time: u64,
+    #[serde(rename = "m")]
+    pub is_buyer_maker: bool,
+    #[serde(rename = "M")]
+    pub is_best_match: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Kline {
+    pub open_time: u64,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal,
+    pub close_time: u64,
+    pub quote_asset_volume: Decimal,
+    pub number_of_trades: u64,
+    pub taker_buy_base_asset_volume: Decimal,
+    pub taker_buy_quote_asset_volume: Decimal,
+}
+
+// klines come as positional arrays:
+// [openTime, open, high, low, close, volume, closeTime, quoteVolume, trades, takerBase, takerQuote, ignore]
+impl<'de> Deserialize<'de> for Kline {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KlineVisitor;
+
+        impl<'de> Visitor<'de> for KlineVisitor {
+            type Value = Kline;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a kline array")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Kline, A::Error> {
+                fn next<'de, T: Deserialize<'de>, A: SeqAccess<'de>>(seq: &mut A, index: usize) -> Result<T, A::Error> {
+                    seq.next_element()?.ok_or_else(|| de::Error::invalid_length(index, &"at least 11 kline fields"))
+                }
+
+                let kline = Kline {
+                    open_time: next(&mut seq, 0)?,
+                    open: next(&mut seq, 1)?,
+                    high: next(&mut seq, 2)?,
+                    low: next(&mut seq, 3)?,
+                    close: next(&mut seq, 4)?,
+                    volume: next(&mut seq, 5)?,
+                    close_time: next(&mut seq, 6)?,
+                    quote_asset_volume: next(&mut seq, 7)?,
+                    number_of_trades: next(&mut seq, 8)?,
+                    taker_buy_base_asset_volume: next(&mut seq, 9)?,
+                    taker_buy_quote_asset_volume: next(&mut seq, 10)?,
+                };
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(kline)
+            }
+        }
+
+        deserializer.deserialize_seq(KlineVisitor)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvgPrice {
+    pub mins: u32,
+    pub price: Decimal,
+    #[serde(default)]
+    pub close_time: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ticker24hr {
+    pub symbol: String,
+    pub price_change: Decimal,
+    pub price_change_percent: Decimal,
+    pub weighted_avg_price: Decimal,
+    pub prev_close_price: Decimal,
+    pub last_price: Decimal,
+    pub last_qty: Decimal,
+    pub bid_price: Decimal,
+    pub bid_qty: Decimal,
+    pub ask_price: Decimal,
+    pub ask_qty: Decimal,
+    pub open_price: Decimal,
+    pub high_price: Decimal,
+    pub low_price: Decimal,
+    pub volume: Decimal,
+    pub quote_volume: Decimal,
+    pub open_time: u64,
+    pub close_time: u64,
+    pub first_id: i64,
+    pub last_id: i64,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TickerPrice {
+    pub symbol: String,
+    pub price: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BookTicker {
+    pub symbol: String,
+    pub bid_price: Decimal,
+    pub bid_qty: Decimal,
+    pub ask_price: Decimal,
+    pub ask_qty: Decimal,
+}
+
+/// Ticker endpoints return an object for one symbol and an array for all of them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub(crate) enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> From<OneOrMany<T>> for Vec<T> {
+    fn from(value: OneOrMany<T>) -> Self {
+        match value {
+            OneOrMany::One(item) => vec![item],
+            OneOrMany::Many(items) => items,
+        }
+    }
+}
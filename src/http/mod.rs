@@ -1,3 +1,12 @@
+pub mod account;
+pub mod cassette;
 pub mod client;
 pub mod market;
+pub mod rate_limit;
+pub mod retry;
+pub mod signer;
+pub mod time_sync;
+pub mod trade;
+pub mod transport;
+pub mod user_data;
 pub mod wallet;
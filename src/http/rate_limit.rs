@@ -0,0 +1,320 @@
+use std::{sync::Mutex, time::{Duration, Instant}};
+
+use hyper::{HeaderMap, Method, StatusCode};
+use log::warn;
+
+use crate::error::{Error, Result};
+use crate::http::client::RequestParam;
+use crate::http::market::model::{RateLimit, RateLimitInterval, RateLimitType};
+
+pub const DEFAULT_MAX_WAIT: Duration = Duration::from_secs(60);
+
+// used when a 429/418 comes without a Retry-After header
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Counter {
+    /// `/api/*` request weight, reported in `X-MBX-USED-WEIGHT-*`.
+    RequestWeight,
+    /// `/api/*` request count.
+    RawRequests,
+    /// Orders placed, reported in `X-MBX-ORDER-COUNT-*`.
+    Orders,
+    /// `/sapi/*` ip weight, reported in `X-SAPI-USED-IP-WEIGHT-*`.
+    SapiIpWeight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    pub counter: Counter,
+    pub interval: Duration,
+    pub used: u32,
+    pub limit: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    counter: Counter,
+    interval_ms: u64,
+    limit: u32,
+    used: u32,
+    window_id: u64,
+}
+
+impl Window {
+    fn new(counter: Counter, interval_ms: u64, limit: u32) -> Self {
+        Window { counter, interval_ms, limit, used: 0, window_id: 0 }
+    }
+
+    fn used_at(&self, now_ms: u64) -> u32 {
+        if now_ms / self.interval_ms == self.window_id { self.used } else { 0 }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    windows: Vec<Window>,
+    blocked_until: Option<Instant>,
+}
+
+/// What a request counts against, see [`request_cost`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestCost {
+    pub weight: u32,
+    pub sapi: bool,
+    /// Orders placed, 2 or 3 for order lists.
+    pub orders: u32,
+}
+
+/// Client side view of the Binance rate limits.
+///
+/// Usage is estimated locally before each request and corrected from the response headers
+/// afterwards. Windows are aligned to server time like Binance's own counters.
+#[derive(Debug)]
+pub struct RateLimiter {
+    max_wait: Duration,
+    state: Mutex<State>,
+}
+
+impl RateLimiter {
+    /// Starts with the spot limits Binance currently publishes; they are replaced by
+    /// `exchangeInfo.rateLimits` whenever exchange info is fetched.
+    pub fn new(max_wait: Duration) -> Self {
+        let windows = vec![
+            Window::new(Counter::RequestWeight, 60_000, 6000),
+            Window::new(Counter::RawRequests, 300_000, 61000),
+            Window::new(Counter::Orders, 10_000, 100),
+            Window::new(Counter::Orders, 86_400_000, 200_000),
+            Window::new(Counter::SapiIpWeight, 60_000, 12000),
+        ];
+
+        RateLimiter {
+            max_wait,
+            state: Mutex::new(State { windows, blocked_until: None }),
+        }
+    }
+
+    pub fn set_limits(&self, rate_limits: &[RateLimit]) {
+        let mut state = self.state.lock().unwrap();
+        let mut windows: Vec<Window> = state.windows.iter()
+            .filter(|w| w.counter == Counter::SapiIpWeight)
+            .copied()
+            .collect();
+
+        for limit in rate_limits {
+            let counter = match limit.rate_limit_type {
+                RateLimitType::RequestWeight => Counter::RequestWeight,
+                RateLimitType::RawRequests => Counter::RawRequests,
+                RateLimitType::Orders => Counter::Orders,
+            };
+            let interval_ms = interval_ms(limit.interval) * limit.interval_num as u64;
+            let mut window = Window::new(counter, interval_ms, limit.limit);
+            if let Some(old) = state.windows.iter().find(|w| w.counter == counter && w.interval_ms == interval_ms) {
+                window.used = old.used;
+                window.window_id = old.window_id;
+            }
+            windows.push(window);
+        }
+        state.windows = windows;
+    }
+
+    pub fn usage(&self, now_ms: u64) -> Vec<Usage> {
+        let state = self.state.lock().unwrap();
+        state.windows.iter()
+            .map(|w| Usage {
+                counter: w.counter,
+                interval: Duration::from_millis(w.interval_ms),
+                used: w.used_at(now_ms),
+                limit: w.limit,
+            })
+            .collect()
+    }
+
+    /// Time left on a 429/418 ban, if any.
+    pub fn retry_after(&self) -> Option<Duration> {
+        let state = self.state.lock().unwrap();
+        state.blocked_until.and_then(|until| until.checked_duration_since(Instant::now()))
+    }
+
+    /// Waits until `cost` fits into every window, then reserves it. `now_ms` is the server time
+    /// in milliseconds, the windows start on its minute, day etc.
+    pub async fn acquire(&self, cost: RequestCost, now_ms: impl Fn() -> u64) -> Result<()> {
+        loop {
+            let wait = match self.try_acquire(cost, now_ms()) {
+                Some(wait) => wait,
+                None => {
+                    return Ok(());
+                },
+            };
+
+            if wait > self.max_wait {
+                return Err(Error::RateLimited { retry_after: wait });
+            }
+            warn!("rate limit reached, waiting {:?}", wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn try_acquire(&self, cost: RequestCost, now_ms: u64) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(until) = state.blocked_until {
+            match until.checked_duration_since(Instant::now()) {
+                Some(wait) => {
+                    return Some(wait);
+                },
+                None => state.blocked_until = None,
+            }
+        }
+
+        let mut wait_ms = 0;
+        for w in state.windows.iter() {
+            let amount = cost_for(w.counter, cost);
+            let used = w.used_at(now_ms);
+            // a request heavier than the whole limit is let through on an empty window
+            if amount > 0 && used > 0 && used + amount > w.limit {
+                let window_end = (now_ms / w.interval_ms + 1) * w.interval_ms;
+                wait_ms = wait_ms.max(window_end - now_ms);
+            }
+        }
+        if wait_ms > 0 {
+            return Some(Duration::from_millis(wait_ms));
+        }
+
+        for w in state.windows.iter_mut() {
+            let amount = cost_for(w.counter, cost);
+            if amount == 0 {
+                continue;
+            }
+            w.used = w.used_at(now_ms) + amount;
+            w.window_id = now_ms / w.interval_ms;
+        }
+        None
+    }
+
+    /// Takes the authoritative usage from the response headers and records 429/418 bans.
+    pub(crate) fn update(&self, status: StatusCode, headers: &HeaderMap, now_ms: u64) {
+        let mut state = self.state.lock().unwrap();
+
+        for (name, value) in headers.iter() {
+            let name = name.as_str();
+            let (counter, interval) = if let Some(interval) = name.strip_prefix("x-mbx-used-weight-") {
+                (Counter::RequestWeight, interval)
+            } else if let Some(interval) = name.strip_prefix("x-mbx-order-count-") {
+                (Counter::Orders, interval)
+            } else if let Some(interval) = name.strip_prefix("x-sapi-used-ip-weight-") {
+                (Counter::SapiIpWeight, interval)
+            } else {
+                continue;
+            };
+
+            let used = match value.to_str().ok().and_then(|v| v.parse::<u32>().ok()) {
+                Some(used) => used,
+                None => continue,
+            };
+            let interval_ms = match parse_interval(interval) {
+                Some(interval_ms) => interval_ms,
+                None => continue,
+            };
+            if let Some(w) = state.windows.iter_mut().find(|w| w.counter == counter && w.interval_ms == interval_ms) {
+                w.used = used;
+                w.window_id = now_ms / interval_ms;
+            }
+        }
+
+        if status == StatusCode::TOO_MANY_REQUESTS || status.as_u16() == 418 {
+            let retry_after = headers.get(hyper::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_RETRY_AFTER);
+            warn!("got {}, backing off for {:?}", status, retry_after);
+            state.blocked_until = Some(Instant::now() + retry_after);
+        }
+    }
+}
+
+fn cost_for(counter: Counter, cost: RequestCost) -> u32 {
+    match counter {
+        Counter::RequestWeight if !cost.sapi => cost.weight,
+        Counter::RawRequests if !cost.sapi => 1,
+        Counter::Orders => cost.orders,
+        Counter::SapiIpWeight if cost.sapi => cost.weight,
+        _ => 0,
+    }
+}
+
+fn interval_ms(interval: RateLimitInterval) -> u64 {
+    match interval {
+        RateLimitInterval::Second => 1_000,
+        RateLimitInterval::Minute => 60_000,
+        RateLimitInterval::Day => 86_400_000,
+    }
+}
+
+// header suffixes look like `1m`, `10s`, `1d`
+fn parse_interval(interval: &str) -> Option<u64> {
+    let unit_ms = match interval.chars().last()? {
+        's' => 1_000,
+        'm' => 60_000,
+        'h' => 3_600_000,
+        'd' => 86_400_000,
+        _ => {
+            return None;
+        },
+    };
+    let num: u64 = interval[..interval.len() - 1].parse().ok()?;
+    Some(num * unit_ms)
+}
+
+fn param_value<'a>(param: &'a [RequestParam], key: &str) -> Option<&'a str> {
+    param.iter().find(|p| p.key == key).map(|p| p.value.as_str())
+}
+
+/// Weight of a request as listed in the Binance docs.
+pub fn request_cost(method: &Method, path: &str, param: &[RequestParam]) -> RequestCost {
+    let has_symbol = param_value(param, "symbol").is_some();
+    let weight = match path {
+        "/api/v3/exchangeInfo" => 20,
+        "/api/v3/depth" => match param_value(param, "limit").and_then(|l| l.parse::<u32>().ok()).unwrap_or(100) {
+            0..=100 => 5,
+            101..=500 => 25,
+            501..=1000 => 50,
+            _ => 250,
+        },
+        "/api/v3/trades" | "/api/v3/historicalTrades" => 25,
+        "/api/v3/aggTrades" | "/api/v3/klines" | "/api/v3/avgPrice" => 2,
+        "/api/v3/ticker/24hr" => if has_symbol { 2 } else { 80 },
+        "/api/v3/ticker/price" | "/api/v3/ticker/bookTicker" => if has_symbol { 2 } else { 4 },
+        "/api/v3/order" if method == Method::GET => 4,
+        "/api/v3/order/test" if param_value(param, "computeCommissionRates") == Some("true") => 20,
+        "/api/v3/openOrders" if method == Method::GET => if has_symbol { 6 } else { 80 },
+        "/api/v3/allOrders" => 20,
+        "/api/v3/orderList" if method == Method::GET => 4,
+        "/api/v3/order/amend/keepPriority" => 4,
+        "/api/v3/account" | "/api/v3/account/commission" => 20,
+        "/api/v3/myTrades" => if param_value(param, "orderId").is_some() { 5 } else { 20 },
+        "/api/v3/rateLimit/order" => 40,
+        "/api/v3/userDataStream" => 2,
+        "/api/v3/myPreventedMatches" => if param_value(param, "preventedMatchId").is_some() { 2 } else { 20 },
+        "/sapi/v1/capital/config/getall" => 10,
+        "/sapi/v1/accountSnapshot" => 2400,
+        "/sapi/v1/capital/deposit/address" => 10,
+        "/sapi/v1/asset/dust" => 10,
+        "/sapi/v1/asset/assetDividend" => 10,
+        _ => 1,
+    };
+
+    let orders = match path {
+        "/api/v3/order" | "/api/v3/order/cancelReplace" => 1,
+        "/api/v3/orderList/oco" | "/api/v3/orderList/oto" => 2,
+        "/api/v3/orderList/otoco" => 3,
+        _ => 0,
+    };
+
+    RequestCost {
+        weight,
+        sapi: path.starts_with("/sapi/"),
+        orders: if method == Method::POST { orders } else { 0 },
+    }
+}
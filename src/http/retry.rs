@@ -0,0 +1,112 @@
+use std::{future::Future, time::Duration};
+
+use log::warn;
+use rand::Rng;
+
+use crate::error::{Error, ErrorCode, Result};
+
+/// Exponential backoff with full jitter for failed requests.
+///
+/// The client only retries GET requests on its own; requests with side effects go through
+/// endpoint specific reconciliation instead, see `wallet::capital_withdraw` or `trade::new_order`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Random delay in `[0, min(max_delay, base_delay * 2^attempt)]`.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self.base_delay.saturating_mul(2u32.saturating_pow(attempt)).min(self.max_delay);
+        let millis = backoff.as_millis() as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=millis))
+    }
+
+    pub fn should_retry(&self, err: &Error, attempt: u32) -> bool {
+        attempt < self.max_retries && is_retryable(err)
+    }
+}
+
+/// Transport failures, timeouts, 5xx, 429 and -1001. A 418 ip ban is never retried.
+pub fn is_retryable(err: &Error) -> bool {
+    match err {
+        Error::Transport(_) | Error::Timeout => true,
+        _ if err.status().map(|status| status.as_u16()) == Some(418) => false,
+        Error::Api(api) if api.code == ErrorCode::Disconnected || api.code == ErrorCode::TooManyRequests => true,
+        _ => match err.status() {
+            Some(status) => status.is_server_error() || status.as_u16() == 429,
+            None => false,
+        },
+    }
+}
+
+/// Whether the request may have been executed even though it failed. A 429 is rejected before
+/// execution, everything else retryable can have gone through.
+pub(crate) fn outcome_unknown(err: &Error) -> bool {
+    match err.status() {
+        Some(status) if status.as_u16() == 429 => false,
+        _ => err.code() != Some(ErrorCode::TooManyRequests),
+    }
+}
+
+/// Sends a request with side effects. Before every resend, `lookup` checks whether the failed
+/// attempt went through anyway and returns its result if so. When the lookup itself fails, or
+/// cannot tell and returns `Error::OutcomeUnknown`, the original error is returned rather than
+/// risking a duplicate.
+pub(crate) async fn send_reconciled<T, S, SF, L, LF>(policy: &RetryPolicy, mut send: S, mut lookup: L) -> Result<T>
+where
+    S: FnMut() -> SF,
+    SF: Future<Output = Result<T>>,
+    L: FnMut() -> LF,
+    LF: Future<Output = Result<Option<T>>>,
+{
+    let mut attempt = 0;
+    loop {
+        let err = match send().await {
+            Ok(resp) => {
+                return Ok(resp);
+            },
+            Err(err) => err,
+        };
+        if !policy.should_retry(&err, attempt) {
+            return Err(err);
+        }
+
+        let delay = policy.delay(attempt);
+        warn!("request failed: {}, checking and retrying in {:?}", err, delay);
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+
+        if outcome_unknown(&err) {
+            match lookup().await {
+                Ok(Some(resp)) => {
+                    return Ok(resp);
+                },
+                Ok(None) => {},
+                Err(lookup_err) => {
+                    warn!("could not check the outcome of the failed request: {}", lookup_err);
+                    return Err(err);
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::{sync::Mutex, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use tokio::sync::MutexGuard;
+
+pub const DEFAULT_TIME_SYNC_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+// the wait after the first failed sync, doubled with every further failure up to the interval
+const FAILED_SYNC_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Difference between Binance server time and the local clock, as last measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeOffset {
+    /// `server time - local time` in milliseconds.
+    pub offset_ms: i64,
+    pub round_trip_ms: u64,
+    pub synced_at: SystemTime,
+}
+
+#[derive(Debug)]
+pub(crate) struct TimeSync {
+    interval: Option<Duration>,
+    state: Mutex<Option<(TimeOffset, Instant)>>,
+    // consecutive failed syncs and when the last one failed
+    failures: Mutex<(u32, Option<Instant>)>,
+    syncing: tokio::sync::Mutex<()>,
+}
+
+impl TimeSync {
+    /// `interval` of `None` disables syncing, timestamps then come from the local clock.
+    pub(crate) fn new(interval: Option<Duration>) -> Self {
+        TimeSync {
+            interval,
+            state: Mutex::new(None),
+            failures: Mutex::new((0, None)),
+            syncing: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        self.interval.is_some()
+    }
+
+    pub(crate) fn needs_sync(&self) -> bool {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => {
+                return false;
+            },
+        };
+
+        if let (failures, Some(failed)) = *self.failures.lock().unwrap() {
+            let backoff = FAILED_SYNC_BACKOFF.saturating_mul(2u32.saturating_pow(failures - 1));
+            if failed.elapsed() < backoff.min(interval) {
+                return false;
+            }
+        }
+        match *self.state.lock().unwrap() {
+            Some((_, synced)) => synced.elapsed() >= interval,
+            None => true,
+        }
+    }
+
+    /// Held while a sync runs, so concurrent requests wait for it instead of syncing too.
+    pub(crate) async fn lock(&self) -> MutexGuard<'_, ()> {
+        self.syncing.lock().await
+    }
+
+    /// Delays the next sync `needs_sync` asks for.
+    pub(crate) fn record_failure(&self) {
+        let mut failures = self.failures.lock().unwrap();
+        *failures = (failures.0 + 1, Some(Instant::now()));
+    }
+
+    pub(crate) fn offset(&self) -> Option<TimeOffset> {
+        self.state.lock().unwrap().map(|(offset, _)| offset)
+    }
+
+    /// Records a `/api/v3/time` measurement taken between `sent_ms` and `received_ms` local time.
+    pub(crate) fn record(&self, server_time: u64, sent_ms: u64, received_ms: u64) -> TimeOffset {
+        let round_trip_ms = received_ms.saturating_sub(sent_ms);
+        let local_mid = sent_ms + round_trip_ms / 2;
+        let offset = TimeOffset {
+            offset_ms: server_time as i64 - local_mid as i64,
+            round_trip_ms,
+            synced_at: SystemTime::now(),
+        };
+        *self.state.lock().unwrap() = Some((offset, Instant::now()));
+        *self.failures.lock().unwrap() = (0, None);
+        offset
+    }
+
+    /// Local time corrected by the last measured offset, in milliseconds.
+    pub(crate) fn timestamp(&self) -> u64 {
+        let offset = self.offset().map(|offset| offset.offset_ms).unwrap_or(0);
+        (local_timestamp() as i64 + offset) as u64
+    }
+}
+
+pub(crate) fn local_timestamp() -> u64 {
+    let now = SystemTime::now();
+    let ts = now.duration_since(UNIX_EPOCH).unwrap();
+    ts.as_millis() as u64
+}
@@ -0,0 +1,3 @@
+pub mod model;
+#[allow(clippy::module_inception)]
+pub mod trade;
@@ -0,0 +1,365 @@
+use serde::Deserialize;
+
+use crate::error::ErrorCode;
+use crate::http::trade::trade::{EOrderType, ESelfTradePreventionMode, ESide, ETimeInForce};
+use crate::Decimal;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderStatus {
+    New,
+    PendingNew,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    PendingCancel,
+    Rejected,
+    Expired,
+    ExpiredInMatch,
+}
+
+impl OrderStatus {
+    /// Whether the order can still trade.
+    pub fn is_open(&self) -> bool {
+        matches!(self, OrderStatus::New | OrderStatus::PendingNew | OrderStatus::PartiallyFilled)
+    }
+}
+
+/// Answer to `new_order`. Only the ACK fields are always present, the others come with
+/// `newOrderRespType` RESULT or FULL, `fills` only with FULL.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewOrderResponse {
+    pub symbol: String,
+    pub order_id: u64,
+    /// -1 unless the order is part of an order list.
+    pub order_list_id: i64,
+    pub client_order_id: String,
+    pub transact_time: u64,
+    #[serde(default)]
+    pub price: Option<Decimal>,
+    #[serde(default)]
+    pub orig_qty: Option<Decimal>,
+    #[serde(default)]
+    pub executed_qty: Option<Decimal>,
+    #[serde(default)]
+    pub cummulative_quote_qty: Option<Decimal>,
+    #[serde(default)]
+    pub status: Option<OrderStatus>,
+    #[serde(default)]
+    pub time_in_force: Option<ETimeInForce>,
+    #[serde(default, rename = "type")]
+    pub order_type: Option<EOrderType>,
+    #[serde(default)]
+    pub side: Option<ESide>,
+    /// -1 while the order is not on the book yet, e.g. a pending order of an OTO.
+    #[serde(default)]
+    pub working_time: Option<i64>,
+    #[serde(default)]
+    pub self_trade_prevention_mode: Option<ESelfTradePreventionMode>,
+    #[serde(default)]
+    pub fills: Vec<Fill>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Fill {
+    pub price: Decimal,
+    pub qty: Decimal,
+    pub commission: Decimal,
+    pub commission_asset: String,
+    pub trade_id: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Order {
+    pub symbol: String,
+    pub order_id: u64,
+    pub order_list_id: i64,
+    pub client_order_id: String,
+    pub price: Decimal,
+    pub orig_qty: Decimal,
+    pub executed_qty: Decimal,
+    pub cummulative_quote_qty: Decimal,
+    pub status: OrderStatus,
+    pub time_in_force: ETimeInForce,
+    #[serde(rename = "type")]
+    pub order_type: EOrderType,
+    pub side: ESide,
+    #[serde(default)]
+    pub stop_price: Option<Decimal>,
+    #[serde(default)]
+    pub iceberg_qty: Option<Decimal>,
+    pub time: u64,
+    pub update_time: u64,
+    pub is_working: bool,
+    #[serde(default)]
+    pub working_time: Option<i64>,
+    pub orig_quote_order_qty: Decimal,
+    #[serde(default)]
+    pub self_trade_prevention_mode: Option<ESelfTradePreventionMode>,
+}
+
+impl From<Order> for NewOrderResponse {
+    fn from(order: Order) -> Self {
+        NewOrderResponse {
+            symbol: order.symbol,
+            order_id: order.order_id,
+            order_list_id: order.order_list_id,
+            client_order_id: order.client_order_id,
+            transact_time: order.time,
+            price: Some(order.price),
+            orig_qty: Some(order.orig_qty),
+            executed_qty: Some(order.executed_qty),
+            cummulative_quote_qty: Some(order.cummulative_quote_qty),
+            status: Some(order.status),
+            time_in_force: Some(order.time_in_force),
+            order_type: Some(order.order_type),
+            side: Some(order.side),
+            working_time: order.working_time,
+            self_trade_prevention_mode: order.self_trade_prevention_mode,
+            fills: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelOrderResponse {
+    pub symbol: String,
+    pub orig_client_order_id: String,
+    pub order_id: u64,
+    pub order_list_id: i64,
+    pub client_order_id: String,
+    #[serde(default)]
+    pub transact_time: Option<u64>,
+    pub price: Decimal,
+    pub orig_qty: Decimal,
+    pub executed_qty: Decimal,
+    pub cummulative_quote_qty: Decimal,
+    pub status: OrderStatus,
+    pub time_in_force: ETimeInForce,
+    #[serde(rename = "type")]
+    pub order_type: EOrderType,
+    pub side: ESide,
+    #[serde(default)]
+    pub stop_price: Option<Decimal>,
+    #[serde(default)]
+    pub self_trade_prevention_mode: Option<ESelfTradePreventionMode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ContingencyType {
+    Oco,
+    Oto,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ListStatusType {
+    Response,
+    ExecStarted,
+    /// An order of the list was amended with `amend_order_keep_priority`.
+    Updated,
+    AllDone,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ListOrderStatus {
+    Executing,
+    AllDone,
+    Reject,
+}
+
+/// An order of an order list, as listed in the list's `orders`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderListOrder {
+    pub symbol: String,
+    pub order_id: u64,
+    pub client_order_id: String,
+}
+
+/// Answer to placing an OCO, OTO or OTOCO. `order_reports` have the RESULT fields unless
+/// `newOrderRespType` ACK was asked for.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderListResponse {
+    pub order_list_id: i64,
+    pub contingency_type: ContingencyType,
+    pub list_status_type: ListStatusType,
+    pub list_order_status: ListOrderStatus,
+    pub list_client_order_id: String,
+    pub transaction_time: u64,
+    pub symbol: String,
+    pub orders: Vec<OrderListOrder>,
+    #[serde(default)]
+    pub order_reports: Vec<NewOrderResponse>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderList {
+    pub order_list_id: i64,
+    pub contingency_type: ContingencyType,
+    pub list_status_type: ListStatusType,
+    pub list_order_status: ListOrderStatus,
+    pub list_client_order_id: String,
+    pub transaction_time: u64,
+    pub symbol: String,
+    pub orders: Vec<OrderListOrder>,
+}
+
+impl From<OrderList> for OrderListResponse {
+    fn from(list: OrderList) -> Self {
+        OrderListResponse {
+            order_list_id: list.order_list_id,
+            contingency_type: list.contingency_type,
+            list_status_type: list.list_status_type,
+            list_order_status: list.list_order_status,
+            list_client_order_id: list.list_client_order_id,
+            transaction_time: list.transaction_time,
+            symbol: list.symbol,
+            orders: list.orders,
+            order_reports: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelOrderListResponse {
+    pub order_list_id: i64,
+    pub contingency_type: ContingencyType,
+    pub list_status_type: ListStatusType,
+    pub list_order_status: ListOrderStatus,
+    pub list_client_order_id: String,
+    pub transaction_time: u64,
+    pub symbol: String,
+    pub orders: Vec<OrderListOrder>,
+    pub order_reports: Vec<CancelOrderResponse>,
+}
+
+/// An entry of `cancel_all_open_orders`: orders of an order list are reported together.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum CanceledOrder {
+    OrderList(CancelOrderListResponse),
+    Order(CancelOrderResponse),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CancelReplaceResult {
+    Success,
+    Failure,
+    NotAttempted,
+}
+
+/// Error Binance reports for one half of a cancel-replace.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct OrderError {
+    pub code: i32,
+    pub msg: String,
+}
+
+impl OrderError {
+    pub fn error_code(&self) -> ErrorCode {
+        ErrorCode::from(self.code)
+    }
+}
+
+/// One half of a cancel-replace: its response, or why it failed.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum CancelReplaceOutcome<T> {
+    Ok(T),
+    Err(OrderError),
+}
+
+/// Answer to `cancel_replace_order`, also when the cancel, the new order or both failed.
+/// A half that was not attempted has no response.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelReplaceResponse {
+    pub cancel_result: CancelReplaceResult,
+    pub new_order_result: CancelReplaceResult,
+    #[serde(default)]
+    pub cancel_response: Option<CancelReplaceOutcome<CancelOrderResponse>>,
+    #[serde(default)]
+    pub new_order_response: Option<CancelReplaceOutcome<NewOrderResponse>>,
+}
+
+impl CancelReplaceResponse {
+    /// Whether the order was canceled and the new one placed.
+    pub fn is_success(&self) -> bool {
+        self.cancel_result == CancelReplaceResult::Success && self.new_order_result == CancelReplaceResult::Success
+    }
+
+    pub fn canceled(&self) -> Option<&CancelOrderResponse> {
+        match &self.cancel_response {
+            Some(CancelReplaceOutcome::Ok(canceled)) => Some(canceled),
+            _ => None,
+        }
+    }
+
+    pub fn new_order(&self) -> Option<&NewOrderResponse> {
+        match &self.new_order_response {
+            Some(CancelReplaceOutcome::Ok(new_order)) => Some(new_order),
+            _ => None,
+        }
+    }
+}
+
+/// The order as it is after `amend_order_keep_priority`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmendedOrder {
+    pub symbol: String,
+    pub order_id: u64,
+    pub order_list_id: i64,
+    pub orig_client_order_id: String,
+    pub client_order_id: String,
+    pub price: Decimal,
+    pub qty: Decimal,
+    pub executed_qty: Decimal,
+    #[serde(default)]
+    pub prevented_qty: Option<Decimal>,
+    #[serde(default)]
+    pub quote_order_qty: Option<Decimal>,
+    pub cumulative_quote_qty: Decimal,
+    pub status: OrderStatus,
+    pub time_in_force: ETimeInForce,
+    #[serde(rename = "type")]
+    pub order_type: EOrderType,
+    pub side: ESide,
+    #[serde(default)]
+    pub working_time: Option<i64>,
+    #[serde(default)]
+    pub self_trade_prevention_mode: Option<ESelfTradePreventionMode>,
+}
+
+/// The order list an amended order belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmendedOrderList {
+    pub order_list_id: i64,
+    pub contingency_type: ContingencyType,
+    pub list_order_status: ListOrderStatus,
+    pub list_client_order_id: String,
+    pub symbol: String,
+    pub orders: Vec<OrderListOrder>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmendOrderResponse {
+    pub transact_time: u64,
+    pub execution_id: u64,
+    pub amended_order: AmendedOrder,
+    #[serde(default)]
+    pub list_status: Option<AmendedOrderList>,
+}
@@ -0,0 +1,1305 @@
+use std::{cmp::Ordering, fmt};
+
+use hyper::{body::Bytes, Method};
+use log::warn;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::error::{Error, ErrorCode, Result};
+use crate::Decimal;
+use crate::http::client::{Client, RequestParam};
+use crate::http::market::market::ticker_price;
+use crate::http::retry::{is_retryable, outcome_unknown, send_reconciled};
+use crate::http::time_sync::local_timestamp;
+use crate::http::trade::model::{
+    AmendOrderResponse, CancelOrderListResponse, CancelOrderResponse, CancelReplaceOutcome, CancelReplaceResponse, CancelReplaceResult,
+    CanceledOrder, NewOrderResponse, Order, OrderList, OrderListResponse, OrderStatus,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum ESide {
+    BUY,
+    SELL,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[allow(non_camel_case_types)]
+pub enum EOrderType {
+    LIMIT,
+    MARKET,
+    STOP_LOSS,
+    STOP_LOSS_LIMIT,
+    TAKE_PROFIT,
+    TAKE_PROFIT_LIMIT,
+    LIMIT_MAKER,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum ETimeInForce {
+    GTC,
+    IOC,
+    FOK,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum ENewOrderRespType {
+    ACK,
+    RESULT,
+    FULL,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[allow(non_camel_case_types)]
+pub enum ESelfTradePreventionMode {
+    NONE,
+    EXPIRE_TAKER,
+    EXPIRE_MAKER,
+    EXPIRE_BOTH,
+    DECREMENT,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[allow(non_camel_case_types)]
+pub enum ECancelRestrictions {
+    ONLY_NEW,
+    ONLY_PARTIALLY_FILLED,
+}
+
+/// Whether a cancel-replace places the new order when the cancel fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[allow(non_camel_case_types)]
+pub enum ECancelReplaceMode {
+    STOP_ON_FAILURE,
+    ALLOW_FAILURE,
+}
+
+/// What a cancel-replace does when the unfilled order count is exceeded: nothing, or only the cancel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[allow(non_camel_case_types)]
+pub enum EOrderRateLimitExceededMode {
+    DO_NOTHING,
+    CANCEL_ONLY,
+}
+
+/// Identifies an existing order, by exchange id or by the client order id it was placed with.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EOrderRef {
+    OrderId(u64),
+    ClientOrderId(String),
+}
+
+/// Identifies an existing order list, by exchange id or by its list client order id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EOrderListRef {
+    OrderListId(i64),
+    ListClientOrderId(String),
+}
+
+const URL_ORDER: &str = "/api/v3/order";
+const URL_ORDER_TEST: &str = "/api/v3/order/test";
+const URL_ORDER_CANCEL_REPLACE: &str = "/api/v3/order/cancelReplace";
+const URL_ORDER_AMEND_KEEP_PRIORITY: &str = "/api/v3/order/amend/keepPriority";
+const URL_OPEN_ORDERS: &str = "/api/v3/openOrders";
+const URL_ALL_ORDERS: &str = "/api/v3/allOrders";
+const URL_ORDER_LIST: &str = "/api/v3/orderList";
+const URL_ORDER_LIST_OCO: &str = "/api/v3/orderList/oco";
+const URL_ORDER_LIST_OTO: &str = "/api/v3/orderList/oto";
+const URL_ORDER_LIST_OTOCO: &str = "/api/v3/orderList/otoco";
+
+impl fmt::Display for ESide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Display for EOrderType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Display for ETimeInForce {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Display for ENewOrderRespType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Display for ESelfTradePreventionMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Display for ECancelRestrictions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Display for ECancelReplaceMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl fmt::Display for EOrderRateLimitExceededMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl EOrderRef {
+    // `orderId` or `origClientOrderId`, as cancel and query expect them
+    pub(crate) fn param(&self) -> RequestParam {
+        match self {
+            EOrderRef::OrderId(order_id) => RequestParam{key: String::from("orderId"), value: order_id.to_string()},
+            EOrderRef::ClientOrderId(client_order_id) => RequestParam{key: String::from("origClientOrderId"), value: client_order_id.clone()},
+        }
+    }
+}
+
+/// Parameters of `POST /api/v3/order`.
+///
+/// The constructors set what each order type requires, e.g. `NewOrder::limit` defaults to GTC;
+/// everything else is optional. Missing or superfluous parameters for the order type are
+/// rejected with `Error::InvalidParameter` before anything is sent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewOrder {
+    pub(crate) symbol: String,
+    pub(crate) side: ESide,
+    pub(crate) order_type: EOrderType,
+    pub(crate) time_in_force: Option<ETimeInForce>,
+    pub(crate) quantity: Option<Decimal>,
+    pub(crate) quote_order_qty: Option<Decimal>,
+    pub(crate) price: Option<Decimal>,
+    pub(crate) new_client_order_id: Option<String>,
+    pub(crate) strategy_id: Option<u64>,
+    pub(crate) strategy_type: Option<u32>,
+    pub(crate) stop_price: Option<Decimal>,
+    pub(crate) trailing_delta: Option<u32>,
+    pub(crate) iceberg_qty: Option<Decimal>,
+    pub(crate) new_order_resp_type: Option<ENewOrderRespType>,
+    pub(crate) self_trade_prevention_mode: Option<ESelfTradePreventionMode>,
+}
+
+impl NewOrder {
+    pub fn new(symbol: impl Into<String>, side: ESide, order_type: EOrderType) -> Self {
+        NewOrder {
+            symbol: symbol.into(),
+            side,
+            order_type,
+            time_in_force: None,
+            quantity: None,
+            quote_order_qty: None,
+            price: None,
+            new_client_order_id: None,
+            strategy_id: None,
+            strategy_type: None,
+            stop_price: None,
+            trailing_delta: None,
+            iceberg_qty: None,
+            new_order_resp_type: None,
+            self_trade_prevention_mode: None,
+        }
+    }
+
+    pub fn limit(symbol: impl Into<String>, side: ESide, quantity: Decimal, price: Decimal) -> Self {
+        NewOrder::new(symbol, side, EOrderType::LIMIT)
+            .quantity(quantity)
+            .price(price)
+            .time_in_force(ETimeInForce::GTC)
+    }
+
+    pub fn market(symbol: impl Into<String>, side: ESide, quantity: Decimal) -> Self {
+        NewOrder::new(symbol, side, EOrderType::MARKET).quantity(quantity)
+    }
+
+    /// Market order spending (BUY) or receiving (SELL) `quote_order_qty` of the quote asset.
+    pub fn market_quote(symbol: impl Into<String>, side: ESide, quote_order_qty: Decimal) -> Self {
+        NewOrder::new(symbol, side, EOrderType::MARKET).quote_order_qty(quote_order_qty)
+    }
+
+    pub fn limit_maker(symbol: impl Into<String>, side: ESide, quantity: Decimal, price: Decimal) -> Self {
+        NewOrder::new(symbol, side, EOrderType::LIMIT_MAKER).quantity(quantity).price(price)
+    }
+
+    pub fn stop_loss(symbol: impl Into<String>, side: ESide, quantity: Decimal, stop_price: Decimal) -> Self {
+        NewOrder::new(symbol, side, EOrderType::STOP_LOSS).quantity(quantity).stop_price(stop_price)
+    }
+
+    pub fn stop_loss_limit(symbol: impl Into<String>, side: ESide, quantity: Decimal, price: Decimal, stop_price: Decimal) -> Self {
+        NewOrder::new(symbol, side, EOrderType::STOP_LOSS_LIMIT)
+            .quantity(quantity)
+            .price(price)
+            .stop_price(stop_price)
+            .time_in_force(ETimeInForce::GTC)
+    }
+
+    pub fn take_profit(symbol: impl Into<String>, side: ESide, quantity: Decimal, stop_price: Decimal) -> Self {
+        NewOrder::new(symbol, side, EOrderType::TAKE_PROFIT).quantity(quantity).stop_price(stop_price)
+    }
+
+    pub fn take_profit_limit(symbol: impl Into<String>, side: ESide, quantity: Decimal, price: Decimal, stop_price: Decimal) -> Self {
+        NewOrder::new(symbol, side, EOrderType::TAKE_PROFIT_LIMIT)
+            .quantity(quantity)
+            .price(price)
+            .stop_price(stop_price)
+            .time_in_force(ETimeInForce::GTC)
+    }
+
+    pub fn time_in_force(mut self, time_in_force: ETimeInForce) -> Self {
+        self.time_in_force = Some(time_in_force);
+        self
+    }
+
+    pub fn quantity(mut self, quantity: Decimal) -> Self {
+        self.quantity = Some(quantity);
+        self
+    }
+
+    pub fn quote_order_qty(mut self, quote_order_qty: Decimal) -> Self {
+        self.quote_order_qty = Some(quote_order_qty);
+        self
+    }
+
+    pub fn price(mut self, price: Decimal) -> Self {
+        self.price = Some(price);
+        self
+    }
+
+    /// Generated by `new_order` when not set, so a failed request can be looked up.
+    pub fn new_client_order_id(mut self, new_client_order_id: impl Into<String>) -> Self {
+        self.new_client_order_id = Some(new_client_order_id.into());
+        self
+    }
+
+    pub fn strategy_id(mut self, strategy_id: u64) -> Self {
+        self.strategy_id = Some(strategy_id);
+        self
+    }
+
+    /// Must be at least 1000000.
+    pub fn strategy_type(mut self, strategy_type: u32) -> Self {
+        self.strategy_type = Some(strategy_type);
+        self
+    }
+
+    pub fn stop_price(mut self, stop_price: Decimal) -> Self {
+        self.stop_price = Some(stop_price);
+        self
+    }
+
+    /// Trailing stop in basis points, for the stop loss and take profit types.
+    pub fn trailing_delta(mut self, trailing_delta: u32) -> Self {
+        self.trailing_delta = Some(trailing_delta);
+        self
+    }
+
+    pub fn iceberg_qty(mut self, iceberg_qty: Decimal) -> Self {
+        self.iceberg_qty = Some(iceberg_qty);
+        self
+    }
+
+    pub fn new_order_resp_type(mut self, new_order_resp_type: ENewOrderRespType) -> Self {
+        self.new_order_resp_type = Some(new_order_resp_type);
+        self
+    }
+
+    pub fn self_trade_prevention_mode(mut self, self_trade_prevention_mode: ESelfTradePreventionMode) -> Self {
+        self.self_trade_prevention_mode = Some(self_trade_prevention_mode);
+        self
+    }
+
+    pub fn get_symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn get_side(&self) -> ESide {
+        self.side
+    }
+
+    pub fn get_order_type(&self) -> EOrderType {
+        self.order_type
+    }
+
+    pub fn get_quantity(&self) -> Option<Decimal> {
+        self.quantity
+    }
+
+    pub fn get_quote_order_qty(&self) -> Option<Decimal> {
+        self.quote_order_qty
+    }
+
+    pub fn get_price(&self) -> Option<Decimal> {
+        self.price
+    }
+
+    pub fn get_stop_price(&self) -> Option<Decimal> {
+        self.stop_price
+    }
+
+    pub fn get_iceberg_qty(&self) -> Option<Decimal> {
+        self.iceberg_qty
+    }
+
+    pub fn get_new_client_order_id(&self) -> Option<&str> {
+        self.new_client_order_id.as_deref()
+    }
+
+    /// Checks the parameters against what Binance requires for the order type.
+    pub fn validate(&self) -> Result<()> {
+        let (time_in_force, price, stop) = match self.order_type {
+            EOrderType::LIMIT => (true, true, false),
+            EOrderType::MARKET => (false, false, false),
+            EOrderType::STOP_LOSS | EOrderType::TAKE_PROFIT => (false, false, true),
+            EOrderType::STOP_LOSS_LIMIT | EOrderType::TAKE_PROFIT_LIMIT => (true, true, true),
+            EOrderType::LIMIT_MAKER => (false, true, false),
+        };
+
+        let invalid = |msg: &str| Err(Error::InvalidParameter(format!("{} order {}", self.order_type, msg)));
+        if self.order_type == EOrderType::MARKET {
+            if self.quantity.is_some() == self.quote_order_qty.is_some() {
+                return invalid("needs either quantity or quoteOrderQty");
+            }
+        } else {
+            if self.quantity.is_none() {
+                return invalid("needs quantity");
+            }
+            if self.quote_order_qty.is_some() {
+                return invalid("does not take quoteOrderQty");
+            }
+        }
+        if time_in_force != self.time_in_force.is_some() {
+            return invalid(if time_in_force { "needs timeInForce" } else { "does not take timeInForce" });
+        }
+        if price != self.price.is_some() {
+            return invalid(if price { "needs price" } else { "does not take price" });
+        }
+        if stop && self.stop_price.is_none() && self.trailing_delta.is_none() {
+            return invalid("needs stopPrice or trailingDelta");
+        }
+        if !stop && (self.stop_price.is_some() || self.trailing_delta.is_some()) {
+            return invalid("does not take stopPrice or trailingDelta");
+        }
+        if self.iceberg_qty.is_some() && !price {
+            return invalid("does not take icebergQty");
+        }
+        Ok(())
+    }
+
+    pub(crate) fn params(&self) -> Result<Vec<RequestParam>> {
+        self.validate()?;
+
+        let mut param = vec![
+            RequestParam{key: String::from("symbol"), value: self.symbol.clone()},
+            RequestParam{key: String::from("side"), value: self.side.to_string()},
+            RequestParam{key: String::from("type"), value: self.order_type.to_string()},
+        ];
+
+        if let Some(time_in_force) = self.time_in_force {
+            param.push(RequestParam{key: String::from("timeInForce"), value: time_in_force.to_string()});
+        }
+        if let Some(quantity) = self.quantity {
+            param.push(RequestParam{key: String::from("quantity"), value: quantity.to_string()});
+        }
+        if let Some(quote_order_qty) = self.quote_order_qty {
+            param.push(RequestParam{key: String::from("quoteOrderQty"), value: quote_order_qty.to_string()});
+        }
+        if let Some(price) = self.price {
+            param.push(RequestParam{key: String::from("price"), value: price.to_string()});
+        }
+        if let Some(new_client_order_id) = &self.new_client_order_id {
+            param.push(RequestParam{key: String::from("newClientOrderId"), value: new_client_order_id.clone()});
+        }
+        if let Some(strategy_id) = self.strategy_id {
+            param.push(RequestParam{key: String::from("strategyId"), value: strategy_id.to_string()});
+        }
+        if let Some(strategy_type) = self.strategy_type {
+            param.push(RequestParam{key: String::from("strategyType"), value: strategy_type.to_string()});
+        }
+        if let Some(stop_price) = self.stop_price {
+            param.push(RequestParam{key: String::from("stopPrice"), value: stop_price.to_string()});
+        }
+        if let Some(trailing_delta) = self.trailing_delta {
+            param.push(RequestParam{key: String::from("trailingDelta"), value: trailing_delta.to_string()});
+        }
+        if let Some(iceberg_qty) = self.iceberg_qty {
+            param.push(RequestParam{key: String::from("icebergQty"), value: iceberg_qty.to_string()});
+        }
+        if let Some(new_order_resp_type) = self.new_order_resp_type {
+            param.push(RequestParam{key: String::from("newOrderRespType"), value: new_order_resp_type.to_string()});
+        }
+        if let Some(self_trade_prevention_mode) = self.self_trade_prevention_mode {
+            param.push(RequestParam{key: String::from("selfTradePreventionMode"), value: self_trade_prevention_mode.to_string()});
+        }
+
+        Ok(param)
+    }
+}
+
+impl EOrderListRef {
+    // cancel takes `listClientOrderId`, query `origClientOrderId`
+    fn param(&self, client_order_id_key: &str) -> RequestParam {
+        match self {
+            EOrderListRef::OrderListId(order_list_id) => RequestParam{key: String::from("orderListId"), value: order_list_id.to_string()},
+            EOrderListRef::ListClientOrderId(list_client_order_id) => RequestParam{key: String::from(client_order_id_key), value: list_client_order_id.clone()},
+        }
+    }
+}
+
+/// One order of an order list, without the side and quantity the list gives it.
+///
+/// The constructors mirror `NewOrder`'s; the same per type checks apply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderListLeg {
+    order_type: EOrderType,
+    price: Option<Decimal>,
+    stop_price: Option<Decimal>,
+    trailing_delta: Option<u32>,
+    time_in_force: Option<ETimeInForce>,
+    client_order_id: Option<String>,
+    iceberg_qty: Option<Decimal>,
+    strategy_id: Option<u64>,
+    strategy_type: Option<u32>,
+}
+
+impl OrderListLeg {
+    pub fn new(order_type: EOrderType) -> Self {
+        OrderListLeg {
+            order_type,
+            price: None,
+            stop_price: None,
+            trailing_delta: None,
+            time_in_force: None,
+            client_order_id: None,
+            iceberg_qty: None,
+            strategy_id: None,
+            strategy_type: None,
+        }
+    }
+
+    pub fn limit(price: Decimal) -> Self {
+        OrderListLeg::new(EOrderType::LIMIT).price(price).time_in_force(ETimeInForce::GTC)
+    }
+
+    pub fn limit_maker(price: Decimal) -> Self {
+        OrderListLeg::new(EOrderType::LIMIT_MAKER).price(price)
+    }
+
+    pub fn market() -> Self {
+        OrderListLeg::new(EOrderType::MARKET)
+    }
+
+    pub fn stop_loss(stop_price: Decimal) -> Self {
+        OrderListLeg::new(EOrderType::STOP_LOSS).stop_price(stop_price)
+    }
+
+    pub fn stop_loss_limit(price: Decimal, stop_price: Decimal) -> Self {
+        OrderListLeg::new(EOrderType::STOP_LOSS_LIMIT).price(price).stop_price(stop_price).time_in_force(ETimeInForce::GTC)
+    }
+
+    pub fn take_profit(stop_price: Decimal) -> Self {
+        OrderListLeg::new(EOrderType::TAKE_PROFIT).stop_price(stop_price)
+    }
+
+    pub fn take_profit_limit(price: Decimal, stop_price: Decimal) -> Self {
+        OrderListLeg::new(EOrderType::TAKE_PROFIT_LIMIT).price(price).stop_price(stop_price).time_in_force(ETimeInForce::GTC)
+    }
+
+    pub fn price(mut self, price: Decimal) -> Self {
+        self.price = Some(price);
+        self
+    }
+
+    pub fn stop_price(mut self, stop_price: Decimal) -> Self {
+        self.stop_price = Some(stop_price);
+        self
+    }
+
+    pub fn trailing_delta(mut self, trailing_delta: u32) -> Self {
+        self.trailing_delta = Some(trailing_delta);
+        self
+    }
+
+    pub fn time_in_force(mut self, time_in_force: ETimeInForce) -> Self {
+        self.time_in_force = Some(time_in_force);
+        self
+    }
+
+    pub fn client_order_id(mut self, client_order_id: impl Into<String>) -> Self {
+        self.client_order_id = Some(client_order_id.into());
+        self
+    }
+
+    pub fn iceberg_qty(mut self, iceberg_qty: Decimal) -> Self {
+        self.iceberg_qty = Some(iceberg_qty);
+        self
+    }
+
+    pub fn strategy_id(mut self, strategy_id: u64) -> Self {
+        self.strategy_id = Some(strategy_id);
+        self
+    }
+
+    pub fn strategy_type(mut self, strategy_type: u32) -> Self {
+        self.strategy_type = Some(strategy_type);
+        self
+    }
+
+    fn to_order(&self, symbol: &str, side: ESide, quantity: Decimal) -> NewOrder {
+        NewOrder {
+            time_in_force: self.time_in_force,
+            quantity: Some(quantity),
+            price: self.price,
+            new_client_order_id: self.client_order_id.clone(),
+            strategy_id: self.strategy_id,
+            strategy_type: self.strategy_type,
+            stop_price: self.stop_price,
+            trailing_delta: self.trailing_delta,
+            iceberg_qty: self.iceberg_qty,
+            ..NewOrder::new(symbol, side, self.order_type)
+        }
+    }
+
+    // the price at which the leg starts trading, unknown for LIMIT, MARKET and pure trailing stops
+    fn trigger_price(&self) -> Option<Decimal> {
+        match self.order_type {
+            EOrderType::LIMIT_MAKER => self.price,
+            EOrderType::LIMIT | EOrderType::MARKET => None,
+            _ => self.stop_price,
+        }
+    }
+
+    fn push_params(&self, prefix: &str, param: &mut Vec<RequestParam>) {
+        let mut push = |key: &str, value: String| param.push(RequestParam{key: format!("{}{}", prefix, key), value});
+
+        push("Type", self.order_type.to_string());
+        if let Some(price) = self.price {
+            push("Price", price.to_string());
+        }
+        if let Some(stop_price) = self.stop_price {
+            push("StopPrice", stop_price.to_string());
+        }
+        if let Some(trailing_delta) = self.trailing_delta {
+            push("TrailingDelta", trailing_delta.to_string());
+        }
+        if let Some(time_in_force) = self.time_in_force {
+            push("TimeInForce", time_in_force.to_string());
+        }
+        if let Some(client_order_id) = &self.client_order_id {
+            push("ClientOrderId", client_order_id.clone());
+        }
+        if let Some(iceberg_qty) = self.iceberg_qty {
+            push("IcebergQty", iceberg_qty.to_string());
+        }
+        if let Some(strategy_id) = self.strategy_id {
+            push("StrategyId", strategy_id.to_string());
+        }
+        if let Some(strategy_type) = self.strategy_type {
+            push("StrategyType", strategy_type.to_string());
+        }
+    }
+}
+
+// whether an order of this type waits for the price to rise (Greater) or to fall (Less)
+fn trigger_direction(side: ESide, order_type: EOrderType) -> Option<Ordering> {
+    let rising = match order_type {
+        EOrderType::LIMIT_MAKER | EOrderType::TAKE_PROFIT | EOrderType::TAKE_PROFIT_LIMIT => side == ESide::SELL,
+        EOrderType::STOP_LOSS | EOrderType::STOP_LOSS_LIMIT => side == ESide::BUY,
+        EOrderType::LIMIT | EOrderType::MARKET => {
+            return None;
+        },
+    };
+    Some(if rising { Ordering::Greater } else { Ordering::Less })
+}
+
+// the above leg has to trigger above `reference` and the below leg under it
+fn validate_oco(symbol: &str, side: ESide, quantity: Decimal, above: &OrderListLeg, below: &OrderListLeg, reference: Option<Decimal>) -> Result<()> {
+    above.to_order(symbol, side, quantity).validate()?;
+    below.to_order(symbol, side, quantity).validate()?;
+
+    let invalid = |msg: String| Err(Error::InvalidParameter(format!("{} OCO {}", side, msg)));
+    if trigger_direction(side, above.order_type) != Some(Ordering::Greater) {
+        return invalid(format!("cannot have a {} above leg", above.order_type));
+    }
+    if trigger_direction(side, below.order_type) != Some(Ordering::Less) {
+        return invalid(format!("cannot have a {} below leg", below.order_type));
+    }
+
+    if let (Some(above), Some(below)) = (above.trigger_price(), below.trigger_price()) {
+        if above <= below {
+            return invalid(format!("above leg price {} must be higher than below leg price {}", above, below));
+        }
+    }
+    if let Some(reference) = reference {
+        if let Some(above) = above.trigger_price().filter(|above| *above <= reference) {
+            return invalid(format!("above leg price {} must be higher than {}", above, reference));
+        }
+        if let Some(below) = below.trigger_price().filter(|below| *below >= reference) {
+            return invalid(format!("below leg price {} must be lower than {}", below, reference));
+        }
+    }
+    Ok(())
+}
+
+fn validate_working(symbol: &str, side: ESide, quantity: Decimal, working: &OrderListLeg) -> Result<Option<Decimal>> {
+    if working.order_type != EOrderType::LIMIT && working.order_type != EOrderType::LIMIT_MAKER {
+        return Err(Error::InvalidParameter(format!("working order must be LIMIT or LIMIT_MAKER, not {}", working.order_type)));
+    }
+    working.to_order(symbol, side, quantity).validate()?;
+    Ok(working.price)
+}
+
+fn push_list_params(
+    param: &mut Vec<RequestParam>,
+    list_client_order_id: &Option<String>,
+    new_order_resp_type: Option<ENewOrderRespType>,
+    self_trade_prevention_mode: Option<ESelfTradePreventionMode>
+) {
+    if let Some(list_client_order_id) = list_client_order_id {
+        param.push(RequestParam{key: String::from("listClientOrderId"), value: list_client_order_id.clone()});
+    }
+    if let Some(new_order_resp_type) = new_order_resp_type {
+        param.push(RequestParam{key: String::from("newOrderRespType"), value: new_order_resp_type.to_string()});
+    }
+    if let Some(self_trade_prevention_mode) = self_trade_prevention_mode {
+        param.push(RequestParam{key: String::from("selfTradePreventionMode"), value: self_trade_prevention_mode.to_string()});
+    }
+}
+
+/// One-cancels-the-other: two orders on the same side, the one that triggers cancels the other.
+///
+/// The above leg must trigger above the below leg: for a SELL, a LIMIT_MAKER or TAKE_PROFIT(_LIMIT)
+/// above and a STOP_LOSS(_LIMIT) below, the other way around for a BUY. The legs are also checked
+/// to be on either side of the market price, as Binance requires; `new_oco_order` fetches it
+/// when `market_price` is not set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OcoOrder {
+    symbol: String,
+    side: ESide,
+    quantity: Decimal,
+    above: OrderListLeg,
+    below: OrderListLeg,
+    market_price: Option<Decimal>,
+    list_client_order_id: Option<String>,
+    new_order_resp_type: Option<ENewOrderRespType>,
+    self_trade_prevention_mode: Option<ESelfTradePreventionMode>,
+}
+
+impl OcoOrder {
+    pub fn new(symbol: impl Into<String>, side: ESide, quantity: Decimal, above: OrderListLeg, below: OrderListLeg) -> Self {
+        OcoOrder {
+            symbol: symbol.into(),
+            side,
+            quantity,
+            above,
+            below,
+            market_price: None,
+            list_client_order_id: None,
+            new_order_resp_type: None,
+            self_trade_prevention_mode: None,
+        }
+    }
+
+    /// Current price to check the legs against, it is not sent. `validate` skips that check
+    /// without it.
+    pub fn market_price(mut self, market_price: Decimal) -> Self {
+        self.market_price = Some(market_price);
+        self
+    }
+
+    /// Generated by `new_oco_order` when not set, so a failed request can be looked up.
+    pub fn list_client_order_id(mut self, list_client_order_id: impl Into<String>) -> Self {
+        self.list_client_order_id = Some(list_client_order_id.into());
+        self
+    }
+
+    pub fn new_order_resp_type(mut self, new_order_resp_type: ENewOrderRespType) -> Self {
+        self.new_order_resp_type = Some(new_order_resp_type);
+        self
+    }
+
+    pub fn self_trade_prevention_mode(mut self, self_trade_prevention_mode: ESelfTradePreventionMode) -> Self {
+        self.self_trade_prevention_mode = Some(self_trade_prevention_mode);
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        validate_oco(&self.symbol, self.side, self.quantity, &self.above, &self.below, self.market_price)
+    }
+
+    pub(crate) fn params(&self) -> Result<Vec<RequestParam>> {
+        self.validate()?;
+
+        let mut param = vec![
+            RequestParam{key: String::from("symbol"), value: self.symbol.clone()},
+            RequestParam{key: String::from("side"), value: self.side.to_string()},
+            RequestParam{key: String::from("quantity"), value: self.quantity.to_string()},
+        ];
+        self.above.push_params("above", &mut param);
+        self.below.push_params("below", &mut param);
+        push_list_params(&mut param, &self.list_client_order_id, self.new_order_resp_type, self.self_trade_prevention_mode);
+        Ok(param)
+    }
+}
+
+/// One-triggers-the-other: a LIMIT or LIMIT_MAKER working order, and a pending order that is
+/// placed once the working order is filled.
+///
+/// A pending stop, take profit or LIMIT_MAKER order must not trigger right at the working price,
+/// e.g. the stop loss of a position bought at 100 has to be below 100.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtoOrder {
+    symbol: String,
+    working_side: ESide,
+    working_quantity: Decimal,
+    working: OrderListLeg,
+    pending_side: ESide,
+    pending_quantity: Decimal,
+    pending: OrderListLeg,
+    list_client_order_id: Option<String>,
+    new_order_resp_type: Option<ENewOrderRespType>,
+    self_trade_prevention_mode: Option<ESelfTradePreventionMode>,
+}
+
+impl OtoOrder {
+    pub fn new(
+        symbol: impl Into<String>,
+        working_side: ESide,
+        working_quantity: Decimal,
+        working: OrderListLeg,
+        pending_side: ESide,
+        pending_quantity: Decimal,
+        pending: OrderListLeg
+    ) -> Self {
+        OtoOrder {
+            symbol: symbol.into(),
+            working_side,
+            working_quantity,
+            working,
+            pending_side,
+            pending_quantity,
+            pending,
+            list_client_order_id: None,
+            new_order_resp_type: None,
+            self_trade_prevention_mode: None,
+        }
+    }
+
+    /// Generated by `new_oto_order` when not set, so a failed request can be looked up.
+    pub fn list_client_order_id(mut self, list_client_order_id: impl Into<String>) -> Self {
+        self.list_client_order_id = Some(list_client_order_id.into());
+        self
+    }
+
+    pub fn new_order_resp_type(mut self, new_order_resp_type: ENewOrderRespType) -> Self {
+        self.new_order_resp_type = Some(new_order_resp_type);
+        self
+    }
+
+    pub fn self_trade_prevention_mode(mut self, self_trade_prevention_mode: ESelfTradePreventionMode) -> Self {
+        self.self_trade_prevention_mode = Some(self_trade_prevention_mode);
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let working_price = validate_working(&self.symbol, self.working_side, self.working_quantity, &self.working)?;
+        self.pending.to_order(&self.symbol, self.pending_side, self.pending_quantity).validate()?;
+
+        let direction = trigger_direction(self.pending_side, self.pending.order_type);
+        if let (Some(direction), Some(trigger), Some(working_price)) = (direction, self.pending.trigger_price(), working_price) {
+            if trigger.cmp(&working_price) != direction {
+                let position = if direction == Ordering::Greater { "higher" } else { "lower" };
+                return Err(Error::InvalidParameter(format!(
+                    "pending {} {} price {} must be {} than the working price {}",
+                    self.pending_side, self.pending.order_type, trigger, position, working_price,
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn params(&self) -> Result<Vec<RequestParam>> {
+        self.validate()?;
+
+        let mut param = vec![
+            RequestParam{key: String::from("symbol"), value: self.symbol.clone()},
+            RequestParam{key: String::from("workingSide"), value: self.working_side.to_string()},
+            RequestParam{key: String::from("workingQuantity"), value: self.working_quantity.to_string()},
+            RequestParam{key: String::from("pendingSide"), value: self.pending_side.to_string()},
+            RequestParam{key: String::from("pendingQuantity"), value: self.pending_quantity.to_string()},
+        ];
+        self.working.push_params("working", &mut param);
+        self.pending.push_params("pending", &mut param);
+        push_list_params(&mut param, &self.list_client_order_id, self.new_order_resp_type, self.self_trade_prevention_mode);
+        Ok(param)
+    }
+}
+
+/// One-triggers-a-one-cancels-the-other: a working order like `OtoOrder`'s, and a pending OCO
+/// placed once it is filled. The pending legs are checked against the working price like an
+/// `OcoOrder` against its market price.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtocoOrder {
+    symbol: String,
+    working_side: ESide,
+    working_quantity: Decimal,
+    working: OrderListLeg,
+    pending_side: ESide,
+    pending_quantity: Decimal,
+    pending_above: OrderListLeg,
+    pending_below: OrderListLeg,
+    list_client_order_id: Option<String>,
+    new_order_resp_type: Option<ENewOrderRespType>,
+    self_trade_prevention_mode: Option<ESelfTradePreventionMode>,
+}
+
+impl OtocoOrder {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        symbol: impl Into<String>,
+        working_side: ESide,
+        working_quantity: Decimal,
+        working: OrderListLeg,
+        pending_side: ESide,
+        pending_quantity: Decimal,
+        pending_above: OrderListLeg,
+        pending_below: OrderListLeg
+    ) -> Self {
+        OtocoOrder {
+            symbol: symbol.into(),
+            working_side,
+            working_quantity,
+            working,
+            pending_side,
+            pending_quantity,
+            pending_above,
+            pending_below,
+            list_client_order_id: None,
+            new_order_resp_type: None,
+            self_trade_prevention_mode: None,
+        }
+    }
+
+    /// Generated by `new_otoco_order` when not set, so a failed request can be looked up.
+    pub fn list_client_order_id(mut self, list_client_order_id: impl Into<String>) -> Self {
+        self.list_client_order_id = Some(list_client_order_id.into());
+        self
+    }
+
+    pub fn new_order_resp_type(mut self, new_order_resp_type: ENewOrderRespType) -> Self {
+        self.new_order_resp_type = Some(new_order_resp_type);
+        self
+    }
+
+    pub fn self_trade_prevention_mode(mut self, self_trade_prevention_mode: ESelfTradePreventionMode) -> Self {
+        self.self_trade_prevention_mode = Some(self_trade_prevention_mode);
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let working_price = validate_working(&self.symbol, self.working_side, self.working_quantity, &self.working)?;
+        validate_oco(&self.symbol, self.pending_side, self.pending_quantity, &self.pending_above, &self.pending_below, working_price)
+    }
+
+    pub(crate) fn params(&self) -> Result<Vec<RequestParam>> {
+        self.validate()?;
+
+        let mut param = vec![
+            RequestParam{key: String::from("symbol"), value: self.symbol.clone()},
+            RequestParam{key: String::from("workingSide"), value: self.working_side.to_string()},
+            RequestParam{key: String::from("workingQuantity"), value: self.working_quantity.to_string()},
+            RequestParam{key: String::from("pendingSide"), value: self.pending_side.to_string()},
+            RequestParam{key: String::from("pendingQuantity"), value: self.pending_quantity.to_string()},
+        ];
+        self.working.push_params("working", &mut param);
+        self.pending_above.push_params("pendingAbove", &mut param);
+        self.pending_below.push_params("pendingBelow", &mut param);
+        push_list_params(&mut param, &self.list_client_order_id, self.new_order_resp_type, self.self_trade_prevention_mode);
+        Ok(param)
+    }
+}
+
+pub(crate) fn generate_client_order_id() -> String {
+    format!("o{}{:08x}", local_timestamp(), rand::thread_rng().gen::<u32>())
+}
+
+/// Places an order.
+///
+/// A request that fails without a definite answer is looked up by its client order id before it
+/// is sent again, so an order is never placed twice. An order found that way is returned as if
+/// placed with `newOrderRespType` RESULT, without `fills`.
+pub async fn new_order(client: &Client, order: &NewOrder) -> Result<NewOrderResponse> {
+    let mut order = order.clone();
+    if order.new_client_order_id.is_none() {
+        order.new_client_order_id = Some(generate_client_order_id());
+    }
+    let param = &order.params()?;
+    let symbol = order.symbol.as_str();
+    let client_order_id = &EOrderRef::ClientOrderId(order.new_client_order_id.clone().unwrap_or_default());
+
+    send_reconciled(
+        client.retry_policy(),
+        || async move {
+            let body = client.send_signed(Method::POST, URL_ORDER, param).await?;
+            Ok(serde_json::from_slice(&body)?)
+        },
+        || async move {
+            match query_order(client, symbol, client_order_id).await {
+                Ok(order) => Ok(Some(NewOrderResponse::from(order))),
+                Err(err) if err.code() == Some(ErrorCode::NoSuchOrder) => Ok(None),
+                Err(err) => Err(err),
+            }
+        },
+    ).await
+}
+
+/// Validates an order with the matching engine without placing it.
+pub async fn test_order(client: &Client, order: &NewOrder) -> Result<()> {
+    test_order_raw(client, order).await?;
+    Ok(())
+}
+
+pub async fn test_order_raw(client: &Client, order: &NewOrder) -> Result<Bytes> {
+    let param = order.params()?;
+    client.send_signed(Method::POST, URL_ORDER_TEST, &param).await
+}
+
+pub async fn cancel_order(
+    client: &Client,
+    symbol: &str,
+    order: &EOrderRef,
+    new_client_order_id: &Option<&str>,
+    cancel_restrictions: Option<ECancelRestrictions>
+) -> Result<CancelOrderResponse> {
+    let body = cancel_order_raw(client, symbol, order, new_client_order_id, cancel_restrictions).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn cancel_order_raw(
+    client: &Client,
+    symbol: &str,
+    order: &EOrderRef,
+    new_client_order_id: &Option<&str>,
+    cancel_restrictions: Option<ECancelRestrictions>
+) -> Result<Bytes> {
+    let mut param = vec![
+        RequestParam{key: String::from("symbol"), value: String::from(symbol)},
+        order.param(),
+    ];
+
+    if let Some(new_client_order_id) = new_client_order_id {
+        param.push(RequestParam{key: String::from("newClientOrderId"), value: String::from(*new_client_order_id)});
+    }
+    if let Some(cancel_restrictions) = cancel_restrictions {
+        param.push(RequestParam{key: String::from("cancelRestrictions"), value: cancel_restrictions.to_string()});
+    }
+
+    client.send_signed(Method::DELETE, URL_ORDER, &param).await
+}
+
+/// Cancels every open order on `symbol`, including order lists.
+pub async fn cancel_all_open_orders(client: &Client, symbol: &str) -> Result<Vec<CanceledOrder>> {
+    let body = cancel_all_open_orders_raw(client, symbol).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn cancel_all_open_orders_raw(client: &Client, symbol: &str) -> Result<Bytes> {
+    let param = vec![
+        RequestParam{key: String::from("symbol"), value: String::from(symbol)},
+    ];
+
+    client.send_signed(Method::DELETE, URL_OPEN_ORDERS, &param).await
+}
+
+pub async fn query_order(client: &Client, symbol: &str, order: &EOrderRef) -> Result<Order> {
+    let body = query_order_raw(client, symbol, order).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn query_order_raw(client: &Client, symbol: &str, order: &EOrderRef) -> Result<Bytes> {
+    let param = vec![
+        RequestParam{key: String::from("symbol"), value: String::from(symbol)},
+        order.param(),
+    ];
+
+    client.send_signed(Method::GET, URL_ORDER, &param).await
+}
+
+/// Open orders on `symbol`, or on every symbol at a much higher request weight.
+pub async fn open_orders(client: &Client, symbol: &Option<&str>) -> Result<Vec<Order>> {
+    let body = open_orders_raw(client, symbol).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn open_orders_raw(client: &Client, symbol: &Option<&str>) -> Result<Bytes> {
+    let mut param = vec![];
+
+    if let Some(symbol) = symbol {
+        param.push(RequestParam{key: String::from("symbol"), value: String::from(*symbol)});
+    }
+
+    client.send_signed(Method::GET, URL_OPEN_ORDERS, &param).await
+}
+
+/// Orders of any status on `symbol`. With `order_id`, orders from that id on, otherwise the most
+/// recent ones; `limit` defaults to 500, at most 1000.
+pub async fn all_orders(
+    client: &Client,
+    symbol: &str,
+    order_id: Option<u64>,
+    start_time: Option<u64>,
+    end_time: Option<u64>,
+    limit: Option<u32>
+) -> Result<Vec<Order>> {
+    let body = all_orders_raw(client, symbol, order_id, start_time, end_time, limit).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn all_orders_raw(
+    client: &Client,
+    symbol: &str,
+    order_id: Option<u64>,
+    start_time: Option<u64>,
+    end_time: Option<u64>,
+    limit: Option<u32>
+) -> Result<Bytes> {
+    let mut param = vec![
+        RequestParam{key: String::from("symbol"), value: String::from(symbol)},
+    ];
+
+    if let Some(order_id) = order_id {
+        param.push(RequestParam{key: String::from("orderId"), value: order_id.to_string()});
+    }
+    if let Some(start_time) = start_time {
+        param.push(RequestParam{key: String::from("startTime"), value: start_time.to_string()});
+    }
+    if let Some(end_time) = end_time {
+        param.push(RequestParam{key: String::from("endTime"), value: end_time.to_string()});
+    }
+    if let Some(limit) = limit {
+        param.push(RequestParam{key: String::from("limit"), value: limit.to_string()});
+    }
+
+    client.send_signed(Method::GET, URL_ALL_ORDERS, &param).await
+}
+
+/// Places an OCO, reconciled like `new_order` through its list client order id. A list found
+/// that way is returned without `order_reports`.
+pub async fn new_oco_order(client: &Client, order: &OcoOrder) -> Result<OrderListResponse> {
+    let mut order = order.clone();
+    if order.market_price.is_none() {
+        // no need to ask for the price of legs that are wrong anyway
+        order.validate()?;
+        let tickers = ticker_price(client, &Some(order.symbol.clone())).await?;
+        order.market_price = tickers.first().map(|ticker| ticker.price);
+    }
+    let list_client_order_id = order.list_client_order_id.get_or_insert_with(generate_client_order_id).clone();
+    send_order_list(client, URL_ORDER_LIST_OCO, &order.params()?, list_client_order_id).await
+}
+
+/// Places an OTO, reconciled like `new_oco_order`.
+pub async fn new_oto_order(client: &Client, order: &OtoOrder) -> Result<OrderListResponse> {
+    let mut order = order.clone();
+    let list_client_order_id = order.list_client_order_id.get_or_insert_with(generate_client_order_id).clone();
+    send_order_list(client, URL_ORDER_LIST_OTO, &order.params()?, list_client_order_id).await
+}
+
+/// Places an OTOCO, reconciled like `new_oco_order`.
+pub async fn new_otoco_order(client: &Client, order: &OtocoOrder) -> Result<OrderListResponse> {
+    let mut order = order.clone();
+    let list_client_order_id = order.list_client_order_id.get_or_insert_with(generate_client_order_id).clone();
+    send_order_list(client, URL_ORDER_LIST_OTOCO, &order.params()?, list_client_order_id).await
+}
+
+async fn send_order_list(client: &Client, path: &str, param: &[RequestParam], list_client_order_id: String) -> Result<OrderListResponse> {
+    let list = &EOrderListRef::ListClientOrderId(list_client_order_id);
+    send_reconciled(
+        client.retry_policy(),
+        || async move {
+            let body = client.send_signed(Method::POST, path, param).await?;
+            Ok(serde_json::from_slice(&body)?)
+        },
+        || async move {
+            match query_order_list(client, list).await {
+                Ok(list) => Ok(Some(OrderListResponse::from(list))),
+                Err(err) if err.code() == Some(ErrorCode::NoSuchOrder) => Ok(None),
+                Err(err) => Err(err),
+            }
+        },
+    ).await
+}
+
+/// Cancels every order of an order list.
+pub async fn cancel_order_list(client: &Client, symbol: &str, list: &EOrderListRef, new_client_order_id: &Option<&str>) -> Result<CancelOrderListResponse> {
+    let body = cancel_order_list_raw(client, symbol, list, new_client_order_id).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn cancel_order_list_raw(client: &Client, symbol: &str, list: &EOrderListRef, new_client_order_id: &Option<&str>) -> Result<Bytes> {
+    let mut param = vec![
+        RequestParam{key: String::from("symbol"), value: String::from(symbol)},
+        list.param("listClientOrderId"),
+    ];
+
+    if let Some(new_client_order_id) = new_client_order_id {
+        param.push(RequestParam{key: String::from("newClientOrderId"), value: String::from(*new_client_order_id)});
+    }
+
+    client.send_signed(Method::DELETE, URL_ORDER_LIST, &param).await
+}
+
+pub async fn query_order_list(client: &Client, list: &EOrderListRef) -> Result<OrderList> {
+    let body = query_order_list_raw(client, list).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn query_order_list_raw(client: &Client, list: &EOrderListRef) -> Result<Bytes> {
+    let param = vec![
+        list.param("origClientOrderId"),
+    ];
+
+    client.send_signed(Method::GET, URL_ORDER_LIST, &param).await
+}
+
+/// Cancels an order and places `order` in one request.
+///
+/// Both outcomes are reported in the response, also when one or both halves failed (-2021 and
+/// -2022). The request is not retried. When its outcome is unknown, the new order is looked up
+/// by its client order id, generated like in `new_order` if not set. If it was placed, the
+/// response is built from the lookup: the new order as with `new_order`, the cancel without a
+/// response. Otherwise the original error is returned, or `Error::OutcomeUnknown` when the new
+/// order was placed but the cancel could not be checked.
+#[allow(clippy::too_many_arguments)]
+pub async fn cancel_replace_order(
+    client: &Client,
+    cancel: &EOrderRef,
+    mode: ECancelReplaceMode,
+    order: &NewOrder,
+    cancel_new_client_order_id: &Option<&str>,
+    cancel_restrictions: Option<ECancelRestrictions>,
+    order_rate_limit_exceeded_mode: Option<EOrderRateLimitExceededMode>
+) -> Result<CancelReplaceResponse> {
+    let mut order = order.clone();
+    let new_client_order_id = order.new_client_order_id.get_or_insert_with(generate_client_order_id).clone();
+    let resp = cancel_replace_order_raw(
+        client, cancel, mode, &order, cancel_new_client_order_id, cancel_restrictions, order_rate_limit_exceeded_mode,
+    ).await;
+    let err = match resp {
+        Ok(body) => {
+            return Ok(serde_json::from_slice(&body)?);
+        },
+        Err(Error::Api(err))
+            if matches!(err.code, ErrorCode::CancelReplacePartiallyFailed | ErrorCode::CancelReplaceFailed) && err.data.is_some() =>
+        {
+            return Ok(serde_json::from_value(err.data.unwrap_or_default())?);
+        },
+        Err(err) => err,
+    };
+    if !is_retryable(&err) || !outcome_unknown(&err) {
+        return Err(err);
+    }
+
+    warn!("cancel-replace failed: {}, looking up new order {}", err, new_client_order_id);
+    let placed = match query_order(client, &order.symbol, &EOrderRef::ClientOrderId(new_client_order_id)).await {
+        Ok(placed) => placed,
+        Err(lookup_err) => {
+            if lookup_err.code() != Some(ErrorCode::NoSuchOrder) {
+                warn!("could not check the outcome of the cancel-replace: {}", lookup_err);
+            }
+            return Err(err);
+        },
+    };
+    // the new order is only placed after a successful cancel, unless failures are allowed
+    let canceled = match mode {
+        ECancelReplaceMode::STOP_ON_FAILURE => true,
+        ECancelReplaceMode::ALLOW_FAILURE => match query_order(client, &order.symbol, cancel).await {
+            Ok(canceled) => canceled.status == OrderStatus::Canceled,
+            Err(lookup_err) => {
+                return Err(Error::OutcomeUnknown(format!(
+                    "new order {} placed, cancel of {:?} unknown: {}", placed.client_order_id, cancel, lookup_err,
+                )));
+            },
+        },
+    };
+    Ok(CancelReplaceResponse {
+        cancel_result: if canceled { CancelReplaceResult::Success } else { CancelReplaceResult::Failure },
+        new_order_result: CancelReplaceResult::Success,
+        cancel_response: None,
+        new_order_response: Some(CancelReplaceOutcome::Ok(NewOrderResponse::from(placed))),
+    })
+}
+
+/// Failed cancel-replaces come back as `Error::Api`, with the outcome of both halves in its `data`.
+#[allow(clippy::too_many_arguments)]
+pub async fn cancel_replace_order_raw(
+    client: &Client,
+    cancel: &EOrderRef,
+    mode: ECancelReplaceMode,
+    order: &NewOrder,
+    cancel_new_client_order_id: &Option<&str>,
+    cancel_restrictions: Option<ECancelRestrictions>,
+    order_rate_limit_exceeded_mode: Option<EOrderRateLimitExceededMode>
+) -> Result<Bytes> {
+    let mut param = order.params()?;
+    param.push(RequestParam{key: String::from("cancelReplaceMode"), value: mode.to_string()});
+    param.push(match cancel {
+        EOrderRef::OrderId(order_id) => RequestParam{key: String::from("cancelOrderId"), value: order_id.to_string()},
+        EOrderRef::ClientOrderId(client_order_id) => RequestParam{key: String::from("cancelOrigClientOrderId"), value: client_order_id.clone()},
+    });
+
+    if let Some(cancel_new_client_order_id) = cancel_new_client_order_id {
+        param.push(RequestParam{key: String::from("cancelNewClientOrderId"), value: String::from(*cancel_new_client_order_id)});
+    }
+    if let Some(cancel_restrictions) = cancel_restrictions {
+        param.push(RequestParam{key: String::from("cancelRestrictions"), value: cancel_restrictions.to_string()});
+    }
+    if let Some(order_rate_limit_exceeded_mode) = order_rate_limit_exceeded_mode {
+        param.push(RequestParam{key: String::from("orderRateLimitExceededMode"), value: order_rate_limit_exceeded_mode.to_string()});
+    }
+
+    client.send_signed(Method::POST, URL_ORDER_CANCEL_REPLACE, &param).await
+}
+
+/// Reduces the quantity of an open order without losing its place in the queue.
+/// `new_qty` has to be below the order's current quantity.
+pub async fn amend_order_keep_priority(
+    client: &Client,
+    symbol: &str,
+    order: &EOrderRef,
+    new_qty: Decimal,
+    new_client_order_id: &Option<&str>
+) -> Result<AmendOrderResponse> {
+    let body = amend_order_keep_priority_raw(client, symbol, order, new_qty, new_client_order_id).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn amend_order_keep_priority_raw(
+    client: &Client,
+    symbol: &str,
+    order: &EOrderRef,
+    new_qty: Decimal,
+    new_client_order_id: &Option<&str>
+) -> Result<Bytes> {
+    if new_qty <= Decimal::ZERO {
+        return Err(Error::InvalidParameter(format!("newQty must be positive, not {}", new_qty)));
+    }
+
+    let mut param = vec![
+        RequestParam{key: String::from("symbol"), value: String::from(symbol)},
+        order.param(),
+        RequestParam{key: String::from("newQty"), value: new_qty.to_string()},
+    ];
+
+    if let Some(new_client_order_id) = new_client_order_id {
+        param.push(RequestParam{key: String::from("newClientOrderId"), value: String::from(*new_client_order_id)});
+    }
+
+    client.send_signed(Method::PUT, URL_ORDER_AMEND_KEEP_PRIORITY, &param).await
+}
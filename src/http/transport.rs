@@ -0,0 +1,103 @@
+use async_trait::async_trait;
+use hyper::{Client as HttpClient, body::Bytes, client::HttpConnector, Body, Request, Response};
+use hyper_proxy::{Proxy, Intercept, ProxyConnector};
+use hyper_tls::HttpsConnector;
+use log::debug;
+
+use std::{fmt, sync::Arc};
+
+use crate::error::{Error, Result};
+
+/// Sends a fully built request and returns the buffered response.
+///
+/// `Client` signs, rate limits and retries requests itself; a transport only moves bytes. The
+/// default is [`HyperTransport`], anything else (an in-memory fake for tests, a middleware
+/// wrapping another transport, a different http stack) can be passed to
+/// `ClientBuilder::transport`. Non-success statuses must be returned as responses, not errors.
+#[async_trait]
+pub trait Transport: fmt::Debug + Send + Sync {
+    async fn send(&self, req: Request<Bytes>) -> Result<Response<Bytes>>;
+}
+
+// lets a caller keep a handle on the transport it gives to the client
+#[async_trait]
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    async fn send(&self, req: Request<Bytes>) -> Result<Response<Bytes>> {
+        (**self).send(req).await
+    }
+}
+
+#[derive(Debug)]
+enum EClient {
+    Client(HttpClient<HttpsConnector<HttpConnector>>),
+    ProxyClient(HttpClient<ProxyConnector<HttpsConnector<HttpConnector>>>),
+}
+
+/// hyper over native-tls, optionally through an http proxy.
+#[derive(Debug)]
+pub struct HyperTransport {
+    http_client: EClient,
+    proxy: Option<ProxyConnector<HttpsConnector<HttpConnector>>>,
+}
+
+impl Default for HyperTransport {
+    fn default() -> Self {
+        HyperTransport::new()
+    }
+}
+
+impl HyperTransport {
+    pub fn new() -> Self {
+        let http_client = HttpClient::builder().build::<_, Body>(HttpsConnector::new());
+        HyperTransport {
+            http_client: EClient::Client(http_client),
+            proxy: None,
+        }
+    }
+
+    pub fn with_proxy(proxy_uri: &str) -> Result<Self> {
+        let proxy_connector = build_proxy_connector(proxy_uri)?;
+        let http_client = HttpClient::builder().build::<_, Body>(proxy_connector.clone());
+        Ok(HyperTransport {
+            http_client: EClient::ProxyClient(http_client),
+            proxy: Some(proxy_connector),
+        })
+    }
+}
+
+#[async_trait]
+impl Transport for HyperTransport {
+    async fn send(&self, req: Request<Bytes>) -> Result<Response<Bytes>> {
+        debug!("req: {} {}", req.method(), req.uri());
+        let mut req = req.map(Body::from);
+        if let Some(proxy) = &self.proxy {
+            if let Some(headers) = proxy.http_headers(req.uri()) {
+                let headers = headers.clone();
+                req.headers_mut().extend(headers);
+            }
+        }
+
+        let resp = match &self.http_client {
+            EClient::Client(client) => client.request(req).await?,
+            EClient::ProxyClient(client) => client.request(req).await?,
+        };
+        let (parts, body) = resp.into_parts();
+        let body = hyper::body::to_bytes(body).await?;
+        Ok(Response::from_parts(parts, body))
+    }
+}
+
+fn build_proxy_connector(proxy_uri: &str) -> Result<ProxyConnector<HttpsConnector<HttpConnector>>> {
+    let proxy_uri = match proxy_uri.parse() {
+        Ok(uri) => uri,
+        Err(err) => {
+            return Err(Error::InvalidParameter(format!("invalid proxy uri {}: {}", proxy_uri, err)));
+        },
+    };
+    let proxy = Proxy::new(Intercept::All, proxy_uri);
+    let connector = HttpsConnector::new();
+    match ProxyConnector::from_proxy(connector, proxy) {
+        Ok(proxy_connector) => Ok(proxy_connector),
+        Err(err) => Err(Error::InvalidParameter(format!("invalid proxy: {}", err))),
+    }
+}
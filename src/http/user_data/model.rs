@@ -0,0 +1,19 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenKey {
+    pub listen_key: String,
+}
+
+/// What the background task of a `ListenKeyHandle` did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ListenKeyEvent {
+    /// The key was kept alive.
+    Renewed,
+    /// The key expired and was replaced: streams opened with `old` have to resubscribe with `new`.
+    Rotated { old: String, new: String },
+    /// Keeping the key alive or replacing it failed, it is tried again after the client's
+    /// retry delay.
+    Failed(String),
+}
@@ -0,0 +1,193 @@
+use std::{sync::Arc, time::Duration};
+
+use hyper::{body::Bytes, Method};
+use log::{debug, warn};
+use tokio::sync::{broadcast, watch, Notify};
+use tokio::task::JoinHandle;
+
+use crate::error::{ErrorCode, Result};
+use crate::http::client::{Client, RequestParam};
+use crate::http::user_data::model::{ListenKey, ListenKeyEvent};
+
+/// Binance closes a listen key that was not kept alive for 60 minutes.
+pub const DEFAULT_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
+const URL_USER_DATA_STREAM: &str = "/api/v3/userDataStream";
+const URL_MARGIN_USER_DATA_STREAM: &str = "/sapi/v1/userDataStream";
+const URL_ISOLATED_MARGIN_USER_DATA_STREAM: &str = "/sapi/v1/userDataStream/isolated";
+
+const EVENT_CAPACITY: usize = 16;
+
+/// The account a listen key streams the events of.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EUserDataStream {
+    Spot,
+    Margin,
+    /// The isolated margin account of a symbol.
+    IsolatedMargin(String),
+}
+
+impl EUserDataStream {
+    fn url(&self) -> &'static str {
+        match self {
+            EUserDataStream::Spot => URL_USER_DATA_STREAM,
+            EUserDataStream::Margin => URL_MARGIN_USER_DATA_STREAM,
+            EUserDataStream::IsolatedMargin(_) => URL_ISOLATED_MARGIN_USER_DATA_STREAM,
+        }
+    }
+
+    fn params(&self, listen_key: Option<&str>) -> Vec<RequestParam> {
+        let mut param = vec![];
+
+        if let EUserDataStream::IsolatedMargin(symbol) = self {
+            param.push(RequestParam{key: String::from("symbol"), value: symbol.clone()});
+        }
+        if let Some(listen_key) = listen_key {
+            param.push(RequestParam{key: String::from("listenKey"), value: String::from(listen_key)});
+        }
+        param
+    }
+}
+
+/// Starts a user data stream, or returns the open key of the account and keeps it alive.
+pub async fn create_listen_key(client: &Client, stream: &EUserDataStream) -> Result<ListenKey> {
+    let body = create_listen_key_raw(client, stream).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn create_listen_key_raw(client: &Client, stream: &EUserDataStream) -> Result<Bytes> {
+    client.send_with_api_key(Method::POST, stream.url(), &stream.params(None)).await
+}
+
+/// Extends the validity of `listen_key` by 60 minutes. Fails with -1125 once it expired.
+pub async fn keepalive_listen_key(client: &Client, stream: &EUserDataStream, listen_key: &str) -> Result<()> {
+    client.send_with_api_key(Method::PUT, stream.url(), &stream.params(Some(listen_key))).await?;
+    Ok(())
+}
+
+pub async fn close_listen_key(client: &Client, stream: &EUserDataStream, listen_key: &str) -> Result<()> {
+    client.send_with_api_key(Method::DELETE, stream.url(), &stream.params(Some(listen_key))).await?;
+    Ok(())
+}
+
+/// A listen key kept alive by a background task.
+///
+/// The task renews the key every `keepalive_interval` and creates a new one when Binance
+/// reports it expired, or when told so through `report_expired`, e.g. after a
+/// `listenKeyExpired` event on the websocket. Dropping the handle stops the task, `close`
+/// also closes the key.
+#[derive(Debug)]
+pub struct ListenKeyHandle {
+    client: Arc<Client>,
+    stream: EUserDataStream,
+    listen_key: watch::Receiver<String>,
+    events: broadcast::Sender<ListenKeyEvent>,
+    expired: Arc<Notify>,
+    task: JoinHandle<()>,
+}
+
+impl ListenKeyHandle {
+    /// Creates the listen key and starts keeping it alive.
+    pub async fn start(client: Arc<Client>, stream: EUserDataStream, keepalive_interval: Duration) -> Result<Self> {
+        let listen_key = create_listen_key(&client, &stream).await?.listen_key;
+        debug!("listen key for {:?} created", stream);
+
+        let (key_tx, key_rx) = watch::channel(listen_key);
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        let expired = Arc::new(Notify::new());
+        let task = tokio::spawn(keep_alive(
+            client.clone(), stream.clone(), keepalive_interval, key_tx, events.clone(), expired.clone(),
+        ));
+
+        Ok(ListenKeyHandle { client, stream, listen_key: key_rx, events, expired, task })
+    }
+
+    /// The current key.
+    pub fn listen_key(&self) -> String {
+        self.listen_key.borrow().clone()
+    }
+
+    /// Follows the current key, it changes on every rotation.
+    pub fn watch(&self) -> watch::Receiver<String> {
+        self.listen_key.clone()
+    }
+
+    /// Events from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<ListenKeyEvent> {
+        self.events.subscribe()
+    }
+
+    /// Replaces the key right away instead of at the next keepalive.
+    pub fn report_expired(&self) {
+        self.expired.notify_one();
+    }
+
+    /// Stops the task and closes the key.
+    pub async fn close(self) -> Result<()> {
+        self.task.abort();
+        close_listen_key(&self.client, &self.stream, &self.listen_key()).await
+    }
+}
+
+impl Drop for ListenKeyHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn keep_alive(
+    client: Arc<Client>,
+    stream: EUserDataStream,
+    keepalive_interval: Duration,
+    key_tx: watch::Sender<String>,
+    events: broadcast::Sender<ListenKeyEvent>,
+    expired: Arc<Notify>,
+) {
+    let mut delay = keepalive_interval;
+    let mut attempt = 0;
+    let mut recreate = false;
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {},
+            _ = expired.notified() => {
+                recreate = true;
+            },
+        }
+
+        let listen_key = key_tx.borrow().clone();
+        let result = if recreate {
+            create_listen_key(&client, &stream).await.map(|key| Some(key.listen_key))
+        } else {
+            match keepalive_listen_key(&client, &stream, &listen_key).await {
+                Err(err) if err.code() == Some(ErrorCode::InvalidListenKey) => {
+                    warn!("listen key for {:?} expired, creating a new one", stream);
+                    recreate = true;
+                    create_listen_key(&client, &stream).await.map(|key| Some(key.listen_key))
+                },
+                result => result.map(|_| None),
+            }
+        };
+
+        // events are dropped when nobody subscribed
+        match result {
+            Ok(Some(new)) if new != listen_key => {
+                debug!("listen key for {:?} rotated", stream);
+                key_tx.send_replace(new.clone());
+                events.send(ListenKeyEvent::Rotated { old: listen_key, new }).ok();
+            },
+            Ok(_) => {
+                events.send(ListenKeyEvent::Renewed).ok();
+            },
+            Err(err) => {
+                delay = client.retry_policy().delay(attempt);
+                attempt += 1;
+                warn!("keeping the listen key for {:?} alive failed: {}, retrying in {:?}", stream, err, delay);
+                events.send(ListenKeyEvent::Failed(err.to_string())).ok();
+                continue;
+            },
+        }
+        recreate = false;
+        attempt = 0;
+        delay = keepalive_interval;
+    }
+}
@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::http::wallet::wallet::EAssetTransferType;
+use crate::Decimal;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoinInfo {
+    pub coin: String,
+    pub name: String,
+    pub deposit_all_enable: bool,
+    pub withdraw_all_enable: bool,
+    pub free: Decimal,
+    pub freeze: Decimal,
+    pub ipoable: Decimal,
+    pub ipoing: Decimal,
+    pub is_legal_money: bool,
+    pub locked: Decimal,
+    pub storage: Decimal,
+    pub trading: bool,
+    pub withdrawing: Decimal,
+    pub network_list: Vec<CoinNetwork>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoinNetwork {
+    pub network: String,
+    pub coin: String,
+    pub name: String,
+    #[serde(default)]
+    pub address_regex: String,
+    #[serde(default)]
+    pub memo_regex: String,
+    pub deposit_enable: bool,
+    #[serde(default)]
+    pub deposit_desc: String,
+    pub withdraw_enable: bool,
+    #[serde(default)]
+    pub withdraw_desc: String,
+    pub is_default: bool,
+    pub min_confirm: u32,
+    pub un_lock_confirm: u32,
+    #[serde(default)]
+    pub reset_address_status: bool,
+    #[serde(default)]
+    pub special_tips: Option<String>,
+    pub withdraw_fee: Decimal,
+    pub withdraw_min: Decimal,
+    pub withdraw_max: Decimal,
+    #[serde(default)]
+    pub withdraw_integer_multiple: Option<Decimal>,
+    #[serde(default)]
+    pub same_address: bool,
+    #[serde(default)]
+    pub estimated_arrival_time: Option<u64>,
+    #[serde(default)]
+    pub busy: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountSnapshotResponse {
+    pub code: i32,
+    pub msg: String,
+    pub snapshot_vos: Vec<AccountSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountSnapshot {
+    pub update_time: u64,
+    #[serde(flatten)]
+    pub data: AccountSnapshotData,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "lowercase")]
+pub enum AccountSnapshotData {
+    Spot(SpotSnapshot),
+    Margin(MarginSnapshot),
+    Futures(FuturesSnapshot),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotSnapshot {
+    pub total_asset_of_btc: Decimal,
+    pub balances: Vec<SnapshotBalance>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SnapshotBalance {
+    pub asset: String,
+    pub free: Decimal,
+    pub locked: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarginSnapshot {
+    pub margin_level: Decimal,
+    pub total_asset_of_btc: Decimal,
+    pub total_liability_of_btc: Decimal,
+    pub total_net_asset_of_btc: Decimal,
+    pub user_assets: Vec<MarginSnapshotAsset>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarginSnapshotAsset {
+    pub asset: String,
+    pub borrowed: Decimal,
+    pub free: Decimal,
+    pub interest: Decimal,
+    pub locked: Decimal,
+    pub net_asset: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FuturesSnapshot {
+    pub assets: Vec<FuturesSnapshotAsset>,
+    pub position: Vec<FuturesSnapshotPosition>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuturesSnapshotAsset {
+    pub asset: String,
+    pub margin_balance: Decimal,
+    pub wallet_balance: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FuturesSnapshotPosition {
+    pub symbol: String,
+    pub entry_price: Decimal,
+    pub mark_price: Decimal,
+    pub position_amt: Decimal,
+    pub un_realized_profit: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(from = "u8")]
+pub enum DepositStatus {
+    Pending,
+    Success,
+    Rejected,
+    CreditedCannotWithdraw,
+    WrongDeposit,
+    WaitingUserConfirm,
+    Other(u8),
+}
+
+impl DepositStatus {
+    pub fn code(&self) -> u8 {
+        match self {
+            DepositStatus::Pending => 0,
+            DepositStatus::Success => 1,
+            DepositStatus::Rejected => 2,
+            DepositStatus::CreditedCannotWithdraw => 6,
+            DepositStatus::WrongDeposit => 7,
+            DepositStatus::WaitingUserConfirm => 8,
+            DepositStatus::Other(code) => *code,
+        }
+    }
+}
+
+impl From<u8> for DepositStatus {
+    fn from(code: u8) -> Self {
+        match code {
+            0 => DepositStatus::Pending,
+            1 => DepositStatus::Success,
+            2 => DepositStatus::Rejected,
+            6 => DepositStatus::CreditedCannotWithdraw,
+            7 => DepositStatus::WrongDeposit,
+            8 => DepositStatus::WaitingUserConfirm,
+            _ => DepositStatus::Other(code),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(from = "u8")]
+pub enum WithdrawStatus {
+    EmailSent,
+    Cancelled,
+    AwaitingApproval,
+    Rejected,
+    Processing,
+    Failure,
+    Completed,
+    Other(u8),
+}
+
+impl WithdrawStatus {
+    pub fn code(&self) -> u8 {
+        match self {
+            WithdrawStatus::EmailSent => 0,
+            WithdrawStatus::Cancelled => 1,
+            WithdrawStatus::AwaitingApproval => 2,
+            WithdrawStatus::Rejected => 3,
+            WithdrawStatus::Processing => 4,
+            WithdrawStatus::Failure => 5,
+            WithdrawStatus::Completed => 6,
+            WithdrawStatus::Other(code) => *code,
+        }
+    }
+}
+
+impl From<u8> for WithdrawStatus {
+    fn from(code: u8) -> Self {
+        match code {
+            0 => WithdrawStatus::EmailSent,
+            1 => WithdrawStatus::Cancelled,
+            2 => WithdrawStatus::AwaitingApproval,
+            3 => WithdrawStatus::Rejected,
+            4 => WithdrawStatus::Processing,
+            5 => WithdrawStatus::Failure,
+            6 => WithdrawStatus::Completed,
+            _ => WithdrawStatus::Other(code),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepositRecord {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub amount: Decimal,
+    pub coin: String,
+    pub network: String,
+    pub status: DepositStatus,
+    pub address: String,
+    #[serde(default)]
+    pub address_tag: String,
+    pub tx_id: String,
+    pub insert_time: u64,
+    pub transfer_type: u8,
+    pub confirm_times: String,
+    #[serde(default)]
+    pub unlock_confirm: u32,
+    #[serde(default)]
+    pub wallet_type: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawRecord {
+    pub id: String,
+    pub amount: Decimal,
+    pub transaction_fee: Decimal,
+    pub coin: String,
+    pub status: WithdrawStatus,
+    pub address: String,
+    #[serde(default)]
+    pub tx_id: String,
+    pub apply_time: String,
+    pub network: String,
+    pub transfer_type: u8,
+    #[serde(default)]
+    pub withdraw_order_id: Option<String>,
+    #[serde(default)]
+    pub info: String,
+    #[serde(default)]
+    pub confirm_no: u32,
+    #[serde(default)]
+    pub wallet_type: u8,
+    #[serde(default)]
+    pub tx_key: String,
+    #[serde(default)]
+    pub complete_time: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DepositAddress {
+    pub address: String,
+    pub coin: String,
+    pub tag: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct WithdrawResponse {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DustLog {
+    pub total: u32,
+    pub user_asset_dribblets: Vec<DustLogEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DustLogEntry {
+    pub operate_time: u64,
+    pub total_transfered_amount: Decimal,
+    pub total_service_charge_amount: Decimal,
+    pub trans_id: u64,
+    pub user_asset_dribblet_details: Vec<DustLogDetail>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DustLogDetail {
+    pub trans_id: u64,
+    pub service_charge_amount: Decimal,
+    pub amount: Decimal,
+    pub operate_time: u64,
+    pub transfered_amount: Decimal,
+    pub from_asset: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DustTransferResult {
+    pub total_service_charge: Decimal,
+    pub total_transfered: Decimal,
+    pub transfer_result: Vec<DustTransfer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DustTransfer {
+    pub amount: Decimal,
+    pub from_asset: String,
+    pub operate_time: u64,
+    pub service_charge_amount: Decimal,
+    pub tran_id: u64,
+    pub transfered_amount: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AssetDividendList {
+    pub total: u32,
+    pub rows: Vec<AssetDividend>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetDividend {
+    pub id: u64,
+    pub amount: Decimal,
+    pub asset: String,
+    pub div_time: u64,
+    pub en_info: String,
+    pub tran_id: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetDetail {
+    pub min_withdraw_amount: Decimal,
+    pub deposit_status: bool,
+    pub withdraw_fee: Decimal,
+    pub withdraw_status: bool,
+    #[serde(default)]
+    pub deposit_tip: Option<String>,
+}
+
+pub type AssetDetails = HashMap<String, AssetDetail>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeFee {
+    pub symbol: String,
+    pub maker_commission: Decimal,
+    pub taker_commission: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AssetTransferList {
+    pub total: u32,
+    #[serde(default)]
+    pub rows: Vec<AssetTransfer>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetTransfer {
+    pub asset: String,
+    pub amount: Decimal,
+    #[serde(rename = "type")]
+    pub transfer_type: EAssetTransferType,
+    pub status: String,
+    pub tran_id: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetTransferResponse {
+    pub tran_id: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FundingAsset {
+    pub asset: String,
+    pub free: Decimal,
+    pub locked: Decimal,
+    pub freeze: Decimal,
+    pub withdrawing: Decimal,
+    pub btc_valuation: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiRestrictions {
+    pub ip_restrict: bool,
+    pub create_time: u64,
+    #[serde(default)]
+    pub enable_reading: bool,
+    #[serde(default)]
+    pub enable_spot_and_margin_trading: bool,
+    #[serde(default)]
+    pub enable_withdrawals: bool,
+    #[serde(default)]
+    pub enable_internal_transfer: bool,
+    #[serde(default)]
+    pub enable_margin: bool,
+    #[serde(default)]
+    pub enable_futures: bool,
+    #[serde(default)]
+    pub permits_universal_transfer: bool,
+    #[serde(default)]
+    pub enable_vanilla_options: bool,
+    #[serde(default)]
+    pub enable_portfolio_margin_trading: bool,
+    #[serde(default)]
+    pub trading_authority_expiration_time: Option<u64>,
+}
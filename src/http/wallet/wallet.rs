@@ -1,14 +1,24 @@
-use std::{time::{SystemTime, UNIX_EPOCH}, fmt};
+use std::{
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
-use hmac::{Hmac, Mac};
-use hyper::body::Bytes;
-use sha2::Sha256;
+use hyper::{body::Bytes, Method};
+use rand::Rng;
+use serde::Deserialize;
 
+use crate::error::{Error, Result};
+use crate::Decimal;
 use crate::http::client::{Client, RequestParam};
-
-type HmacSha256 = Hmac<Sha256>;
-
-#[derive(Debug)]
+use crate::http::retry::send_reconciled;
+use crate::http::time_sync::local_timestamp;
+use crate::http::wallet::model::{
+    AccountSnapshotResponse, ApiRestrictions, AssetDetails, AssetDividendList, AssetTransferList, AssetTransferResponse, CoinInfo,
+    DepositAddress, DepositRecord, DepositStatus, DustLog, DustTransfer, DustTransferResult, FundingAsset, TradeFee,
+    WithdrawRecord, WithdrawResponse, WithdrawStatus,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
 #[allow(non_camel_case_types)]
 pub enum EAssetTransferType {
     MAIN_UMFUTURE,
@@ -55,70 +65,34 @@ const URL_ASSET_TRANSFER: &str = "/sapi/v1/asset/transfer";
 const URL_GET_FUNDING_ASSET: &str = "/sapi/v1/asset/get-funding-asset";
 const URL_ACCOUNT_API_RESTRICTIONS: &str = "/sapi/v1/account/apiRestrictions";
 
+// how far around the send time a lost transfer or dust conversion is looked for
+const LOOKUP_SLACK_MS: u64 = 1_000;
+
 impl fmt::Display for EAssetTransferType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self)
     }
 }
 
-fn get_timestamp() -> u64 {
-    let now = SystemTime::now();
-    let ts = now.duration_since(UNIX_EPOCH).unwrap();
-    ts.as_secs() * 1000
-}
-
-fn get_signature(param: &String, secret: &String) -> String {
-    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC Error".into());
-    mac.update(param.as_bytes());
-    let result_slice = &mac.finalize().into_bytes()[..];
-    let result = hex::encode(result_slice);
-    result
+pub async fn system_status(client: &Client) -> Result<Bytes> {
+    client.send(Method::GET, URL_SYSTEM_STATUS, &[]).await
 }
 
-fn param2string(param: &Vec<RequestParam>) -> String {
-    let mut param_str = String::new();
-    for p in param {
-        param_str.push_str(&format!("{}={}&", &p.key, &p.value));
-    }
-    param_str.remove(param_str.len() - 1);
-    param_str
+pub async fn capital_all(client: &Client) -> Result<Vec<CoinInfo>> {
+    let body = capital_all_raw(client).await?;
+    Ok(serde_json::from_slice(&body)?)
 }
 
-pub async fn system_status(client: &Client) -> Result<Bytes, String> {
-    let resp = client.get(URL_SYSTEM_STATUS).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-
-    Ok(body_bytes)
+pub async fn capital_all_raw(client: &Client) -> Result<Bytes> {
+    client.send_signed(Method::GET, URL_CAPITAL_ALL, &[]).await
 }
 
-pub async fn capital_all(client: &Client) -> Result<Bytes, String> {
-    let timestamp = get_timestamp();
-    let mut param = vec![
-        RequestParam{key: String::from("timestamp"), value: timestamp.to_string()},
-    ];
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    println!("signature: {}", &signature);
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.get_with_param(URL_CAPITAL_ALL, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-
-    Ok(body_bytes)
+pub async fn account_snapshot(client: &Client, account_type: &String, start_time: Option<u64>, end_time: Option<u64>, limit: Option<u32>) -> Result<AccountSnapshotResponse> {
+    let body = account_snapshot_raw(client, account_type, start_time, end_time, limit).await?;
+    Ok(serde_json::from_slice(&body)?)
 }
 
-pub async fn account_snapshot(client: &Client, account_type: &String, start_time: Option<u64>, end_time: Option<u64>, limit: Option<u32>) -> Result<Bytes, String> {
+pub async fn account_snapshot_raw(client: &Client, account_type: &String, start_time: Option<u64>, end_time: Option<u64>, limit: Option<u32>) -> Result<Bytes> {
     let mut param = vec![
         RequestParam{key: String::from("type"), value: String::from(account_type)},
     ];
@@ -132,91 +106,26 @@ pub async fn account_snapshot(client: &Client, account_type: &String, start_time
         param.push(RequestParam{key: String::from("limit"), value: limit.to_string()});
     }
 
-    let timestamp = get_timestamp();
-    param.push(RequestParam{key: String::from("timestamp"), value: timestamp.to_string()});
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.get_with_param(URL_ACCOUNT_SNAPSHOT, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-
-    Ok(body_bytes)
+    client.send_signed(Method::GET, URL_ACCOUNT_SNAPSHOT, &param).await
 }
 
-pub async fn asset_dust_btc(client: &Client) -> Result<Bytes, String> {
-    let mut param = vec![];
-
-    let timestamp = get_timestamp();
-    param.push(RequestParam{key: String::from("timestamp"), value: timestamp.to_string()});
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.post(URL_ASSET_DUST_BTC, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-
-    Ok(body_bytes)
+pub async fn asset_dust_btc(client: &Client) -> Result<Bytes> {
+    client.send_signed(Method::POST, URL_ASSET_DUST_BTC, &[]).await
 }
 
-pub async fn disable_fast_withdraw_switch(client: &Client) -> Result<Bytes, String> {
-    let mut param = vec![];
-
-    let timestamp = get_timestamp();
-    param.push(RequestParam{key: String::from("timestamp"), value: timestamp.to_string()});
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.post(URL_ACCOUNT_DISABLE_FAST_WITHDRAW_SWITCH, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-
-    Ok(body_bytes)
+pub async fn disable_fast_withdraw_switch(client: &Client) -> Result<Bytes> {
+    client.send_signed(Method::POST, URL_ACCOUNT_DISABLE_FAST_WITHDRAW_SWITCH, &[]).await
 }
 
-pub async fn enable_fast_withdraw_switch(client: &Client) -> Result<Bytes, String> {
-    let mut param = vec![];
-
-    let timestamp = get_timestamp();
-    param.push(RequestParam{key: String::from("timestamp"), value: timestamp.to_string()});
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.post(URL_ACCOUNT_ENABLE_FAST_WITHDRAW_SWITCH, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-
-    Ok(body_bytes)
+pub async fn enable_fast_withdraw_switch(client: &Client) -> Result<Bytes> {
+    client.send_signed(Method::POST, URL_ACCOUNT_ENABLE_FAST_WITHDRAW_SWITCH, &[]).await
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn capital_withdraw(
     client: &Client,
     coin: &str,
-    amount: f64,
+    amount: Decimal,
     address: &str,
     withdraw_order_id: &Option<&str>,
     network: &Option<&str>,
@@ -224,16 +133,19 @@ pub async fn capital_withdraw(
     transaction_fee_flag: &Option<bool>,
     name: &Option<&str>,
     wallet_type: &Option<u8>
-) -> Result<Bytes, String> {
+) -> Result<WithdrawResponse> {
+    // the withdrawOrderId is what lets a failed attempt be looked up before resending
+    let withdraw_order_id = match withdraw_order_id {
+        Some(withdraw_order_id) => String::from(*withdraw_order_id),
+        None => format!("w{}{:08x}", local_timestamp(), rand::thread_rng().gen::<u32>()),
+    };
     let mut param = vec![
         RequestParam{key: String::from("coin"), value: String::from(coin)},
         RequestParam{key: String::from("address"), value: String::from(address)},
         RequestParam{key: String::from("amount"), value: amount.to_string()},
+        RequestParam{key: String::from("withdrawOrderId"), value: withdraw_order_id.clone()},
     ];
 
-    if let Some(withdraw_order_id) = withdraw_order_id {
-        param.push(RequestParam{key: String::from("withdrawOrderId"), value: String::from(*withdraw_order_id)});
-    }
     if let Some(network) = network {
         param.push(RequestParam{key: String::from("network"), value: String::from(*network)});
     }
@@ -249,39 +161,58 @@ pub async fn capital_withdraw(
     if let Some(wallet_type) = wallet_type {
         param.push(RequestParam{key: String::from("walletType"), value: wallet_type.to_string()});
     }
-    param.push(RequestParam{key: String::from("timestamp"), value: get_timestamp().to_string()});
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
 
-    let resp = client.post(URL_CAPITAL_WITHDRAW, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
+    let param = &param;
+    let withdraw_order_id = withdraw_order_id.as_str();
+    send_reconciled(
+        client.retry_policy(),
+        || async move {
+            let body = client.send_signed(Method::POST, URL_CAPITAL_WITHDRAW, param).await?;
+            Ok(serde_json::from_slice(&body)?)
         },
-    };
-
-    Ok(body_bytes)
+        // the history can lag behind, an id it does not list yet is no reason to pay out twice
+        || async move {
+            let records = capital_withdraw_history(client, &Some(coin), &Some(withdraw_order_id), None, None, None, None, None).await?;
+            let records: Vec<_> = records.into_iter()
+                .filter(|record| record.withdraw_order_id.as_deref() == Some(withdraw_order_id))
+                .collect();
+            match records.as_slice() {
+                [record] => Ok(Some(WithdrawResponse { id: record.id.clone() })),
+                records => Err(Error::OutcomeUnknown(format!("{} withdrawals with id {}", records.len(), withdraw_order_id))),
+            }
+        },
+    ).await
 }
 
 pub async fn capital_deposit_hisrec(
     client: &Client,
     coin: &Option<&str>,
-    status: Option<u32>,
+    status: Option<DepositStatus>,
+    start_time: Option<u64>,
+    end_time: Option<u64>,
+    offset: Option<i32>,
+    limit: Option<u32>,
+) -> Result<Vec<DepositRecord>> {
+    let body = capital_deposit_hisrec_raw(client, coin, status, start_time, end_time, offset, limit).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn capital_deposit_hisrec_raw(
+    client: &Client,
+    coin: &Option<&str>,
+    status: Option<DepositStatus>,
     start_time: Option<u64>,
     end_time: Option<u64>,
     offset: Option<i32>,
     limit: Option<u32>,
-) -> Result<Bytes, String> {
+) -> Result<Bytes> {
     let mut param = vec![];
 
     if let Some(coin) = coin {
         param.push(RequestParam{key: String::from("coin"), value: String::from(*coin)});
     }
     if let Some(status) = status {
-        param.push(RequestParam{key: String::from("status"), value: status.to_string()});
+        param.push(RequestParam{key: String::from("status"), value: status.code().to_string()});
     }
     if let Some(start_time) = start_time {
         param.push(RequestParam{key: String::from("startTime"), value: start_time.to_string()});
@@ -295,33 +226,36 @@ pub async fn capital_deposit_hisrec(
     if let Some(limit) = limit {
         param.push(RequestParam{key: String::from("limit"), value: limit.to_string()});
     }
-    param.push(RequestParam{key: String::from("timestamp"), value: get_timestamp().to_string()});
 
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.get_with_param(URL_CAPITAL_DEPOSIT_HISREC, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-
-    Ok(body_bytes)
+    client.send_signed(Method::GET, URL_CAPITAL_DEPOSIT_HISREC, &param).await
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn capital_withdraw_history(
     client: &Client,
     coin: &Option<&str>,
     withdraw_order_id: &Option<&str>,
-    status: Option<u32>,
+    status: Option<WithdrawStatus>,
     offset: Option<i32>,
     limit: Option<u32>,
     start_time: Option<u64>,
     end_time: Option<u64>
-) -> Result<Bytes, String> {
+) -> Result<Vec<WithdrawRecord>> {
+    let body = capital_withdraw_history_raw(client, coin, withdraw_order_id, status, offset, limit, start_time, end_time).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn capital_withdraw_history_raw(
+    client: &Client,
+    coin: &Option<&str>,
+    withdraw_order_id: &Option<&str>,
+    status: Option<WithdrawStatus>,
+    offset: Option<i32>,
+    limit: Option<u32>,
+    start_time: Option<u64>,
+    end_time: Option<u64>
+) -> Result<Bytes> {
     let mut param = vec![];
 
     if let Some(coin) = coin {
@@ -331,7 +265,7 @@ pub async fn capital_withdraw_history(
         param.push(RequestParam{key: String::from("withdrawOrderId"), value: String::from(*withdraw_order_id)});
     }
     if let Some(status) = status {
-        param.push(RequestParam{key: String::from("status"), value: status.to_string()});
+        param.push(RequestParam{key: String::from("status"), value: status.code().to_string()});
     }
     if let Some(start_time) = start_time {
         param.push(RequestParam{key: String::from("startTime"), value: start_time.to_string()});
@@ -345,24 +279,16 @@ pub async fn capital_withdraw_history(
     if let Some(limit) = limit {
         param.push(RequestParam{key: String::from("limit"), value: limit.to_string()});
     }
-    param.push(RequestParam{key: String::from("timestamp"), value: get_timestamp().to_string()});
 
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.get_with_param(URL_CAPITAL_WITHDRAW_HISTORY, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
+    client.send_signed(Method::GET, URL_CAPITAL_WITHDRAW_HISTORY, &param).await
+}
 
-    Ok(body_bytes)
+pub async fn capital_deposit_address(client: &Client, coin: &str, network: &Option<&str>) -> Result<DepositAddress> {
+    let body = capital_deposit_address_raw(client, coin, network).await?;
+    Ok(serde_json::from_slice(&body)?)
 }
 
-pub async fn capital_deposit_address(client: &Client, coin: &str, network: &Option<&str>) -> Result<Bytes, String> {
+pub async fn capital_deposit_address_raw(client: &Client, coin: &str, network: &Option<&str>) -> Result<Bytes> {
     let mut param = vec![
         RequestParam{key: String::from("coin"), value: String::from(coin)},
     ];
@@ -370,64 +296,24 @@ pub async fn capital_deposit_address(client: &Client, coin: &str, network: &Opti
     if let Some(network) = network {
         param.push(RequestParam{key: String::from("network"), value: String::from(*network)});
     }
-    param.push(RequestParam{key: String::from("timestamp"), value: get_timestamp().to_string()});
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.get_with_param(URL_CAPITAL_DEPOSIT_ADDRESS, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
 
-    Ok(body_bytes)
+    client.send_signed(Method::GET, URL_CAPITAL_DEPOSIT_ADDRESS, &param).await
 }
 
-pub async fn account_status(client: &Client) -> Result<Bytes, String> {
-    let mut param = vec![
-        RequestParam{key: String::from("timestamp"), value: get_timestamp().to_string()},
-    ];
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.get_with_param(URL_ACCOUNT_STATUS, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-
-    Ok(body_bytes)
+pub async fn account_status(client: &Client) -> Result<Bytes> {
+    client.send_signed(Method::GET, URL_ACCOUNT_STATUS, &[]).await
 }
 
-pub async fn account_api_trading_status(client: &Client) -> Result<Bytes, String> {
-    let mut param = vec![
-        RequestParam{key: String::from("timestamp"), value: get_timestamp().to_string()},
-    ];
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.get_with_param(URL_ACCOUNT_API_TRADING_STATUS, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
+pub async fn account_api_trading_status(client: &Client) -> Result<Bytes> {
+    client.send_signed(Method::GET, URL_ACCOUNT_API_TRADING_STATUS, &[]).await
+}
 
-    Ok(body_bytes)
+pub async fn asset_dribblet(client: &Client, start_time: Option<u64>, end_time: Option<u64>) -> Result<DustLog> {
+    let body = asset_dribblet_raw(client, start_time, end_time).await?;
+    Ok(serde_json::from_slice(&body)?)
 }
 
-pub async fn asset_dribblet(client: &Client, start_time: Option<u64>, end_time: Option<u64>) -> Result<Bytes, String> {
+pub async fn asset_dribblet_raw(client: &Client, start_time: Option<u64>, end_time: Option<u64>) -> Result<Bytes> {
     let mut param = vec![];
 
     if let Some(start_time) = start_time {
@@ -436,53 +322,74 @@ pub async fn asset_dribblet(client: &Client, start_time: Option<u64>, end_time:
     if let Some(end_time) = end_time {
         param.push(RequestParam{key: String::from("endTime"), value: end_time.to_string()});
     }
-    param.push(RequestParam{key: String::from("timestamp"), value: get_timestamp().to_string()});
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.get_with_param(URL_ASSET_DRIBBLET, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
 
-    Ok(body_bytes)
+    client.send_signed(Method::GET, URL_ASSET_DRIBBLET, &param).await
 }
 
-pub async fn asset_dust(client: &Client, asset: &Vec<&str>) -> Result<Bytes, String> {
-    let mut asset_str = String::new();
+/// A failed attempt is looked up in the dribblet history before it is resent.
+pub async fn asset_dust(client: &Client, asset: &[&str]) -> Result<DustTransferResult> {
+    let mut param = vec![];
     for a in asset {
-        asset_str.push_str(format!("&asset={}", a).as_str());
-    }
-    asset_str.remove(0);
-
-    let mut param = vec![
-        RequestParam{key: String::from("asset"), value: asset_str},
-    ];
-
-    let timestamp = get_timestamp();
-    param.push(RequestParam{key: String::from("timestamp"), value: timestamp.to_string()});
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.post(URL_ASSET_DUST, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
+        param.push(RequestParam{key: String::from("asset"), value: String::from(*a)});
+    }
+
+    // the lost conversion has to be the only one of exactly these assets around the send time,
+    // anything else leaves the outcome unknown
+    let param = &param;
+    let sent_at = &AtomicU64::new(0);
+    let mut requested: Vec<&str> = asset.to_vec();
+    requested.sort_unstable();
+    requested.dedup();
+    let requested = &requested;
+    send_reconciled(
+        client.retry_policy(),
+        || async move {
+            sent_at.store(client.timestamp(), Ordering::Relaxed);
+            let body = client.send_signed(Method::POST, URL_ASSET_DUST, param).await?;
+            Ok(serde_json::from_slice(&body)?)
         },
-    };
+        || async move {
+            let start_time = sent_at.load(Ordering::Relaxed).saturating_sub(LOOKUP_SLACK_MS);
+            let end_time = client.timestamp() + LOOKUP_SLACK_MS;
+            let log = asset_dribblet(client, Some(start_time), Some(end_time)).await?;
+            let mut candidates: Vec<_> = log.user_asset_dribblets.into_iter()
+                .filter(|entry| (start_time..=end_time).contains(&entry.operate_time))
+                .filter(|entry| {
+                    let mut converted: Vec<&str> = entry.user_asset_dribblet_details.iter().map(|detail| detail.from_asset.as_str()).collect();
+                    converted.sort_unstable();
+                    converted == *requested
+                })
+                .collect();
+            if candidates.len() != 1 {
+                return Err(Error::OutcomeUnknown(format!(
+                    "{} dust conversions of {:?} since {}", candidates.len(), requested, start_time,
+                )));
+            }
+            let entry = candidates.remove(0);
+            Ok(Some(DustTransferResult {
+                total_service_charge: entry.total_service_charge_amount,
+                total_transfered: entry.total_transfered_amount,
+                transfer_result: entry.user_asset_dribblet_details.into_iter()
+                    .map(|detail| DustTransfer {
+                        amount: detail.amount,
+                        from_asset: detail.from_asset,
+                        operate_time: detail.operate_time,
+                        service_charge_amount: detail.service_charge_amount,
+                        tran_id: detail.trans_id,
+                        transfered_amount: detail.transfered_amount,
+                    })
+                    .collect(),
+            }))
+        },
+    ).await
+}
 
-    Ok(body_bytes)
+pub async fn asset_dividend(client: &Client, asset: &Option<&str>, start_time: Option<u64>, end_time: Option<u64>, limit: Option<u32>) -> Result<AssetDividendList> {
+    let body = asset_dividend_raw(client, asset, start_time, end_time, limit).await?;
+    Ok(serde_json::from_slice(&body)?)
 }
 
-pub async fn asset_dividend(client: &Client, asset: &Option<&str>, start_time: Option<u64>, end_time: Option<u64>, limit: Option<u32>) -> Result<Bytes, String> {
+pub async fn asset_dividend_raw(client: &Client, asset: &Option<&str>, start_time: Option<u64>, end_time: Option<u64>, limit: Option<u32>) -> Result<Bytes> {
     let mut param = vec![];
 
     if let Some(asset) = asset {
@@ -497,77 +404,48 @@ pub async fn asset_dividend(client: &Client, asset: &Option<&str>, start_time: O
     if let Some(limit) = limit {
         param.push(RequestParam{key: String::from("limit"), value: limit.to_string()});
     }
-    param.push(RequestParam{key: String::from("timestamp"), value: get_timestamp().to_string()});
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
 
-    let resp = client.get_with_param(URL_ASSET_DIVIDEND, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
+    client.send_signed(Method::GET, URL_ASSET_DIVIDEND, &param).await
+}
 
-    Ok(body_bytes)
+pub async fn asset_detail(client: &Client, asset: &Option<&str>) -> Result<AssetDetails> {
+    let body = asset_detail_raw(client, asset).await?;
+    Ok(serde_json::from_slice(&body)?)
 }
 
-pub async fn asset_detail(client: &Client, asset: &Option<&str>) -> Result<Bytes, String> {
+pub async fn asset_detail_raw(client: &Client, asset: &Option<&str>) -> Result<Bytes> {
     let mut param = vec![];
 
     if let Some(asset) = asset {
         param.push(RequestParam{key: String::from("asset"), value: String::from(*asset)});
     }
-    param.push(RequestParam{key: String::from("timestamp"), value: get_timestamp().to_string()});
 
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.get_with_param(URL_ASSET_DETAIL, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
+    client.send_signed(Method::GET, URL_ASSET_DETAIL, &param).await
+}
 
-    Ok(body_bytes)
+pub async fn asset_trade_fee(client: &Client, symbol: &Option<&str>) -> Result<Vec<TradeFee>> {
+    let body = asset_trade_fee_raw(client, symbol).await?;
+    Ok(serde_json::from_slice(&body)?)
 }
 
-pub async fn asset_trade_fee(client: &Client, symbol: &Option<&str>) -> Result<Bytes, String> {
+pub async fn asset_trade_fee_raw(client: &Client, symbol: &Option<&str>) -> Result<Bytes> {
     let mut param = vec![];
 
     if let Some(symbol) = symbol {
         param.push(RequestParam{key: String::from("symbol"), value: String::from(*symbol)});
     }
-    param.push(RequestParam{key: String::from("timestamp"), value: get_timestamp().to_string()});
 
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.get_with_param(URL_ASSET_TRADE_FEE, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-
-    Ok(body_bytes)
+    client.send_signed(Method::GET, URL_ASSET_TRADE_FEE, &param).await
 }
 
 pub async fn make_asset_transfer(
     client: &Client,
     transfer_type: EAssetTransferType,
     asset: &str,
-    amount: f64,
+    amount: Decimal,
     from_symbol: &Option<&str>,
     to_symbol: &Option<&str>
-) -> Result<Bytes, String> {
+) -> Result<AssetTransferResponse> {
     let mut param = vec![
         RequestParam{key: String::from("type"), value: transfer_type.to_string()},
         RequestParam{key: String::from("asset"), value: String::from(asset)},
@@ -580,23 +458,37 @@ pub async fn make_asset_transfer(
     if let Some(to_symbol) = to_symbol {
         param.push(RequestParam{key: String::from("toSymbol"), value: String::from(*to_symbol)});
     }
-    param.push(RequestParam{key: String::from("timestamp"), value: get_timestamp().to_string()});
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
 
-    let resp = client.post(URL_ASSET_TRANSFER, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
+    // transfers carry no client id, so the lost one is looked for by what it was and when it was
+    // sent; anything but exactly one candidate leaves the outcome unknown
+    let param = &param;
+    let sent_at = &AtomicU64::new(0);
+    send_reconciled(
+        client.retry_policy(),
+        || async move {
+            sent_at.store(client.timestamp(), Ordering::Relaxed);
+            let body = client.send_signed(Method::POST, URL_ASSET_TRANSFER, param).await?;
+            Ok(serde_json::from_slice(&body)?)
         },
-    };
-
-    Ok(body_bytes)
+        || async move {
+            let start_time = sent_at.load(Ordering::Relaxed).saturating_sub(LOOKUP_SLACK_MS);
+            let end_time = client.timestamp() + LOOKUP_SLACK_MS;
+            let list = get_asset_transfer(client, transfer_type, Some(start_time), Some(end_time), None, Some(100), from_symbol, to_symbol).await?;
+            let candidates: Vec<_> = list.rows.into_iter()
+                .filter(|transfer| transfer.transfer_type == transfer_type && transfer.asset == asset && transfer.amount == amount)
+                .filter(|transfer| (start_time..=end_time).contains(&transfer.timestamp))
+                .collect();
+            match candidates.as_slice() {
+                [transfer] => Ok(Some(AssetTransferResponse { tran_id: transfer.tran_id })),
+                candidates => Err(Error::OutcomeUnknown(format!(
+                    "{} {} transfers of {} {} since {}", candidates.len(), transfer_type, amount, asset, start_time,
+                ))),
+            }
+        },
+    ).await
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn get_asset_transfer(
     client: &Client,
     transfer_type: EAssetTransferType,
@@ -606,7 +498,22 @@ pub async fn get_asset_transfer(
     size: Option<u32>,
     from_symbol: &Option<&str>,
     to_symbol: &Option<&str>
-) -> Result<Bytes, String> {
+) -> Result<AssetTransferList> {
+    let body = get_asset_transfer_raw(client, transfer_type, start_time, end_time, current, size, from_symbol, to_symbol).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn get_asset_transfer_raw(
+    client: &Client,
+    transfer_type: EAssetTransferType,
+    start_time: Option<u64>,
+    end_time: Option<u64>,
+    current: Option<i32>,
+    size: Option<u32>,
+    from_symbol: &Option<&str>,
+    to_symbol: &Option<&str>
+) -> Result<Bytes> {
     let mut param = vec![
         RequestParam{key: String::from("type"), value: transfer_type.to_string()},
     ];
@@ -629,28 +536,24 @@ pub async fn get_asset_transfer(
     if let Some(to_symbol) = to_symbol {
         param.push(RequestParam{key: String::from("toSymbol"), value: String::from(*to_symbol)});
     }
-    param.push(RequestParam{key: String::from("timestamp"), value: get_timestamp().to_string()});
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
 
-    let resp = client.get_with_param(URL_ASSET_TRANSFER, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-
-    Ok(body_bytes)
+    client.send_signed(Method::GET, URL_ASSET_TRANSFER, &param).await
 }
 
 pub async fn get_funding_asset(
     client: &Client,
     asset: &Option<&str>,
     need_btc_valuation: &Option<&str>,
-) -> Result<Bytes, String> {
+) -> Result<Vec<FundingAsset>> {
+    let body = get_funding_asset_raw(client, asset, need_btc_valuation).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+pub async fn get_funding_asset_raw(
+    client: &Client,
+    asset: &Option<&str>,
+    need_btc_valuation: &Option<&str>,
+) -> Result<Bytes> {
     let mut param = vec![];
 
     if let Some(asset) = asset {
@@ -659,39 +562,15 @@ pub async fn get_funding_asset(
     if let Some(need_btc_valuation) = need_btc_valuation {
         param.push(RequestParam{key: String::from("needBtcValuation"), value: String::from(*need_btc_valuation)});
     }
-    param.push(RequestParam{key: String::from("timestamp"), value: get_timestamp().to_string()});
 
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.post(URL_GET_FUNDING_ASSET, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
-
-    Ok(body_bytes)
+    client.send_signed(Method::POST, URL_GET_FUNDING_ASSET, &param).await
 }
 
-pub async fn account_api_restrictions(client: &Client) -> Result<Bytes, String> {
-    let mut param = vec![
-        RequestParam{key: String::from("timestamp"), value: get_timestamp().to_string()},
-    ];
-
-    let param_str = param2string(&param);
-    let signature = get_signature(&param_str, client.get_secret_key());
-    param.push(RequestParam{key: String::from("signature"), value: signature});
-
-    let resp = client.get_with_param(URL_ACCOUNT_API_RESTRICTIONS, &param).await?;
-    let body_bytes = match hyper::body::to_bytes(resp.into_body()).await {
-        Ok(bytes) => bytes,
-        Err(err) => {
-            return Err(err.to_string());
-        },
-    };
+pub async fn account_api_restrictions(client: &Client) -> Result<ApiRestrictions> {
+    let body = account_api_restrictions_raw(client).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
 
-    Ok(body_bytes)
+pub async fn account_api_restrictions_raw(client: &Client) -> Result<Bytes> {
+    client.send_signed(Method::GET, URL_ACCOUNT_API_RESTRICTIONS, &[]).await
 }
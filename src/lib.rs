@@ -1,2 +1,8 @@
+pub mod error;
 pub mod http;
+#[cfg(feature = "mock-server")]
+pub mod mock;
 pub mod ws;
+
+pub use error::{Error, Result};
+pub use rust_decimal::Decimal;
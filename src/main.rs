@@ -2,12 +2,14 @@
 
 use std::env;
 
+use futures::StreamExt;
 use log::{error, info};
 use binance_sdk_rs::{http::client::Client, http::market, http::wallet, ws};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     env::set_var("RUST_LOG", "info");
+    env_logger::init();
 
     // test APIKEY and SECRETKEY
     //env::set_var("APIKEY", "your api key");
@@ -43,7 +45,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     //let asset_dust_btc = wallet::asset_dust_btc(&client).await?;
     //println!("asset_dust_btc: {:?}", asset_dust_btc);
 
-    ws::client::test_connect();
+    let ws_client = ws::client::Client::from_http(&client)?;
+    let mut trades = ws_client.connect("/ws/btcusdt@trade").await?;
+    for _ in 0..3 {
+        match trades.next().await {
+            Some(trade) => println!("trade: {}", trade?),
+            None => break,
+        }
+    }
+    trades.close().await?;
 
     Ok(())
 }
@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+
+use hyper::Method;
+use serde_json::{json, Value};
+
+use crate::mock::market::{find_symbol, fmt};
+use crate::mock::trade::MockOrder;
+use crate::mock::{MockError, Params, Reply, State};
+use crate::Decimal;
+
+const DEFAULT_TRADES_LIMIT: usize = 500;
+const MAX_TRADES_LIMIT: usize = 1000;
+const MAX_TRADES_WINDOW_MS: u64 = 24 * 60 * 60 * 1000;
+const DEFAULT_PREVENTED_MATCHES_LIMIT: u32 = 500;
+
+/// Balances before any order, the mock trades against these.
+const INITIAL_BALANCES: [(&str, i64); 4] = [("BTC", 1), ("ETH", 10), ("BNB", 20), ("USDT", 100_000)];
+
+pub(crate) fn handle(state: &mut State, method: &Method, path: &str, params: &Params) -> Option<Reply> {
+    if method != Method::GET {
+        return None;
+    }
+
+    let reply = match path {
+        "/api/v3/account" => account(state, params),
+        "/api/v3/myTrades" => my_trades(state, params),
+        "/api/v3/rateLimit/order" => Ok(rate_limit_order(state)),
+        "/api/v3/myPreventedMatches" => my_prevented_matches(params),
+        "/api/v3/account/commission" => account_commission(params),
+        _ => {
+            return None;
+        },
+    };
+    Some(reply)
+}
+
+/// Initial balances, plus what the fills moved, minus what open orders lock.
+fn balances(state: &State) -> BTreeMap<&'static str, (Decimal, Decimal)> {
+    let mut balances: BTreeMap<&'static str, (Decimal, Decimal)> = INITIAL_BALANCES.iter()
+        .map(|(asset, amount)| (*asset, (Decimal::from(*amount), Decimal::ZERO)))
+        .collect();
+
+    for order in &state.trade.orders {
+        let s = match find_symbol(&order.symbol) {
+            Ok(s) => s,
+            Err(_) => continue,
+        };
+        let buy = order.side == "BUY";
+        for fill in &order.fills {
+            let qty = decimal(&fill["qty"]);
+            let quote_qty = qty * decimal(&fill["price"]);
+            let (base, quote) = if buy { (qty, -quote_qty) } else { (-qty, quote_qty) };
+            balances.entry(s.base).or_default().0 += base;
+            balances.entry(s.quote).or_default().0 += quote;
+            if let Some(asset) = fill["commissionAsset"].as_str().and_then(|asset| [s.base, s.quote].into_iter().find(|a| *a == asset)) {
+                balances.entry(asset).or_default().0 -= decimal(&fill["commission"]);
+            }
+        }
+
+        // pending orders lock nothing until they are placed
+        if order.is_open() && order.status != "PENDING_NEW" {
+            let remaining = order.orig_qty - order.executed_qty;
+            let (asset, locked) = if !buy {
+                (s.base, remaining)
+            } else if !order.price.is_zero() {
+                (s.quote, remaining * order.price)
+            } else {
+                continue;
+            };
+            let balance = balances.entry(asset).or_default();
+            balance.0 -= locked;
+            balance.1 += locked;
+        }
+    }
+    balances
+}
+
+fn decimal(value: &Value) -> Decimal {
+    value.as_str().and_then(|value| value.parse().ok()).unwrap_or_default()
+}
+
+fn commission_rates(maker: &str, taker: &str) -> Value {
+    json!({"maker": maker, "taker": taker, "buyer": "0.00000000", "seller": "0.00000000"})
+}
+
+fn account(state: &State, params: &Params) -> Reply {
+    let omit_zero_balances = params.parse_opt::<bool>("omitZeroBalances")?.unwrap_or(false);
+    let update_time = state.trade.orders.iter().map(|o| o.update_time).max().unwrap_or(0);
+
+    let balances: Vec<Value> = balances(state).into_iter()
+        .filter(|(_, (free, locked))| !omit_zero_balances || !free.is_zero() || !locked.is_zero())
+        .map(|(asset, (free, locked))| json!({"asset": asset, "free": fmt(free), "locked": fmt(locked)}))
+        .collect();
+    Ok(json!({
+        "makerCommission": 10,
+        "takerCommission": 10,
+        "buyerCommission": 0,
+        "sellerCommission": 0,
+        "commissionRates": commission_rates("0.00100000", "0.00100000"),
+        "canTrade": true,
+        "canWithdraw": true,
+        "canDeposit": true,
+        "brokered": false,
+        "requireSelfTradePrevention": false,
+        "preventSor": false,
+        "updateTime": update_time,
+        "accountType": "SPOT",
+        "balances": balances,
+        "permissions": ["SPOT"],
+        "uid": 354937868,
+    }))
+}
+
+fn trade_json(order: &MockOrder, fill: &Value) -> Value {
+    let qty = decimal(&fill["qty"]);
+    let price = decimal(&fill["price"]);
+    json!({
+        "symbol": order.symbol,
+        "id": fill["tradeId"],
+        "orderId": order.order_id,
+        "orderListId": order.order_list_id,
+        "price": fill["price"],
+        "qty": fill["qty"],
+        "quoteQty": fmt(qty * price),
+        "commission": fill["commission"],
+        "commissionAsset": fill["commissionAsset"],
+        "time": order.update_time,
+        // mock orders only fill when they are placed, as takers
+        "isBuyer": order.side == "BUY",
+        "isMaker": false,
+        "isBestMatch": true,
+    })
+}
+
+fn my_trades(state: &State, params: &Params) -> Reply {
+    let symbol = find_symbol(params.required("symbol")?)?.symbol;
+    let order_id = params.parse_opt::<u64>("orderId")?;
+    let start_time = params.parse_opt::<u64>("startTime")?;
+    let end_time = params.parse_opt::<u64>("endTime")?;
+    let from_id = params.parse_opt::<u64>("fromId")?;
+    let limit = params.parse_opt::<usize>("limit")?.unwrap_or(DEFAULT_TRADES_LIMIT).min(MAX_TRADES_LIMIT);
+
+    if from_id.is_some() && (start_time.is_some() || end_time.is_some()) {
+        return Err(MockError::bad_request(-1128, "Combination of optional parameters invalid."));
+    }
+    if let (Some(start_time), Some(end_time)) = (start_time, end_time) {
+        if end_time < start_time || end_time - start_time > MAX_TRADES_WINDOW_MS {
+            return Err(MockError::bad_request(-1127, "More than 24 hours between startTime and endTime."));
+        }
+    }
+
+    let mut trades: Vec<(u64, u64, Value)> = state.trade.orders.iter()
+        .filter(|o| o.symbol == symbol && order_id.is_none_or(|id| o.order_id == id))
+        .flat_map(|o| o.fills.iter().map(move |fill| (fill["tradeId"].as_u64().unwrap_or(0), o.update_time, trade_json(o, fill))))
+        .filter(|(id, time, _)| {
+            from_id.is_none_or(|from_id| *id >= from_id)
+                && start_time.is_none_or(|start_time| *time >= start_time)
+                && end_time.is_none_or(|end_time| *time <= end_time)
+        })
+        .collect();
+    trades.sort_by_key(|(id, _, _)| *id);
+
+    // from fromId or startTime on, otherwise the most recent ones
+    let trades = if from_id.is_some() || start_time.is_some() {
+        &trades[..limit.min(trades.len())]
+    } else {
+        &trades[trades.len().saturating_sub(limit)..]
+    };
+    Ok(Value::Array(trades.iter().map(|(_, _, trade)| trade.clone()).collect()))
+}
+
+fn rate_limit_order(state: &State) -> Value {
+    let now = state.now();
+    let count = |counter: (u64, u32), window: u64| if counter.0 == window { counter.1 } else { 0 };
+    json!([
+        {"rateLimitType": "ORDERS", "interval": "SECOND", "intervalNum": 10, "limit": 100, "count": count(state.orders_10s, now / 10_000)},
+        {"rateLimitType": "ORDERS", "interval": "DAY", "intervalNum": 1, "limit": 200000, "count": count(state.orders_1d, now / 86_400_000)},
+    ])
+}
+
+/// Orders of one account never meet on the mock's book, so no match is ever prevented.
+fn my_prevented_matches(params: &Params) -> Reply {
+    find_symbol(params.required("symbol")?)?;
+    let prevented_match_id = params.parse_opt::<u64>("preventedMatchId")?;
+    let order_id = params.parse_opt::<u64>("orderId")?;
+    let from_prevented_match_id = params.parse_opt::<u64>("fromPreventedMatchId")?;
+    let limit = params.parse_opt::<u32>("limit")?.unwrap_or(DEFAULT_PREVENTED_MATCHES_LIMIT);
+
+    let valid = match (prevented_match_id, order_id) {
+        (Some(_), None) => from_prevented_match_id.is_none(),
+        (None, Some(_)) => limit <= MAX_TRADES_LIMIT as u32,
+        _ => false,
+    };
+    if !valid {
+        return Err(MockError::bad_request(-1128, "Combination of optional parameters invalid."));
+    }
+    Ok(json!([]))
+}
+
+fn account_commission(params: &Params) -> Reply {
+    let symbol = find_symbol(params.required("symbol")?)?.symbol;
+    Ok(json!({
+        "symbol": symbol,
+        "standardCommission": commission_rates("0.00100000", "0.00100000"),
+        "taxCommission": commission_rates("0.00000000", "0.00000000"),
+        "discount": {
+            "enabledForAccount": true,
+            "enabledForSymbol": true,
+            "discountAsset": "BNB",
+            "discount": "0.75000000",
+        },
+    }))
+}
@@ -0,0 +1,390 @@
+use hyper::Method;
+use serde_json::{json, Value};
+
+use crate::mock::{MockError, Params, Reply, State};
+use crate::Decimal;
+
+const DEFAULT_TRADES_LIMIT: u32 = 500;
+const MAX_TRADES_LIMIT: u32 = 1000;
+const MAX_DEPTH_LIMIT: u32 = 5000;
+
+#[derive(Debug)]
+pub(crate) struct MarketState {
+    pub(crate) last_update_id: u64,
+}
+
+impl Default for MarketState {
+    fn default() -> Self {
+        MarketState { last_update_id: 1027024 }
+    }
+}
+
+/// A symbol the mock exchange lists.
+#[derive(Debug, Clone)]
+pub(crate) struct MockSymbol {
+    pub(crate) symbol: &'static str,
+    pub(crate) base: &'static str,
+    pub(crate) quote: &'static str,
+    pub(crate) price: Decimal,
+    pub(crate) tick_size: Decimal,
+    pub(crate) step_size: Decimal,
+    pub(crate) min_notional: Decimal,
+    pub(crate) last_trade_id: u64,
+}
+
+pub(crate) fn symbols() -> Vec<MockSymbol> {
+    vec![
+        MockSymbol {
+            symbol: "BTCUSDT",
+            base: "BTC",
+            quote: "USDT",
+            price: Decimal::new(4300000, 2),
+            tick_size: Decimal::new(1, 2),
+            step_size: Decimal::new(1, 5),
+            min_notional: Decimal::new(5, 0),
+            last_trade_id: 3312846571,
+        },
+        MockSymbol {
+            symbol: "ETHBTC",
+            base: "ETH",
+            quote: "BTC",
+            price: Decimal::new(5512, 5),
+            tick_size: Decimal::new(1, 5),
+            step_size: Decimal::new(1, 4),
+            min_notional: Decimal::new(1, 4),
+            last_trade_id: 440087413,
+        },
+        MockSymbol {
+            symbol: "BNBUSDT",
+            base: "BNB",
+            quote: "USDT",
+            price: Decimal::new(3105, 1),
+            tick_size: Decimal::new(1, 1),
+            step_size: Decimal::new(1, 3),
+            min_notional: Decimal::new(5, 0),
+            last_trade_id: 742318290,
+        },
+    ]
+}
+
+pub(crate) fn find_symbol(symbol: &str) -> std::result::Result<MockSymbol, MockError> {
+    match symbols().into_iter().find(|s| s.symbol == symbol) {
+        Some(symbol) => Ok(symbol),
+        None => Err(MockError::bad_request(-1121, "Invalid symbol.")),
+    }
+}
+
+pub(crate) fn handle(state: &mut State, method: &Method, path: &str, params: &Params) -> Option<Reply> {
+    if method != Method::GET {
+        return None;
+    }
+
+    let reply = match path {
+        "/api/v3/ping" => Ok(json!({})),
+        "/api/v3/time" => Ok(json!({"serverTime": state.now()})),
+        "/api/v3/exchangeInfo" => exchange_info(state, params),
+        "/api/v3/depth" => depth(state, params),
+        "/api/v3/trades" => trades(params, false),
+        "/api/v3/historicalTrades" => trades(params, true),
+        "/api/v3/aggTrades" => agg_trades(state, params),
+        "/api/v3/klines" => klines(state, params),
+        "/api/v3/avgPrice" => avg_price(state, params),
+        "/api/v3/ticker/24hr" => for_symbols(params, |s| ticker_24hr(state, s)),
+        "/api/v3/ticker/price" => for_symbols(params, |s| json!({"symbol": s.symbol, "price": fmt(s.price)})),
+        "/api/v3/ticker/bookTicker" => for_symbols(params, book_ticker),
+        _ => {
+            return None;
+        },
+    };
+    Some(reply)
+}
+
+// prices and quantities are sent with 8 decimals like the real api
+pub(crate) fn fmt(value: Decimal) -> String {
+    format!("{:.8}", value)
+}
+
+// deterministic pseudo random quantity in [step, 1000 * step]
+fn qty(symbol: &MockSymbol, seed: u64) -> Decimal {
+    symbol.step_size * Decimal::from(seed.wrapping_mul(7919) % 1000 + 1)
+}
+
+fn exchange_info(state: &State, params: &Params) -> Reply {
+    let listed = match (params.get("symbol"), params.get("symbols")) {
+        (Some(symbol), _) => vec![find_symbol(symbol)?],
+        (None, Some(names)) => {
+            let names: Vec<String> = match serde_json::from_str(names) {
+                Ok(names) => names,
+                Err(_) => {
+                    return Err(MockError::illegal("symbols"));
+                },
+            };
+            let mut listed = vec![];
+            for name in names.iter() {
+                listed.push(find_symbol(name)?);
+            }
+            listed
+        },
+        (None, None) => symbols(),
+    };
+
+    Ok(json!({
+        "timezone": "UTC",
+        "serverTime": state.now(),
+        "rateLimits": [
+            {"rateLimitType": "REQUEST_WEIGHT", "interval": "MINUTE", "intervalNum": 1, "limit": 6000},
+            {"rateLimitType": "ORDERS", "interval": "SECOND", "intervalNum": 10, "limit": 100},
+            {"rateLimitType": "ORDERS", "interval": "DAY", "intervalNum": 1, "limit": 200000},
+            {"rateLimitType": "RAW_REQUESTS", "interval": "MINUTE", "intervalNum": 5, "limit": 61000},
+        ],
+        "exchangeFilters": [],
+        "symbols": listed.iter().map(symbol_info).collect::<Vec<_>>(),
+    }))
+}
+
+fn symbol_info(s: &MockSymbol) -> Value {
+    json!({
+        "symbol": s.symbol,
+        "status": "TRADING",
+        "baseAsset": s.base,
+        "baseAssetPrecision": 8,
+        "quoteAsset": s.quote,
+        "quotePrecision": 8,
+        "quoteAssetPrecision": 8,
+        "baseCommissionPrecision": 8,
+        "quoteCommissionPrecision": 8,
+        "orderTypes": ["LIMIT", "LIMIT_MAKER", "MARKET", "STOP_LOSS", "STOP_LOSS_LIMIT", "TAKE_PROFIT", "TAKE_PROFIT_LIMIT"],
+        "icebergAllowed": true,
+        "ocoAllowed": true,
+        "otoAllowed": true,
+        "quoteOrderQtyMarketAllowed": true,
+        "allowTrailingStop": true,
+        "cancelReplaceAllowed": true,
+        "isSpotTradingAllowed": true,
+        "isMarginTradingAllowed": true,
+        "filters": [
+            {"filterType": "PRICE_FILTER", "minPrice": fmt(s.tick_size), "maxPrice": "1000000.00000000", "tickSize": fmt(s.tick_size)},
+            {"filterType": "LOT_SIZE", "minQty": fmt(s.step_size), "maxQty": "9000.00000000", "stepSize": fmt(s.step_size)},
+            {"filterType": "ICEBERG_PARTS", "limit": 10},
+            {"filterType": "MARKET_LOT_SIZE", "minQty": "0.00000000", "maxQty": "100.00000000", "stepSize": "0.00000000"},
+            {"filterType": "TRAILING_DELTA", "minTrailingAboveDelta": 10, "maxTrailingAboveDelta": 2000, "minTrailingBelowDelta": 10, "maxTrailingBelowDelta": 2000},
+            {
+                "filterType": "PERCENT_PRICE_BY_SIDE",
+                "bidMultiplierUp": "5", "bidMultiplierDown": "0.2",
+                "askMultiplierUp": "5", "askMultiplierDown": "0.2",
+                "avgPriceMins": 5,
+            },
+            {
+                "filterType": "NOTIONAL",
+                "minNotional": fmt(s.min_notional), "applyMinToMarket": true,
+                "maxNotional": "9000000.00000000", "applyMaxToMarket": false,
+                "avgPriceMins": 5,
+            },
+            {"filterType": "MAX_NUM_ORDERS", "maxNumOrders": 200},
+            {"filterType": "MAX_NUM_ALGO_ORDERS", "maxNumAlgoOrders": 5},
+        ],
+        "permissions": [],
+        "permissionSets": [["SPOT", "MARGIN"]],
+        "defaultSelfTradePreventionMode": "EXPIRE_MAKER",
+        "allowedSelfTradePreventionModes": ["EXPIRE_TAKER", "EXPIRE_MAKER", "EXPIRE_BOTH"],
+    })
+}
+
+fn depth(state: &mut State, params: &Params) -> Reply {
+    let s = find_symbol(params.required("symbol")?)?;
+    let limit = params.parse_opt::<u32>("limit")?.unwrap_or(100).min(MAX_DEPTH_LIMIT);
+    if limit == 0 {
+        return Err(MockError::bad_request(-1100, "Illegal characters found in parameter 'limit'; legal range is '1'."));
+    }
+
+    state.market.last_update_id += 3;
+    let level = |i: u32, side: i64| {
+        let price = s.price + s.tick_size * Decimal::from(side * (i as i64 + 1));
+        json!([fmt(price), fmt(qty(&s, i as u64 * 2 + (side > 0) as u64))])
+    };
+    Ok(json!({
+        "lastUpdateId": state.market.last_update_id,
+        "bids": (0..limit).map(|i| level(i, -1)).collect::<Vec<_>>(),
+        "asks": (0..limit).map(|i| level(i, 1)).collect::<Vec<_>>(),
+    }))
+}
+
+fn trade(s: &MockSymbol, id: u64) -> Value {
+    let offset = s.last_trade_id - id;
+    let price = s.price + s.tick_size * Decimal::from(offset % 7) - s.tick_size * Decimal::from(3);
+    let qty = qty(s, id);
+    json!({
+        "id": id,
+        "price": fmt(price),
+        "qty": fmt(qty),
+        "quoteQty": fmt(price * qty),
+        "time": 1700000000000u64 + id % 1_000_000_000,
+        "isBuyerMaker": id.is_multiple_of(2),
+        "isBestMatch": true,
+    })
+}
+
+fn trades(params: &Params, historical: bool) -> Reply {
+    let s = find_symbol(params.required("symbol")?)?;
+    let limit = params.parse_opt::<u32>("limit")?.unwrap_or(DEFAULT_TRADES_LIMIT).min(MAX_TRADES_LIMIT) as u64;
+
+    let from_id = match params.parse_opt::<u64>("fromId")? {
+        Some(from_id) if historical => from_id.min(s.last_trade_id),
+        _ => s.last_trade_id + 1 - limit,
+    };
+    let to_id = (from_id + limit - 1).min(s.last_trade_id);
+    Ok(Value::Array((from_id..=to_id).map(|id| trade(&s, id)).collect()))
+}
+
+fn agg_trades(state: &State, params: &Params) -> Reply {
+    let s = find_symbol(params.required("symbol")?)?;
+    let limit = params.parse_opt::<u32>("limit")?.unwrap_or(DEFAULT_TRADES_LIMIT).min(MAX_TRADES_LIMIT) as u64;
+    let start_time = params.parse_opt::<u64>("startTime")?;
+    let end_time = params.parse_opt::<u64>("endTime")?;
+    if let (Some(start_time), Some(end_time)) = (start_time, end_time) {
+        if end_time < start_time || end_time - start_time > 3_600_000 {
+            return Err(MockError::bad_request(-1127, "More than 1 hours between startTime and endTime."));
+        }
+    }
+
+    // one aggregate trade every 100ms, ids counted from the epoch
+    let last_id = state.now() / 100;
+    let first_id = match (params.parse_opt::<u64>("fromId")?, start_time) {
+        (Some(from_id), _) => from_id,
+        (None, Some(start_time)) => start_time.div_ceil(100),
+        (None, None) => last_id + 1 - limit,
+    };
+    let mut last = (first_id + limit - 1).min(last_id);
+    if let Some(end_time) = end_time {
+        last = last.min(end_time / 100);
+    }
+
+    let aggs: Vec<Value> = (first_id..=last).map(|id| {
+        let price = s.price + s.tick_size * Decimal::from(id % 5);
+        json!({
+            "a": id,
+            "p": fmt(price),
+            "q": fmt(qty(&s, id)),
+            "f": id * 2,
+            "l": id * 2 + 1,
+            "T": id * 100,
+            "m": id % 3 == 0,
+            "M": true,
+        })
+    }).collect();
+    Ok(Value::Array(aggs))
+}
+
+fn interval_ms(interval: &str) -> Option<u64> {
+    let ms = match interval {
+        "1s" => 1_000,
+        "1m" => 60_000,
+        "3m" => 180_000,
+        "5m" => 300_000,
+        "15m" => 900_000,
+        "30m" => 1_800_000,
+        "1h" => 3_600_000,
+        "2h" => 7_200_000,
+        "4h" => 14_400_000,
+        "6h" => 21_600_000,
+        "8h" => 28_800_000,
+        "12h" => 43_200_000,
+        "1d" => 86_400_000,
+        "3d" => 259_200_000,
+        "1w" => 604_800_000,
+        "1M" => 2_592_000_000,
+        _ => {
+            return None;
+        },
+    };
+    Some(ms)
+}
+
+fn klines(state: &State, params: &Params) -> Reply {
+    let s = find_symbol(params.required("symbol")?)?;
+    let interval = match interval_ms(params.required("interval")?) {
+        Some(interval) => interval,
+        None => {
+            return Err(MockError::bad_request(-1120, "Invalid interval."));
+        },
+    };
+    let limit = params.parse_opt::<u32>("limit")?.unwrap_or(DEFAULT_TRADES_LIMIT).min(MAX_TRADES_LIMIT) as u64;
+    let end = params.parse_opt::<u64>("endTime")?.unwrap_or_else(|| state.now()) / interval;
+    let first = match params.parse_opt::<u64>("startTime")? {
+        Some(start_time) => start_time.div_ceil(interval),
+        None => (end + 1).saturating_sub(limit),
+    };
+    let last = (first + limit - 1).min(end);
+
+    let klines: Vec<Value> = (first..=last).map(|n| {
+        let open = s.price + s.tick_size * Decimal::from(n % 11);
+        let close = s.price + s.tick_size * Decimal::from((n + 3) % 11);
+        let volume = qty(&s, n) * Decimal::from(10);
+        json!([
+            n * interval,
+            fmt(open),
+            fmt(open.max(close) + s.tick_size * Decimal::from(2)),
+            fmt(open.min(close) - s.tick_size * Decimal::from(2)),
+            fmt(close),
+            fmt(volume),
+            (n + 1) * interval - 1,
+            fmt(volume * s.price),
+            n % 900 + 100,
+            fmt(volume / Decimal::from(2)),
+            fmt(volume * s.price / Decimal::from(2)),
+            "0",
+        ])
+    }).collect();
+    Ok(Value::Array(klines))
+}
+
+fn avg_price(state: &State, params: &Params) -> Reply {
+    let s = find_symbol(params.required("symbol")?)?;
+    Ok(json!({"mins": 5, "price": fmt(s.price), "closeTime": state.now()}))
+}
+
+fn for_symbols(params: &Params, f: impl Fn(&MockSymbol) -> Value) -> Reply {
+    match params.get("symbol") {
+        Some(symbol) => Ok(f(&find_symbol(symbol)?)),
+        None => Ok(Value::Array(symbols().iter().map(f).collect())),
+    }
+}
+
+fn ticker_24hr(state: &State, s: &MockSymbol) -> Value {
+    let change = s.tick_size * Decimal::from(125);
+    let open = s.price - change;
+    let volume = qty(s, 1) * Decimal::from(10000);
+    json!({
+        "symbol": s.symbol,
+        "priceChange": fmt(change),
+        "priceChangePercent": format!("{:.3}", change / open * Decimal::from(100)),
+        "weightedAvgPrice": fmt(s.price - change / Decimal::from(2)),
+        "prevClosePrice": fmt(open),
+        "lastPrice": fmt(s.price),
+        "lastQty": fmt(qty(s, 2)),
+        "bidPrice": fmt(s.price - s.tick_size),
+        "bidQty": fmt(qty(s, 3)),
+        "askPrice": fmt(s.price + s.tick_size),
+        "askQty": fmt(qty(s, 4)),
+        "openPrice": fmt(open),
+        "highPrice": fmt(s.price + change),
+        "lowPrice": fmt(open - change),
+        "volume": fmt(volume),
+        "quoteVolume": fmt(volume * s.price),
+        "openTime": state.now() - 86_400_000,
+        "closeTime": state.now(),
+        "firstId": s.last_trade_id - 250_000,
+        "lastId": s.last_trade_id,
+        "count": 250_001,
+    })
+}
+
+fn book_ticker(s: &MockSymbol) -> Value {
+    json!({
+        "symbol": s.symbol,
+        "bidPrice": fmt(s.price - s.tick_size),
+        "bidQty": fmt(qty(s, 3)),
+        "askPrice": fmt(s.price + s.tick_size),
+        "askQty": fmt(qty(s, 4)),
+    })
+}
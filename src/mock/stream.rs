@@ -0,0 +1,231 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures::{SinkExt, StreamExt};
+use hyper::{header, Body, Request, Response, StatusCode};
+use log::debug;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio_tungstenite::WebSocketStream;
+use tungstenite::{handshake::derive_accept_key, protocol::Role, Message};
+
+use crate::mock::State;
+
+#[derive(Debug, Default)]
+pub(crate) struct StreamState {
+    next_connection_id: u64,
+    connections: Vec<MockConnection>,
+    pub(crate) control_messages: Vec<Value>,
+    pub(crate) delays: HashMap<String, Duration>,
+}
+
+impl StreamState {
+    pub(crate) fn connection_count(&self) -> usize {
+        self.connections.len()
+    }
+}
+
+// Binance drops a connection that sends more than 5 messages a second
+const MAX_MESSAGES_PER_SECOND: usize = 5;
+
+#[derive(Debug)]
+struct MockConnection {
+    id: u64,
+    streams: Vec<String>,
+    // `/stream?streams=` wraps events in `{"stream": ..., "data": ...}`, `/ws/` does not
+    combined: bool,
+    tx: mpsc::UnboundedSender<Message>,
+}
+
+pub(crate) fn is_websocket(req: &Request<Body>) -> bool {
+    req.headers()
+        .get(header::UPGRADE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("websocket"))
+}
+
+/// Accepts `/ws/<stream>` and `/stream?streams=<a>/<b>`, the stream names as subscribed.
+/// More can be added with `SUBSCRIBE` messages.
+pub(crate) fn upgrade(state: Arc<Mutex<State>>, req: Request<Body>) -> Response<Body> {
+    let (streams, combined) = match (req.uri().path(), req.uri().query()) {
+        ("/stream", query) => {
+            let streams = query.unwrap_or_default().strip_prefix("streams=").unwrap_or_default();
+            (streams.split('/').filter(|s| !s.is_empty()).map(String::from).collect(), true)
+        },
+        (path, _) if path.starts_with("/ws/") => (vec![path.trim_start_matches("/ws/").to_string()], false),
+        _ => {
+            return Response::builder().status(StatusCode::NOT_FOUND).body(Body::empty()).unwrap();
+        },
+    };
+    let response = match switching_protocols(&req) {
+        Some(response) => response,
+        None => {
+            return Response::builder().status(StatusCode::BAD_REQUEST).body(Body::empty()).unwrap();
+        },
+    };
+
+    // registered before the handshake completes, so nothing pushed after `connect` is lost
+    let (tx, rx) = mpsc::unbounded_channel();
+    let id = {
+        let mut state = state.lock().unwrap();
+        let streams_state = &mut state.streams;
+        streams_state.next_connection_id += 1;
+        let id = streams_state.next_connection_id;
+        streams_state.connections.push(MockConnection { id, streams, combined, tx });
+        id
+    };
+    tokio::spawn(async move {
+        match hyper::upgrade::on(req).await {
+            Ok(upgraded) => {
+                let socket = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+                run(&state, id, socket, rx).await;
+            },
+            Err(err) => debug!("mock websocket upgrade failed: {}", err),
+        }
+        state.lock().unwrap().streams.connections.retain(|c| c.id != id);
+    });
+
+    response
+}
+
+/// The 101 answer to a websocket handshake, `None` without a `Sec-WebSocket-Key`.
+pub(crate) fn switching_protocols(req: &Request<Body>) -> Option<Response<Body>> {
+    let key = derive_accept_key(req.headers().get(header::SEC_WEBSOCKET_KEY)?.as_bytes());
+    let response = Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header(header::CONNECTION, "Upgrade")
+        .header(header::UPGRADE, "websocket")
+        .header(header::SEC_WEBSOCKET_ACCEPT, key)
+        .body(Body::empty())
+        .unwrap();
+    Some(response)
+}
+
+async fn run<S>(state: &Mutex<State>, id: u64, mut socket: WebSocketStream<S>, mut rx: mpsc::UnboundedReceiver<Message>)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    let mut received = VecDeque::new();
+    loop {
+        tokio::select! {
+            message = rx.recv() => match message {
+                Some(Message::Close(frame)) => {
+                    socket.close(frame).await.ok();
+                },
+                Some(message) => {
+                    if socket.send(message).await.is_err() {
+                        return;
+                    }
+                },
+                None => {
+                    return;
+                },
+            },
+            frame = socket.next() => match frame {
+                Some(Ok(Message::Text(text))) => {
+                    let now = Instant::now();
+                    received.push_back(now);
+                    received.retain(|at| now.duration_since(*at) < Duration::from_secs(1));
+                    if received.len() > MAX_MESSAGES_PER_SECOND {
+                        debug!("mock websocket {} sent too many messages", id);
+                        return;
+                    }
+                    let (reply, delay) = {
+                        let mut state = state.lock().unwrap();
+                        let (reply, method) = control(&mut state, id, &text);
+                        let tx = state.streams.connections.iter().find(|c| c.id == id).map(|c| c.tx.clone());
+                        (reply, state.streams.delays.get(&method).copied().zip(tx))
+                    };
+                    match delay {
+                        Some((delay, tx)) => {
+                            tokio::spawn(async move {
+                                tokio::time::sleep(delay).await;
+                                tx.send(Message::Text(reply.to_string())).ok();
+                            });
+                        },
+                        None => {
+                            if socket.send(Message::Text(reply.to_string())).await.is_err() {
+                                return;
+                            }
+                        },
+                    }
+                },
+                Some(Ok(_)) => {},
+                _ => {
+                    return;
+                },
+            },
+        }
+    }
+}
+
+/// Answers `SUBSCRIBE`, `UNSUBSCRIBE` and `LIST_SUBSCRIPTIONS` like Binance does, along with the
+/// method.
+fn control(state: &mut State, id: u64, text: &str) -> (Value, String) {
+    let request: Value = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(_) => {
+            return (json!({"error": {"code": 3, "msg": "Invalid JSON"}, "id": null}), String::new());
+        },
+    };
+    state.streams.control_messages.push(request.clone());
+    let method = request["method"].as_str().unwrap_or_default().to_string();
+    (answer(state, id, &request), method)
+}
+
+fn answer(state: &mut State, id: u64, request: &Value) -> Value {
+    let request_id = request["id"].clone();
+    let invalid = |msg: &str| json!({"error": {"code": 2, "msg": format!("Invalid request: {}", msg)}, "id": request_id});
+    let connection = match state.streams.connections.iter_mut().find(|c| c.id == id) {
+        Some(connection) => connection,
+        None => {
+            return invalid("connection closed");
+        },
+    };
+    let params: Option<Vec<String>> = request["params"]
+        .as_array()
+        .map(|params| params.iter().filter_map(Value::as_str).filter(|s| !s.is_empty()).map(String::from).collect());
+
+    match (request["method"].as_str(), params) {
+        (Some("SUBSCRIBE"), Some(params)) if !params.is_empty() => {
+            for stream in params {
+                if !connection.streams.contains(&stream) {
+                    connection.streams.push(stream);
+                }
+            }
+            json!({"result": null, "id": request_id})
+        },
+        (Some("UNSUBSCRIBE"), Some(params)) if !params.is_empty() => {
+            connection.streams.retain(|stream| !params.contains(stream));
+            json!({"result": null, "id": request_id})
+        },
+        (Some("LIST_SUBSCRIPTIONS"), _) => json!({"result": connection.streams, "id": request_id}),
+        (Some("SUBSCRIBE"), _) | (Some("UNSUBSCRIBE"), _) => invalid("streams missing"),
+        _ => invalid("unknown method"),
+    }
+}
+
+/// Ends every connection without a close frame, like a network failure.
+pub(crate) fn drop_connections(state: &mut State) -> usize {
+    // the connection tasks end once their senders are gone
+    state.streams.connections.drain(..).count()
+}
+
+/// Sends `data` to every connection subscribed to `stream`, returns how many there were.
+pub(crate) fn push(state: &mut State, stream: &str, data: &Value) -> usize {
+    let mut sent = 0;
+    for connection in state.streams.connections.iter().filter(|c| c.streams.iter().any(|s| s == stream)) {
+        let text = if connection.combined {
+            json!({"stream": stream, "data": data}).to_string()
+        } else {
+            data.to_string()
+        };
+        if connection.tx.send(Message::Text(text)).is_ok() {
+            sent += 1;
+        }
+    }
+    sent
+}
@@ -0,0 +1,903 @@
+use hyper::Method;
+use serde_json::{json, Map, Value};
+
+use crate::mock::market::{find_symbol, fmt, MockSymbol};
+use crate::mock::{MockError, Params, Reply, State};
+use crate::Decimal;
+
+const DEFAULT_ORDERS_LIMIT: usize = 500;
+const MAX_ORDERS_LIMIT: usize = 1000;
+const MAX_NUM_ORDERS: usize = 200;
+const MAX_ICEBERG_PARTS: u32 = 10;
+const DEFAULT_STP_MODE: &str = "EXPIRE_MAKER";
+
+/// Orders placed against the server. Marketable orders fill completely at the symbol's price,
+/// everything else rests until canceled; stop orders never trigger.
+#[derive(Debug)]
+pub(crate) struct TradeState {
+    next_order_id: u64,
+    next_trade_id: u64,
+    next_order_list_id: i64,
+    next_execution_id: u64,
+    pub(crate) orders: Vec<MockOrder>,
+    pub(crate) order_lists: Vec<MockOrderList>,
+}
+
+impl Default for TradeState {
+    fn default() -> Self {
+        TradeState {
+            next_order_id: 28457,
+            next_trade_id: 9_000_000,
+            next_order_list_id: 1200,
+            next_execution_id: 60_000_000,
+            orders: vec![],
+            order_lists: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct MockOrder {
+    pub(crate) symbol: String,
+    pub(crate) order_id: u64,
+    pub(crate) order_list_id: i64,
+    pub(crate) client_order_id: String,
+    pub(crate) price: Decimal,
+    pub(crate) orig_qty: Decimal,
+    pub(crate) executed_qty: Decimal,
+    pub(crate) cummulative_quote_qty: Decimal,
+    pub(crate) status: &'static str,
+    pub(crate) time_in_force: String,
+    pub(crate) order_type: String,
+    pub(crate) side: String,
+    pub(crate) stop_price: Decimal,
+    pub(crate) iceberg_qty: Decimal,
+    pub(crate) time: u64,
+    pub(crate) update_time: u64,
+    pub(crate) working_time: i64,
+    pub(crate) orig_quote_order_qty: Decimal,
+    pub(crate) self_trade_prevention_mode: String,
+    pub(crate) fills: Vec<Value>,
+}
+
+impl MockOrder {
+    pub(crate) fn is_open(&self) -> bool {
+        self.status == "NEW" || self.status == "PARTIALLY_FILLED" || self.status == "PENDING_NEW"
+    }
+
+    /// As `GET /api/v3/order` reports it.
+    pub(crate) fn to_json(&self) -> Value {
+        json!({
+            "symbol": self.symbol,
+            "orderId": self.order_id,
+            "orderListId": self.order_list_id,
+            "clientOrderId": self.client_order_id,
+            "price": fmt(self.price),
+            "origQty": fmt(self.orig_qty),
+            "executedQty": fmt(self.executed_qty),
+            "cummulativeQuoteQty": fmt(self.cummulative_quote_qty),
+            "status": self.status,
+            "timeInForce": self.time_in_force,
+            "type": self.order_type,
+            "side": self.side,
+            "stopPrice": fmt(self.stop_price),
+            "icebergQty": fmt(self.iceberg_qty),
+            "time": self.time,
+            "updateTime": self.update_time,
+            "isWorking": self.working_time >= 0,
+            "workingTime": self.working_time,
+            "origQuoteOrderQty": fmt(self.orig_quote_order_qty),
+            "selfTradePreventionMode": self.self_trade_prevention_mode,
+        })
+    }
+
+    /// The order part of a `newOrderRespType` RESULT answer, shared by order lists.
+    pub(crate) fn result_json(&self) -> Map<String, Value> {
+        let mut result = Map::new();
+        result.insert(String::from("symbol"), json!(self.symbol));
+        result.insert(String::from("orderId"), json!(self.order_id));
+        result.insert(String::from("orderListId"), json!(self.order_list_id));
+        result.insert(String::from("clientOrderId"), json!(self.client_order_id));
+        result.insert(String::from("transactTime"), json!(self.time));
+        result.insert(String::from("price"), json!(fmt(self.price)));
+        result.insert(String::from("origQty"), json!(fmt(self.orig_qty)));
+        result.insert(String::from("executedQty"), json!(fmt(self.executed_qty)));
+        result.insert(String::from("origQuoteOrderQty"), json!(fmt(self.orig_quote_order_qty)));
+        result.insert(String::from("cummulativeQuoteQty"), json!(fmt(self.cummulative_quote_qty)));
+        result.insert(String::from("status"), json!(self.status));
+        result.insert(String::from("timeInForce"), json!(self.time_in_force));
+        result.insert(String::from("type"), json!(self.order_type));
+        result.insert(String::from("side"), json!(self.side));
+        if !self.stop_price.is_zero() {
+            result.insert(String::from("stopPrice"), json!(fmt(self.stop_price)));
+        }
+        if !self.iceberg_qty.is_zero() {
+            result.insert(String::from("icebergQty"), json!(fmt(self.iceberg_qty)));
+        }
+        result.insert(String::from("workingTime"), json!(self.working_time));
+        result.insert(String::from("selfTradePreventionMode"), json!(self.self_trade_prevention_mode));
+        result
+    }
+
+    pub(crate) fn response(&self, resp_type: &str) -> Value {
+        match resp_type {
+            "ACK" => json!({
+                "symbol": self.symbol,
+                "orderId": self.order_id,
+                "orderListId": self.order_list_id,
+                "clientOrderId": self.client_order_id,
+                "transactTime": self.time,
+            }),
+            "RESULT" => Value::Object(self.result_json()),
+            _ => {
+                let mut result = self.result_json();
+                result.insert(String::from("fills"), Value::Array(self.fills.clone()));
+                Value::Object(result)
+            },
+        }
+    }
+
+    /// As `DELETE /api/v3/order` reports it, `client_order_id` being the id of the cancel.
+    pub(crate) fn cancel_json(&self, client_order_id: &str, transact_time: u64) -> Value {
+        let mut result = self.result_json();
+        result.remove("workingTime");
+        result.insert(String::from("origClientOrderId"), json!(self.client_order_id));
+        result.insert(String::from("clientOrderId"), json!(client_order_id));
+        result.insert(String::from("transactTime"), json!(transact_time));
+        Value::Object(result)
+    }
+}
+
+/// An OCO, OTO or OTOCO. Its status follows from the status of its orders.
+#[derive(Debug, Clone)]
+pub(crate) struct MockOrderList {
+    pub(crate) order_list_id: i64,
+    pub(crate) contingency_type: &'static str,
+    pub(crate) list_client_order_id: String,
+    pub(crate) symbol: String,
+    pub(crate) transaction_time: u64,
+    pub(crate) order_ids: Vec<u64>,
+}
+
+impl MockOrderList {
+    fn orders<'a>(&'a self, state: &'a State) -> impl Iterator<Item = &'a MockOrder> + 'a {
+        state.trade.orders.iter().filter(move |o| self.order_ids.contains(&o.order_id))
+    }
+
+    /// As `GET /api/v3/orderList` reports it.
+    fn to_json(&self, state: &State) -> Map<String, Value> {
+        let done = !self.orders(state).any(MockOrder::is_open);
+        let mut list = Map::new();
+        list.insert(String::from("orderListId"), json!(self.order_list_id));
+        list.insert(String::from("contingencyType"), json!(self.contingency_type));
+        list.insert(String::from("listStatusType"), json!(if done { "ALL_DONE" } else { "EXEC_STARTED" }));
+        list.insert(String::from("listOrderStatus"), json!(if done { "ALL_DONE" } else { "EXECUTING" }));
+        list.insert(String::from("listClientOrderId"), json!(self.list_client_order_id));
+        list.insert(String::from("transactionTime"), json!(self.transaction_time));
+        list.insert(String::from("symbol"), json!(self.symbol));
+        list.insert(String::from("orders"), Value::Array(self.orders(state).map(|o| json!({
+            "symbol": o.symbol,
+            "orderId": o.order_id,
+            "clientOrderId": o.client_order_id,
+        })).collect()));
+        list
+    }
+}
+
+pub(crate) fn handle(state: &mut State, method: &Method, path: &str, params: &Params) -> Option<Reply> {
+    let reply = match (method.clone(), path) {
+        (Method::POST, "/api/v3/order") => new_order(state, params),
+        (Method::POST, "/api/v3/order/test") => test_order(state, params),
+        (Method::DELETE, "/api/v3/order") => cancel_order(state, params),
+        (Method::GET, "/api/v3/order") => query_order(state, params),
+        (Method::DELETE, "/api/v3/openOrders") => cancel_open_orders(state, params),
+        (Method::GET, "/api/v3/openOrders") => open_orders(state, params),
+        (Method::GET, "/api/v3/allOrders") => all_orders(state, params),
+        (Method::POST, "/api/v3/order/cancelReplace") => cancel_replace(state, params),
+        (Method::PUT, "/api/v3/order/amend/keepPriority") => amend_keep_priority(state, params),
+        (Method::POST, "/api/v3/orderList/oco") => new_oco(state, params),
+        (Method::POST, "/api/v3/orderList/oto") => new_oto(state, params, false),
+        (Method::POST, "/api/v3/orderList/otoco") => new_oto(state, params, true),
+        (Method::DELETE, "/api/v3/orderList") => cancel_order_list(state, params),
+        (Method::GET, "/api/v3/orderList") => query_order_list(state, params),
+        _ => {
+            return None;
+        },
+    };
+    Some(reply)
+}
+
+fn one_of(params: &Params, key: &str, values: &[&str], code: i32, msg: &str) -> std::result::Result<Option<String>, MockError> {
+    match params.get(key) {
+        Some(value) if values.contains(&value) => Ok(Some(value.to_string())),
+        Some(_) => Err(MockError::bad_request(code, msg)),
+        None => Ok(None),
+    }
+}
+
+fn not_required(key: &str) -> MockError {
+    MockError::bad_request(-1106, format!("Parameter '{}' sent when not required.", key))
+}
+
+fn filter_failure(filter: &str) -> MockError {
+    MockError::bad_request(-1013, format!("Filter failure: {}", filter))
+}
+
+fn unknown_order() -> MockError {
+    MockError::bad_request(-2011, "Unknown order sent.")
+}
+
+pub(crate) fn client_order_id(state: &State, order_id: u64) -> String {
+    format!("mock{:x}{:x}", state.now(), order_id)
+}
+
+/// Builds the order and executes it, without storing it.
+fn place_order(state: &mut State, params: &Params) -> std::result::Result<MockOrder, MockError> {
+    let mut order = build_order(state, params)?;
+    let s = find_symbol(&order.symbol)?;
+    execute(state, &s, &mut order)?;
+    Ok(order)
+}
+
+/// Checks the order params like the matching engine does and builds the order, without executing it.
+fn build_order(state: &mut State, params: &Params) -> std::result::Result<MockOrder, MockError> {
+    let s = find_symbol(params.required("symbol")?)?;
+    let side = one_of(params, "side", &["BUY", "SELL"], -1100, "Illegal characters found in parameter 'side'.")?
+        .ok_or_else(|| MockError::mandatory("side"))?;
+    let order_type = one_of(
+        params,
+        "type",
+        &["LIMIT", "MARKET", "STOP_LOSS", "STOP_LOSS_LIMIT", "TAKE_PROFIT", "TAKE_PROFIT_LIMIT", "LIMIT_MAKER"],
+        -1116,
+        "Invalid orderType.",
+    )?.ok_or_else(|| MockError::mandatory("type"))?;
+    let time_in_force = one_of(params, "timeInForce", &["GTC", "IOC", "FOK"], -1115, "Invalid timeInForce.")?;
+    let quantity = params.parse_opt::<Decimal>("quantity")?;
+    let quote_order_qty = params.parse_opt::<Decimal>("quoteOrderQty")?;
+    let price = params.parse_opt::<Decimal>("price")?;
+    let stop_price = params.parse_opt::<Decimal>("stopPrice")?;
+    let trailing_delta = params.parse_opt::<u32>("trailingDelta")?;
+    let iceberg_qty = params.parse_opt::<Decimal>("icebergQty")?;
+    let stp_mode = one_of(
+        params,
+        "selfTradePreventionMode",
+        &["NONE", "EXPIRE_TAKER", "EXPIRE_MAKER", "EXPIRE_BOTH", "DECREMENT"],
+        -1100,
+        "Illegal characters found in parameter 'selfTradePreventionMode'.",
+    )?.unwrap_or_else(|| String::from(DEFAULT_STP_MODE));
+
+    let (needs_time_in_force, needs_price, needs_stop) = match order_type.as_str() {
+        "LIMIT" => (true, true, false),
+        "MARKET" => (false, false, false),
+        "STOP_LOSS" | "TAKE_PROFIT" => (false, false, true),
+        "STOP_LOSS_LIMIT" | "TAKE_PROFIT_LIMIT" => (true, true, true),
+        _ => (false, true, false),
+    };
+    match (needs_time_in_force, &time_in_force) {
+        (true, None) => {
+            return Err(MockError::mandatory("timeInForce"));
+        },
+        (false, Some(_)) => {
+            return Err(not_required("timeInForce"));
+        },
+        _ => {},
+    }
+    match (needs_price, price) {
+        (true, None) => {
+            return Err(MockError::mandatory("price"));
+        },
+        (false, Some(_)) => {
+            return Err(not_required("price"));
+        },
+        _ => {},
+    }
+    if needs_stop && stop_price.is_none() && trailing_delta.is_none() {
+        return Err(MockError::bad_request(-1102, "Param 'stopPrice' or 'trailingDelta' must be sent, but both were empty/null!"));
+    }
+    if !needs_stop && stop_price.is_some() {
+        return Err(not_required("stopPrice"));
+    }
+    if !needs_stop && trailing_delta.is_some() {
+        return Err(not_required("trailingDelta"));
+    }
+    if !needs_price && iceberg_qty.is_some() {
+        return Err(not_required("icebergQty"));
+    }
+    let quantity = match (order_type.as_str(), quantity, quote_order_qty) {
+        ("MARKET", None, None) => {
+            return Err(MockError::bad_request(-1102, "Param 'quantity' or 'quoteOrderQty' must be sent, but both were empty/null!"));
+        },
+        ("MARKET", Some(_), Some(_)) => {
+            return Err(not_required("quoteOrderQty"));
+        },
+        // the quote amount is converted at the current price, rounded down to the step size
+        ("MARKET", None, Some(quote_order_qty)) => (quote_order_qty / s.price / s.step_size).floor() * s.step_size,
+        (_, Some(quantity), None) => quantity,
+        (_, None, _) => {
+            return Err(MockError::mandatory("quantity"));
+        },
+        (_, Some(_), Some(_)) => {
+            return Err(not_required("quoteOrderQty"));
+        },
+    };
+
+    check_filters(state, &s, price, stop_price, quantity, iceberg_qty)?;
+
+    let now = state.now();
+    state.trade.next_order_id += 1;
+    let order_id = state.trade.next_order_id;
+    let client_order_id = match params.get("newClientOrderId") {
+        Some(client_order_id) => client_order_id.to_string(),
+        None => client_order_id(state, order_id),
+    };
+    let order = MockOrder {
+        symbol: s.symbol.to_string(),
+        order_id,
+        order_list_id: -1,
+        client_order_id,
+        price: price.unwrap_or_default(),
+        orig_qty: quantity,
+        executed_qty: Decimal::ZERO,
+        cummulative_quote_qty: Decimal::ZERO,
+        status: "NEW",
+        time_in_force: time_in_force.unwrap_or_else(|| String::from("GTC")),
+        order_type,
+        side,
+        stop_price: stop_price.unwrap_or_default(),
+        iceberg_qty: iceberg_qty.unwrap_or_default(),
+        time: now,
+        update_time: now,
+        working_time: now as i64,
+        orig_quote_order_qty: quote_order_qty.unwrap_or_default(),
+        self_trade_prevention_mode: stp_mode,
+        fills: vec![],
+    };
+    Ok(order)
+}
+
+fn check_filters(
+    state: &State,
+    s: &MockSymbol,
+    price: Option<Decimal>,
+    stop_price: Option<Decimal>,
+    quantity: Decimal,
+    iceberg_qty: Option<Decimal>,
+) -> std::result::Result<(), MockError> {
+    for price in price.iter().chain(stop_price.iter()) {
+        if *price < s.tick_size || !(*price % s.tick_size).is_zero() {
+            return Err(filter_failure("PRICE_FILTER"));
+        }
+    }
+    if quantity < s.step_size || !(quantity % s.step_size).is_zero() {
+        return Err(filter_failure("LOT_SIZE"));
+    }
+    if let Some(price) = price {
+        let (up, down) = (Decimal::from(5), Decimal::new(2, 1));
+        if price > s.price * up || price < s.price * down {
+            return Err(filter_failure("PERCENT_PRICE_BY_SIDE"));
+        }
+    }
+    if price.unwrap_or(s.price) * quantity < s.min_notional {
+        return Err(filter_failure("NOTIONAL"));
+    }
+    if let Some(iceberg_qty) = iceberg_qty {
+        if iceberg_qty.is_zero() || (quantity / iceberg_qty).ceil() > Decimal::from(MAX_ICEBERG_PARTS) {
+            return Err(filter_failure("ICEBERG_PARTS"));
+        }
+    }
+    let open = state.trade.orders.iter().filter(|o| o.symbol == s.symbol && o.is_open()).count();
+    if open >= MAX_NUM_ORDERS {
+        return Err(filter_failure("MAX_NUM_ORDERS"));
+    }
+    Ok(())
+}
+
+/// Fills marketable orders at the symbol's price and rejects what Binance would reject.
+fn execute(state: &mut State, s: &MockSymbol, order: &mut MockOrder) -> std::result::Result<(), MockError> {
+    let buy = order.side == "BUY";
+    let marketable = match order.order_type.as_str() {
+        "MARKET" => true,
+        "LIMIT" | "LIMIT_MAKER" => if buy { order.price >= s.price } else { order.price <= s.price },
+        _ => false,
+    };
+
+    if order.order_type.starts_with("STOP_LOSS") || order.order_type.starts_with("TAKE_PROFIT") {
+        let stop_loss = order.order_type.starts_with("STOP_LOSS");
+        let triggered = !order.stop_price.is_zero() && if buy == stop_loss {
+            s.price >= order.stop_price
+        } else {
+            s.price <= order.stop_price
+        };
+        if triggered {
+            return Err(MockError::bad_request(-2010, "Stop price would trigger immediately."));
+        }
+        order.working_time = -1;
+        return Ok(());
+    }
+    if order.order_type == "LIMIT_MAKER" && marketable {
+        return Err(MockError::bad_request(-2010, "Order would immediately match and take."));
+    }
+    if !marketable {
+        if order.time_in_force != "GTC" {
+            order.status = "EXPIRED";
+        }
+        return Ok(());
+    }
+
+    state.trade.next_trade_id += 1;
+    let quote_qty = order.orig_qty * s.price;
+    let (commission, commission_asset) = if buy {
+        (order.orig_qty * Decimal::new(1, 3), s.base)
+    } else {
+        (quote_qty * Decimal::new(1, 3), s.quote)
+    };
+    order.fills.push(json!({
+        "price": fmt(s.price),
+        "qty": fmt(order.orig_qty),
+        "commission": fmt(commission),
+        "commissionAsset": commission_asset,
+        "tradeId": state.trade.next_trade_id,
+    }));
+    order.executed_qty = order.orig_qty;
+    order.cummulative_quote_qty = quote_qty;
+    order.status = "FILLED";
+    Ok(())
+}
+
+fn new_order(state: &mut State, params: &Params) -> Reply {
+    let resp_type = one_of(params, "newOrderRespType", &["ACK", "RESULT", "FULL"], -1100, "Illegal characters found in parameter 'newOrderRespType'.")?;
+    if let Some(client_order_id) = params.get("newClientOrderId") {
+        if state.trade.orders.iter().any(|o| o.client_order_id == client_order_id && o.is_open()) {
+            return Err(MockError::bad_request(-2010, "Duplicate order sent."));
+        }
+    }
+
+    let order = place_order(state, params)?;
+    // LIMIT and MARKET default to FULL, every other type to ACK
+    let resp_type = resp_type.unwrap_or_else(|| {
+        String::from(if order.order_type == "LIMIT" || order.order_type == "MARKET" { "FULL" } else { "ACK" })
+    });
+    let resp = order.response(&resp_type);
+    state.trade.orders.push(order);
+    Ok(resp)
+}
+
+fn test_order(state: &mut State, params: &Params) -> Reply {
+    let next_order_id = state.trade.next_order_id;
+    let next_trade_id = state.trade.next_trade_id;
+    let order = place_order(state, params);
+    state.trade.next_order_id = next_order_id;
+    state.trade.next_trade_id = next_trade_id;
+    order?;
+    Ok(json!({}))
+}
+
+fn find_order<'a>(state: &'a mut State, params: &Params) -> std::result::Result<Option<&'a mut MockOrder>, MockError> {
+    let symbol = params.required("symbol")?;
+    let order_id = params.parse_opt::<u64>("orderId")?;
+    let orig_client_order_id = params.get("origClientOrderId").map(String::from);
+    if order_id.is_none() && orig_client_order_id.is_none() {
+        return Err(MockError::bad_request(-1102, "Param 'origClientOrderId' or 'orderId' must be sent, but both were empty/null!"));
+    }
+    find_symbol(symbol)?;
+
+    // the latest order wins if a client order id was reused
+    Ok(state.trade.orders.iter_mut().rev().find(|o| {
+        o.symbol == symbol
+            && order_id.is_none_or(|id| o.order_id == id)
+            && orig_client_order_id.as_ref().is_none_or(|id| o.client_order_id == *id)
+    }))
+}
+
+fn cancel_order(state: &mut State, params: &Params) -> Reply {
+    let restrictions = one_of(params, "cancelRestrictions", &["ONLY_NEW", "ONLY_PARTIALLY_FILLED"], -1100, "Illegal characters found in parameter 'cancelRestrictions'.")?;
+    let now = state.now();
+    let cancel_id = match params.get("newClientOrderId") {
+        Some(cancel_id) => cancel_id.to_string(),
+        None => client_order_id(state, 0),
+    };
+
+    let order = match find_order(state, params)? {
+        Some(order) if order.is_open() => order,
+        _ => {
+            return Err(unknown_order());
+        },
+    };
+    let allowed = match restrictions.as_deref() {
+        Some("ONLY_NEW") => order.status == "NEW",
+        Some(_) => order.status == "PARTIALLY_FILLED",
+        None => true,
+    };
+    if !allowed {
+        return Err(MockError::bad_request(-2011, "Order was not canceled due to cancel restrictions."));
+    }
+
+    order.status = "CANCELED";
+    order.update_time = now;
+    let resp = order.cancel_json(&cancel_id, now);
+
+    // canceling one order of a list cancels the whole list
+    let order_list_id = order.order_list_id;
+    if order_list_id >= 0 {
+        for order in state.trade.orders.iter_mut().filter(|o| o.order_list_id == order_list_id && o.is_open()) {
+            order.status = "CANCELED";
+            order.update_time = now;
+        }
+    }
+    Ok(resp)
+}
+
+fn query_order(state: &mut State, params: &Params) -> Reply {
+    match find_order(state, params)? {
+        Some(order) => Ok(order.to_json()),
+        None => Err(MockError::bad_request(-2013, "Order does not exist.")),
+    }
+}
+
+fn cancel_open_orders(state: &mut State, params: &Params) -> Reply {
+    let symbol = find_symbol(params.required("symbol")?)?.symbol;
+    let now = state.now();
+    let cancel_id = client_order_id(state, 0);
+
+    let mut canceled = vec![];
+    let mut lists = vec![];
+    for order in state.trade.orders.iter_mut().filter(|o| o.symbol == symbol && o.is_open()) {
+        order.status = "CANCELED";
+        order.update_time = now;
+        if order.order_list_id < 0 {
+            canceled.push(order.cancel_json(&cancel_id, now));
+        } else if !lists.contains(&order.order_list_id) {
+            lists.push(order.order_list_id);
+        }
+    }
+    // orders of a list are reported with their list, in place of the first of them
+    for order_list_id in lists {
+        let list = state.trade.order_lists.iter().find(|l| l.order_list_id == order_list_id).cloned();
+        if let Some(list) = list {
+            canceled.push(cancel_list_json(state, &list, &cancel_id, now));
+        }
+    }
+    if canceled.is_empty() {
+        return Err(unknown_order());
+    }
+    Ok(Value::Array(canceled))
+}
+
+fn open_orders(state: &State, params: &Params) -> Reply {
+    let symbol = match params.get("symbol") {
+        Some(symbol) => Some(find_symbol(symbol)?.symbol),
+        None => None,
+    };
+    Ok(Value::Array(
+        state.trade.orders.iter()
+            .filter(|o| o.is_open() && symbol.is_none_or(|s| o.symbol == s))
+            .map(MockOrder::to_json)
+            .collect(),
+    ))
+}
+
+fn all_orders(state: &State, params: &Params) -> Reply {
+    let symbol = find_symbol(params.required("symbol")?)?.symbol;
+    let order_id = params.parse_opt::<u64>("orderId")?;
+    let start_time = params.parse_opt::<u64>("startTime")?.unwrap_or(0);
+    let end_time = params.parse_opt::<u64>("endTime")?.unwrap_or(u64::MAX);
+    let limit = params.parse_opt::<usize>("limit")?.unwrap_or(DEFAULT_ORDERS_LIMIT).min(MAX_ORDERS_LIMIT);
+
+    let orders: Vec<&MockOrder> = state.trade.orders.iter()
+        .filter(|o| o.symbol == symbol && o.time >= start_time && o.time <= end_time)
+        .filter(|o| order_id.is_none_or(|id| o.order_id >= id))
+        .collect();
+    // from orderId on, otherwise the most recent ones
+    let orders = match order_id {
+        Some(_) => &orders[..limit.min(orders.len())],
+        None => &orders[orders.len().saturating_sub(limit)..],
+    };
+    Ok(Value::Array(orders.iter().map(|o| o.to_json()).collect()))
+}
+
+// Some(true) if an order of this type waits for the price to rise, Some(false) if for it to fall
+fn rises(side: &str, order_type: &str) -> Option<bool> {
+    match order_type {
+        "LIMIT_MAKER" | "TAKE_PROFIT" | "TAKE_PROFIT_LIMIT" => Some(side == "SELL"),
+        "STOP_LOSS" | "STOP_LOSS_LIMIT" => Some(side == "BUY"),
+        _ => None,
+    }
+}
+
+fn trigger_price(order: &MockOrder) -> Option<Decimal> {
+    let price = if order.order_type == "LIMIT_MAKER" { order.price } else { order.stop_price };
+    Some(price).filter(|price| !price.is_zero())
+}
+
+fn wrong_relationship() -> MockError {
+    MockError::bad_request(-2010, "The relationship of the prices for the orders is not correct.")
+}
+
+/// The params of one order of a list, e.g. `aboveType` and `abovePrice` become `type` and `price`.
+fn leg_params(params: &Params, prefix: &str, side: &str, quantity: &str) -> std::result::Result<Params, MockError> {
+    let mut leg = vec![
+        (String::from("symbol"), params.required("symbol")?.to_string()),
+        (String::from("side"), params.required(side)?.to_string()),
+        (String::from("quantity"), params.required(quantity)?.to_string()),
+    ];
+    for key in ["Type", "Price", "StopPrice", "TrailingDelta", "TimeInForce", "IcebergQty"] {
+        if let Some(value) = params.get(&format!("{}{}", prefix, key)) {
+            let mut key = key.to_string();
+            key[..1].make_ascii_lowercase();
+            leg.push((key, value.to_string()));
+        }
+    }
+    if let Some(client_order_id) = params.get(&format!("{}ClientOrderId", prefix)) {
+        leg.push((String::from("newClientOrderId"), client_order_id.to_string()));
+    }
+    if let Some(stp_mode) = params.get("selfTradePreventionMode") {
+        leg.push((String::from("selfTradePreventionMode"), stp_mode.to_string()));
+    }
+    Ok(Params(leg))
+}
+
+// the above order of an OCO has to trigger above the below one
+fn build_oco(state: &mut State, params: &Params, prefixes: [&str; 2], side: &str, quantity: &str) -> std::result::Result<Vec<MockOrder>, MockError> {
+    let above = build_order(state, &leg_params(params, prefixes[0], side, quantity)?)?;
+    let below = build_order(state, &leg_params(params, prefixes[1], side, quantity)?)?;
+    if rises(&above.side, &above.order_type) != Some(true) || rises(&below.side, &below.order_type) != Some(false) {
+        return Err(MockError::bad_request(-1116, "Invalid orderType."));
+    }
+    if let (Some(above), Some(below)) = (trigger_price(&above), trigger_price(&below)) {
+        if above <= below {
+            return Err(wrong_relationship());
+        }
+    }
+    Ok(vec![above, below])
+}
+
+fn new_oco(state: &mut State, params: &Params) -> Reply {
+    place_order_list(state, params, "OCO", |state| {
+        let mut orders = build_oco(state, params, ["above", "below"], "side", "quantity")?;
+        let s = find_symbol(&orders[0].symbol)?;
+        for order in orders.iter_mut() {
+            execute(state, &s, order)?;
+        }
+        Ok(orders)
+    })
+}
+
+/// OTO, or OTOCO with `pending_oco`. The pending orders wait with PENDING_NEW until the working
+/// order is filled, which only happens right away here.
+fn new_oto(state: &mut State, params: &Params, pending_oco: bool) -> Reply {
+    place_order_list(state, params, "OTO", |state| {
+        let mut working = build_order(state, &leg_params(params, "working", "workingSide", "workingQuantity")?)?;
+        if working.order_type != "LIMIT" && working.order_type != "LIMIT_MAKER" {
+            return Err(MockError::bad_request(-1116, "Invalid orderType."));
+        }
+        let mut pending = if pending_oco {
+            build_oco(state, params, ["pendingAbove", "pendingBelow"], "pendingSide", "pendingQuantity")?
+        } else {
+            vec![build_order(state, &leg_params(params, "pending", "pendingSide", "pendingQuantity")?)?]
+        };
+
+        // a pending order must not trigger right at the working price
+        for order in &pending {
+            if let (Some(rises), Some(trigger)) = (rises(&order.side, &order.order_type), trigger_price(order)) {
+                if (rises && trigger <= working.price) || (!rises && trigger >= working.price) {
+                    return Err(wrong_relationship());
+                }
+            }
+        }
+
+        let s = find_symbol(&working.symbol)?;
+        execute(state, &s, &mut working)?;
+        for order in pending.iter_mut() {
+            if working.status != "FILLED" {
+                order.status = "PENDING_NEW";
+                order.working_time = -1;
+            } else if execute(state, &s, order).is_err() {
+                order.status = "EXPIRED";
+            }
+        }
+        let mut orders = vec![working];
+        orders.append(&mut pending);
+        Ok(orders)
+    })
+}
+
+fn place_order_list<F>(state: &mut State, params: &Params, contingency_type: &'static str, build: F) -> Reply
+where
+    F: FnOnce(&mut State) -> std::result::Result<Vec<MockOrder>, MockError>,
+{
+    let resp_type = one_of(params, "newOrderRespType", &["ACK", "RESULT", "FULL"], -1100, "Illegal characters found in parameter 'newOrderRespType'.")?
+        .unwrap_or_else(|| String::from("RESULT"));
+    let list_client_order_id = params.get("listClientOrderId").map(String::from);
+    if let Some(list_client_order_id) = &list_client_order_id {
+        let duplicate = state.trade.order_lists.iter()
+            .any(|l| l.list_client_order_id == *list_client_order_id && l.orders(state).any(MockOrder::is_open));
+        if duplicate {
+            return Err(MockError::bad_request(-2010, "Duplicate order sent."));
+        }
+    }
+
+    // nothing is kept of a list rejected halfway
+    let next_order_id = state.trade.next_order_id;
+    let next_trade_id = state.trade.next_trade_id;
+    let mut orders = match build(state) {
+        Ok(orders) => orders,
+        Err(err) => {
+            state.trade.next_order_id = next_order_id;
+            state.trade.next_trade_id = next_trade_id;
+            return Err(err);
+        },
+    };
+
+    state.trade.next_order_list_id += 1;
+    let order_list_id = state.trade.next_order_list_id;
+    let list = MockOrderList {
+        order_list_id,
+        contingency_type,
+        list_client_order_id: list_client_order_id.unwrap_or_else(|| client_order_id(state, order_list_id as u64)),
+        symbol: orders[0].symbol.clone(),
+        transaction_time: state.now(),
+        order_ids: orders.iter().map(|o| o.order_id).collect(),
+    };
+    for order in orders.iter_mut() {
+        order.order_list_id = order_list_id;
+    }
+    let reports: Vec<Value> = orders.iter().map(|o| o.response(&resp_type)).collect();
+    state.trade.orders.append(&mut orders);
+
+    let mut resp = list.to_json(state);
+    resp.insert(String::from("orderReports"), Value::Array(reports));
+    state.trade.order_lists.push(list);
+    Ok(Value::Object(resp))
+}
+
+fn find_order_list(state: &State, params: &Params, client_order_id_key: &str) -> std::result::Result<Option<MockOrderList>, MockError> {
+    let order_list_id = params.parse_opt::<i64>("orderListId")?;
+    let list_client_order_id = params.get(client_order_id_key);
+    if order_list_id.is_none() && list_client_order_id.is_none() {
+        return Err(MockError::bad_request(
+            -1102,
+            format!("Param 'orderListId' or '{}' must be sent, but both were empty/null!", client_order_id_key),
+        ));
+    }
+
+    Ok(state.trade.order_lists.iter().rev().find(|l| {
+        order_list_id.is_none_or(|id| l.order_list_id == id)
+            && list_client_order_id.is_none_or(|id| l.list_client_order_id == id)
+    }).cloned())
+}
+
+fn cancel_list_json(state: &State, list: &MockOrderList, cancel_id: &str, now: u64) -> Value {
+    let mut resp = list.to_json(state);
+    resp.insert(String::from("transactionTime"), json!(now));
+    resp.insert(String::from("orderReports"), Value::Array(list.orders(state).map(|o| o.cancel_json(cancel_id, now)).collect()));
+    Value::Object(resp)
+}
+
+fn cancel_order_list(state: &mut State, params: &Params) -> Reply {
+    let symbol = find_symbol(params.required("symbol")?)?.symbol;
+    let now = state.now();
+    let cancel_id = match params.get("newClientOrderId") {
+        Some(cancel_id) => cancel_id.to_string(),
+        None => client_order_id(state, 0),
+    };
+
+    let list = match find_order_list(state, params, "listClientOrderId")? {
+        Some(list) if list.symbol == symbol && list.orders(state).any(MockOrder::is_open) => list,
+        _ => {
+            return Err(MockError::bad_request(-2011, "Unknown order list sent."));
+        },
+    };
+    for order in state.trade.orders.iter_mut().filter(|o| list.order_ids.contains(&o.order_id) && o.is_open()) {
+        order.status = "CANCELED";
+        order.update_time = now;
+    }
+    Ok(cancel_list_json(state, &list, &cancel_id, now))
+}
+
+fn query_order_list(state: &mut State, params: &Params) -> Reply {
+    match find_order_list(state, params, "origClientOrderId")? {
+        Some(list) => Ok(Value::Object(list.to_json(state))),
+        None => Err(MockError::bad_request(-2013, "Order list does not exist.")),
+    }
+}
+
+fn outcome(reply: &Reply) -> (&'static str, Value) {
+    match reply {
+        Ok(resp) => ("SUCCESS", resp.clone()),
+        Err(err) => ("FAILURE", json!({"code": err.code, "msg": err.msg})),
+    }
+}
+
+/// Cancels with the `cancel*` params, then places the order the other params describe.
+fn cancel_replace(state: &mut State, params: &Params) -> Reply {
+    let mode = one_of(params, "cancelReplaceMode", &["STOP_ON_FAILURE", "ALLOW_FAILURE"], -1100, "Illegal characters found in parameter 'cancelReplaceMode'.")?
+        .ok_or_else(|| MockError::mandatory("cancelReplaceMode"))?;
+    let symbol = find_symbol(params.required("symbol")?)?.symbol;
+    let mut cancel = vec![(String::from("symbol"), symbol.to_string())];
+    for (key, cancel_key) in [
+        ("orderId", "cancelOrderId"),
+        ("origClientOrderId", "cancelOrigClientOrderId"),
+        ("newClientOrderId", "cancelNewClientOrderId"),
+        ("cancelRestrictions", "cancelRestrictions"),
+    ] {
+        if let Some(value) = params.get(cancel_key) {
+            cancel.push((key.to_string(), value.to_string()));
+        }
+    }
+    if params.get("cancelOrderId").is_none() && params.get("cancelOrigClientOrderId").is_none() {
+        return Err(MockError::bad_request(-1102, "Param 'cancelOrigClientOrderId' or 'cancelOrderId' must be sent, but both were empty/null!"));
+    }
+
+    let (cancel_result, cancel_response) = outcome(&cancel_order(state, &Params(cancel)));
+    let (new_order_result, new_order_response) = if cancel_result == "SUCCESS" || mode == "ALLOW_FAILURE" {
+        outcome(&new_order(state, params))
+    } else {
+        ("NOT_ATTEMPTED", Value::Null)
+    };
+
+    let resp = json!({
+        "cancelResult": cancel_result,
+        "newOrderResult": new_order_result,
+        "cancelResponse": cancel_response,
+        "newOrderResponse": new_order_response,
+    });
+    match (cancel_result, new_order_result) {
+        ("SUCCESS", "SUCCESS") => Ok(resp),
+        ("SUCCESS", _) | (_, "SUCCESS") => Err(MockError::bad_request(-2021, "Order cancel-replace partially failed.").with_data(resp)),
+        _ => Err(MockError::bad_request(-2022, "Order cancel-replace failed.").with_data(resp)),
+    }
+}
+
+/// Lowers the quantity of an open order, keeping its place in the queue.
+fn amend_keep_priority(state: &mut State, params: &Params) -> Reply {
+    let s = find_symbol(params.required("symbol")?)?;
+    let new_qty = params.decimal("newQty")?;
+    let now = state.now();
+    let new_client_order_id = match params.get("newClientOrderId") {
+        Some(new_client_order_id) => new_client_order_id.to_string(),
+        None => client_order_id(state, 0),
+    };
+    if new_qty <= Decimal::ZERO || !(new_qty % s.step_size).is_zero() {
+        return Err(filter_failure("LOT_SIZE"));
+    }
+
+    let order = match find_order(state, params)? {
+        Some(order) if order.is_open() => order,
+        _ => {
+            return Err(MockError::bad_request(-2013, "Order does not exist."));
+        },
+    };
+    if new_qty >= order.orig_qty {
+        return Err(MockError::bad_request(-2038, "Order amend (quantity increase) rejected."));
+    }
+
+    let orig_client_order_id = std::mem::replace(&mut order.client_order_id, new_client_order_id);
+    order.orig_qty = new_qty;
+    order.update_time = now;
+    let order = order.clone();
+    state.trade.next_execution_id += 1;
+
+    let mut amended = order.result_json();
+    for key in ["transactTime", "origQty", "cummulativeQuoteQty", "origQuoteOrderQty", "stopPrice", "icebergQty"] {
+        amended.remove(key);
+    }
+    amended.insert(String::from("origClientOrderId"), json!(orig_client_order_id));
+    amended.insert(String::from("qty"), json!(fmt(order.orig_qty)));
+    amended.insert(String::from("preventedQty"), json!("0.00000000"));
+    amended.insert(String::from("quoteOrderQty"), json!(fmt(order.orig_quote_order_qty)));
+    amended.insert(String::from("cumulativeQuoteQty"), json!(fmt(order.cummulative_quote_qty)));
+
+    let mut resp = json!({
+        "transactTime": now,
+        "executionId": state.trade.next_execution_id,
+        "amendedOrder": amended,
+    });
+    if let Some(list) = state.trade.order_lists.iter().find(|l| l.order_list_id == order.order_list_id) {
+        let mut list = list.to_json(state);
+        for key in ["listStatusType", "transactionTime"] {
+            list.remove(key);
+        }
+        resp["listStatus"] = Value::Object(list);
+    }
+    Ok(resp)
+}
@@ -0,0 +1,89 @@
+use hyper::Method;
+use rand::{distributions::Alphanumeric, Rng};
+use serde_json::json;
+
+use crate::mock::market::find_symbol;
+use crate::mock::{MockError, Params, Reply, State};
+
+const LISTEN_KEY_VALIDITY_MS: u64 = 60 * 60 * 1000;
+const LISTEN_KEY_LEN: usize = 60;
+
+/// Open listen keys. Creating a key for an account that has one returns the same key.
+#[derive(Debug, Default)]
+pub(crate) struct UserDataState {
+    pub(crate) listen_keys: Vec<MockListenKey>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct MockListenKey {
+    pub(crate) listen_key: String,
+    /// SPOT, MARGIN or the symbol of an isolated margin account.
+    pub(crate) account: String,
+    pub(crate) expires: u64,
+}
+
+pub(crate) fn handle(state: &mut State, method: &Method, path: &str, params: &Params) -> Option<Reply> {
+    let account = match path {
+        "/api/v3/userDataStream" => Ok(String::from("SPOT")),
+        "/sapi/v1/userDataStream" => Ok(String::from("MARGIN")),
+        "/sapi/v1/userDataStream/isolated" => params.required("symbol").and_then(find_symbol).map(|s| s.symbol.to_string()),
+        _ => {
+            return None;
+        },
+    };
+    let account = match account {
+        Ok(account) => account,
+        Err(err) => {
+            return Some(Err(err));
+        },
+    };
+
+    let reply = match method.clone() {
+        Method::POST => Ok(create(state, account)),
+        Method::PUT => keepalive(state, &account, params),
+        Method::DELETE => close(state, &account, params),
+        _ => {
+            return None;
+        },
+    };
+    Some(reply)
+}
+
+fn create(state: &mut State, account: String) -> serde_json::Value {
+    let now = state.now();
+    let keys = &mut state.user_data.listen_keys;
+    keys.retain(|k| k.expires > now);
+
+    let listen_key = match keys.iter_mut().find(|k| k.account == account) {
+        Some(key) => {
+            key.expires = now + LISTEN_KEY_VALIDITY_MS;
+            key.listen_key.clone()
+        },
+        None => {
+            let listen_key: String = rand::thread_rng().sample_iter(&Alphanumeric).take(LISTEN_KEY_LEN).map(char::from).collect();
+            keys.push(MockListenKey { listen_key: listen_key.clone(), account, expires: now + LISTEN_KEY_VALIDITY_MS });
+            listen_key
+        },
+    };
+    json!({"listenKey": listen_key})
+}
+
+fn find<'a>(state: &'a mut State, account: &str, params: &Params) -> std::result::Result<&'a mut MockListenKey, MockError> {
+    let listen_key = params.required("listenKey")?;
+    let now = state.now();
+    state.user_data.listen_keys.iter_mut()
+        .find(|k| k.listen_key == listen_key && k.account == account && k.expires > now)
+        .ok_or_else(|| MockError::bad_request(-1125, "This listenKey does not exist."))
+}
+
+fn keepalive(state: &mut State, account: &str, params: &Params) -> Reply {
+    let now = state.now();
+    find(state, account, params)?.expires = now + LISTEN_KEY_VALIDITY_MS;
+    Ok(json!({}))
+}
+
+fn close(state: &mut State, account: &str, params: &Params) -> Reply {
+    let listen_key = find(state, account, params)?.listen_key.clone();
+    state.user_data.listen_keys.retain(|k| k.listen_key != listen_key);
+    Ok(json!({}))
+}
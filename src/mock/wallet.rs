@@ -0,0 +1,546 @@
+use hyper::Method;
+use serde_json::{json, Map, Value};
+
+use crate::mock::{MockError, Params, Reply, State};
+use crate::Decimal;
+
+const TRANSFER_TYPES: [&str; 21] = [
+    "MAIN_UMFUTURE", "MAIN_CMFUTURE", "MAIN_MARGIN", "UMFUTURE_MAIN", "UMFUTURE_MARGIN", "CMFUTURE_MAIN",
+    "MARGIN_MAIN", "MARGIN_UMFUTURE", "MARGIN_CMFUTURE", "CMFUTURE_MARGIN", "ISOLATEDMARGIN_MARGIN",
+    "MARGIN_ISOLATEDMARGIN", "ISOLATEDMARGIN_ISOLATEDMARGIN", "MAIN_FUNDING", "FUNDING_MAIN", "FUNDING_UMFUTURE",
+    "UMFUTURE_FUNDING", "MARGIN_FUNDING", "FUNDING_MARGIN", "FUNDING_CMFUTURE", "CMFUTURE_FUNDING",
+];
+
+/// Withdrawals, transfers and dust conversions made against the server, with their time.
+#[derive(Debug)]
+pub(crate) struct WalletState {
+    next_id: u64,
+    withdrawals: Vec<(u64, Value)>,
+    transfers: Vec<(u64, Value)>,
+    dribblets: Vec<(u64, Value)>,
+    fast_withdraw: bool,
+}
+
+impl Default for WalletState {
+    fn default() -> Self {
+        WalletState {
+            next_id: 4359321,
+            withdrawals: vec![],
+            transfers: vec![],
+            dribblets: vec![],
+            fast_withdraw: false,
+        }
+    }
+}
+
+impl WalletState {
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+}
+
+pub(crate) fn handle(state: &mut State, method: &Method, path: &str, params: &Params) -> Option<Reply> {
+    let reply = match (method.clone(), path) {
+        (Method::GET, "/sapi/v1/system/status") => Ok(json!({"status": 0, "msg": "normal"})),
+        (Method::GET, "/sapi/v1/capital/config/getall") => Ok(capital_all()),
+        (Method::GET, "/sapi/v1/accountSnapshot") => account_snapshot(state, params),
+        (Method::POST, "/sapi/v1/asset/dust-btc") => Ok(dust_btc()),
+        (Method::POST, "/sapi/v1/account/disableFastWithdrawSwitch") => {
+            state.wallet.fast_withdraw = false;
+            Ok(json!({}))
+        },
+        (Method::POST, "/sapi/v1/account/enableFastWithdrawSwitch") => {
+            state.wallet.fast_withdraw = true;
+            Ok(json!({}))
+        },
+        (Method::POST, "/sapi/v1/capital/withdraw/apply") => withdraw(state, params),
+        (Method::GET, "/sapi/v1/capital/deposit/hisrec") => deposit_history(params),
+        (Method::GET, "/sapi/v1/capital/withdraw/history") => withdraw_history(state, params),
+        (Method::GET, "/sapi/v1/capital/deposit/address") => deposit_address(params),
+        (Method::GET, "/sapi/v1/account/status") => Ok(json!({"data": "Normal"})),
+        (Method::GET, "/sapi/v1/account/apiTradingStatus") => Ok(api_trading_status(state)),
+        (Method::GET, "/sapi/v1/asset/dribblet") => dribblet(state, params),
+        (Method::POST, "/sapi/v1/asset/dust") => dust(state, params),
+        (Method::GET, "/sapi/v1/asset/assetDividend") => asset_dividend(params),
+        (Method::GET, "/sapi/v1/asset/assetDetail") => Ok(asset_detail(params)),
+        (Method::GET, "/sapi/v1/asset/tradeFee") => trade_fee(params),
+        (Method::POST, "/sapi/v1/asset/transfer") => transfer(state, params),
+        (Method::GET, "/sapi/v1/asset/transfer") => transfer_history(state, params),
+        (Method::POST, "/sapi/v1/asset/get-funding-asset") => Ok(funding_asset(params)),
+        (Method::GET, "/sapi/v1/account/apiRestrictions") => Ok(api_restrictions(state)),
+        _ => {
+            return None;
+        },
+    };
+    Some(reply)
+}
+
+fn in_range(time: u64, params: &Params) -> std::result::Result<bool, MockError> {
+    let start_time = params.parse_opt::<u64>("startTime")?.unwrap_or(0);
+    let end_time = params.parse_opt::<u64>("endTime")?.unwrap_or(u64::MAX);
+    Ok(time >= start_time && time <= end_time)
+}
+
+// `yyyy-MM-dd HH:mm:ss` in UTC, as withdraw history reports it
+fn fmt_time(ms: u64) -> String {
+    let secs = ms / 1000;
+    let days = (secs / 86_400) as i64;
+    let (hour, minute, second) = (secs % 86_400 / 3600, secs % 3600 / 60, secs % 60);
+
+    // days since 1970-01-01 to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, hour, minute, second)
+}
+
+fn network(coin: &str, network: &str, name: &str, fee: &str, min: &str, is_default: bool) -> Value {
+    json!({
+        "network": network,
+        "coin": coin,
+        "name": name,
+        "addressRegex": "^[a-zA-Z0-9]{26,62}$",
+        "memoRegex": "",
+        "depositEnable": true,
+        "depositDesc": "",
+        "withdrawEnable": true,
+        "withdrawDesc": "",
+        "isDefault": is_default,
+        "minConfirm": 1,
+        "unLockConfirm": 2,
+        "resetAddressStatus": false,
+        "specialTips": "",
+        "withdrawFee": fee,
+        "withdrawMin": min,
+        "withdrawMax": "9999999999.99999999",
+        "withdrawIntegerMultiple": "0.00000001",
+        "sameAddress": false,
+        "estimatedArrivalTime": 25,
+        "busy": false,
+    })
+}
+
+fn coin(coin: &str, name: &str, free: &str, networks: Vec<Value>) -> Value {
+    json!({
+        "coin": coin,
+        "name": name,
+        "depositAllEnable": true,
+        "withdrawAllEnable": true,
+        "free": free,
+        "freeze": "0",
+        "ipoable": "0",
+        "ipoing": "0",
+        "isLegalMoney": false,
+        "locked": "0",
+        "storage": "0",
+        "trading": true,
+        "withdrawing": "0",
+        "networkList": networks,
+    })
+}
+
+fn capital_all() -> Value {
+    json!([
+        coin("BTC", "Bitcoin", "0.08074558", vec![
+            network("BTC", "BTC", "Bitcoin", "0.0002", "0.001", true),
+            network("BTC", "BSC", "BNB Smart Chain (BEP20)", "0.0000035", "0.000007", false),
+        ]),
+        coin("USDT", "TetherUS", "1204.35000000", vec![
+            network("USDT", "ETH", "Ethereum (ERC20)", "4.5", "10", false),
+            network("USDT", "TRX", "Tron (TRC20)", "1", "10", true),
+        ]),
+        coin("BNB", "BNB", "2.01000000", vec![
+            network("BNB", "BSC", "BNB Smart Chain (BEP20)", "0.0005", "0.01", true),
+        ]),
+    ])
+}
+
+fn account_snapshot(state: &State, params: &Params) -> Reply {
+    let update_time = state.now() / 86_400_000 * 86_400_000 - 1;
+    let data = match params.required("type")? {
+        "SPOT" => json!({
+            "type": "spot",
+            "updateTime": update_time,
+            "data": {
+                "totalAssetOfBtc": "0.09942700",
+                "balances": [
+                    {"asset": "BTC", "free": "0.08074558", "locked": "0.00000000"},
+                    {"asset": "USDT", "free": "1204.35000000", "locked": "120.00000000"},
+                ],
+            },
+        }),
+        "MARGIN" => json!({
+            "type": "margin",
+            "updateTime": update_time,
+            "data": {
+                "marginLevel": "2748.02909813",
+                "totalAssetOfBtc": "0.00274803",
+                "totalLiabilityOfBtc": "0.00000100",
+                "totalNetAssetOfBtc": "0.00274750",
+                "userAssets": [
+                    {"asset": "XRP", "borrowed": "0.00000000", "free": "1.00000000", "interest": "0.00000000", "locked": "0.00000000", "netAsset": "1.00000000"},
+                ],
+            },
+        }),
+        "FUTURES" => json!({
+            "type": "futures",
+            "updateTime": update_time,
+            "data": {
+                "assets": [
+                    {"asset": "USDT", "marginBalance": "118.99782335", "walletBalance": "120.23811389"},
+                ],
+                "position": [
+                    {"symbol": "BTCUSDT", "entryPrice": "42800.0", "markPrice": "43000.00000000", "positionAmt": "0.001", "unRealizedProfit": "0.20000000"},
+                ],
+            },
+        }),
+        _ => {
+            return Err(MockError::illegal("type"));
+        },
+    };
+    Ok(json!({"code": 200, "msg": "", "snapshotVos": [data]}))
+}
+
+fn dust_btc() -> Value {
+    json!({
+        "details": [
+            {
+                "asset": "ADA",
+                "assetFullName": "ADA",
+                "amountFree": "6.21",
+                "toBTC": "0.00016848",
+                "toBNB": "0.01777302",
+                "toBNBOffExchange": "0.01741756",
+                "exchange": "0.00035546",
+            },
+        ],
+        "totalTransferBtc": "0.00016848",
+        "totalTransferBNB": "0.01777302",
+        "dribbletPercentage": "0.02",
+    })
+}
+
+fn withdraw(state: &mut State, params: &Params) -> Reply {
+    let coin = params.required("coin")?.to_string();
+    let address = params.required("address")?.to_string();
+    let amount = params.decimal("amount")?;
+    if amount <= Decimal::ZERO {
+        return Err(MockError::illegal("amount"));
+    }
+
+    let now = state.now();
+    let id = format!("{:032x}", state.wallet.next_id());
+    let record = json!({
+        "id": id,
+        "amount": amount.to_string(),
+        "transactionFee": "0.0002",
+        "coin": coin,
+        "status": 4,
+        "address": address,
+        "txId": "",
+        "applyTime": fmt_time(now),
+        "network": params.get("network").unwrap_or("BTC"),
+        "transferType": 0,
+        "withdrawOrderId": params.get("withdrawOrderId"),
+        "info": "",
+        "confirmNo": 0,
+        "walletType": params.parse_opt::<u8>("walletType")?.unwrap_or(0),
+        "txKey": "",
+    });
+    state.wallet.withdrawals.push((now, record));
+    Ok(json!({"id": id}))
+}
+
+fn withdraw_history(state: &State, params: &Params) -> Reply {
+    let mut records = vec![];
+    for (time, record) in state.wallet.withdrawals.iter().rev() {
+        if !in_range(*time, params)? {
+            continue;
+        }
+        if params.get("coin").is_some_and(|coin| record["coin"] != coin) {
+            continue;
+        }
+        if params.get("withdrawOrderId").is_some_and(|id| record["withdrawOrderId"] != id) {
+            continue;
+        }
+        if params.parse_opt::<u8>("status")?.is_some_and(|status| record["status"] != status) {
+            continue;
+        }
+        records.push(record.clone());
+    }
+    let limit = params.parse_opt::<usize>("limit")?.unwrap_or(1000);
+    let offset = params.parse_opt::<usize>("offset")?.unwrap_or(0);
+    Ok(Value::Array(records.into_iter().skip(offset).take(limit).collect()))
+}
+
+fn deposit_history(params: &Params) -> Reply {
+    let deposits = [
+        json!({
+            "id": "769800519366885376",
+            "amount": "0.001",
+            "coin": "BNB",
+            "network": "BNB",
+            "status": 1,
+            "address": "bnb136ns6lfw4zs5hg4n85vdthaad7hq5m4gtkgf23",
+            "addressTag": "101764890",
+            "txId": "98A3EA560C6B3336D348B6C83F0F95ECE4F1F5919E94BD006E5BF3BF264FACFC",
+            "insertTime": 1661493146000u64,
+            "transferType": 0,
+            "confirmTimes": "1/1",
+            "unlockConfirm": 0,
+            "walletType": 0,
+        }),
+        json!({
+            "id": "769754833590042625",
+            "amount": "0.50000000",
+            "coin": "IOTA",
+            "network": "IOTA",
+            "status": 6,
+            "address": "SIZ9VLMHWATXKV99LH99CIGFJFUMLEHGWVZVNNZXRJJVWBPHYWPPBOSDORZ9EQSHCZAMPVAPGFYQAUUV9DROOXJLNW",
+            "addressTag": "",
+            "txId": "ESBFVQUTPIWQNJSPXFNHNYHSQNTGKRVKPRABQWTAXCDWOAKDKYWPTVG9BGXNVNKTLEJGESAVXIKIZ9999",
+            "insertTime": 1599620082000u64,
+            "transferType": 0,
+            "confirmTimes": "1/1",
+            "unlockConfirm": 0,
+            "walletType": 0,
+        }),
+    ];
+
+    let mut records = vec![];
+    for deposit in deposits.iter() {
+        if !in_range(deposit["insertTime"].as_u64().unwrap_or(0), params)? {
+            continue;
+        }
+        if params.get("coin").is_some_and(|coin| deposit["coin"] != coin) {
+            continue;
+        }
+        if params.parse_opt::<u8>("status")?.is_some_and(|status| deposit["status"] != status) {
+            continue;
+        }
+        records.push(deposit.clone());
+    }
+    Ok(Value::Array(records))
+}
+
+fn deposit_address(params: &Params) -> Reply {
+    let coin = params.required("coin")?;
+    let address = match coin {
+        "BTC" => "1HPn8Rx2y6nNSfagQBKy27GB99Vbzg89wv",
+        "USDT" => "TBqkEhYjWS2D7Wi7kZLE3gZN9i9oXvsPEj",
+        "BNB" => "0x6d8b4e9f7fd2a2d4b2fd5b3a4bd2c1e8aa17c4f2",
+        _ => {
+            return Err(MockError::illegal("coin"));
+        },
+    };
+    Ok(json!({
+        "address": address,
+        "coin": coin,
+        "tag": "",
+        "url": format!("https://btc.com/{}", address),
+    }))
+}
+
+fn api_trading_status(state: &State) -> Value {
+    json!({
+        "data": {
+            "isLocked": false,
+            "plannedRecoverTime": 0,
+            "triggerCondition": {"GCR": 150, "IFER": 150, "UFR": 300},
+            "updateTime": state.now(),
+        },
+    })
+}
+
+fn dribblet(state: &State, params: &Params) -> Reply {
+    let mut entries = vec![];
+    for (time, entry) in state.wallet.dribblets.iter().rev() {
+        if in_range(*time, params)? {
+            entries.push(entry.clone());
+        }
+    }
+    Ok(json!({"total": entries.len(), "userAssetDribblets": entries}))
+}
+
+fn dust(state: &mut State, params: &Params) -> Reply {
+    let assets = params.all("asset");
+    if assets.is_empty() {
+        return Err(MockError::mandatory("asset"));
+    }
+
+    let now = state.now();
+    let trans_id = state.wallet.next_id();
+    let (amount, transfered, charge) = (Decimal::new(3, 2), Decimal::new(2, 5), Decimal::new(4, 7));
+    let mut results = vec![];
+    let mut details = vec![];
+    for asset in assets.iter() {
+        results.push(json!({
+            "amount": amount.to_string(),
+            "fromAsset": asset,
+            "operateTime": now,
+            "serviceChargeAmount": charge.to_string(),
+            "tranId": trans_id,
+            "transferedAmount": transfered.to_string(),
+        }));
+        details.push(json!({
+            "transId": trans_id,
+            "serviceChargeAmount": charge.to_string(),
+            "amount": amount.to_string(),
+            "operateTime": now,
+            "transferedAmount": transfered.to_string(),
+            "fromAsset": asset,
+        }));
+    }
+
+    let count = Decimal::from(assets.len());
+    state.wallet.dribblets.push((now, json!({
+        "operateTime": now,
+        "totalTransferedAmount": (transfered * count).to_string(),
+        "totalServiceChargeAmount": (charge * count).to_string(),
+        "transId": trans_id,
+        "userAssetDribbletDetails": details,
+    })));
+    Ok(json!({
+        "totalServiceCharge": (charge * count).to_string(),
+        "totalTransfered": (transfered * count).to_string(),
+        "transferResult": results,
+    }))
+}
+
+fn asset_dividend(params: &Params) -> Reply {
+    let rows = [
+        json!({"id": 1637366104, "amount": "10.00000000", "asset": "BHFT", "divTime": 1563189166000u64, "enInfo": "BHFT distribution", "tranId": 2968885920u64}),
+        json!({"id": 1631750237, "amount": "10.00000000", "asset": "BHFT", "divTime": 1563189165000u64, "enInfo": "BHFT distribution", "tranId": 2968885920u64}),
+        json!({"id": 1631750238, "amount": "0.00090000", "asset": "BNB", "divTime": 1563189164000u64, "enInfo": "BNB Vault", "tranId": 2968885921u64}),
+    ];
+
+    let mut filtered = vec![];
+    for row in rows.iter() {
+        if !in_range(row["divTime"].as_u64().unwrap_or(0), params)? {
+            continue;
+        }
+        if params.get("asset").is_some_and(|asset| row["asset"] != asset) {
+            continue;
+        }
+        filtered.push(row.clone());
+    }
+    let limit = params.parse_opt::<usize>("limit")?.unwrap_or(20);
+    filtered.truncate(limit);
+    Ok(json!({"rows": filtered, "total": filtered.len()}))
+}
+
+fn asset_detail(params: &Params) -> Value {
+    let details = [
+        ("BTC", json!({"minWithdrawAmount": "0.00100000", "depositStatus": true, "withdrawFee": "0.0002", "withdrawStatus": true})),
+        ("USDT", json!({"minWithdrawAmount": "10.00000000", "depositStatus": true, "withdrawFee": "1", "withdrawStatus": true})),
+        ("SKY", json!({"minWithdrawAmount": "0.02000000", "depositStatus": true, "withdrawFee": "0.01", "withdrawStatus": true, "depositTip": "Wallet Maintenance, Deposit Suspended"})),
+    ];
+
+    let mut map = Map::new();
+    for (asset, detail) in details.iter() {
+        if params.get("asset").is_none_or(|a| a == *asset) {
+            map.insert(asset.to_string(), detail.clone());
+        }
+    }
+    Value::Object(map)
+}
+
+fn trade_fee(params: &Params) -> Reply {
+    let fee = |symbol: &str| json!({"symbol": symbol, "makerCommission": "0.001", "takerCommission": "0.001"});
+    match params.get("symbol") {
+        Some(symbol) => Ok(json!([fee(crate::mock::market::find_symbol(symbol)?.symbol)])),
+        None => Ok(Value::Array(crate::mock::market::symbols().iter().map(|s| fee(s.symbol)).collect())),
+    }
+}
+
+fn transfer_type(params: &Params) -> std::result::Result<String, MockError> {
+    let transfer_type = params.required("type")?;
+    if !TRANSFER_TYPES.contains(&transfer_type) {
+        return Err(MockError::illegal("type"));
+    }
+    Ok(transfer_type.to_string())
+}
+
+fn transfer(state: &mut State, params: &Params) -> Reply {
+    let transfer_type = transfer_type(params)?;
+    let asset = params.required("asset")?.to_string();
+    let amount = params.decimal("amount")?;
+    if amount <= Decimal::ZERO {
+        return Err(MockError::illegal("amount"));
+    }
+
+    let now = state.now();
+    let tran_id = state.wallet.next_id();
+    state.wallet.transfers.push((now, json!({
+        "asset": asset,
+        "amount": amount.to_string(),
+        "type": transfer_type,
+        "status": "CONFIRMED",
+        "tranId": tran_id,
+        "timestamp": now,
+    })));
+    Ok(json!({"tranId": tran_id}))
+}
+
+fn transfer_history(state: &State, params: &Params) -> Reply {
+    let transfer_type = transfer_type(params)?;
+    let size = params.parse_opt::<usize>("size")?.unwrap_or(10).min(100);
+    let current = params.parse_opt::<usize>("current")?.unwrap_or(1).max(1);
+
+    let mut rows = vec![];
+    for (time, row) in state.wallet.transfers.iter().rev() {
+        if row["type"] == transfer_type.as_str() && in_range(*time, params)? {
+            rows.push(row.clone());
+        }
+    }
+    let total = rows.len();
+    let rows: Vec<Value> = rows.into_iter().skip((current - 1) * size).take(size).collect();
+    // Binance leaves `rows` out of an empty page
+    if rows.is_empty() {
+        return Ok(json!({"total": total}));
+    }
+    Ok(json!({"total": total, "rows": rows}))
+}
+
+fn funding_asset(params: &Params) -> Value {
+    let with_btc = params.get("needBtcValuation") == Some("true");
+    let assets = [("USDT", "1", "0", "0.00001020"), ("BNB", "0.5", "0", "0.00652000")];
+    let rows: Vec<Value> = assets.iter()
+        .filter(|(asset, ..)| params.get("asset").is_none_or(|a| a == *asset))
+        .map(|(asset, free, locked, btc)| json!({
+            "asset": asset,
+            "free": free,
+            "locked": locked,
+            "freeze": "0",
+            "withdrawing": "0",
+            "btcValuation": if with_btc { *btc } else { "0" },
+        }))
+        .collect();
+    Value::Array(rows)
+}
+
+fn api_restrictions(state: &State) -> Value {
+    json!({
+        "ipRestrict": false,
+        "createTime": 1698645219000u64,
+        "enableReading": true,
+        "enableWithdrawals": true,
+        "enableInternalTransfer": true,
+        "enableMargin": false,
+        "enableFutures": false,
+        "permitsUniversalTransfer": true,
+        "enableVanillaOptions": false,
+        "enableFixApiTrade": false,
+        "enableFixReadOnly": true,
+        "enableSpotAndMarginTrading": true,
+        "enablePortfolioMarginTrading": false,
+        "tradingAuthorityExpirationTime": state.now() + 90 * 86_400_000,
+    })
+}
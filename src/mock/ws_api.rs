@@ -0,0 +1,311 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::{SinkExt, StreamExt};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use log::debug;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio_tungstenite::WebSocketStream;
+use tungstenite::{protocol::Role, Message};
+
+use crate::http::client::RequestParam;
+use crate::http::rate_limit::{request_cost, RequestCost};
+use crate::mock::{
+    account, check_api_key, check_timestamp, count, internal_error, invalid_timestamp, market, stream, trade, verify, Failure,
+    MockError, Params, Reply, State, API_KEY,
+};
+
+pub(crate) const PATH: &str = "/ws-api/v3";
+
+// what Binance charges for the session methods
+const SESSION_WEIGHT: u32 = 2;
+
+#[derive(Debug, Default)]
+pub(crate) struct WsApiState {
+    pub(crate) requests: Vec<Value>,
+    pub(crate) delays: HashMap<String, Duration>,
+    next_connection_id: u64,
+    // responses to send, the connection ends once its sender is dropped
+    connections: HashMap<u64, mpsc::UnboundedSender<String>>,
+}
+
+#[derive(Debug)]
+struct Session {
+    connected_since: u64,
+    api_key: Option<String>,
+    authorized_since: Option<u64>,
+}
+
+pub(crate) fn upgrade(state: Arc<Mutex<State>>, req: Request<Body>) -> Response<Body> {
+    let response = match stream::switching_protocols(&req) {
+        Some(response) => response,
+        None => {
+            return Response::builder().status(StatusCode::BAD_REQUEST).body(Body::empty()).unwrap();
+        },
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    let id = {
+        let ws_api = &mut state.lock().unwrap().ws_api;
+        ws_api.next_connection_id += 1;
+        ws_api.connections.insert(ws_api.next_connection_id, tx);
+        ws_api.next_connection_id
+    };
+    tokio::spawn(async move {
+        match hyper::upgrade::on(req).await {
+            Ok(upgraded) => {
+                let socket = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+                run(&state, id, socket, rx).await;
+            },
+            Err(err) => debug!("mock ws-api upgrade failed: {}", err),
+        }
+        state.lock().unwrap().ws_api.connections.remove(&id);
+    });
+    response
+}
+
+async fn run<S>(state: &Mutex<State>, id: u64, mut socket: WebSocketStream<S>, mut rx: mpsc::UnboundedReceiver<String>)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    let mut session = Session {
+        connected_since: state.lock().unwrap().now(),
+        api_key: None,
+        authorized_since: None,
+    };
+    loop {
+        tokio::select! {
+            frame = socket.next() => match frame {
+                Some(Ok(Message::Text(text))) => {
+                    let (response, delay) = {
+                        let mut state = state.lock().unwrap();
+                        let (response, method) = handle(&mut state, &mut session, &text);
+                        let delay = state.ws_api.delays.get(&method).copied();
+                        (response, delay.zip(state.ws_api.connections.get(&id).cloned()))
+                    };
+                    match delay {
+                        Some((delay, tx)) => {
+                            tokio::spawn(async move {
+                                tokio::time::sleep(delay).await;
+                                tx.send(response.to_string()).ok();
+                            });
+                        },
+                        None => {
+                            if socket.send(Message::Text(response.to_string())).await.is_err() {
+                                return;
+                            }
+                        },
+                    }
+                },
+                Some(Ok(_)) => {},
+                _ => {
+                    return;
+                },
+            },
+            text = rx.recv() => match text {
+                Some(text) => {
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        return;
+                    }
+                },
+                None => {
+                    return;
+                },
+            },
+        }
+    }
+}
+
+/// Ends every WebSocket API connection without a close frame.
+pub(crate) fn drop_connections(state: &mut State) -> usize {
+    state.ws_api.connections.drain().count()
+}
+
+/// Answers a request like the WebSocket API does, returns the response and the method.
+fn handle(state: &mut State, session: &mut Session, text: &str) -> (Value, String) {
+    let request: Value = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(_) => {
+            let error = MockError::bad_request(-1000, "Invalid JSON.");
+            return (response(Value::Null, Err(error), vec![]), String::new());
+        },
+    };
+    state.ws_api.requests.push(request.clone());
+
+    let id = request["id"].clone();
+    let method = request["method"].as_str().unwrap_or_default().to_string();
+    let params = Params(request["params"].as_object().into_iter().flatten()
+        .map(|(key, value)| match value {
+            Value::String(value) => (key.clone(), value.clone()),
+            value => (key.clone(), value.to_string()),
+        })
+        .collect());
+
+    let route = route(&method);
+    let cost = match route {
+        Some((http_method, path, _)) => {
+            let param: Vec<RequestParam> = params.0.iter()
+                .map(|(key, value)| RequestParam{key: key.clone(), value: value.clone()})
+                .collect();
+            request_cost(&http_method, path, &param)
+        },
+        None => RequestCost { weight: SESSION_WEIGHT, sapi: false, orders: 0 },
+    };
+
+    let failure = state.failures.iter().position(|(p, _)| *p == method).map(|i| state.failures.remove(i).1);
+    let now = state.now();
+    let reply = match failure {
+        Some(Failure::TooManyRequests { retry_after }) => {
+            Err(MockError::new(StatusCode::TOO_MANY_REQUESTS, -1003, "Too much request weight used; current limit is 6000 request weight per 1 MINUTE. Please use WebSocket Streams for live updates to avoid polling the API.")
+                .with_data(json!({"serverTime": now, "retryAfter": now + retry_after * 1000})))
+        },
+        Some(Failure::Banned { retry_after }) => {
+            Err(MockError::new(StatusCode::IM_A_TEAPOT, -1003, "Way too much request weight used; IP banned until further notice.")
+                .with_data(json!({"serverTime": now, "retryAfter": now + retry_after * 1000})))
+        },
+        Some(Failure::ServerError(status)) => Err(internal_error(status)),
+        Some(Failure::InvalidTimestamp) => Err(invalid_timestamp()),
+        Some(Failure::ExecutedThenServerError(status)) => match execute(state, session, &method, &params) {
+            Ok(_) => Err(internal_error(status)),
+            Err(err) => Err(err),
+        },
+        None => execute(state, session, &method, &params),
+    };
+
+    let rate_limits = rate_limits(state, cost);
+    (response(id, reply, rate_limits), method)
+}
+
+// the REST endpoint a method is served like, and whether it is signed
+fn route(method: &str) -> Option<(Method, &'static str, bool)> {
+    let route = match method {
+        "ping" => (Method::GET, "/api/v3/ping", false),
+        "time" => (Method::GET, "/api/v3/time", false),
+        "exchangeInfo" => (Method::GET, "/api/v3/exchangeInfo", false),
+        "depth" => (Method::GET, "/api/v3/depth", false),
+        "trades.recent" => (Method::GET, "/api/v3/trades", false),
+        "klines" => (Method::GET, "/api/v3/klines", false),
+        "avgPrice" => (Method::GET, "/api/v3/avgPrice", false),
+        "ticker.24hr" => (Method::GET, "/api/v3/ticker/24hr", false),
+        "ticker.price" => (Method::GET, "/api/v3/ticker/price", false),
+        "ticker.book" => (Method::GET, "/api/v3/ticker/bookTicker", false),
+        "order.place" => (Method::POST, "/api/v3/order", true),
+        "order.test" => (Method::POST, "/api/v3/order/test", true),
+        "order.status" => (Method::GET, "/api/v3/order", true),
+        "order.cancel" => (Method::DELETE, "/api/v3/order", true),
+        "openOrders.status" => (Method::GET, "/api/v3/openOrders", true),
+        "openOrders.cancelAll" => (Method::DELETE, "/api/v3/openOrders", true),
+        "allOrders" => (Method::GET, "/api/v3/allOrders", true),
+        "account.status" => (Method::GET, "/api/v3/account", true),
+        "account.rateLimits.orders" => (Method::GET, "/api/v3/rateLimit/order", true),
+        "myTrades" => (Method::GET, "/api/v3/myTrades", true),
+        _ => {
+            return None;
+        },
+    };
+    Some(route)
+}
+
+fn execute(state: &mut State, session: &mut Session, method: &str, params: &Params) -> Reply {
+    match method {
+        "session.logon" => {
+            let api_key = authenticate(state, params)?;
+            if api_key == API_KEY {
+                return Err(MockError::bad_request(-4056, "HMAC_SHA256 API key is not supported."));
+            }
+            session.api_key = Some(api_key);
+            session.authorized_since = Some(state.now());
+            return Ok(session_status(state, session));
+        },
+        "session.status" => {
+            return Ok(session_status(state, session));
+        },
+        "session.logout" => {
+            session.api_key = None;
+            session.authorized_since = None;
+            return Ok(session_status(state, session));
+        },
+        _ => {},
+    }
+
+    let (http_method, path, signed) = match route(method) {
+        Some(route) => route,
+        None => {
+            return Err(MockError::bad_request(-1020, "Unsupported operation."));
+        },
+    };
+    if signed {
+        match (&session.api_key, params.get("signature")) {
+            (Some(_), None) => check_timestamp(state, params)?,
+            _ => {
+                authenticate(state, params)?;
+            },
+        }
+    }
+
+    market::handle(state, &http_method, path, params)
+        .or_else(|| trade::handle(state, &http_method, path, params))
+        .or_else(|| account::handle(state, &http_method, path, params))
+        .unwrap_or_else(|| Err(MockError::bad_request(-1020, "Unsupported operation.")))
+}
+
+// checks `apiKey`, `signature` and `timestamp` of a request that is signed, returns the key
+fn authenticate(state: &State, params: &Params) -> std::result::Result<String, MockError> {
+    let api_key = params.get("apiKey");
+    check_api_key(api_key)?;
+
+    // the params sorted by name, without the signature itself
+    let mut payload: Vec<&(String, String)> = params.0.iter().filter(|(key, _)| key != "signature").collect();
+    payload.sort();
+    let payload = payload.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>().join("&");
+    verify(api_key.unwrap_or_default(), &payload, params.required("signature")?)?;
+    check_timestamp(state, params)?;
+    Ok(api_key.unwrap_or_default().to_string())
+}
+
+fn session_status(state: &State, session: &Session) -> Value {
+    json!({
+        "apiKey": session.api_key,
+        "authorizedSince": session.authorized_since,
+        "connectedSince": session.connected_since,
+        "returnRateLimits": true,
+        "serverTime": state.now(),
+        "userDataStream": false,
+    })
+}
+
+// counts the request like the REST endpoint would and reports the usage
+fn rate_limits(state: &mut State, cost: RequestCost) -> Vec<Value> {
+    let now = state.now();
+    let weight = count(&mut state.used_weight, now / 60_000, cost.weight);
+    let mut rate_limits = vec![
+        json!({"rateLimitType": "REQUEST_WEIGHT", "interval": "MINUTE", "intervalNum": 1, "limit": 6000, "count": weight}),
+    ];
+    if cost.orders > 0 {
+        let orders_10s = count(&mut state.orders_10s, now / 10_000, cost.orders);
+        let orders_1d = count(&mut state.orders_1d, now / 86_400_000, cost.orders);
+        rate_limits.push(json!({"rateLimitType": "ORDERS", "interval": "SECOND", "intervalNum": 10, "limit": 100, "count": orders_10s}));
+        rate_limits.push(json!({"rateLimitType": "ORDERS", "interval": "DAY", "intervalNum": 1, "limit": 200000, "count": orders_1d}));
+    }
+    rate_limits
+}
+
+fn response(id: Value, reply: Reply, rate_limits: Vec<Value>) -> Value {
+    let mut response = match reply {
+        Ok(result) => json!({"id": id, "status": 200, "result": result}),
+        Err(err) => {
+            let mut error = json!({"code": err.code, "msg": err.msg});
+            if let Some(data) = err.data {
+                error["data"] = data;
+            }
+            json!({"id": id, "status": err.status.as_u16(), "error": error})
+        },
+    };
+    if !rate_limits.is_empty() {
+        response["rateLimits"] = Value::Array(rate_limits);
+    }
+    response
+}
@@ -0,0 +1,496 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use futures::StreamExt;
+use hyper::StatusCode;
+use log::{debug, warn};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::error::{ApiError, Error, ErrorCode, Result};
+use crate::http::account::model::{AccountInformation, OrderRateLimit};
+use crate::http::client::{Credentials, RequestParam};
+use crate::http::market::model::{ExchangeInfo, OrderBookSnapshot, ServerTime, TickerPrice};
+use crate::http::time_sync::{local_timestamp, TimeOffset, TimeSync, DEFAULT_TIME_SYNC_INTERVAL};
+use crate::http::trade::model::{CancelOrderResponse, CanceledOrder, NewOrderResponse, Order};
+use crate::http::trade::trade::{EOrderRef, NewOrder};
+use crate::ws::api::model::{ApiResponse, Frame, RateLimitStatus, SessionStatus};
+use crate::ws::client::{Client, Connection};
+
+pub const BASE_URL: &str = "wss://ws-api.binance.com:443";
+pub const BASE_URL_9443: &str = "wss://ws-api.binance.com:9443";
+pub const BASE_URL_TESTNET: &str = "wss://ws-api.testnet.binance.vision";
+
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+const URL_WS_API: &str = "/ws-api/v3";
+
+const METHOD_PING: &str = "ping";
+const METHOD_TIME: &str = "time";
+const METHOD_EXCHANGE_INFO: &str = "exchangeInfo";
+const METHOD_DEPTH: &str = "depth";
+const METHOD_TICKER_PRICE: &str = "ticker.price";
+const METHOD_SESSION_LOGON: &str = "session.logon";
+const METHOD_SESSION_STATUS: &str = "session.status";
+const METHOD_SESSION_LOGOUT: &str = "session.logout";
+const METHOD_ORDER_PLACE: &str = "order.place";
+const METHOD_ORDER_TEST: &str = "order.test";
+const METHOD_ORDER_STATUS: &str = "order.status";
+const METHOD_ORDER_CANCEL: &str = "order.cancel";
+const METHOD_OPEN_ORDERS_STATUS: &str = "openOrders.status";
+const METHOD_OPEN_ORDERS_CANCEL_ALL: &str = "openOrders.cancelAll";
+const METHOD_ACCOUNT_STATUS: &str = "account.status";
+const METHOD_ACCOUNT_RATE_LIMITS_ORDERS: &str = "account.rateLimits.orders";
+
+#[derive(Debug)]
+pub struct ApiClientBuilder {
+    credentials: Option<Credentials>,
+    recv_window: Option<u64>,
+    request_timeout: Duration,
+    time_sync_interval: Option<Duration>,
+}
+
+impl Default for ApiClientBuilder {
+    fn default() -> Self {
+        ApiClientBuilder {
+            credentials: None,
+            recv_window: None,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            time_sync_interval: Some(DEFAULT_TIME_SYNC_INTERVAL),
+        }
+    }
+}
+
+impl ApiClientBuilder {
+    pub fn new() -> Self {
+        ApiClientBuilder::default()
+    }
+
+    /// Signs requests the way the REST client does; `session.logon` needs an Ed25519 key.
+    pub fn credentials(mut self, credentials: Credentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Reads `APIKEY` and `SECRETKEY` from the environment.
+    pub fn credentials_from_env(mut self) -> Self {
+        self.credentials = Credentials::from_env();
+        self
+    }
+
+    /// `recvWindow` for signed requests, Binance defaults to 5000ms.
+    pub fn recv_window(mut self, recv_window: u64) -> Self {
+        self.recv_window = Some(recv_window);
+        self
+    }
+
+    /// How long a request waits for its response before it fails with `Error::Timeout`,
+    /// 10 seconds by default.
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// How often signed requests resync with the server time, every 30 minutes by default.
+    pub fn time_sync_interval(mut self, interval: Duration) -> Self {
+        self.time_sync_interval = Some(interval);
+        self
+    }
+
+    /// Signs with the local clock.
+    pub fn disable_time_sync(mut self) -> Self {
+        self.time_sync_interval = None;
+        self
+    }
+
+    /// Connects to the WebSocket API under the base url of `client`, e.g. one of the
+    /// `BASE_URL_*` constants of this module. The server time is synced right away when
+    /// credentials are set, so the first order does not wait for it.
+    pub async fn connect(self, client: &Client) -> Result<ApiClient> {
+        let connection = client.connect_unthrottled(URL_WS_API).await?;
+        let (commands, commands_rx) = mpsc::unbounded_channel();
+        let rate_limits = Arc::new(Mutex::new(vec![]));
+        tokio::spawn(run(connection, commands_rx, rate_limits.clone()));
+
+        let api = ApiClient {
+            commands,
+            next_id: AtomicU64::new(1),
+            credentials: self.credentials,
+            recv_window: self.recv_window,
+            request_timeout: self.request_timeout,
+            time_sync: TimeSync::new(self.time_sync_interval),
+            logged_on: AtomicBool::new(false),
+            rate_limits,
+        };
+        if api.credentials.is_some() && api.time_sync.enabled() {
+            api.sync_time().await?;
+        }
+        Ok(api)
+    }
+}
+
+#[derive(Debug)]
+enum Command {
+    Request { id: u64, text: String, reply: oneshot::Sender<Result<ApiResponse>> },
+    Close(oneshot::Sender<Result<()>>),
+}
+
+/// A persistent connection to the Binance WebSocket API.
+///
+/// Requests can be sent concurrently, responses are matched to them by `id`. Signed requests
+/// carry `apiKey` and `signature` until the session is authenticated with `logon`. Every
+/// response reports the current rate limit usage, see `rate_limits`.
+///
+/// The connection is not reopened: once it ends, requests fail with `Error::WebSocket`.
+#[derive(Debug)]
+pub struct ApiClient {
+    commands: mpsc::UnboundedSender<Command>,
+    next_id: AtomicU64,
+    credentials: Option<Credentials>,
+    recv_window: Option<u64>,
+    request_timeout: Duration,
+    time_sync: TimeSync,
+    logged_on: AtomicBool,
+    rate_limits: Arc<Mutex<Vec<RateLimitStatus>>>,
+}
+
+impl ApiClient {
+    pub fn builder() -> ApiClientBuilder {
+        ApiClientBuilder::new()
+    }
+
+    /// Connects without credentials, for the market data methods.
+    pub async fn connect(client: &Client) -> Result<Self> {
+        ApiClient::builder().connect(client).await
+    }
+
+    pub fn credentials(&self) -> Option<&Credentials> {
+        self.credentials.as_ref()
+    }
+
+    /// Whether `logon` authenticated the session.
+    pub fn logged_on(&self) -> bool {
+        self.logged_on.load(Ordering::SeqCst)
+    }
+
+    /// Rate limit usage as of the last response, empty before the first one.
+    pub fn rate_limits(&self) -> Vec<RateLimitStatus> {
+        self.rate_limits.lock().unwrap().clone()
+    }
+
+    /// Current time in milliseconds, corrected by the server time offset.
+    pub fn timestamp(&self) -> u64 {
+        self.time_sync.timestamp()
+    }
+
+    /// Measures the offset between the local clock and Binance server time.
+    pub async fn sync_time(&self) -> Result<TimeOffset> {
+        let sent = local_timestamp();
+        let server_time = self.time().await?;
+        let received = local_timestamp();
+
+        let offset = self.time_sync.record(server_time.server_time, sent, received);
+        debug!("ws-api server time offset {}ms, round trip {}ms", offset.offset_ms, offset.round_trip_ms);
+        Ok(offset)
+    }
+
+    /// Closes the connection, requests still waiting fail.
+    pub async fn close(self) -> Result<()> {
+        let (done_tx, done) = oneshot::channel();
+        if self.commands.send(Command::Close(done_tx)).is_err() {
+            return Ok(());
+        }
+        done.await.unwrap_or(Ok(()))
+    }
+}
+
+impl ApiClient {
+    /// Sends an unsigned request and waits for its response.
+    pub async fn request(&self, method: &str, param: &[RequestParam]) -> Result<ApiResponse> {
+        let params = param.iter().map(|p| (p.key.clone(), Value::String(p.value.clone()))).collect();
+        self.send(method, params).await
+    }
+
+    /// Appends `recvWindow` and `timestamp` and, unless the session is logged on, `apiKey` and
+    /// the signature of the params sorted by name. On a -1021 timestamp error the server time is
+    /// resynced and the request is signed and sent once more.
+    pub async fn request_signed(&self, method: &str, param: &[RequestParam]) -> Result<ApiResponse> {
+        self.send_signed(method, param, !self.logged_on()).await
+    }
+
+    async fn send_signed(&self, method: &str, param: &[RequestParam], sign: bool) -> Result<ApiResponse> {
+        if self.time_sync.needs_sync() {
+            if let Err(err) = self.sync_time().await {
+                warn!("server time sync failed, using local clock: {}", err);
+            }
+        }
+
+        let mut resynced = false;
+        loop {
+            let params = self.signed_params(param, sign)?;
+            match self.send(method, params).await {
+                Err(err) if err.code() == Some(ErrorCode::InvalidTimestamp) && self.time_sync.enabled() && !resynced => {
+                    warn!("{}, resyncing server time", err);
+                    self.sync_time().await?;
+                    resynced = true;
+                },
+                resp => {
+                    return resp;
+                },
+            }
+        }
+    }
+
+    fn signed_params(&self, param: &[RequestParam], sign: bool) -> Result<Map<String, Value>> {
+        let mut params: BTreeMap<String, Value> = param.iter()
+            .map(|p| (p.key.clone(), Value::String(p.value.clone())))
+            .collect();
+        if let Some(recv_window) = self.recv_window {
+            params.insert(String::from("recvWindow"), json!(recv_window));
+        }
+        params.insert(String::from("timestamp"), json!(self.time_sync.timestamp()));
+
+        if sign {
+            let credentials = self.credentials.as_ref().ok_or(Error::MissingCredentials)?;
+            params.insert(String::from("apiKey"), json!(credentials.api_key()));
+            let signature = credentials.sign(&signature_payload(&params))?;
+            params.insert(String::from("signature"), json!(signature));
+        }
+        Ok(params.into_iter().collect())
+    }
+
+    async fn send(&self, method: &str, params: Map<String, Value>) -> Result<ApiResponse> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut request = json!({"id": id, "method": method});
+        if !params.is_empty() {
+            request["params"] = Value::Object(params);
+        }
+
+        let (reply, response) = oneshot::channel();
+        let command = Command::Request { id, text: request.to_string(), reply };
+        if self.commands.send(command).is_err() {
+            return Err(closed());
+        }
+        match tokio::time::timeout(self.request_timeout, response).await {
+            Ok(Ok(response)) => response,
+            // the connection ended before the response came
+            Ok(Err(_)) => Err(closed()),
+            Err(_) => Err(Error::Timeout),
+        }
+    }
+}
+
+impl ApiClient {
+    pub async fn ping(&self) -> Result<()> {
+        self.request(METHOD_PING, &[]).await?;
+        Ok(())
+    }
+
+    pub async fn time(&self) -> Result<ServerTime> {
+        parse(self.request(METHOD_TIME, &[]).await?)
+    }
+
+    /// Trading rules of `symbol`, or of every symbol.
+    pub async fn exchange_info(&self, symbol: Option<&str>) -> Result<ExchangeInfo> {
+        let mut param = vec![];
+        if let Some(symbol) = symbol {
+            param.push(RequestParam{key: String::from("symbol"), value: String::from(symbol)});
+        }
+        parse(self.request(METHOD_EXCHANGE_INFO, &param).await?)
+    }
+
+    pub async fn depth(&self, symbol: &str, limit: u32) -> Result<OrderBookSnapshot> {
+        let param = vec![
+            RequestParam{key: String::from("symbol"), value: String::from(symbol)},
+            RequestParam{key: String::from("limit"), value: limit.to_string()},
+        ];
+        parse(self.request(METHOD_DEPTH, &param).await?)
+    }
+
+    pub async fn ticker_price(&self, symbol: &str) -> Result<TickerPrice> {
+        let param = vec![RequestParam{key: String::from("symbol"), value: String::from(symbol)}];
+        parse(self.request(METHOD_TICKER_PRICE, &param).await?)
+    }
+}
+
+impl ApiClient {
+    /// Authenticates the session with the api key, later signed requests need no signature.
+    /// Binance only accepts Ed25519 keys here.
+    pub async fn logon(&self) -> Result<SessionStatus> {
+        let status = parse(self.send_signed(METHOD_SESSION_LOGON, &[], true).await?)?;
+        self.logged_on.store(true, Ordering::SeqCst);
+        Ok(status)
+    }
+
+    pub async fn session_status(&self) -> Result<SessionStatus> {
+        parse(self.request(METHOD_SESSION_STATUS, &[]).await?)
+    }
+
+    /// Ends the authentication of the session, the connection stays open.
+    pub async fn logout(&self) -> Result<SessionStatus> {
+        let status = parse(self.request(METHOD_SESSION_LOGOUT, &[]).await?)?;
+        self.logged_on.store(false, Ordering::SeqCst);
+        Ok(status)
+    }
+}
+
+impl ApiClient {
+    /// Places an order. Unlike `trade::new_order`, a request without a definite answer is not
+    /// looked up and sent again.
+    pub async fn new_order(&self, order: &NewOrder) -> Result<NewOrderResponse> {
+        let param = order.params()?;
+        parse(self.request_signed(METHOD_ORDER_PLACE, &param).await?)
+    }
+
+    /// Validates an order with the matching engine without placing it.
+    pub async fn test_order(&self, order: &NewOrder) -> Result<()> {
+        let param = order.params()?;
+        self.request_signed(METHOD_ORDER_TEST, &param).await?;
+        Ok(())
+    }
+
+    pub async fn query_order(&self, symbol: &str, order: &EOrderRef) -> Result<Order> {
+        let param = vec![
+            RequestParam{key: String::from("symbol"), value: String::from(symbol)},
+            order.param(),
+        ];
+        parse(self.request_signed(METHOD_ORDER_STATUS, &param).await?)
+    }
+
+    pub async fn cancel_order(&self, symbol: &str, order: &EOrderRef) -> Result<CancelOrderResponse> {
+        let param = vec![
+            RequestParam{key: String::from("symbol"), value: String::from(symbol)},
+            order.param(),
+        ];
+        parse(self.request_signed(METHOD_ORDER_CANCEL, &param).await?)
+    }
+
+    /// Open orders on `symbol`, or on every symbol at a much higher request weight.
+    pub async fn open_orders(&self, symbol: Option<&str>) -> Result<Vec<Order>> {
+        let mut param = vec![];
+        if let Some(symbol) = symbol {
+            param.push(RequestParam{key: String::from("symbol"), value: String::from(symbol)});
+        }
+        parse(self.request_signed(METHOD_OPEN_ORDERS_STATUS, &param).await?)
+    }
+
+    /// Cancels every open order on `symbol`, including order lists.
+    pub async fn cancel_all_open_orders(&self, symbol: &str) -> Result<Vec<CanceledOrder>> {
+        let param = vec![RequestParam{key: String::from("symbol"), value: String::from(symbol)}];
+        parse(self.request_signed(METHOD_OPEN_ORDERS_CANCEL_ALL, &param).await?)
+    }
+
+    pub async fn account(&self) -> Result<AccountInformation> {
+        parse(self.request_signed(METHOD_ACCOUNT_STATUS, &[]).await?)
+    }
+
+    pub async fn rate_limit_order(&self) -> Result<Vec<OrderRateLimit>> {
+        parse(self.request_signed(METHOD_ACCOUNT_RATE_LIMITS_ORDERS, &[]).await?)
+    }
+}
+
+fn parse<T: DeserializeOwned>(response: ApiResponse) -> Result<T> {
+    Ok(serde_json::from_value(response.result)?)
+}
+
+// `key=value` pairs in the order of the keys, values as they are sent
+fn signature_payload(params: &BTreeMap<String, Value>) -> String {
+    params.iter()
+        .map(|(key, value)| match value {
+            Value::String(value) => format!("{}={}", key, value),
+            value => format!("{}={}", key, value),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn closed() -> Error {
+    tungstenite::Error::AlreadyClosed.into()
+}
+
+async fn run(
+    mut connection: Connection,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    rate_limits: Arc<Mutex<Vec<RateLimitStatus>>>,
+) {
+    let mut pending: HashMap<u64, oneshot::Sender<Result<ApiResponse>>> = HashMap::new();
+    loop {
+        tokio::select! {
+            command = commands.recv() => match command {
+                Some(Command::Request { id, text, reply }) => {
+                    // requests that timed out
+                    pending.retain(|_, reply| !reply.is_closed());
+                    match connection.send(text) {
+                        Ok(()) => {
+                            pending.insert(id, reply);
+                        },
+                        Err(err) => {
+                            reply.send(Err(err)).ok();
+                        },
+                    }
+                },
+                Some(Command::Close(done)) => {
+                    done.send(connection.close().await).ok();
+                    return;
+                },
+                // the client was dropped
+                None => {
+                    return;
+                },
+            },
+            message = connection.next() => match message {
+                Some(Ok(text)) => {
+                    dispatch(&text, &mut pending, &rate_limits);
+                },
+                Some(Err(err)) => {
+                    warn!("ws-api connection failed: {}", err);
+                },
+                // waiting requests fail as their replies are dropped
+                None => {
+                    return;
+                },
+            },
+        }
+    }
+}
+
+fn dispatch(text: &str, pending: &mut HashMap<u64, oneshot::Sender<Result<ApiResponse>>>, rate_limits: &Mutex<Vec<RateLimitStatus>>) {
+    let frame: Frame = match serde_json::from_str(text) {
+        Ok(frame) => frame,
+        Err(err) => {
+            debug!("skipping ws-api message {}: {}", text, err);
+            return;
+        },
+    };
+    if !frame.rate_limits.is_empty() {
+        *rate_limits.lock().unwrap() = frame.rate_limits.clone();
+    }
+
+    let reply = match frame.id.as_ref().and_then(Value::as_u64).and_then(|id| pending.remove(&id)) {
+        Some(reply) => reply,
+        None => {
+            debug!("ws-api response without a waiting request: {}", text);
+            return;
+        },
+    };
+    let response = match frame.error {
+        Some(error) => Err(Error::Api(ApiError {
+            status: StatusCode::from_u16(frame.status).unwrap_or(StatusCode::BAD_REQUEST),
+            code: ErrorCode::from(error.code),
+            msg: error.msg,
+            data: error.data,
+        })),
+        None => Ok(ApiResponse {
+            result: frame.result.unwrap_or(Value::Null),
+            rate_limits: frame.rate_limits,
+        }),
+    };
+    reply.send(response).ok();
+}
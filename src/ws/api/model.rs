@@ -0,0 +1,58 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::http::market::model::{RateLimitInterval, RateLimitType};
+
+/// Usage of a rate limit as the WebSocket API reports it with every response.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitStatus {
+    pub rate_limit_type: RateLimitType,
+    pub interval: RateLimitInterval,
+    pub interval_num: u32,
+    pub limit: u32,
+    pub count: u32,
+}
+
+/// The `result` of a request with the rate limits reported along with it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiResponse {
+    pub result: Value,
+    pub rate_limits: Vec<RateLimitStatus>,
+}
+
+/// Answer to `session.logon`, `session.status` and `session.logout`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStatus {
+    /// The key the session is authenticated with, `None` before `session.logon`.
+    pub api_key: Option<String>,
+    pub authorized_since: Option<u64>,
+    pub connected_since: u64,
+    pub return_rate_limits: bool,
+    pub server_time: u64,
+    #[serde(default)]
+    pub user_data_stream: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ErrorBody {
+    pub(crate) code: i32,
+    pub(crate) msg: String,
+    #[serde(default)]
+    pub(crate) data: Option<Value>,
+}
+
+/// A response as it comes over the connection, `result` or `error` set.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Frame {
+    pub(crate) id: Option<Value>,
+    pub(crate) status: u16,
+    #[serde(default)]
+    pub(crate) result: Option<Value>,
+    #[serde(default)]
+    pub(crate) error: Option<ErrorBody>,
+    #[serde(default)]
+    pub(crate) rate_limits: Vec<RateLimitStatus>,
+}
@@ -1,11 +1,418 @@
-use tungstenite::connect;
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
 
+use futures::{SinkExt, Stream, StreamExt};
+use hyper::{StatusCode, Uri};
+use log::{debug, warn};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+use tokio_tungstenite::{client_async_tls, MaybeTlsStream, WebSocketStream};
+use tungstenite::Message;
 
+use crate::error::{Error, Result};
+use crate::http::client::Client as HttpClient;
+use crate::http::retry::RetryPolicy;
+
+pub const BASE_URL: &str = "wss://stream.binance.com:9443";
+pub const BASE_URL_443: &str = "wss://stream.binance.com:443";
+/// Market data streams only.
+pub const BASE_URL_DATA: &str = "wss://data-stream.binance.vision";
+pub const BASE_URL_TESTNET: &str = "wss://stream.testnet.binance.vision";
+
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Streams reconnect until they are closed.
+pub const DEFAULT_RECONNECT_POLICY: RetryPolicy = RetryPolicy {
+    max_retries: u32::MAX,
+    base_delay: Duration::from_secs(1),
+    max_delay: Duration::from_secs(60),
+};
+/// Binance ends every connection after 24 hours, streams move to a new one before that.
+pub const DEFAULT_ROTATION_INTERVAL: Duration = Duration::from_secs(23 * 60 * 60);
+pub const DEFAULT_ROTATION_OVERLAP: Duration = Duration::from_secs(10);
+
+// how long closing waits for the server to answer the close frame
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+// an answer to CONNECT longer than this is not coming from an http proxy
+const MAX_PROXY_RESPONSE: usize = 8 * 1024;
+// Binance allows 5 incoming messages per second on a connection
+const MIN_SEND_INTERVAL: Duration = Duration::from_millis(200);
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Opens websocket connections to the Binance streams.
+#[derive(Debug, Clone)]
 pub struct Client {
+    base_url: String,
+    proxy_uri: Option<String>,
+    connect_timeout: Duration,
+    reconnect_policy: RetryPolicy,
+    rotation_interval: Duration,
+    rotation_overlap: Duration,
+}
+
+#[derive(Debug)]
+pub struct ClientBuilder {
+    base_url: String,
+    proxy_uri: Option<String>,
+    connect_timeout: Duration,
+    reconnect_policy: RetryPolicy,
+    rotation_interval: Duration,
+    rotation_overlap: Duration,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        ClientBuilder {
+            base_url: BASE_URL.to_string(),
+            proxy_uri: None,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            reconnect_policy: DEFAULT_RECONNECT_POLICY,
+            rotation_interval: DEFAULT_ROTATION_INTERVAL,
+            rotation_overlap: DEFAULT_ROTATION_OVERLAP,
+        }
+    }
+}
+
+impl ClientBuilder {
+    pub fn new() -> Self {
+        ClientBuilder::default()
+    }
+
+    /// e.g. one of the `BASE_URL_*` constants, or the address of a local test server.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Tunnels connections through an http proxy with `CONNECT`.
+    pub fn proxy(mut self, proxy_uri: impl Into<String>) -> Self {
+        self.proxy_uri = Some(proxy_uri.into());
+        self
+    }
+
+    /// Uses the same proxy as `client`, if it has one.
+    pub fn proxy_from(mut self, client: &HttpClient) -> Self {
+        self.proxy_uri = client.proxy_uri().map(String::from);
+        self
+    }
+
+    /// Limit for the tcp connect, proxy tunnel, tls and websocket handshakes together,
+    /// 10 seconds by default.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Backoff between the attempts of a stream to reconnect; it gives up after `max_retries`.
+    pub fn reconnect_policy(mut self, policy: RetryPolicy) -> Self {
+        self.reconnect_policy = policy;
+        self
+    }
+
+    /// How long a stream uses a connection before it moves to a new one, 23 hours by default.
+    pub fn rotation_interval(mut self, interval: Duration) -> Self {
+        self.rotation_interval = interval;
+        self
+    }
+
+    /// How long both connections are read while a stream rotates, 10 seconds by default.
+    pub fn rotation_overlap(mut self, overlap: Duration) -> Self {
+        self.rotation_overlap = overlap;
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let base_url = parse_uri(&self.base_url)?;
+        if !matches!(base_url.scheme_str(), Some("ws") | Some("wss")) {
+            return Err(Error::InvalidParameter(format!("invalid websocket url {}", self.base_url)));
+        }
+        if let Some(proxy_uri) = &self.proxy_uri {
+            parse_uri(proxy_uri)?;
+        }
+        if self.rotation_overlap >= self.rotation_interval {
+            return Err(Error::InvalidParameter(String::from("the rotation overlap has to be shorter than the interval")));
+        }
+
+        Ok(Client {
+            base_url: self.base_url,
+            proxy_uri: self.proxy_uri,
+            connect_timeout: self.connect_timeout,
+            reconnect_policy: self.reconnect_policy,
+            rotation_interval: self.rotation_interval,
+            rotation_overlap: self.rotation_overlap,
+        })
+    }
+}
+
+impl Client {
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// Client for `stream.binance.com:9443`, without a proxy.
+    pub fn new() -> Self {
+        Client {
+            base_url: BASE_URL.to_string(),
+            proxy_uri: None,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            reconnect_policy: DEFAULT_RECONNECT_POLICY,
+            rotation_interval: DEFAULT_ROTATION_INTERVAL,
+            rotation_overlap: DEFAULT_ROTATION_OVERLAP,
+        }
+    }
+
+    /// Client for `stream.binance.com:9443` going through the proxy of the http `client`.
+    pub fn from_http(client: &HttpClient) -> Result<Self> {
+        Client::builder().proxy_from(client).build()
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn reconnect_policy(&self) -> &RetryPolicy {
+        &self.reconnect_policy
+    }
+
+    pub(crate) fn rotation_interval(&self) -> Duration {
+        self.rotation_interval
+    }
+
+    pub(crate) fn rotation_overlap(&self) -> Duration {
+        self.rotation_overlap
+    }
+
+    /// Connects to `path` under the base url, e.g. `/ws/btcusdt@trade` for a raw stream or
+    /// `/stream?streams=btcusdt@trade/ethusdt@trade` for a combined one.
+    pub async fn connect(&self, path: &str) -> Result<Connection> {
+        self.open(path, MIN_SEND_INTERVAL).await
+    }
+
+    /// Like `connect`, but messages go out as soon as they are sent. The WebSocket API counts
+    /// request weight instead of messages.
+    pub(crate) async fn connect_unthrottled(&self, path: &str) -> Result<Connection> {
+        self.open(path, Duration::ZERO).await
+    }
+
+    async fn open(&self, path: &str, send_interval: Duration) -> Result<Connection> {
+        let url = format!("{}{}", self.base_url, path);
+        let socket = match tokio::time::timeout(self.connect_timeout, self.handshake(&url)).await {
+            Ok(socket) => socket?,
+            Err(_) => {
+                return Err(Error::Timeout);
+            },
+        };
+
+        let (messages_tx, messages) = mpsc::unbounded_channel();
+        let (commands, commands_rx) = mpsc::unbounded_channel();
+        tokio::spawn(run(url, socket, send_interval, messages_tx, commands_rx));
+        Ok(Connection { messages, commands })
+    }
+
+    async fn handshake(&self, url: &str) -> Result<Socket> {
+        let uri = parse_uri(url)?;
+        let default_port = if uri.scheme_str() == Some("wss") { 443 } else { 80 };
+        let addr = format!("{}:{}", uri.host().unwrap_or_default(), uri.port_u16().unwrap_or(default_port));
+
+        let stream = match &self.proxy_uri {
+            Some(proxy_uri) => connect_through_proxy(proxy_uri, &addr).await?,
+            None => TcpStream::connect(&addr).await.map_err(tungstenite::Error::from)?,
+        };
+        let (socket, response) = client_async_tls(url, stream).await?;
+        debug!("websocket {} connected: {}", url, response.status());
+        Ok(socket)
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client::new()
+    }
 }
 
-pub fn test_connect() {
-    let (mut _socket, response) = connect("wss://fsserver-gyd.glodon.com/").unwrap();
-    println!("websocket connected");
-    println!("status: {}", response.status());
+fn parse_uri(uri: &str) -> Result<Uri> {
+    match uri.parse::<Uri>() {
+        Ok(parsed) if parsed.host().is_some() => Ok(parsed),
+        Ok(_) => Err(Error::InvalidParameter(format!("invalid uri {}: missing host", uri))),
+        Err(err) => Err(Error::InvalidParameter(format!("invalid uri {}: {}", uri, err))),
+    }
+}
+
+async fn connect_through_proxy(proxy_uri: &str, addr: &str) -> Result<TcpStream> {
+    let proxy = parse_uri(proxy_uri)?;
+    let proxy_addr = format!("{}:{}", proxy.host().unwrap_or_default(), proxy.port_u16().unwrap_or(80));
+    let mut stream = TcpStream::connect(&proxy_addr).await.map_err(tungstenite::Error::from)?;
+
+    let request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n\r\n", addr);
+    stream.write_all(request.as_bytes()).await.map_err(tungstenite::Error::from)?;
+
+    // byte by byte, whatever follows the head already belongs to the tunnel
+    let mut head = vec![];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() > MAX_PROXY_RESPONSE {
+            return Err(Error::InvalidParameter(format!("{} is not an http proxy", proxy_uri)));
+        }
+        head.push(stream.read_u8().await.map_err(tungstenite::Error::from)?);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let status = head.split_whitespace().nth(1).and_then(|code| code.parse::<u16>().ok());
+    match status.and_then(|code| StatusCode::from_u16(code).ok()) {
+        Some(status) if status.is_success() => Ok(stream),
+        Some(status) => Err(Error::Http { status, body: head.trim_end().to_string() }),
+        None => Err(Error::InvalidParameter(format!("{} is not an http proxy", proxy_uri))),
+    }
+}
+
+#[derive(Debug)]
+enum Command {
+    Send(String),
+    Close(oneshot::Sender<Result<()>>),
+}
+
+/// An open websocket connection, a `Stream` of the text messages the server sends.
+///
+/// A background task reads the socket, so pings are answered even while nobody polls the
+/// stream. The stream ends once the connection is closed; an abnormal end is yielded as an
+/// error first. Dropping the connection closes it in the background, `close` waits for the
+/// close handshake.
+#[derive(Debug)]
+pub struct Connection {
+    messages: mpsc::UnboundedReceiver<Result<String>>,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl Connection {
+    /// Sends a text message, e.g. a `SUBSCRIBE` request. Write errors end the stream.
+    ///
+    /// Messages go out at most 5 per second as Binance allows, faster ones are queued.
+    pub fn send(&self, text: impl Into<String>) -> Result<()> {
+        match self.commands.send(Command::Send(text.into())) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(tungstenite::Error::AlreadyClosed.into()),
+        }
+    }
+
+    /// Closes the connection and waits for the server to acknowledge it.
+    pub async fn close(self) -> Result<()> {
+        let (done_tx, done) = oneshot::channel();
+        if self.commands.send(Command::Close(done_tx)).is_err() {
+            // the task is gone, the connection already ended
+            return Ok(());
+        }
+        done.await.unwrap_or(Ok(()))
+    }
+}
+
+impl Stream for Connection {
+    type Item = Result<String>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.messages.poll_recv(cx)
+    }
+}
+
+async fn run(
+    url: String,
+    mut socket: Socket,
+    send_interval: Duration,
+    messages: mpsc::UnboundedSender<Result<String>>,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+) {
+    let mut outgoing = VecDeque::new();
+    let mut next_send = Instant::now();
+    loop {
+        tokio::select! {
+            frame = socket.next() => match frame {
+                Some(Ok(Message::Text(text))) => {
+                    messages.send(Ok(text)).ok();
+                },
+                Some(Ok(Message::Ping(_))) => {
+                    // tungstenite queued the pong, send it right away
+                    if let Err(err) = socket.flush().await {
+                        messages.send(Err(err.into())).ok();
+                        return;
+                    }
+                },
+                Some(Ok(Message::Close(frame))) => {
+                    debug!("websocket {} closed by the server: {:?}", url, frame);
+                },
+                Some(Ok(_)) => {},
+                Some(Err(tungstenite::Error::ConnectionClosed)) | None => {
+                    return;
+                },
+                Some(Err(err)) => {
+                    warn!("websocket {} failed: {}", url, err);
+                    messages.send(Err(err.into())).ok();
+                    return;
+                },
+            },
+            command = commands.recv() => match command {
+                Some(Command::Send(text)) => {
+                    outgoing.push_back(text);
+                },
+                Some(Command::Close(done)) => {
+                    let closed = match flush(&mut socket, &mut outgoing, next_send, send_interval).await {
+                        Ok(()) => close(&url, &mut socket).await,
+                        Err(err) => Err(err),
+                    };
+                    done.send(closed).ok();
+                    return;
+                },
+                // the connection was dropped
+                None => {
+                    if flush(&mut socket, &mut outgoing, next_send, send_interval).await.is_ok() {
+                        close(&url, &mut socket).await.ok();
+                    }
+                    return;
+                },
+            },
+            _ = tokio::time::sleep_until(next_send), if !outgoing.is_empty() => {
+                let text = outgoing.pop_front().unwrap_or_default();
+                if let Err(err) = socket.send(Message::Text(text)).await {
+                    messages.send(Err(err.into())).ok();
+                    return;
+                }
+                next_send = Instant::now() + send_interval;
+            },
+        }
+    }
+}
+
+// sends what is still queued, as slowly as `run` would
+async fn flush(socket: &mut Socket, outgoing: &mut VecDeque<String>, mut next_send: Instant, send_interval: Duration) -> Result<()> {
+    while let Some(text) = outgoing.pop_front() {
+        tokio::time::sleep_until(next_send).await;
+        socket.send(Message::Text(text)).await?;
+        next_send = Instant::now() + send_interval;
+    }
+    Ok(())
+}
+
+async fn close(url: &str, socket: &mut Socket) -> Result<()> {
+    match socket.close(None).await {
+        Ok(()) => {},
+        Err(tungstenite::Error::ConnectionClosed) | Err(tungstenite::Error::AlreadyClosed) => {
+            return Ok(());
+        },
+        Err(err) => {
+            return Err(err.into());
+        },
+    }
+
+    // the server answers with its own close frame, then ends the connection
+    let handshake = async {
+        while let Some(Ok(_)) = socket.next().await {}
+    };
+    if tokio::time::timeout(CLOSE_TIMEOUT, handshake).await.is_err() {
+        warn!("websocket {} did not acknowledge the close in {:?}", url, CLOSE_TIMEOUT);
+    }
+    debug!("websocket {} closed", url);
+    Ok(())
 }
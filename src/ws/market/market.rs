@@ -0,0 +1,219 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{Stream, StreamExt};
+use log::debug;
+use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::ws::client::{Client, Connection};
+use crate::ws::market::model::{MarketEvent, MarketMessage};
+use crate::ws::stream::{CombinedStream, StreamEvent, StreamMessage};
+
+const URL_COMBINED_STREAM: &str = "/stream?streams=";
+
+// streams Binance allows on one connection
+const MAX_STREAMS: usize = 1024;
+const KLINE_INTERVALS: [&str; 16] = ["1s", "1m", "3m", "5m", "15m", "30m", "1h", "2h", "4h", "6h", "8h", "12h", "1d", "3d", "1w", "1M"];
+const ROLLING_WINDOW_SIZES: [&str; 3] = ["1h", "4h", "1d"];
+const PARTIAL_DEPTH_LEVELS: [u32; 3] = [5, 10, 20];
+
+/// A market data stream of one symbol.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EMarketStream {
+    Trade(String),
+    AggTrade(String),
+    /// Symbol and interval, e.g. `1m` or `1M`.
+    Kline(String, String),
+    MiniTicker(String),
+    Ticker(String),
+    /// Symbol and window size: `1h`, `4h` or `1d`.
+    RollingWindowTicker(String, String),
+    BookTicker(String),
+    /// The top 5, 10 or 20 levels every 100ms.
+    PartialDepth(String, u32),
+    /// Order book changes every 100ms.
+    DiffDepth(String),
+}
+
+impl EMarketStream {
+    /// The stream name, e.g. `btcusdt@kline_1m`.
+    pub fn name(&self) -> String {
+        let symbol = self.symbol().to_lowercase();
+        match self {
+            EMarketStream::Trade(_) => format!("{}@trade", symbol),
+            EMarketStream::AggTrade(_) => format!("{}@aggTrade", symbol),
+            EMarketStream::Kline(_, interval) => format!("{}@kline_{}", symbol, interval),
+            EMarketStream::MiniTicker(_) => format!("{}@miniTicker", symbol),
+            EMarketStream::Ticker(_) => format!("{}@ticker", symbol),
+            EMarketStream::RollingWindowTicker(_, window_size) => format!("{}@ticker_{}", symbol, window_size),
+            EMarketStream::BookTicker(_) => format!("{}@bookTicker", symbol),
+            EMarketStream::PartialDepth(_, levels) => format!("{}@depth{}@100ms", symbol, levels),
+            EMarketStream::DiffDepth(_) => format!("{}@depth@100ms", symbol),
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        match self {
+            EMarketStream::Trade(symbol)
+            | EMarketStream::AggTrade(symbol)
+            | EMarketStream::Kline(symbol, _)
+            | EMarketStream::MiniTicker(symbol)
+            | EMarketStream::Ticker(symbol)
+            | EMarketStream::RollingWindowTicker(symbol, _)
+            | EMarketStream::BookTicker(symbol)
+            | EMarketStream::PartialDepth(symbol, _)
+            | EMarketStream::DiffDepth(symbol) => symbol,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let valid = match self {
+            EMarketStream::Kline(_, interval) => KLINE_INTERVALS.contains(&interval.as_str()),
+            EMarketStream::RollingWindowTicker(_, window_size) => ROLLING_WINDOW_SIZES.contains(&window_size.as_str()),
+            EMarketStream::PartialDepth(_, levels) => PARTIAL_DEPTH_LEVELS.contains(levels),
+            _ => true,
+        };
+        if !valid || self.symbol().is_empty() {
+            return Err(Error::InvalidParameter(format!("invalid stream {}", self.name())));
+        }
+        Ok(())
+    }
+
+    fn parse(&self, data: Value) -> Result<MarketEvent> {
+        let event = match self {
+            EMarketStream::Trade(_) => MarketEvent::Trade(serde_json::from_value(data)?),
+            EMarketStream::AggTrade(_) => MarketEvent::AggTrade(serde_json::from_value(data)?),
+            EMarketStream::Kline(..) => MarketEvent::Kline(serde_json::from_value(data)?),
+            EMarketStream::MiniTicker(_) => MarketEvent::MiniTicker(serde_json::from_value(data)?),
+            EMarketStream::Ticker(_) => MarketEvent::Ticker(serde_json::from_value(data)?),
+            EMarketStream::RollingWindowTicker(..) => MarketEvent::RollingWindowTicker(serde_json::from_value(data)?),
+            EMarketStream::BookTicker(_) => MarketEvent::BookTicker(serde_json::from_value(data)?),
+            EMarketStream::PartialDepth(..) => MarketEvent::PartialDepth(serde_json::from_value(data)?),
+            EMarketStream::DiffDepth(_) => MarketEvent::DiffDepth(serde_json::from_value(data)?),
+        };
+        Ok(event)
+    }
+}
+
+impl fmt::Display for EMarketStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Subscribes to `streams` on one combined stream connection, events are routed by stream name.
+/// The connection reconnects and rotates on its own, see `CombinedStream`.
+pub async fn subscribe(client: &Client, streams: &[EMarketStream]) -> Result<MarketStream> {
+    validate(streams, streams.len())?;
+    let names: Vec<String> = streams.iter().map(EMarketStream::name).collect();
+    let stream = CombinedStream::connect(client, &names).await?;
+    let streams = streams.iter().map(|stream| (stream.name(), stream.clone())).collect();
+    Ok(MarketStream { stream, streams })
+}
+
+/// The combined stream connection, with the `{"stream": ..., "data": ...}` messages as they come.
+pub async fn subscribe_raw(client: &Client, streams: &[EMarketStream]) -> Result<Connection> {
+    validate(streams, streams.len())?;
+    let names: Vec<String> = streams.iter().map(EMarketStream::name).collect();
+    client.connect(&format!("{}{}", URL_COMBINED_STREAM, names.join("/"))).await
+}
+
+// `total` is how many streams the connection has afterwards
+fn validate(streams: &[EMarketStream], total: usize) -> Result<()> {
+    if streams.is_empty() || total > MAX_STREAMS {
+        return Err(Error::InvalidParameter(format!("between 1 and {} streams are allowed per connection", MAX_STREAMS)));
+    }
+    for stream in streams {
+        stream.validate()?;
+    }
+    Ok(())
+}
+
+/// A `Stream` of typed market events. An event that cannot be parsed is yielded as an error
+/// and the stream goes on.
+#[derive(Debug)]
+pub struct MarketStream {
+    stream: CombinedStream,
+    streams: HashMap<String, EMarketStream>,
+}
+
+impl MarketStream {
+    pub fn streams(&self) -> impl Iterator<Item = &EMarketStream> {
+        self.streams.values()
+    }
+
+    /// Adds `streams` to the connection, they are restored after a reconnect as well.
+    pub async fn subscribe(&mut self, streams: &[EMarketStream]) -> Result<()> {
+        let added: Vec<EMarketStream> = streams.iter().filter(|stream| !self.streams.contains_key(&stream.name())).cloned().collect();
+        validate(streams, self.streams.len() + added.len())?;
+
+        // routed from the moment the server starts sending
+        for stream in &added {
+            self.streams.insert(stream.name(), stream.clone());
+        }
+        let names: Vec<String> = streams.iter().map(EMarketStream::name).collect();
+        if let Err(err) = self.stream.subscribe(&names).await {
+            for stream in &added {
+                self.streams.remove(&stream.name());
+            }
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    pub async fn unsubscribe(&mut self, streams: &[EMarketStream]) -> Result<()> {
+        let names: Vec<String> = streams.iter().map(EMarketStream::name).collect();
+        self.stream.unsubscribe(&names).await?;
+        for name in &names {
+            self.streams.remove(name);
+        }
+        Ok(())
+    }
+
+    /// The stream names the server has for the connection.
+    pub async fn list_subscriptions(&self) -> Result<Vec<String>> {
+        self.stream.list_subscriptions().await
+    }
+
+    pub async fn close(self) -> Result<()> {
+        self.stream.close().await
+    }
+
+    fn route(&self, message: StreamMessage) -> Option<Result<MarketMessage>> {
+        let stream = match self.streams.get(&message.stream) {
+            Some(stream) => stream,
+            None => {
+                debug!("skipping event of unknown stream {}", message.stream);
+                return None;
+            },
+        };
+        Some(stream.parse(message.data).map(|event| MarketMessage { stream: stream.clone(), event }))
+    }
+}
+
+impl Stream for MarketStream {
+    type Item = Result<StreamEvent<MarketMessage>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let message = match self.stream.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(StreamEvent::Message(message)))) => message,
+                Poll::Ready(Some(Ok(StreamEvent::Disconnected(reason)))) => {
+                    return Poll::Ready(Some(Ok(StreamEvent::Disconnected(reason))));
+                },
+                Poll::Ready(Some(Ok(StreamEvent::Reconnected))) => return Poll::Ready(Some(Ok(StreamEvent::Reconnected))),
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+            if let Some(message) = self.route(message) {
+                return Poll::Ready(Some(message.map(StreamEvent::Message)));
+            }
+        }
+    }
+}
@@ -0,0 +1,4 @@
+#[allow(clippy::module_inception)]
+pub mod market;
+pub mod model;
+pub mod order_book;
@@ -0,0 +1,332 @@
+use serde::Deserialize;
+
+use crate::http::market::model::{AggTrade, BookTicker, Kline, OrderBookSnapshot, PriceLevel, Ticker24hr, TickerPrice};
+use crate::ws::market::market::EMarketStream;
+use crate::Decimal;
+
+/// Does not convert to the REST `Trade`, the stream leaves out whether the trade was the best
+/// price match.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TradeEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "t")]
+    pub id: u64,
+    #[serde(rename = "p")]
+    pub price: Decimal,
+    #[serde(rename = "q")]
+    pub qty: Decimal,
+    #[serde(rename = "T")]
+    pub time: u64,
+    #[serde(rename = "m")]
+    pub is_buyer_maker: bool,
+}
+
+/// Same fields as the REST aggregate trade.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AggTradeEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(flatten)]
+    pub agg_trade: AggTrade,
+}
+
+impl From<AggTradeEvent> for AggTrade {
+    fn from(event: AggTradeEvent) -> Self {
+        event.agg_trade
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct KlineEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "k")]
+    pub kline: StreamKline,
+}
+
+/// The kline so far; it is final once `is_closed`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct StreamKline {
+    #[serde(rename = "t")]
+    pub open_time: u64,
+    #[serde(rename = "T")]
+    pub close_time: u64,
+    #[serde(rename = "i")]
+    pub interval: String,
+    /// -1 until the first trade.
+    #[serde(rename = "f")]
+    pub first_trade_id: i64,
+    #[serde(rename = "L")]
+    pub last_trade_id: i64,
+    #[serde(rename = "o")]
+    pub open: Decimal,
+    #[serde(rename = "c")]
+    pub close: Decimal,
+    #[serde(rename = "h")]
+    pub high: Decimal,
+    #[serde(rename = "l")]
+    pub low: Decimal,
+    #[serde(rename = "v")]
+    pub volume: Decimal,
+    #[serde(rename = "n")]
+    pub number_of_trades: u64,
+    #[serde(rename = "x")]
+    pub is_closed: bool,
+    #[serde(rename = "q")]
+    pub quote_asset_volume: Decimal,
+    #[serde(rename = "V")]
+    pub taker_buy_base_asset_volume: Decimal,
+    #[serde(rename = "Q")]
+    pub taker_buy_quote_asset_volume: Decimal,
+}
+
+impl From<StreamKline> for Kline {
+    fn from(kline: StreamKline) -> Self {
+        Kline {
+            open_time: kline.open_time,
+            open: kline.open,
+            high: kline.high,
+            low: kline.low,
+            close: kline.close,
+            volume: kline.volume,
+            close_time: kline.close_time,
+            quote_asset_volume: kline.quote_asset_volume,
+            number_of_trades: kline.number_of_trades,
+            taker_buy_base_asset_volume: kline.taker_buy_base_asset_volume,
+            taker_buy_quote_asset_volume: kline.taker_buy_quote_asset_volume,
+        }
+    }
+}
+
+impl From<KlineEvent> for Kline {
+    fn from(event: KlineEvent) -> Self {
+        event.kline.into()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MiniTickerEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "c")]
+    pub close_price: Decimal,
+    #[serde(rename = "o")]
+    pub open_price: Decimal,
+    #[serde(rename = "h")]
+    pub high_price: Decimal,
+    #[serde(rename = "l")]
+    pub low_price: Decimal,
+    #[serde(rename = "v")]
+    pub volume: Decimal,
+    #[serde(rename = "q")]
+    pub quote_volume: Decimal,
+}
+
+/// The last price, as `market::ticker_price` returns it.
+impl From<MiniTickerEvent> for TickerPrice {
+    fn from(event: MiniTickerEvent) -> Self {
+        TickerPrice {
+            symbol: event.symbol,
+            price: event.close_price,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TickerEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "p")]
+    pub price_change: Decimal,
+    #[serde(rename = "P")]
+    pub price_change_percent: Decimal,
+    #[serde(rename = "w")]
+    pub weighted_avg_price: Decimal,
+    #[serde(rename = "x")]
+    pub prev_close_price: Decimal,
+    #[serde(rename = "c")]
+    pub last_price: Decimal,
+    #[serde(rename = "Q")]
+    pub last_qty: Decimal,
+    #[serde(rename = "b")]
+    pub bid_price: Decimal,
+    #[serde(rename = "B")]
+    pub bid_qty: Decimal,
+    #[serde(rename = "a")]
+    pub ask_price: Decimal,
+    #[serde(rename = "A")]
+    pub ask_qty: Decimal,
+    #[serde(rename = "o")]
+    pub open_price: Decimal,
+    #[serde(rename = "h")]
+    pub high_price: Decimal,
+    #[serde(rename = "l")]
+    pub low_price: Decimal,
+    #[serde(rename = "v")]
+    pub volume: Decimal,
+    #[serde(rename = "q")]
+    pub quote_volume: Decimal,
+    #[serde(rename = "O")]
+    pub open_time: u64,
+    #[serde(rename = "C")]
+    pub close_time: u64,
+    #[serde(rename = "F")]
+    pub first_id: i64,
+    #[serde(rename = "L")]
+    pub last_id: i64,
+    #[serde(rename = "n")]
+    pub count: u64,
+}
+
+impl From<TickerEvent> for Ticker24hr {
+    fn from(event: TickerEvent) -> Self {
+        Ticker24hr {
+            symbol: event.symbol,
+            price_change: event.price_change,
+            price_change_percent: event.price_change_percent,
+            weighted_avg_price: event.weighted_avg_price,
+            prev_close_price: event.prev_close_price,
+            last_price: event.last_price,
+            last_qty: event.last_qty,
+            bid_price: event.bid_price,
+            bid_qty: event.bid_qty,
+            ask_price: event.ask_price,
+            ask_qty: event.ask_qty,
+            open_price: event.open_price,
+            high_price: event.high_price,
+            low_price: event.low_price,
+            volume: event.volume,
+            quote_volume: event.quote_volume,
+            open_time: event.open_time,
+            close_time: event.close_time,
+            first_id: event.first_id,
+            last_id: event.last_id,
+            count: event.count,
+        }
+    }
+}
+
+/// Statistics over a rolling window of 1h, 4h or 1d.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RollingWindowTickerEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "p")]
+    pub price_change: Decimal,
+    #[serde(rename = "P")]
+    pub price_change_percent: Decimal,
+    #[serde(rename = "o")]
+    pub open_price: Decimal,
+    #[serde(rename = "h")]
+    pub high_price: Decimal,
+    #[serde(rename = "l")]
+    pub low_price: Decimal,
+    #[serde(rename = "c")]
+    pub last_price: Decimal,
+    #[serde(rename = "w")]
+    pub weighted_avg_price: Decimal,
+    #[serde(rename = "v")]
+    pub volume: Decimal,
+    #[serde(rename = "q")]
+    pub quote_volume: Decimal,
+    #[serde(rename = "O")]
+    pub open_time: u64,
+    #[serde(rename = "C")]
+    pub close_time: u64,
+    #[serde(rename = "F")]
+    pub first_id: i64,
+    #[serde(rename = "L")]
+    pub last_id: i64,
+    #[serde(rename = "n")]
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BookTickerEvent {
+    #[serde(rename = "u")]
+    pub update_id: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "b")]
+    pub bid_price: Decimal,
+    #[serde(rename = "B")]
+    pub bid_qty: Decimal,
+    #[serde(rename = "a")]
+    pub ask_price: Decimal,
+    #[serde(rename = "A")]
+    pub ask_qty: Decimal,
+}
+
+impl From<BookTickerEvent> for BookTicker {
+    fn from(event: BookTickerEvent) -> Self {
+        BookTicker {
+            symbol: event.symbol,
+            bid_price: event.bid_price,
+            bid_qty: event.bid_qty,
+            ask_price: event.ask_price,
+            ask_qty: event.ask_qty,
+        }
+    }
+}
+
+/// Changes to the order book; a level with a zero quantity was removed.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DiffDepthEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "U")]
+    pub first_update_id: u64,
+    #[serde(rename = "u")]
+    pub final_update_id: u64,
+    #[serde(rename = "b")]
+    pub bids: Vec<PriceLevel>,
+    #[serde(rename = "a")]
+    pub asks: Vec<PriceLevel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarketEvent {
+    Trade(TradeEvent),
+    AggTrade(AggTradeEvent),
+    Kline(KlineEvent),
+    MiniTicker(MiniTickerEvent),
+    Ticker(TickerEvent),
+    RollingWindowTicker(RollingWindowTickerEvent),
+    BookTicker(BookTickerEvent),
+    /// The top levels of the book, the same as a REST depth snapshot.
+    PartialDepth(OrderBookSnapshot),
+    DiffDepth(DiffDepthEvent),
+}
+
+/// An event and the stream it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketMessage {
+    pub stream: EMarketStream,
+    pub event: MarketEvent,
+}
+
+/// What happened to an `OrderBook`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderBookEvent {
+    /// The book was (re)built from a snapshot and the buffered updates.
+    Synced { last_update_id: u64 },
+    /// A diff was applied, with the levels it changed; a zero quantity removed the level.
+    Updated { last_update_id: u64, bids: Vec<PriceLevel>, asks: Vec<PriceLevel> },
+    /// Updates were missed or the stream failed, the book is rebuilt.
+    OutOfSync(String),
+}
@@ -0,0 +1,410 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use futures::StreamExt;
+use log::{debug, warn};
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+
+use crate::error::{Error, Result};
+use crate::http::client::Client as HttpClient;
+use crate::http::market::market as http_market;
+use crate::http::market::model::{OrderBookSnapshot, PriceLevel};
+use crate::http::trade::trade::ESide;
+use crate::ws::client::Client;
+use crate::ws::market::market::{self, EMarketStream, MarketStream};
+use crate::ws::market::model::{DiffDepthEvent, MarketEvent, OrderBookEvent};
+use crate::ws::stream::StreamEvent;
+use crate::Decimal;
+
+/// Levels per side of the REST snapshot the book starts from.
+pub const SNAPSHOT_LIMIT: u32 = 5000;
+
+/// Snapshots fetched again while they are older than the stream, before the sync is given up.
+pub const MAX_STALE_SNAPSHOTS: u32 = 5;
+
+const EVENT_CAPACITY: usize = 64;
+
+/// What `LocalBook::apply` did with a diff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApplyOutcome {
+    Applied,
+    /// The diff is older than the book and was ignored.
+    Stale,
+    /// Updates between the book and the diff are missing, the book has to be rebuilt.
+    Gap { expected: u64, first_update_id: u64 },
+}
+
+/// An order book built from a depth snapshot and kept current with diff depth events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalBook {
+    last_update_id: u64,
+    // the first diff after the snapshot may start before it, later ones must follow on exactly
+    continued: bool,
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+}
+
+impl LocalBook {
+    pub fn new(snapshot: OrderBookSnapshot) -> Self {
+        let levels = |levels: Vec<PriceLevel>| {
+            levels.into_iter().filter(|l| !l.qty.is_zero()).map(|l| (l.price, l.qty)).collect()
+        };
+        LocalBook {
+            last_update_id: snapshot.last_update_id,
+            continued: false,
+            bids: levels(snapshot.bids),
+            asks: levels(snapshot.asks),
+        }
+    }
+
+    pub fn last_update_id(&self) -> u64 {
+        self.last_update_id
+    }
+
+    /// Applies `event` if it is the next one: the first diff after the snapshot has to cover
+    /// `last_update_id + 1`, every later one has to start right after the previous one.
+    pub fn apply(&mut self, event: &DiffDepthEvent) -> ApplyOutcome {
+        if event.final_update_id <= self.last_update_id {
+            return ApplyOutcome::Stale;
+        }
+        let expected = self.last_update_id + 1;
+        let in_sequence = if self.continued {
+            event.first_update_id == expected
+        } else {
+            event.first_update_id <= expected
+        };
+        if !in_sequence {
+            return ApplyOutcome::Gap { expected, first_update_id: event.first_update_id };
+        }
+
+        for level in event.bids.iter() {
+            update(&mut self.bids, level);
+        }
+        for level in event.asks.iter() {
+            update(&mut self.asks, level);
+        }
+        self.last_update_id = event.final_update_id;
+        self.continued = true;
+        ApplyOutcome::Applied
+    }
+
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.bids.iter().next_back().map(to_level)
+    }
+
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.asks.iter().next().map(to_level)
+    }
+
+    /// The best `levels` bids and asks.
+    pub fn depth(&self, levels: usize) -> OrderBookSnapshot {
+        OrderBookSnapshot {
+            last_update_id: self.last_update_id,
+            bids: self.bids.iter().rev().take(levels).map(to_level).collect(),
+            asks: self.asks.iter().take(levels).map(to_level).collect(),
+        }
+    }
+
+    /// Average price of a market order of `qty` on `side`, a buy takes the asks and a sell the
+    /// bids. `None` if the book is not deep enough.
+    pub fn vwap(&self, side: ESide, qty: Decimal) -> Option<Decimal> {
+        if qty <= Decimal::ZERO {
+            return None;
+        }
+        let levels: Box<dyn Iterator<Item = (&Decimal, &Decimal)>> = match side {
+            ESide::BUY => Box::new(self.asks.iter()),
+            ESide::SELL => Box::new(self.bids.iter().rev()),
+        };
+
+        let mut remaining = qty;
+        let mut notional = Decimal::ZERO;
+        for (price, level_qty) in levels {
+            let take = remaining.min(*level_qty);
+            notional += *price * take;
+            remaining -= take;
+            if remaining.is_zero() {
+                return Some(notional / qty);
+            }
+        }
+        None
+    }
+}
+
+fn update(side: &mut BTreeMap<Decimal, Decimal>, level: &PriceLevel) {
+    if level.qty.is_zero() {
+        side.remove(&level.price);
+    } else {
+        side.insert(level.price, level.qty);
+    }
+}
+
+fn to_level((price, qty): (&Decimal, &Decimal)) -> PriceLevel {
+    PriceLevel { price: *price, qty: *qty }
+}
+
+/// A `LocalBook` of one symbol maintained by a background task.
+///
+/// The task follows Binance's procedure: it buffers the `depth@100ms` diffs, fetches a snapshot
+/// of `SNAPSHOT_LIMIT` levels once the first diff arrived, drops the diffs the snapshot already
+/// contains and applies the rest in sequence. A gap, a broken event or a failed stream starts
+/// over from a new snapshot. Reads return `None` while the book is out of sync. Dropping it stops
+/// the task.
+#[derive(Debug)]
+pub struct OrderBook {
+    symbol: String,
+    book: Arc<RwLock<Option<LocalBook>>>,
+    synced: watch::Receiver<bool>,
+    events: broadcast::Sender<OrderBookEvent>,
+    task: JoinHandle<()>,
+}
+
+impl OrderBook {
+    /// Subscribes to the diff depth stream of `symbol` and starts syncing the book.
+    pub async fn start(http: Arc<HttpClient>, ws: &Client, symbol: impl Into<String>) -> Result<Self> {
+        let symbol = symbol.into();
+        let stream = market::subscribe(ws, &[EMarketStream::DiffDepth(symbol.clone())]).await?;
+
+        let book = Arc::new(RwLock::new(None));
+        let (synced_tx, synced) = watch::channel(false);
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        let sync = BookSync {
+            http,
+            ws: ws.clone(),
+            symbol: symbol.clone(),
+            book: book.clone(),
+            synced: synced_tx,
+            events: events.clone(),
+        };
+        let task = tokio::spawn(sync.run(stream));
+
+        Ok(OrderBook { symbol, book, synced, events, task })
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn is_synced(&self) -> bool {
+        *self.synced.borrow()
+    }
+
+    /// Waits until the book is in sync.
+    pub async fn wait_synced(&self) {
+        let mut synced = self.synced.clone();
+        // the sender only goes away with the task, which lives as long as `self`
+        synced.wait_for(|synced| *synced).await.ok();
+    }
+
+    /// Events from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<OrderBookEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn last_update_id(&self) -> Option<u64> {
+        self.read(|book| book.last_update_id())
+    }
+
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.read(|book| book.best_bid()).flatten()
+    }
+
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.read(|book| book.best_ask()).flatten()
+    }
+
+    /// The best `levels` bids and asks.
+    pub fn depth(&self, levels: usize) -> Option<OrderBookSnapshot> {
+        self.read(|book| book.depth(levels))
+    }
+
+    /// See `LocalBook::vwap`.
+    pub fn vwap(&self, side: ESide, qty: Decimal) -> Option<Decimal> {
+        self.read(|book| book.vwap(side, qty)).flatten()
+    }
+
+    /// A copy of the whole book.
+    pub fn snapshot(&self) -> Option<LocalBook> {
+        self.read(|book| book.clone())
+    }
+
+    fn read<T>(&self, f: impl FnOnce(&LocalBook) -> T) -> Option<T> {
+        self.book.read().unwrap().as_ref().map(f)
+    }
+}
+
+impl Drop for OrderBook {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+struct BookSync {
+    http: Arc<HttpClient>,
+    ws: Client,
+    symbol: String,
+    book: Arc<RwLock<Option<LocalBook>>>,
+    synced: watch::Sender<bool>,
+    events: broadcast::Sender<OrderBookEvent>,
+}
+
+// why a sync ended
+#[derive(Debug)]
+enum Resync {
+    /// Missed or unreadable updates, the stream itself goes on.
+    Gap(String),
+    Snapshot(Error),
+    /// The snapshot stayed older than the first buffered update.
+    StaleSnapshot { last_update_id: u64, first_update_id: u64 },
+    Stream(Error),
+}
+
+impl BookSync {
+    async fn run(self, mut stream: MarketStream) {
+        let mut attempt = 0;
+        loop {
+            let resync = self.sync(&mut stream).await;
+            if *self.synced.borrow() {
+                attempt = 0;
+            }
+            let reason = match &resync {
+                Resync::Gap(reason) => reason.clone(),
+                Resync::Snapshot(err) => format!("depth snapshot failed: {}", err),
+                Resync::StaleSnapshot { last_update_id, first_update_id } => format!(
+                    "depth snapshot at {} still older than the update from {} after {} attempts",
+                    last_update_id, first_update_id, MAX_STALE_SNAPSHOTS + 1,
+                ),
+                Resync::Stream(err) => format!("depth stream failed: {}", err),
+            };
+            warn!("order book of {} out of sync: {}", self.symbol, reason);
+            *self.book.write().unwrap() = None;
+            self.synced.send_replace(false);
+            self.events.send(OrderBookEvent::OutOfSync(reason)).ok();
+
+            match resync {
+                Resync::Gap(_) => {},
+                Resync::Snapshot(_) | Resync::StaleSnapshot { .. } => {
+                    tokio::time::sleep(self.http.retry_policy().delay(attempt)).await;
+                    attempt += 1;
+                },
+                Resync::Stream(_) => loop {
+                    tokio::time::sleep(self.http.retry_policy().delay(attempt)).await;
+                    attempt += 1;
+                    match market::subscribe(&self.ws, &[EMarketStream::DiffDepth(self.symbol.clone())]).await {
+                        Ok(new) => {
+                            stream = new;
+                            break;
+                        },
+                        Err(err) => warn!("resubscribing the depth of {} failed: {}", self.symbol, err),
+                    }
+                },
+            }
+        }
+    }
+
+    // builds the book and keeps it current, until it cannot
+    async fn sync(&self, stream: &mut MarketStream) -> Resync {
+        let mut buffer = match next_diff(stream).await {
+            Ok(event) => vec![event],
+            Err(resync) => return resync,
+        };
+        let mut stale = 0;
+        let snapshot = loop {
+            let fetch = http_market::depth(&self.http, &self.symbol, SNAPSHOT_LIMIT);
+            let snapshot = match buffering(stream, &mut buffer, fetch).await {
+                Ok(snapshot) => snapshot,
+                Err(resync) => return resync,
+            };
+            let first_update_id = buffer[0].first_update_id;
+            match snapshot {
+                Ok(snapshot) if snapshot.last_update_id + 1 >= first_update_id => break snapshot,
+                Ok(snapshot) if stale == MAX_STALE_SNAPSHOTS => {
+                    return Resync::StaleSnapshot { last_update_id: snapshot.last_update_id, first_update_id };
+                },
+                Ok(_) => {
+                    let delay = self.http.retry_policy().delay(stale);
+                    stale += 1;
+                    debug!("depth snapshot of {} is older than the stream, fetching another one in {:?}", self.symbol, delay);
+                    if let Err(resync) = buffering(stream, &mut buffer, tokio::time::sleep(delay)).await {
+                        return resync;
+                    }
+                },
+                Err(err) => return Resync::Snapshot(err),
+            }
+        };
+
+        let mut book = LocalBook::new(snapshot);
+        for event in buffer.iter() {
+            if let ApplyOutcome::Gap { expected, first_update_id } = book.apply(event) {
+                return gap(expected, first_update_id);
+            }
+        }
+        let last_update_id = book.last_update_id();
+        *self.book.write().unwrap() = Some(book);
+        self.synced.send_replace(true);
+        debug!("order book of {} synced at {}", self.symbol, last_update_id);
+        self.events.send(OrderBookEvent::Synced { last_update_id }).ok();
+
+        loop {
+            let event = match next_diff(stream).await {
+                Ok(event) => event,
+                Err(resync) => return resync,
+            };
+            let outcome = match self.book.write().unwrap().as_mut() {
+                Some(book) => book.apply(&event),
+                None => ApplyOutcome::Stale,
+            };
+            match outcome {
+                ApplyOutcome::Applied => {
+                    self.events.send(OrderBookEvent::Updated {
+                        last_update_id: event.final_update_id,
+                        bids: event.bids,
+                        asks: event.asks,
+                    }).ok();
+                },
+                ApplyOutcome::Stale => {},
+                ApplyOutcome::Gap { expected, first_update_id } => {
+                    return gap(expected, first_update_id);
+                },
+            }
+        }
+    }
+}
+
+fn gap(expected: u64, first_update_id: u64) -> Resync {
+    Resync::Gap(format!("depth update {} expected, got one from {}", expected, first_update_id))
+}
+
+// runs `future` to completion, buffering the diffs that come meanwhile
+async fn buffering<T>(
+    stream: &mut MarketStream,
+    buffer: &mut Vec<DiffDepthEvent>,
+    future: impl std::future::Future<Output = T>,
+) -> std::result::Result<T, Resync> {
+    tokio::pin!(future);
+    loop {
+        tokio::select! {
+            output = &mut future => return Ok(output),
+            event = next_diff(stream) => buffer.push(event?),
+        }
+    }
+}
+
+async fn next_diff(stream: &mut MarketStream) -> std::result::Result<DiffDepthEvent, Resync> {
+    loop {
+        return match stream.next().await {
+            Some(Ok(StreamEvent::Message(message))) => match message.event {
+                MarketEvent::DiffDepth(event) => Ok(event),
+                event => Err(Resync::Gap(format!("unexpected event {:?}", event))),
+            },
+            // the stream reconnects on its own, the updates meanwhile are lost
+            Some(Ok(StreamEvent::Disconnected(reason))) => Err(Resync::Gap(format!("depth stream disconnected: {}", reason))),
+            Some(Ok(StreamEvent::Reconnected)) => continue,
+            Some(Err(Error::Deserialize(err))) => Err(Resync::Gap(format!("unreadable depth update: {}", err))),
+            Some(Err(err)) => Err(Resync::Stream(err)),
+            None => Err(Resync::Stream(tungstenite::Error::ConnectionClosed.into())),
+        };
+    }
+}
@@ -1 +1,5 @@
+pub mod api;
 pub mod client;
+pub mod market;
+pub mod stream;
+pub mod user_data;
@@ -0,0 +1,644 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{FutureExt, Stream, StreamExt};
+use hyper::StatusCode;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::error::{ApiError, Error, ErrorCode, Result};
+use crate::ws::client::{Client, Connection};
+
+const URL_COMBINED_STREAM: &str = "/stream";
+
+// how long restoring a connection waits for each answer
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a reconnecting stream yields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamEvent<T> {
+    Message(T),
+    /// The connection was lost, events are missed until `Reconnected`.
+    Disconnected(String),
+    /// A new connection is up with every subscription restored.
+    Reconnected,
+}
+
+/// A message of a combined stream connection.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct StreamMessage {
+    pub stream: String,
+    pub data: Value,
+}
+
+enum Request {
+    Subscribe(Vec<String>, oneshot::Sender<Result<()>>),
+    Unsubscribe(Vec<String>, oneshot::Sender<Result<()>>),
+    ListSubscriptions(oneshot::Sender<Result<Vec<String>>>),
+}
+
+impl Request {
+    fn message(&self, id: u64) -> String {
+        match self {
+            Request::Subscribe(streams, _) => control_message("SUBSCRIBE", Some(streams), id),
+            Request::Unsubscribe(streams, _) => control_message("UNSUBSCRIBE", Some(streams), id),
+            Request::ListSubscriptions(_) => control_message("LIST_SUBSCRIPTIONS", None, id),
+        }
+    }
+
+    // the subscription change it makes, if it makes one
+    fn change(&self) -> Option<(&'static str, Vec<String>)> {
+        match self {
+            Request::Subscribe(streams, _) => Some(("SUBSCRIBE", streams.clone())),
+            Request::Unsubscribe(streams, _) => Some(("UNSUBSCRIBE", streams.clone())),
+            Request::ListSubscriptions(_) => None,
+        }
+    }
+
+    fn fail(self, err: Error) {
+        match self {
+            Request::Subscribe(_, done) | Request::Unsubscribe(_, done) => {
+                done.send(Err(err)).ok();
+            },
+            Request::ListSubscriptions(done) => {
+                done.send(Err(err)).ok();
+            },
+        }
+    }
+}
+
+enum Command {
+    Request(Request),
+    Close(oneshot::Sender<Result<()>>),
+}
+
+/// A combined stream connection that stays up.
+///
+/// A lost connection is reconnected with the client's `reconnect_policy` and its subscriptions
+/// are restored with `SUBSCRIBE`, checked with `LIST_SUBSCRIPTIONS`. `Disconnected` and
+/// `Reconnected` mark where events were missed. Before Binance ends a connection after 24 hours
+/// the stream moves to a new one: it is opened while the old one is still served, then both are
+/// read for the `rotation_overlap` and messages that arrive on both are yielded once, so nothing
+/// is missed. Requests the old connection did not answer are sent again on the new one.
+///
+/// The stream ends with an error when reconnecting gives up. Dropping it closes the connection.
+#[derive(Debug)]
+pub struct CombinedStream {
+    events: mpsc::UnboundedReceiver<Result<StreamEvent<StreamMessage>>>,
+    commands: mpsc::UnboundedSender<Command>,
+    subscriptions: Arc<Mutex<Vec<String>>>,
+}
+
+impl CombinedStream {
+    /// Connects with `streams` already subscribed; there may be none.
+    pub async fn connect(client: &Client, streams: &[String]) -> Result<Self> {
+        let path = if streams.is_empty() {
+            URL_COMBINED_STREAM.to_string()
+        } else {
+            format!("{}?streams={}", URL_COMBINED_STREAM, streams.join("/"))
+        };
+        let connection = client.connect(&path).await?;
+
+        let mut subscriptions = vec![];
+        add(&mut subscriptions, streams);
+        let subscriptions = Arc::new(Mutex::new(subscriptions));
+        let (events_tx, events) = mpsc::unbounded_channel();
+        let (commands, commands_rx) = mpsc::unbounded_channel();
+        let task = Task {
+            client: client.clone(),
+            subscriptions: subscriptions.clone(),
+            events: events_tx,
+            commands: commands_rx,
+            ids: Arc::new(AtomicU64::new(0)),
+            pending: HashMap::new(),
+            seen: HashSet::new(),
+            dedupe_until: None,
+        };
+        tokio::spawn(task.run(connection));
+        Ok(CombinedStream { events, commands, subscriptions })
+    }
+
+    /// Sends `SUBSCRIBE` and waits for the answer.
+    pub async fn subscribe(&self, streams: &[String]) -> Result<()> {
+        let streams = non_empty(streams)?;
+        self.request(|done| Request::Subscribe(streams, done)).await
+    }
+
+    /// Sends `UNSUBSCRIBE` and waits for the answer.
+    pub async fn unsubscribe(&self, streams: &[String]) -> Result<()> {
+        let streams = non_empty(streams)?;
+        self.request(|done| Request::Unsubscribe(streams, done)).await
+    }
+
+    /// The streams the server has for the connection, from `LIST_SUBSCRIPTIONS`.
+    pub async fn list_subscriptions(&self) -> Result<Vec<String>> {
+        self.request(Request::ListSubscriptions).await
+    }
+
+    /// The streams restored on a new connection.
+    pub fn subscriptions(&self) -> Vec<String> {
+        self.subscriptions.lock().unwrap().clone()
+    }
+
+    pub async fn close(self) -> Result<()> {
+        let (done_tx, done) = oneshot::channel();
+        if self.commands.send(Command::Close(done_tx)).is_err() {
+            return Ok(());
+        }
+        done.await.unwrap_or(Ok(()))
+    }
+
+    async fn request<T>(&self, request: impl FnOnce(oneshot::Sender<Result<T>>) -> Request) -> Result<T> {
+        let (done_tx, done) = oneshot::channel();
+        if self.commands.send(Command::Request(request(done_tx))).is_err() {
+            return Err(closed());
+        }
+        done.await.unwrap_or_else(|_| Err(closed()))
+    }
+}
+
+impl Stream for CombinedStream {
+    type Item = Result<StreamEvent<StreamMessage>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+fn non_empty(streams: &[String]) -> Result<Vec<String>> {
+    if streams.is_empty() {
+        return Err(Error::InvalidParameter(String::from("no streams given")));
+    }
+    Ok(streams.to_vec())
+}
+
+fn add(subscriptions: &mut Vec<String>, streams: &[String]) {
+    for stream in streams {
+        if !subscriptions.contains(stream) {
+            subscriptions.push(stream.clone());
+        }
+    }
+}
+
+fn closed() -> Error {
+    tungstenite::Error::AlreadyClosed.into()
+}
+
+fn control_message(method: &str, params: Option<&[String]>, id: u64) -> String {
+    match params {
+        Some(params) => json!({"method": method, "params": params, "id": id}),
+        None => json!({"method": method, "id": id}),
+    }
+    .to_string()
+}
+
+enum Incoming {
+    Message(StreamMessage),
+    Response { id: u64, result: Result<Value> },
+    Other,
+}
+
+fn parse(text: &str) -> Incoming {
+    let value: Value = match serde_json::from_str(text) {
+        Ok(value) => value,
+        Err(_) => {
+            return Incoming::Other;
+        },
+    };
+    if value.get("stream").is_some() {
+        return match serde_json::from_value(value) {
+            Ok(message) => Incoming::Message(message),
+            Err(_) => Incoming::Other,
+        };
+    }
+    match value.get("id").and_then(Value::as_u64) {
+        Some(id) => {
+            let result = match value.get("error") {
+                // stream errors come without a status, they are all about the request
+                Some(error) => Err(Error::Api(ApiError {
+                    status: StatusCode::BAD_REQUEST,
+                    code: ErrorCode::from(error["code"].as_i64().unwrap_or_default() as i32),
+                    msg: error["msg"].as_str().unwrap_or_default().to_string(),
+                    data: None,
+                })),
+                None => Ok(value.get("result").cloned().unwrap_or(Value::Null)),
+            };
+            Incoming::Response { id, result }
+        },
+        None => Incoming::Other,
+    }
+}
+
+// the same event comes as the same text on every connection
+fn fingerprint(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A connection for a rotation being opened in the background.
+struct Opening {
+    task: JoinHandle<Result<(Connection, Vec<String>)>>,
+    // subscription changes sent meanwhile, which the new connection may have missed
+    changes: Vec<(&'static str, Vec<String>)>,
+}
+
+impl Drop for Opening {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// The connection taking over, read along with the old one until `switch_at`.
+struct Rotation {
+    connection: Connection,
+    buffer: Vec<String>,
+    switch_at: Instant,
+}
+
+enum Step {
+    Current(Option<Result<String>>),
+    Next(Option<Result<String>>),
+    Command(Option<Command>),
+    Rotate,
+    Opened(Result<(Connection, Vec<String>)>),
+    Switch,
+}
+
+struct Task {
+    client: Client,
+    subscriptions: Arc<Mutex<Vec<String>>>,
+    events: mpsc::UnboundedSender<Result<StreamEvent<StreamMessage>>>,
+    commands: mpsc::UnboundedReceiver<Command>,
+    // shared with the connections opened in the background
+    ids: Arc<AtomicU64>,
+    // requests waiting for the current connection to answer
+    pending: HashMap<u64, Request>,
+    // messages yielded from the old connection during a rotation
+    seen: HashSet<u64>,
+    dedupe_until: Option<Instant>,
+}
+
+impl Task {
+    async fn run(mut self, mut current: Connection) {
+        let mut opening: Option<Opening> = None;
+        let mut rotation: Option<Rotation> = None;
+        let mut rotate_at = Instant::now() + self.client.rotation_interval();
+        let mut rotation_attempt = 0;
+
+        loop {
+            let switch_at = rotation.as_ref().map(|rotation| rotation.switch_at);
+            let step = tokio::select! {
+                text = current.next() => Step::Current(text),
+                text = next_text(&mut rotation), if rotation.is_some() => Step::Next(text),
+                command = self.commands.recv() => Step::Command(command),
+                _ = tokio::time::sleep_until(rotate_at), if rotation.is_none() && opening.is_none() => Step::Rotate,
+                opened = opened(&mut opening), if opening.is_some() => Step::Opened(opened),
+                _ = tokio::time::sleep_until(switch_at.unwrap_or(rotate_at)), if switch_at.is_some() => Step::Switch,
+            };
+
+            match step {
+                Step::Current(Some(Ok(text))) => {
+                    if !self.receive(&text, opening.is_some() || rotation.is_some()) {
+                        break;
+                    }
+                },
+                Step::Current(ended) => {
+                    let reason = match ended {
+                        Some(Err(err)) => err.to_string(),
+                        _ => String::from("connection closed by the server"),
+                    };
+                    opening = None;
+                    current = match rotation.take() {
+                        // the new connection already has everything
+                        Some(next) => match self.switch(None, next) {
+                            Some(connection) => connection,
+                            None => return,
+                        },
+                        None => match self.reconnect(reason).await {
+                            Some(connection) => connection,
+                            None => return,
+                        },
+                    };
+                    rotate_at = Instant::now() + self.client.rotation_interval();
+                },
+                Step::Next(Some(Ok(text))) => {
+                    if let Some(rotation) = rotation.as_mut() {
+                        rotation.buffer.push(text);
+                    }
+                },
+                Step::Next(ended) => {
+                    warn!("stream rotation failed: {:?}", ended);
+                    rotation = None;
+                    self.seen.clear();
+                    rotation_attempt += 1;
+                    rotate_at = Instant::now() + self.client.reconnect_policy().delay(rotation_attempt);
+                },
+                Step::Command(Some(Command::Request(request))) => {
+                    if let Some((opening, change)) = opening.as_mut().zip(request.change()) {
+                        opening.changes.push(change);
+                    }
+                    self.send(request, &current, rotation.as_ref().map(|rotation| &rotation.connection));
+                },
+                Step::Command(Some(Command::Close(done))) => {
+                    if let Some(rotation) = rotation {
+                        rotation.connection.close().await.ok();
+                    }
+                    done.send(current.close().await).ok();
+                    return;
+                },
+                Step::Command(None) => {
+                    break;
+                },
+                Step::Rotate => {
+                    // the old connection is served meanwhile, what it yields may come on the new one too
+                    self.seen.clear();
+                    self.dedupe_until = None;
+                    let task = tokio::spawn(open(self.client.clone(), self.subscriptions.clone(), self.ids.clone()));
+                    opening = Some(Opening { task, changes: vec![] });
+                },
+                Step::Opened(opened) => match opened {
+                    Ok((connection, buffer)) => {
+                        debug!("stream rotation started");
+                        let changes = opening.take().map(|mut opening| std::mem::take(&mut opening.changes));
+                        for (method, streams) in changes.unwrap_or_default() {
+                            // its answer is not waited for, like during the overlap
+                            connection.send(control_message(method, Some(&streams), next_id(&self.ids))).ok();
+                        }
+                        rotation_attempt = 0;
+                        rotation = Some(Rotation {
+                            connection,
+                            buffer,
+                            switch_at: Instant::now() + self.client.rotation_overlap(),
+                        });
+                    },
+                    Err(err) => {
+                        warn!("stream rotation failed: {}", err);
+                        opening = None;
+                        self.seen.clear();
+                        rotation_attempt += 1;
+                        rotate_at = Instant::now() + self.client.reconnect_policy().delay(rotation_attempt);
+                    },
+                },
+                Step::Switch => {
+                    let next = match rotation.take() {
+                        Some(next) => next,
+                        None => continue,
+                    };
+                    current = match self.switch(Some(current), next) {
+                        Some(connection) => connection,
+                        None => return,
+                    };
+                    rotate_at = Instant::now() + self.client.rotation_interval();
+                },
+            }
+        }
+
+        // nobody listens anymore
+        if let Some(rotation) = rotation {
+            rotation.connection.close().await.ok();
+        }
+        current.close().await.ok();
+    }
+
+    /// Handles a message of the current connection, false once nobody listens anymore.
+    fn receive(&mut self, text: &str, rotating: bool) -> bool {
+        match parse(text) {
+            Incoming::Message(message) => {
+                let fingerprint = fingerprint(text);
+                if rotating {
+                    self.seen.insert(fingerprint);
+                } else if self.is_duplicate(fingerprint) {
+                    return true;
+                }
+                self.emit(Ok(StreamEvent::Message(message)))
+            },
+            Incoming::Response { id, result } => {
+                self.respond(id, result);
+                true
+            },
+            Incoming::Other => {
+                debug!("skipping non-stream message {}", text);
+                true
+            },
+        }
+    }
+
+    fn is_duplicate(&mut self, fingerprint: u64) -> bool {
+        match self.dedupe_until {
+            Some(until) if Instant::now() < until => self.seen.contains(&fingerprint),
+            Some(_) => {
+                self.seen.clear();
+                self.dedupe_until = None;
+                false
+            },
+            None => false,
+        }
+    }
+
+    fn emit(&self, event: Result<StreamEvent<StreamMessage>>) -> bool {
+        self.events.send(event).is_ok()
+    }
+
+    fn respond(&mut self, id: u64, result: Result<Value>) {
+        match self.pending.remove(&id) {
+            Some(Request::Subscribe(streams, done)) => {
+                if result.is_ok() {
+                    add(&mut self.subscriptions.lock().unwrap(), &streams);
+                }
+                done.send(result.map(|_| ())).ok();
+            },
+            Some(Request::Unsubscribe(streams, done)) => {
+                if result.is_ok() {
+                    self.subscriptions.lock().unwrap().retain(|stream| !streams.contains(stream));
+                }
+                done.send(result.map(|_| ())).ok();
+            },
+            Some(Request::ListSubscriptions(done)) => {
+                done.send(result.and_then(|streams| Ok(serde_json::from_value(streams)?))).ok();
+            },
+            None => match result {
+                Err(err) => warn!("stream request {} failed: {}", id, err),
+                Ok(_) => debug!("stream request {} answered", id),
+            },
+        }
+    }
+
+    /// Sends a request on the current connection, and on the one taking over if there is one.
+    fn send(&mut self, request: Request, current: &Connection, next: Option<&Connection>) {
+        let id = next_id(&self.ids);
+        if let Err(err) = current.send(request.message(id)) {
+            request.fail(err);
+            return;
+        }
+        if let Some(next) = next {
+            if !matches!(request, Request::ListSubscriptions(_)) {
+                // its answer is not waited for
+                next.send(request.message(next_id(&self.ids))).ok();
+            }
+        }
+        self.pending.insert(id, request);
+    }
+
+    // the requests the lost connection did not answer, in the order they were sent; they may
+    // have taken effect or not, subscribing and unsubscribing again is harmless
+    fn take_pending(&mut self) -> Vec<Request> {
+        let mut pending: Vec<(u64, Request)> = self.pending.drain().collect();
+        pending.sort_by_key(|(id, _)| *id);
+        pending.into_iter().map(|(_, request)| request).collect()
+    }
+
+    /// Moves to the new connection of a rotation; what both connections got is yielded once.
+    fn switch(&mut self, old: Option<Connection>, next: Rotation) -> Option<Connection> {
+        if let Some(mut old) = old {
+            // what already arrived on the old connection
+            while let Some(Some(Ok(text))) = old.next().now_or_never() {
+                if !self.receive(&text, true) {
+                    return None;
+                }
+            }
+        }
+        // the new connection answers what the old one did not
+        for request in self.take_pending() {
+            self.send(request, &next.connection, None);
+        }
+        self.dedupe_until = Some(Instant::now() + self.client.rotation_overlap());
+        for text in next.buffer {
+            if !self.receive(&text, false) {
+                return None;
+            }
+        }
+        info!("stream moved to a new connection");
+        Some(next.connection)
+    }
+
+    async fn reconnect(&mut self, reason: String) -> Option<Connection> {
+        warn!("stream connection lost: {}", reason);
+        // sent again on the new connection
+        let mut deferred = self.take_pending();
+        self.seen.clear();
+        self.dedupe_until = None;
+        if !self.emit(Ok(StreamEvent::Disconnected(reason))) {
+            return None;
+        }
+
+        let policy = self.client.reconnect_policy().clone();
+        let mut attempt = 0;
+        loop {
+            let delay = tokio::time::sleep(policy.delay(attempt));
+            tokio::pin!(delay);
+            loop {
+                tokio::select! {
+                    _ = &mut delay => break,
+                    command = self.commands.recv() => match command {
+                        Some(Command::Request(request)) => deferred.push(request),
+                        Some(Command::Close(done)) => {
+                            done.send(Ok(())).ok();
+                            return None;
+                        },
+                        None => {
+                            return None;
+                        },
+                    },
+                }
+            }
+
+            match open(self.client.clone(), self.subscriptions.clone(), self.ids.clone()).await {
+                Ok((connection, buffer)) => {
+                    info!("stream reconnected after {} attempts", attempt + 1);
+                    if !self.emit(Ok(StreamEvent::Reconnected)) {
+                        return None;
+                    }
+                    for text in buffer {
+                        if !self.receive(&text, false) {
+                            return None;
+                        }
+                    }
+                    for request in deferred {
+                        self.send(request, &connection, None);
+                    }
+                    return Some(connection);
+                },
+                Err(err) if attempt >= policy.max_retries => {
+                    self.emit(Err(err));
+                    return None;
+                },
+                Err(err) => {
+                    warn!("stream reconnect attempt {} failed: {}", attempt + 1, err);
+                    attempt += 1;
+                },
+            }
+        }
+    }
+}
+
+/// A new connection with every subscription restored, and the messages that came meanwhile.
+async fn open(client: Client, subscriptions: Arc<Mutex<Vec<String>>>, ids: Arc<AtomicU64>) -> Result<(Connection, Vec<String>)> {
+    let mut connection = client.connect(URL_COMBINED_STREAM).await?;
+    let mut buffer = vec![];
+    let subscriptions = subscriptions.lock().unwrap().clone();
+    if subscriptions.is_empty() {
+        return Ok((connection, buffer));
+    }
+
+    call(&mut connection, &mut buffer, &ids, "SUBSCRIBE", Some(&subscriptions)).await?;
+    let listed: Vec<String> = serde_json::from_value(call(&mut connection, &mut buffer, &ids, "LIST_SUBSCRIPTIONS", None).await?)?;
+    let missing: Vec<&String> = subscriptions.iter().filter(|stream| !listed.contains(stream)).collect();
+    if !missing.is_empty() {
+        return Err(Error::InvalidParameter(format!("streams {:?} were not restored", missing)));
+    }
+    let extra: Vec<String> = listed.into_iter().filter(|stream| !subscriptions.contains(stream)).collect();
+    if !extra.is_empty() {
+        call(&mut connection, &mut buffer, &ids, "UNSUBSCRIBE", Some(&extra)).await?;
+    }
+    Ok((connection, buffer))
+}
+
+async fn call(connection: &mut Connection, buffer: &mut Vec<String>, ids: &AtomicU64, method: &str, params: Option<&[String]>) -> Result<Value> {
+    let id = next_id(ids);
+    connection.send(control_message(method, params, id))?;
+
+    let answer = async {
+        while let Some(text) = connection.next().await {
+            let text = text?;
+            match parse(&text) {
+                Incoming::Response { id: answered, result } if answered == id => return result,
+                _ => buffer.push(text),
+            }
+        }
+        Err(closed())
+    };
+    tokio::time::timeout(CONTROL_TIMEOUT, answer).await.unwrap_or(Err(Error::Timeout))
+}
+
+fn next_id(ids: &AtomicU64) -> u64 {
+    ids.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+async fn opened(opening: &mut Option<Opening>) -> Result<(Connection, Vec<String>)> {
+    match opening {
+        // only a panic ends the task otherwise, it is aborted once dropped
+        Some(opening) => (&mut opening.task).await.unwrap_or_else(|_| Err(closed())),
+        None => futures::future::pending().await,
+    }
+}
+
+async fn next_text(rotation: &mut Option<Rotation>) -> Option<Result<String>> {
+    match rotation {
+        Some(rotation) => rotation.connection.next().await,
+        None => futures::future::pending().await,
+    }
+}
@@ -0,0 +1,3 @@
+#[allow(clippy::module_inception)]
+pub mod user_data;
+pub mod model;
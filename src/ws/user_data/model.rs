@@ -0,0 +1,251 @@
+use serde::Deserialize;
+
+use crate::http::account::model::Balance;
+use crate::http::trade::model::{ContingencyType, Fill, ListOrderStatus, ListStatusType, Order, OrderListOrder, OrderStatus};
+use crate::http::trade::trade::{EOrderType, ESelfTradePreventionMode, ESide, ETimeInForce};
+use crate::Decimal;
+
+/// The balances that changed with an account update.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct OutboundAccountPositionEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "u")]
+    pub last_update_time: u64,
+    #[serde(rename = "B")]
+    pub balances: Vec<StreamBalance>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct StreamBalance {
+    #[serde(rename = "a")]
+    pub asset: String,
+    #[serde(rename = "f")]
+    pub free: Decimal,
+    #[serde(rename = "l")]
+    pub locked: Decimal,
+}
+
+impl From<StreamBalance> for Balance {
+    fn from(balance: StreamBalance) -> Self {
+        Balance {
+            asset: balance.asset,
+            free: balance.free,
+            locked: balance.locked,
+        }
+    }
+}
+
+/// A deposit, withdrawal or transfer between accounts.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BalanceUpdateEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "a")]
+    pub asset: String,
+    #[serde(rename = "d")]
+    pub delta: Decimal,
+    #[serde(rename = "T")]
+    pub clear_time: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ExecutionType {
+    New,
+    Canceled,
+    Replaced,
+    Rejected,
+    Trade,
+    Expired,
+    TradePrevention,
+    /// The quantity was lowered with `amend_order_keep_priority`.
+    Amendment,
+}
+
+/// An order was placed, changed or traded.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ExecutionReportEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "c")]
+    pub client_order_id: String,
+    #[serde(rename = "S")]
+    pub side: ESide,
+    #[serde(rename = "o")]
+    pub order_type: EOrderType,
+    #[serde(rename = "f")]
+    pub time_in_force: ETimeInForce,
+    #[serde(rename = "q")]
+    pub qty: Decimal,
+    #[serde(rename = "p")]
+    pub price: Decimal,
+    #[serde(rename = "P")]
+    pub stop_price: Decimal,
+    #[serde(rename = "F")]
+    pub iceberg_qty: Decimal,
+    /// -1 unless the order is part of an order list.
+    #[serde(rename = "g")]
+    pub order_list_id: i64,
+    /// The client id of the canceled order, empty for other execution types.
+    #[serde(rename = "C")]
+    pub orig_client_order_id: String,
+    #[serde(rename = "x")]
+    pub execution_type: ExecutionType,
+    #[serde(rename = "X")]
+    pub order_status: OrderStatus,
+    /// `NONE` unless the order was rejected.
+    #[serde(rename = "r")]
+    pub reject_reason: String,
+    #[serde(rename = "i")]
+    pub order_id: u64,
+    #[serde(rename = "l")]
+    pub last_executed_qty: Decimal,
+    #[serde(rename = "z")]
+    pub cumulative_filled_qty: Decimal,
+    #[serde(rename = "L")]
+    pub last_executed_price: Decimal,
+    #[serde(rename = "n")]
+    pub commission_amount: Decimal,
+    /// None until the order traded.
+    #[serde(rename = "N")]
+    pub commission_asset: Option<String>,
+    #[serde(rename = "T")]
+    pub transaction_time: u64,
+    /// -1 unless the execution type is `TRADE`.
+    #[serde(rename = "t")]
+    pub trade_id: i64,
+    #[serde(rename = "v", default)]
+    pub prevented_match_id: Option<u64>,
+    #[serde(rename = "w")]
+    pub is_working: bool,
+    #[serde(rename = "m")]
+    pub is_maker: bool,
+    #[serde(rename = "O")]
+    pub order_creation_time: u64,
+    #[serde(rename = "Z")]
+    pub cumulative_quote_qty: Decimal,
+    #[serde(rename = "Y")]
+    pub last_quote_qty: Decimal,
+    #[serde(rename = "Q")]
+    pub quote_order_qty: Decimal,
+    #[serde(rename = "W", default)]
+    pub working_time: Option<i64>,
+    #[serde(rename = "V")]
+    pub self_trade_prevention_mode: ESelfTradePreventionMode,
+}
+
+impl ExecutionReportEvent {
+    /// The trade of a `TRADE` report, as `new_order` lists it in its fills.
+    pub fn fill(&self) -> Option<Fill> {
+        if self.execution_type != ExecutionType::Trade || self.trade_id < 0 {
+            return None;
+        }
+        Some(Fill {
+            price: self.last_executed_price,
+            qty: self.last_executed_qty,
+            commission: self.commission_amount,
+            commission_asset: self.commission_asset.clone().unwrap_or_default(),
+            trade_id: self.trade_id as u64,
+        })
+    }
+}
+
+/// The order as `trade::query_order` would return it after this report.
+impl From<ExecutionReportEvent> for Order {
+    fn from(event: ExecutionReportEvent) -> Self {
+        let client_order_id = if event.orig_client_order_id.is_empty() {
+            event.client_order_id
+        } else {
+            event.orig_client_order_id
+        };
+        Order {
+            symbol: event.symbol,
+            order_id: event.order_id,
+            order_list_id: event.order_list_id,
+            client_order_id,
+            price: event.price,
+            orig_qty: event.qty,
+            executed_qty: event.cumulative_filled_qty,
+            cummulative_quote_qty: event.cumulative_quote_qty,
+            status: event.order_status,
+            time_in_force: event.time_in_force,
+            order_type: event.order_type,
+            side: event.side,
+            stop_price: Some(event.stop_price),
+            iceberg_qty: Some(event.iceberg_qty),
+            time: event.order_creation_time,
+            update_time: event.transaction_time,
+            is_working: event.is_working,
+            working_time: event.working_time,
+            orig_quote_order_qty: event.quote_order_qty,
+            self_trade_prevention_mode: Some(event.self_trade_prevention_mode),
+        }
+    }
+}
+
+/// An order list was placed, changed or finished.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ListStatusEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "g")]
+    pub order_list_id: i64,
+    #[serde(rename = "c")]
+    pub contingency_type: ContingencyType,
+    #[serde(rename = "l")]
+    pub list_status_type: ListStatusType,
+    #[serde(rename = "L")]
+    pub list_order_status: ListOrderStatus,
+    /// `NONE` unless the list was rejected.
+    #[serde(rename = "r")]
+    pub list_reject_reason: String,
+    #[serde(rename = "C")]
+    pub list_client_order_id: String,
+    #[serde(rename = "T")]
+    pub transaction_time: u64,
+    #[serde(rename = "O")]
+    pub orders: Vec<StreamListOrder>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct StreamListOrder {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "i")]
+    pub order_id: u64,
+    #[serde(rename = "c")]
+    pub client_order_id: String,
+}
+
+impl From<StreamListOrder> for OrderListOrder {
+    fn from(order: StreamListOrder) -> Self {
+        OrderListOrder {
+            symbol: order.symbol,
+            order_id: order.order_id,
+            client_order_id: order.client_order_id,
+        }
+    }
+}
+
+/// The listen key expired, no more events come with it.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ListenKeyExpiredEvent {
+    #[serde(rename = "E")]
+    pub event_time: u64,
+    #[serde(rename = "listenKey")]
+    pub listen_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserDataEvent {
+    OutboundAccountPosition(OutboundAccountPositionEvent),
+    BalanceUpdate(BalanceUpdateEvent),
+    ExecutionReport(Box<ExecutionReportEvent>),
+    ListStatus(ListStatusEvent),
+    ListenKeyExpired(ListenKeyExpiredEvent),
+}
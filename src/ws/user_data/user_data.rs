@@ -0,0 +1,134 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{Stream, StreamExt};
+use log::{debug, warn};
+use serde_json::Value;
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::task::JoinHandle;
+
+use crate::error::Result;
+use crate::http::user_data::user_data::ListenKeyHandle;
+use crate::ws::client::Client;
+use crate::ws::stream::{CombinedStream, StreamEvent};
+use crate::ws::user_data::model::UserDataEvent;
+
+/// Streams the events of the listen key `handle` keeps alive.
+///
+/// The stream follows the handle: when the key is replaced it subscribes to the new one before
+/// it drops the old one, and a `listenKeyExpired` event makes the handle replace the key right
+/// away. Dropping the stream stops the keepalive as well, `close` also closes the key.
+pub async fn subscribe(client: &Client, handle: ListenKeyHandle) -> Result<UserDataStream> {
+    let listen_key = handle.watch();
+    let stream = CombinedStream::connect(client, &[listen_key.borrow().clone()]).await?;
+
+    let (events_tx, events) = mpsc::unbounded_channel();
+    let (close_tx, close) = oneshot::channel();
+    let task = tokio::spawn(run(stream, handle, events_tx, close));
+    Ok(UserDataStream { events, listen_key, close: close_tx, task })
+}
+
+/// A `Stream` of user data events. An event that cannot be parsed is yielded as an error and
+/// the stream goes on.
+#[derive(Debug)]
+pub struct UserDataStream {
+    events: mpsc::UnboundedReceiver<Result<StreamEvent<UserDataEvent>>>,
+    listen_key: watch::Receiver<String>,
+    close: oneshot::Sender<()>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl UserDataStream {
+    /// The key the events come with now.
+    pub fn listen_key(&self) -> String {
+        self.listen_key.borrow().clone()
+    }
+
+    /// Closes the connection and the listen key.
+    pub async fn close(self) -> Result<()> {
+        self.close.send(()).ok();
+        self.task.await.unwrap_or(Ok(()))
+    }
+}
+
+impl Stream for UserDataStream {
+    type Item = Result<StreamEvent<UserDataEvent>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+fn parse(data: Value) -> Option<Result<UserDataEvent>> {
+    let event_type = data.get("e").and_then(Value::as_str).unwrap_or_default().to_string();
+    let event = match event_type.as_str() {
+        "outboundAccountPosition" => serde_json::from_value(data).map(UserDataEvent::OutboundAccountPosition),
+        "balanceUpdate" => serde_json::from_value(data).map(UserDataEvent::BalanceUpdate),
+        "executionReport" => serde_json::from_value(data).map(|report| UserDataEvent::ExecutionReport(Box::new(report))),
+        "listStatus" => serde_json::from_value(data).map(UserDataEvent::ListStatus),
+        "listenKeyExpired" => serde_json::from_value(data).map(UserDataEvent::ListenKeyExpired),
+        _ => {
+            debug!("skipping user data event {}", data);
+            return None;
+        },
+    };
+    Some(event.map_err(Into::into))
+}
+
+async fn run(
+    mut stream: CombinedStream,
+    handle: ListenKeyHandle,
+    events: mpsc::UnboundedSender<Result<StreamEvent<UserDataEvent>>>,
+    mut close: oneshot::Receiver<()>,
+) -> Result<()> {
+    let mut watch = handle.watch();
+    let mut listen_key = watch.borrow_and_update().clone();
+    loop {
+        tokio::select! {
+            event = stream.next() => {
+                let event = match event {
+                    Some(Ok(StreamEvent::Message(message))) if message.stream == listen_key => match parse(message.data) {
+                        Some(Ok(UserDataEvent::ListenKeyExpired(expired))) => {
+                            warn!("listen key expired at {}, replacing it", expired.event_time);
+                            handle.report_expired();
+                            Ok(StreamEvent::Message(UserDataEvent::ListenKeyExpired(expired)))
+                        },
+                        Some(event) => event.map(StreamEvent::Message),
+                        None => continue,
+                    },
+                    // the key just replaced
+                    Some(Ok(StreamEvent::Message(_))) => continue,
+                    Some(Ok(StreamEvent::Disconnected(reason))) => Ok(StreamEvent::Disconnected(reason)),
+                    Some(Ok(StreamEvent::Reconnected)) => Ok(StreamEvent::Reconnected),
+                    Some(Err(err)) => Err(err),
+                    None => return Ok(()),
+                };
+                if events.send(event).is_err() {
+                    return Ok(());
+                }
+            },
+            changed = watch.changed() => {
+                if changed.is_err() {
+                    return Ok(());
+                }
+                let new = watch.borrow_and_update().clone();
+                debug!("user data stream moving to a new listen key");
+                if let Err(err) = stream.subscribe(std::slice::from_ref(&new)).await {
+                    events.send(Err(err)).ok();
+                    continue;
+                }
+                stream.unsubscribe(&[std::mem::replace(&mut listen_key, new)]).await.ok();
+            },
+            closed = &mut close => {
+                // dropped rather than closed: the key stays open
+                if closed.is_err() {
+                    return Ok(());
+                }
+                stream.close().await.ok();
+                return handle.close().await;
+            },
+        }
+    }
+}
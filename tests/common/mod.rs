@@ -0,0 +1,57 @@
+//! Fixtures shared by the integration tests, included with `mod common;`.
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use binance_sdk_rs::http::client::Client;
+use binance_sdk_rs::http::retry::RetryPolicy;
+use binance_sdk_rs::mock::MockServer;
+use binance_sdk_rs::ws::client::{Client as WsClient, ClientBuilder as WsClientBuilder};
+use binance_sdk_rs::Decimal;
+
+pub fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+/// Retries like the default policy, without waiting long between them.
+pub fn fast_retries() -> RetryPolicy {
+    RetryPolicy {
+        max_retries: 3,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(5),
+    }
+}
+
+/// For tests that count requests or want the first failure.
+pub fn no_retries() -> RetryPolicy {
+    RetryPolicy { max_retries: 0, ..fast_retries() }
+}
+
+/// A client for the mock server's REST api, signing with its credentials.
+pub fn http_client(server: &MockServer, retry_policy: RetryPolicy) -> Client {
+    Client::builder()
+        .base_url(server.url())
+        .credentials(MockServer::credentials())
+        .retry_policy(retry_policy)
+        .build()
+        .unwrap()
+}
+
+/// A websocket client builder for the mock server, reconnecting without waiting long.
+pub fn ws_builder(server: &MockServer) -> WsClientBuilder {
+    WsClient::builder()
+        .base_url(server.ws_url())
+        .reconnect_policy(RetryPolicy { max_retries: 5, ..fast_retries() })
+}
+
+/// A websocket client for the mock server's streams and WebSocket API.
+pub fn ws_client(server: &MockServer) -> WsClient {
+    ws_builder(server).build().unwrap()
+}
+
+/// A mock server and a client for it with fast retries.
+pub async fn setup() -> (MockServer, Client) {
+    let server = MockServer::start().await;
+    let client = http_client(&server, fast_retries());
+    (server, client)
+}
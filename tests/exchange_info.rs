@@ -0,0 +1,188 @@
+use std::{sync::Arc, time::Duration};
+
+use binance_sdk_rs::error::ErrorCode;
+use binance_sdk_rs::http::client::Client;
+use binance_sdk_rs::http::market::exchange_info::{ExchangeInfoCache, DEFAULT_REFRESH_INTERVAL};
+use binance_sdk_rs::http::market::filters::{OrderContext, SymbolFilters};
+use binance_sdk_rs::http::market::model::{LotSize, MinNotional, PriceFilter};
+use binance_sdk_rs::http::trade::trade::{self, ESide, NewOrder};
+use binance_sdk_rs::mock::{Failure, MockServer};
+use binance_sdk_rs::Error;
+
+mod common;
+use common::{dec, fast_retries, http_client, no_retries};
+
+async fn setup(refresh_interval: Duration) -> (MockServer, Arc<Client>, ExchangeInfoCache) {
+    let server = MockServer::start().await;
+    let client = Arc::new(http_client(&server, no_retries()));
+    let cache = ExchangeInfoCache::start(client.clone(), refresh_interval).await.unwrap();
+    (server, client, cache)
+}
+
+// the filter a local or a -1013 error names
+fn failed_filter(err: Error) -> String {
+    let msg = match err {
+        Error::InvalidParameter(msg) => msg,
+        Error::Api(err) => {
+            assert_eq!(err.code, ErrorCode::InvalidMessage);
+            err.msg
+        },
+        err => panic!("unexpected {:?}", err),
+    };
+    let filter = msg.strip_prefix("Filter failure: ").unwrap_or_else(|| panic!("{}", msg));
+    filter.split(':').next().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn symbols_are_looked_up() {
+    let (server, _client, cache) = setup(DEFAULT_REFRESH_INTERVAL).await;
+
+    assert_eq!(cache.exchange_info().symbols.len(), 3);
+    assert_eq!(cache.symbol("btcusdt").unwrap().base_asset, "BTC");
+    assert_eq!(cache.symbol_by_assets("eth", "BTC").unwrap().symbol, "ETHBTC");
+    assert!(cache.symbol("DOGEUSDT").is_none());
+    assert!(cache.symbol_by_assets("BTC", "ETH").is_none());
+
+    let filters = cache.filters("BTCUSDT").unwrap();
+    let price_filter = filters.price_filter.unwrap();
+    assert_eq!((price_filter.tick_size, price_filter.max_price), (dec("0.01"), dec("1000000")));
+    assert_eq!(filters.lot_size.unwrap().step_size, dec("0.00001"));
+    assert_eq!(filters.market_lot_size.unwrap().max_qty, dec("100"));
+    assert_eq!(filters.notional.unwrap().min_notional, dec("5"));
+    assert_eq!(filters.percent_price_by_side.unwrap().bid_multiplier_up, dec("5"));
+    assert_eq!((filters.max_num_orders, filters.iceberg_parts), (Some(200), Some(10)));
+    assert!(filters.min_notional.is_none());
+
+    assert_eq!(server.requests_to("/api/v3/exchangeInfo").len(), 1);
+    cache.refresh().await.unwrap();
+    assert_eq!(server.requests_to("/api/v3/exchangeInfo").len(), 2);
+
+    // orders for unknown symbols are not guessed at
+    let order = NewOrder::limit("DOGEUSDT", ESide::BUY, dec("1"), dec("0.1"));
+    assert!(matches!(cache.validate(&order, &OrderContext::default()), Err(Error::InvalidParameter(_))));
+    assert!(cache.round(&order).is_err());
+}
+
+#[tokio::test]
+async fn refreshes_in_the_background() {
+    let (server, _client, cache) = setup(Duration::from_millis(100)).await;
+
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert!(server.requests_to("/api/v3/exchangeInfo").len() >= 3);
+
+    // a failed refresh keeps the last exchange info
+    server.fail_next("/api/v3/exchangeInfo", Failure::ServerError(503));
+    assert!(cache.refresh().await.is_err());
+    assert_eq!(cache.symbol("BTCUSDT").unwrap().symbol, "BTCUSDT");
+
+    let requests = server.requests_to("/api/v3/exchangeInfo").len();
+    drop(cache);
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert_eq!(server.requests_to("/api/v3/exchangeInfo").len(), requests);
+}
+
+#[tokio::test]
+async fn failed_refreshes_wait_for_the_next_interval() {
+    let server = MockServer::start().await;
+    let client = Arc::new(http_client(&server, fast_retries()));
+    let started = std::time::Instant::now();
+    let _cache = ExchangeInfoCache::start(client, Duration::from_millis(500)).await.unwrap();
+    for _ in 0..100 {
+        server.fail_next("/api/v3/exchangeInfo", Failure::ServerError(503));
+    }
+
+    // each refresh is retried as a GET, and the refreshes are retried with the same policy
+    let attempts = fast_retries().max_retries as usize + 1;
+    tokio::time::sleep(Duration::from_millis(800).saturating_sub(started.elapsed())).await;
+    assert_eq!(server.requests_to("/api/v3/exchangeInfo").len(), 1 + attempts * attempts);
+    tokio::time::sleep(Duration::from_millis(1200).saturating_sub(started.elapsed())).await;
+    assert_eq!(server.requests_to("/api/v3/exchangeInfo").len(), 1 + 2 * attempts * attempts);
+}
+
+#[tokio::test]
+async fn orders_are_checked_like_the_exchange_does() {
+    let (_server, client, cache) = setup(DEFAULT_REFRESH_INTERVAL).await;
+    let context = OrderContext { avg_price: Some(dec("43000")), open_orders: Some(0) };
+
+    let orders = [
+        ("PRICE_FILTER", NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.01"), dec("42000.005"))),
+        ("LOT_SIZE", NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.000015"), dec("42000"))),
+        ("PERCENT_PRICE_BY_SIDE", NewOrder::limit("BTCUSDT", ESide::SELL, dec("0.01"), dec("250000"))),
+        ("NOTIONAL", NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.0001"), dec("42000"))),
+        ("ICEBERG_PARTS", NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.011"), dec("42000")).iceberg_qty(dec("0.001"))),
+    ];
+    for (filter, order) in orders {
+        assert_eq!(failed_filter(cache.validate(&order, &context).unwrap_err()), filter);
+        // the exchange rejects it for the same reason
+        assert_eq!(failed_filter(trade::new_order(&client, &order).await.unwrap_err()), filter);
+    }
+
+    let order = NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.01"), dec("42000"));
+    cache.validate(&order, &context).unwrap();
+    let full = OrderContext { open_orders: Some(200), ..context };
+    assert_eq!(failed_filter(cache.validate(&order, &full).unwrap_err()), "MAX_NUM_ORDERS");
+    // without an average price the percent price check is skipped
+    let far = NewOrder::limit("BTCUSDT", ESide::SELL, dec("0.01"), dec("250000"));
+    cache.validate(&far, &OrderContext::default()).unwrap();
+
+    // market orders are valued at the average price and have their own lot size
+    let market = NewOrder::market("BTCUSDT", ESide::BUY, dec("150"));
+    assert_eq!(failed_filter(cache.validate(&market, &context).unwrap_err()), "MARKET_LOT_SIZE");
+    let small = NewOrder::market("BTCUSDT", ESide::BUY, dec("0.0001"));
+    assert_eq!(failed_filter(cache.validate(&small, &context).unwrap_err()), "NOTIONAL");
+    let quote = NewOrder::market_quote("BTCUSDT", ESide::BUY, dec("1"));
+    assert_eq!(failed_filter(cache.validate(&quote, &OrderContext::default()).unwrap_err()), "NOTIONAL");
+}
+
+#[tokio::test]
+async fn orders_are_rounded_onto_tick_and_step() {
+    let (_server, client, cache) = setup(DEFAULT_REFRESH_INTERVAL).await;
+
+    let buy = NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.0123456"), dec("42000.005"));
+    assert!(trade::new_order(&client, &buy).await.is_err());
+    let rounded = cache.prepare(&buy, &OrderContext::default()).unwrap();
+    assert_eq!(rounded, NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.01234"), dec("42000")));
+    assert_eq!((rounded.get_symbol(), rounded.get_side()), ("BTCUSDT", ESide::BUY));
+    assert_eq!((rounded.get_quantity(), rounded.get_price()), (Some(dec("0.01234")), Some(dec("42000"))));
+    assert_eq!((rounded.get_stop_price(), rounded.get_quote_order_qty()), (None, None));
+    trade::new_order(&client, &rounded).await.unwrap();
+
+    // sells round the price up, the stop price too
+    let sell = NewOrder::limit("ETHBTC", ESide::SELL, dec("1.00009"), dec("0.055121")).stop_price(dec("0.055016"));
+    let expected = NewOrder::limit("ETHBTC", ESide::SELL, dec("1"), dec("0.05513")).stop_price(dec("0.05502"));
+    assert_eq!(cache.round(&sell).unwrap(), expected);
+
+    // what rounds below the minimum still fails
+    let dust = NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.000009"), dec("42000"));
+    assert_eq!(failed_filter(cache.prepare(&dust, &OrderContext::default()).unwrap_err()), "LOT_SIZE");
+}
+
+#[test]
+fn min_notional_can_spare_market_orders() {
+    let filters = SymbolFilters {
+        lot_size: Some(LotSize { min_qty: dec("0.001"), max_qty: dec("1000"), step_size: dec("0.001") }),
+        min_notional: Some(MinNotional { min_notional: dec("10"), apply_to_market: false, avg_price_mins: 5 }),
+        ..SymbolFilters::default()
+    };
+    let context = OrderContext { avg_price: Some(dec("2")), open_orders: None };
+
+    let limit = NewOrder::limit("XYZUSDT", ESide::BUY, dec("1"), dec("2"));
+    assert_eq!(failed_filter(filters.validate(&limit, &context).unwrap_err()), "MIN_NOTIONAL");
+    filters.validate(&NewOrder::market("XYZUSDT", ESide::BUY, dec("1")), &context).unwrap();
+    assert_eq!(filters.round(&NewOrder::market("XYZUSDT", ESide::BUY, dec("1.0005"))), NewOrder::market("XYZUSDT", ESide::BUY, dec("1")));
+}
+
+#[test]
+fn stop_prices_round_like_prices() {
+    let filters = SymbolFilters {
+        price_filter: Some(PriceFilter { min_price: dec("0.01"), max_price: dec("1000"), tick_size: dec("0.01") }),
+        ..SymbolFilters::default()
+    };
+
+    // exactly half a tick goes the order's way, not to the even tick
+    for (side, stop_price) in [(ESide::BUY, "100.00"), (ESide::SELL, "100.01")] {
+        let order = NewOrder::limit("XYZUSDT", side, dec("1"), dec("100.005")).stop_price(dec("100.005"));
+        let expected = NewOrder::limit("XYZUSDT", side, dec("1"), dec(stop_price)).stop_price(dec(stop_price));
+        assert_eq!(filters.round(&order), expected);
+    }
+}
@@ -0,0 +1,100 @@
+use binance_sdk_rs::error::ErrorCode;
+use binance_sdk_rs::http::account::account::{self, MyTradesPager};
+use binance_sdk_rs::http::client::Client;
+use binance_sdk_rs::http::market::model::RateLimitInterval;
+use binance_sdk_rs::http::trade::trade::{self, ESide, NewOrder};
+use binance_sdk_rs::Error;
+
+mod common;
+use common::{dec, setup};
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+async fn buy_btc(client: &Client, times: usize) {
+    for _ in 0..times {
+        trade::new_order(client, &NewOrder::market("BTCUSDT", ESide::BUY, dec("0.001"))).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn balances_follow_orders() {
+    let (_server, client) = setup().await;
+
+    let info = account::account(&client, None).await.unwrap();
+    assert!(info.can_trade);
+    assert_eq!(info.commission_rates.taker, dec("0.001"));
+    assert_eq!(info.balance("BTC").unwrap().free, dec("1"));
+    assert_eq!(info.permissions, ["SPOT"]);
+
+    // 0.01 BTC at 43000, the 0.1% commission is paid in BTC
+    trade::new_order(&client, &NewOrder::market("BTCUSDT", ESide::BUY, dec("0.01"))).await.unwrap();
+    trade::new_order(&client, &NewOrder::limit("BTCUSDT", ESide::SELL, dec("0.5"), dec("44000"))).await.unwrap();
+
+    let info = account::account(&client, Some(true)).await.unwrap();
+    let btc = info.balance("BTC").unwrap();
+    assert_eq!(btc.free, dec("0.50999"));
+    assert_eq!(btc.locked, dec("0.5"));
+    assert_eq!(info.balance("USDT").unwrap().free, dec("99570"));
+}
+
+#[tokio::test]
+async fn my_trades_by_id() {
+    let (_server, client) = setup().await;
+    buy_btc(&client, 5).await;
+
+    let trades = account::my_trades(&client, "BTCUSDT", None, None, None, None, None).await.unwrap();
+    assert_eq!(trades.len(), 5);
+    assert!(trades.iter().all(|t| t.is_buyer && t.qty == dec("0.001") && t.quote_qty == dec("43")));
+    let last = account::my_trades(&client, "BTCUSDT", Some(trades[4].order_id), None, None, None, None).await.unwrap();
+    assert_eq!(last, trades[4..]);
+
+    let mut pager = MyTradesPager::from_id(&client, "BTCUSDT", trades[1].id).limit(2);
+    let mut pages = vec![];
+    while let Some(page) = pager.next_page().await.unwrap() {
+        pages.push(page.len());
+    }
+    assert_eq!(pages, [2, 2]);
+
+    let all = MyTradesPager::from_id(&client, "BTCUSDT", 0).limit(2).collect().await.unwrap();
+    assert_eq!(all, trades);
+    assert!(MyTradesPager::from_id(&client, "ETHBTC", 0).collect().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn my_trades_by_time() {
+    let (server, client) = setup().await;
+    buy_btc(&client, 5).await;
+    let now = client.timestamp();
+
+    let trades = MyTradesPager::between(&client, "BTCUSDT", now - 3 * DAY_MS, now + 1000).limit(2).collect().await.unwrap();
+    assert_eq!(trades.len(), 5);
+    assert!(trades.windows(2).all(|t| t[0].id < t[1].id));
+    // two empty days, then the last one fills a page and is continued by id
+    assert_eq!(server.requests_to("/api/v3/myTrades").len(), 5);
+
+    let none = MyTradesPager::between(&client, "BTCUSDT", now - 3 * DAY_MS, now - 2 * DAY_MS).collect().await.unwrap();
+    assert!(none.is_empty());
+
+    let err = account::my_trades(&client, "BTCUSDT", None, Some(now - 2 * DAY_MS), Some(now), None, None).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::MoreThanXxHours));
+}
+
+#[tokio::test]
+async fn rate_limits_and_commission() {
+    let (_server, client) = setup().await;
+    buy_btc(&client, 2).await;
+
+    let limits = account::rate_limit_order(&client).await.unwrap();
+    assert_eq!(limits.len(), 2);
+    assert!(limits.iter().all(|l| l.count == 2));
+    assert_eq!(limits[0].interval, RateLimitInterval::Second);
+
+    let commission = account::account_commission(&client, "BTCUSDT").await.unwrap();
+    assert_eq!(commission.standard_commission.maker, dec("0.001"));
+    assert_eq!(commission.discount.discount_asset, "BNB");
+
+    let matches = account::my_prevented_matches(&client, "BTCUSDT", None, Some(1), None, Some(10)).await.unwrap();
+    assert!(matches.is_empty());
+    let err = account::my_prevented_matches(&client, "BTCUSDT", Some(1), Some(1), None, None).await.unwrap_err();
+    assert!(matches!(err, Error::InvalidParameter(_)));
+}
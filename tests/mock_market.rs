@@ -0,0 +1,187 @@
+use std::time::{Duration, Instant};
+
+use binance_sdk_rs::error::ErrorCode;
+use binance_sdk_rs::http::client::Client;
+use binance_sdk_rs::http::market::market;
+use binance_sdk_rs::mock::{Failure, MockServer};
+use binance_sdk_rs::Error;
+use hyper::StatusCode;
+
+mod common;
+use common::{no_retries, setup};
+
+fn btcusdt() -> String {
+    String::from("BTCUSDT")
+}
+
+#[tokio::test]
+async fn ping_and_time() {
+    let (_server, client) = setup().await;
+
+    market::ping(&client).await.unwrap();
+    let server_time = market::time(&client).await.unwrap();
+    assert!(server_time.server_time > 1_600_000_000_000);
+}
+
+#[tokio::test]
+async fn exchange_info() {
+    let (_server, client) = setup().await;
+
+    let info = market::exchange_info(&client).await.unwrap();
+    assert_eq!(info.symbols.len(), 3);
+    assert_eq!(info.rate_limits.len(), 4);
+
+    let info = market::exchange_info_symbol(&client, &btcusdt()).await.unwrap();
+    assert_eq!(info.symbols[0].base_asset, "BTC");
+    assert_eq!(info.symbols[0].quote_asset, "USDT");
+
+    let info = market::exchange_info_symbols(&client, &[btcusdt(), String::from("ETHBTC")]).await.unwrap();
+    let names: Vec<_> = info.symbols.iter().map(|s| s.symbol.as_str()).collect();
+    assert_eq!(names, ["BTCUSDT", "ETHBTC"]);
+}
+
+#[tokio::test]
+async fn unknown_symbol_is_rejected() {
+    let (_server, client) = setup().await;
+
+    let err = market::exchange_info_symbol(&client, &String::from("NOPE")).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::BadSymbol));
+    let err = market::avg_price(&client, &String::from("NOPE")).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::BadSymbol));
+}
+
+#[tokio::test]
+async fn depth() {
+    let (_server, client) = setup().await;
+
+    let book = market::depth(&client, &btcusdt(), 5).await.unwrap();
+    assert_eq!(book.bids.len(), 5);
+    assert_eq!(book.asks.len(), 5);
+    assert!(book.bids[0].price < book.asks[0].price);
+    assert!(book.bids.windows(2).all(|w| w[0].price > w[1].price));
+
+    let next = market::depth(&client, &btcusdt(), 100).await.unwrap();
+    assert!(next.last_update_id > book.last_update_id);
+    assert!(market::depth(&client, &btcusdt(), 7).await.is_err());
+}
+
+#[tokio::test]
+async fn trades() {
+    let (_server, client) = setup().await;
+
+    let trades = market::trades(&client, &btcusdt(), 10).await.unwrap();
+    assert_eq!(trades.len(), 10);
+    assert!(trades.windows(2).all(|w| w[0].id + 1 == w[1].id));
+
+    let from_id = trades[0].id - 100;
+    let historical = market::historical_trades(&client, &btcusdt(), 20, Some(from_id)).await.unwrap();
+    assert_eq!(historical.len(), 20);
+    assert_eq!(historical[0].id, from_id);
+}
+
+#[tokio::test]
+async fn agg_trades() {
+    let (_server, client) = setup().await;
+
+    let aggs = market::agg_trades(&client, &btcusdt(), None, None, None, Some(30)).await.unwrap();
+    assert_eq!(aggs.len(), 30);
+
+    let start = aggs[0].time;
+    let aggs = market::agg_trades(&client, &btcusdt(), None, Some(start), Some(start + 999), None).await.unwrap();
+    assert_eq!(aggs.len(), 10);
+    assert!(aggs.iter().all(|a| a.time >= start && a.time <= start + 999));
+
+    let err = market::agg_trades(&client, &btcusdt(), None, Some(start), Some(start + 7_200_000), None).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::MoreThanXxHours));
+}
+
+#[tokio::test]
+async fn klines() {
+    let (_server, client) = setup().await;
+
+    let klines = market::klines(&client, &btcusdt(), &String::from("1m"), None, None, Some(12)).await.unwrap();
+    assert_eq!(klines.len(), 12);
+    for k in klines.iter() {
+        assert_eq!(k.close_time - k.open_time, 59_999);
+        assert!(k.high >= k.open && k.high >= k.close && k.low <= k.open && k.low <= k.close);
+    }
+
+    let err = market::klines(&client, &btcusdt(), &String::from("7m"), None, None, None).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::BadInterval));
+}
+
+#[tokio::test]
+async fn avg_price() {
+    let (_server, client) = setup().await;
+
+    let avg = market::avg_price(&client, &btcusdt()).await.unwrap();
+    assert_eq!(avg.mins, 5);
+    assert_eq!(avg.price.to_string(), "43000.00000000");
+}
+
+#[tokio::test]
+async fn tickers() {
+    let (_server, client) = setup().await;
+
+    assert_eq!(market::ticker_24hr(&client, &Some(btcusdt())).await.unwrap().len(), 1);
+    assert_eq!(market::ticker_24hr(&client, &None).await.unwrap().len(), 3);
+    assert_eq!(market::ticker_price(&client, &Some(btcusdt())).await.unwrap()[0].symbol, "BTCUSDT");
+    assert_eq!(market::ticker_price(&client, &None).await.unwrap().len(), 3);
+    let book = market::ticker_book(&client, &Some(btcusdt())).await.unwrap();
+    assert!(book[0].bid_price < book[0].ask_price);
+    assert_eq!(market::ticker_book(&client, &None).await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn raw_variants_return_the_body() {
+    let (_server, client) = setup().await;
+
+    let body = market::ping_raw(&client).await.unwrap();
+    assert_eq!(&body[..], b"{}");
+    let body = market::ticker_price_raw(&client, &Some(btcusdt())).await.unwrap();
+    assert!(std::str::from_utf8(&body).unwrap().contains("\"BTCUSDT\""));
+}
+
+#[tokio::test]
+async fn weight_headers_are_tracked() {
+    let (server, client) = setup().await;
+
+    market::exchange_info(&client).await.unwrap();
+    market::depth(&client, &btcusdt(), 5000).await.unwrap();
+    let used: u32 = client.rate_limit_usage().iter().map(|usage| usage.used).max().unwrap();
+    assert_eq!(used, 20 + 250);
+
+    // weight used elsewhere on the ip only shows in the headers
+    server.use_weight(1000);
+    market::ping(&client).await.unwrap();
+    let used: u32 = client.rate_limit_usage().iter().map(|usage| usage.used).max().unwrap();
+    assert_eq!(used, 20 + 250 + 1000 + 1);
+}
+
+#[tokio::test]
+async fn retry_after_blocks_later_requests() {
+    let server = MockServer::start().await;
+    let client = Client::builder()
+        .base_url(server.url())
+        .retry_policy(no_retries())
+        .rate_limit_max_wait(Duration::from_secs(2))
+        .build()
+        .unwrap();
+
+    server.fail_next("/api/v3/ping", Failure::TooManyRequests { retry_after: 1 });
+    assert_eq!(market::ping(&client).await.unwrap_err().status(), Some(StatusCode::TOO_MANY_REQUESTS));
+    assert!(client.rate_limiter().retry_after().unwrap() > Duration::from_millis(900));
+
+    // the next request waits out the second before it is sent
+    let started = Instant::now();
+    market::time(&client).await.unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(900));
+    assert_eq!(server.requests().len(), 2);
+
+    // a ban longer than the client waits fails right away, without a request
+    server.fail_next("/api/v3/ping", Failure::Banned { retry_after: 60 });
+    assert_eq!(market::ping(&client).await.unwrap_err().status().map(|status| status.as_u16()), Some(418));
+    let err = market::time(&client).await.unwrap_err();
+    assert!(matches!(err, Error::RateLimited { retry_after } if retry_after > Duration::from_secs(50)), "{:?}", err);
+    assert_eq!(server.requests().len(), 3);
+}
@@ -0,0 +1,180 @@
+use binance_sdk_rs::error::ErrorCode;
+use binance_sdk_rs::http::rate_limit::Counter;
+use binance_sdk_rs::http::trade::model::{CanceledOrder, ContingencyType, ListOrderStatus, ListStatusType, OrderStatus};
+use binance_sdk_rs::http::trade::trade::{
+    self, EOrderListRef, EOrderRef, ESide, OcoOrder, OrderListLeg, OtoOrder, OtocoOrder,
+};
+use binance_sdk_rs::mock::Failure;
+use binance_sdk_rs::Error;
+
+mod common;
+use common::{dec, setup};
+
+// BTCUSDT trades at 43000 on the mock server
+fn sell_oco() -> OcoOrder {
+    OcoOrder::new(
+        "BTCUSDT",
+        ESide::SELL,
+        dec("0.01"),
+        OrderListLeg::limit_maker(dec("45000")),
+        OrderListLeg::stop_loss_limit(dec("41000"), dec("41100")),
+    )
+}
+
+#[tokio::test]
+async fn oco_lifecycle() {
+    let (_server, client) = setup().await;
+
+    let order = sell_oco().market_price(dec("43000")).list_client_order_id("oco-1");
+    let placed = trade::new_oco_order(&client, &order).await.unwrap();
+    assert_eq!(placed.contingency_type, ContingencyType::Oco);
+    assert_eq!(placed.list_status_type, ListStatusType::ExecStarted);
+    assert_eq!(placed.list_client_order_id, "oco-1");
+    assert_eq!(placed.orders.len(), 2);
+    assert_eq!(placed.order_reports.len(), 2);
+    assert!(placed.order_reports.iter().all(|r| r.order_list_id == placed.order_list_id));
+    assert_eq!(placed.order_reports[1].working_time, Some(-1));
+
+    let list = trade::query_order_list(&client, &EOrderListRef::ListClientOrderId(String::from("oco-1"))).await.unwrap();
+    assert_eq!(list.order_list_id, placed.order_list_id);
+    assert_eq!(list.list_order_status, ListOrderStatus::Executing);
+    assert_eq!(trade::open_orders(&client, &Some("BTCUSDT")).await.unwrap().len(), 2);
+
+    // canceling one leg cancels the other
+    let maker = placed.orders[0].order_id;
+    trade::cancel_order(&client, "BTCUSDT", &EOrderRef::OrderId(maker), &None, None).await.unwrap();
+    assert!(trade::open_orders(&client, &None).await.unwrap().is_empty());
+    let list = trade::query_order_list(&client, &EOrderListRef::OrderListId(placed.order_list_id)).await.unwrap();
+    assert_eq!(list.list_status_type, ListStatusType::AllDone);
+
+    let err = trade::cancel_order_list(&client, "BTCUSDT", &EOrderListRef::OrderListId(placed.order_list_id), &None).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::CancelRejected));
+    let err = trade::query_order_list(&client, &EOrderListRef::OrderListId(1)).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::NoSuchOrder));
+}
+
+#[tokio::test]
+async fn client_side_price_checks() {
+    let (server, client) = setup().await;
+
+    let leg = |price: &str| OrderListLeg::limit_maker(dec(price));
+    let stop = |price: &str| OrderListLeg::stop_loss(dec(price));
+    let invalid = [
+        // limit below the market
+        sell_oco().market_price(dec("46000")),
+        // stop above the market
+        sell_oco().market_price(dec("41050")),
+        // legs the wrong way around
+        OcoOrder::new("BTCUSDT", ESide::SELL, dec("0.01"), stop("41000"), leg("45000")),
+        OcoOrder::new("BTCUSDT", ESide::BUY, dec("0.01"), leg("41000"), stop("45000")),
+        OcoOrder::new("BTCUSDT", ESide::SELL, dec("0.01"), OrderListLeg::take_profit(dec("41000")), stop("42000")),
+        OcoOrder::new("BTCUSDT", ESide::SELL, dec("0.01"), OrderListLeg::limit(dec("45000")), stop("41000")),
+        // a leg invalid on its own
+        OcoOrder::new("BTCUSDT", ESide::SELL, dec("0.01"), leg("45000"), OrderListLeg::new(trade::EOrderType::STOP_LOSS)),
+    ];
+    for order in invalid.iter() {
+        assert!(matches!(order.validate(), Err(Error::InvalidParameter(_))), "{:?}", order);
+        let err = trade::new_oco_order(&client, order).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidParameter(_)));
+    }
+
+    // a stop loss of a buy at 42000 has to be under 42000
+    let oto = OtoOrder::new("BTCUSDT", ESide::BUY, dec("0.01"), OrderListLeg::limit(dec("42000")), ESide::SELL, dec("0.01"), stop("42500"));
+    assert!(matches!(oto.validate(), Err(Error::InvalidParameter(_))));
+    let oto = OtoOrder::new("BTCUSDT", ESide::BUY, dec("0.01"), OrderListLeg::market(), ESide::SELL, dec("0.01"), stop("41000"));
+    assert!(matches!(oto.validate(), Err(Error::InvalidParameter(_))));
+    let otoco = OtocoOrder::new(
+        "BTCUSDT", ESide::BUY, dec("0.01"), OrderListLeg::limit(dec("42000")),
+        ESide::SELL, dec("0.01"), leg("41900"), stop("41000"),
+    );
+    assert!(matches!(trade::new_otoco_order(&client, &otoco).await, Err(Error::InvalidParameter(_))));
+
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn server_side_price_checks() {
+    let (_server, client) = setup().await;
+
+    // fine with a stale market price, but the limit maker would take at 43000
+    let order = OcoOrder::new(
+        "BTCUSDT", ESide::SELL, dec("0.01"),
+        OrderListLeg::limit_maker(dec("42000")), OrderListLeg::stop_loss(dec("41000")),
+    );
+    let err = trade::new_oco_order(&client, &order.clone().market_price(dec("41500"))).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::NewOrderRejected));
+    assert!(trade::open_orders(&client, &None).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn market_price_is_fetched_when_not_set() {
+    let (server, client) = setup().await;
+
+    // a SELL with the limit maker under the market price of 43000
+    let order = OcoOrder::new(
+        "BTCUSDT", ESide::SELL, dec("0.01"),
+        OrderListLeg::limit_maker(dec("42000")), OrderListLeg::stop_loss(dec("41000")),
+    );
+    assert!(order.validate().is_ok());
+    let err = trade::new_oco_order(&client, &order).await.unwrap_err();
+    assert!(matches!(err, Error::InvalidParameter(_)));
+    assert_eq!(server.requests_to("/api/v3/ticker/price").len(), 1);
+    assert!(server.requests_to("/api/v3/orderList/oco").is_empty());
+
+    trade::new_oco_order(&client, &sell_oco()).await.unwrap();
+    assert_eq!(server.requests_to("/api/v3/ticker/price").len(), 2);
+}
+
+#[tokio::test]
+async fn oto_and_otoco() {
+    let (_server, client) = setup().await;
+
+    let oto = OtoOrder::new(
+        "BTCUSDT", ESide::BUY, dec("0.01"), OrderListLeg::limit(dec("42000")),
+        ESide::SELL, dec("0.01"), OrderListLeg::stop_loss_limit(dec("41000"), dec("41100")),
+    );
+    let placed = trade::new_oto_order(&client, &oto).await.unwrap();
+    assert_eq!(placed.contingency_type, ContingencyType::Oto);
+    assert_eq!(placed.order_reports[0].status, Some(OrderStatus::New));
+    assert_eq!(placed.order_reports[1].status, Some(OrderStatus::PendingNew));
+    assert_eq!(placed.order_reports[1].working_time, Some(-1));
+
+    let canceled = trade::cancel_order_list(&client, "BTCUSDT", &EOrderListRef::OrderListId(placed.order_list_id), &None).await.unwrap();
+    assert_eq!(canceled.list_order_status, ListOrderStatus::AllDone);
+    assert!(canceled.order_reports.iter().all(|r| r.status == OrderStatus::Canceled));
+
+    // the working order fills right away, which places the pending OCO
+    let otoco = OtocoOrder::new(
+        "BTCUSDT", ESide::BUY, dec("0.01"), OrderListLeg::limit(dec("43500")),
+        ESide::SELL, dec("0.01"), OrderListLeg::limit_maker(dec("45000")), OrderListLeg::stop_loss(dec("41000")),
+    );
+    let placed = trade::new_otoco_order(&client, &otoco).await.unwrap();
+    let status: Vec<_> = placed.order_reports.iter().map(|r| r.status.unwrap()).collect();
+    assert_eq!(status, [OrderStatus::Filled, OrderStatus::New, OrderStatus::New]);
+
+    let canceled = trade::cancel_all_open_orders(&client, "BTCUSDT").await.unwrap();
+    assert_eq!(canceled.len(), 1);
+    assert!(matches!(&canceled[0], CanceledOrder::OrderList(list) if list.order_list_id == placed.order_list_id));
+
+    let orders: Vec<_> = client.rate_limit_usage().into_iter().filter(|u| u.counter == Counter::Orders).collect();
+    assert!(orders.iter().all(|u| u.used == 5));
+}
+
+#[tokio::test]
+async fn order_list_is_not_placed_twice() {
+    let (server, client) = setup().await;
+
+    server.fail_next("/api/v3/orderList/oco", Failure::ExecutedThenServerError(503));
+    let placed = trade::new_oco_order(&client, &sell_oco()).await.unwrap();
+    assert_eq!(placed.orders.len(), 2);
+    assert!(placed.order_reports.is_empty());
+    assert_eq!(server.requests_to("/api/v3/orderList/oco").len(), 1);
+    assert_eq!(trade::open_orders(&client, &None).await.unwrap().len(), 2);
+}
+
+#[test]
+fn amended_list_status_decodes() {
+    // amend_order_keep_priority leaves the list it touched in status UPDATED
+    let status: ListStatusType = serde_json::from_str("\"UPDATED\"").unwrap();
+    assert_eq!(status, ListStatusType::Updated);
+}
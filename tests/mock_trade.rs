@@ -0,0 +1,331 @@
+use binance_sdk_rs::error::ErrorCode;
+use binance_sdk_rs::http::rate_limit::Counter;
+use binance_sdk_rs::http::trade::model::{CancelReplaceOutcome, CancelReplaceResult, CanceledOrder, OrderStatus};
+use binance_sdk_rs::http::trade::trade::{
+    self, ECancelReplaceMode, ECancelRestrictions, ENewOrderRespType, EOrderRef, EOrderType, ESide, ETimeInForce, NewOrder,
+};
+use binance_sdk_rs::mock::Failure;
+use binance_sdk_rs::Error;
+
+mod common;
+use common::{dec, setup};
+
+#[tokio::test]
+async fn limit_order_lifecycle() {
+    let (_server, client) = setup().await;
+
+    let order = NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.01"), dec("42000.00")).new_client_order_id("bid-1");
+    let placed = trade::new_order(&client, &order).await.unwrap();
+    assert_eq!(placed.client_order_id, "bid-1");
+    assert_eq!(placed.order_list_id, -1);
+    assert_eq!(placed.status, Some(OrderStatus::New));
+    assert_eq!(placed.order_type, Some(EOrderType::LIMIT));
+    assert_eq!(placed.time_in_force, Some(ETimeInForce::GTC));
+    assert!(placed.fills.is_empty());
+
+    let queried = trade::query_order(&client, "BTCUSDT", &EOrderRef::ClientOrderId(String::from("bid-1"))).await.unwrap();
+    assert_eq!(queried.order_id, placed.order_id);
+    assert_eq!(queried.price, dec("42000"));
+    assert!(queried.is_working);
+
+    let open = trade::open_orders(&client, &Some("BTCUSDT")).await.unwrap();
+    assert_eq!(open.len(), 1);
+    assert!(trade::open_orders(&client, &Some("ETHBTC")).await.unwrap().is_empty());
+
+    let canceled = trade::cancel_order(&client, "BTCUSDT", &EOrderRef::OrderId(placed.order_id), &Some("cancel-1"), None).await.unwrap();
+    assert_eq!(canceled.orig_client_order_id, "bid-1");
+    assert_eq!(canceled.client_order_id, "cancel-1");
+    assert_eq!(canceled.status, OrderStatus::Canceled);
+    assert!(trade::open_orders(&client, &None).await.unwrap().is_empty());
+
+    let err = trade::cancel_order(&client, "BTCUSDT", &EOrderRef::OrderId(placed.order_id), &None, None).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::CancelRejected));
+    let err = trade::query_order(&client, "BTCUSDT", &EOrderRef::OrderId(1)).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::NoSuchOrder));
+}
+
+#[tokio::test]
+async fn market_orders_fill() {
+    let (_server, client) = setup().await;
+
+    let resp = trade::new_order(&client, &NewOrder::market("BTCUSDT", ESide::BUY, dec("0.002"))).await.unwrap();
+    assert_eq!(resp.status, Some(OrderStatus::Filled));
+    assert_eq!(resp.executed_qty, Some(dec("0.002")));
+    assert_eq!(resp.cummulative_quote_qty, Some(dec("86")));
+    assert_eq!(resp.fills.len(), 1);
+    assert_eq!(resp.fills[0].price, dec("43000"));
+    assert_eq!(resp.fills[0].commission, dec("0.000002"));
+    assert_eq!(resp.fills[0].commission_asset, "BTC");
+
+    // 100 USDT at 43000 is 0.00232558, rounded down to the 0.00001 step
+    let resp = trade::new_order(&client, &NewOrder::market_quote("BTCUSDT", ESide::BUY, dec("100"))).await.unwrap();
+    assert_eq!(resp.orig_qty, Some(dec("0.00232")));
+
+    let resp = trade::new_order(&client, &NewOrder::market("ETHBTC", ESide::SELL, dec("1")).new_order_resp_type(ENewOrderRespType::ACK)).await.unwrap();
+    assert_eq!(resp.symbol, "ETHBTC");
+    assert_eq!(resp.status, None);
+    assert!(resp.fills.is_empty());
+
+    let orders = trade::all_orders(&client, "BTCUSDT", None, None, None, None).await.unwrap();
+    assert_eq!(orders.len(), 2);
+    assert!(orders.iter().all(|o| o.status == OrderStatus::Filled));
+    let orders = trade::all_orders(&client, "BTCUSDT", Some(orders[1].order_id), None, None, Some(10)).await.unwrap();
+    assert_eq!(orders.len(), 1);
+}
+
+#[tokio::test]
+async fn stop_and_maker_orders() {
+    let (_server, client) = setup().await;
+
+    let order = NewOrder::stop_loss_limit("BTCUSDT", ESide::SELL, dec("0.01"), dec("40000"), dec("40100"));
+    let resp = trade::new_order(&client, &order).await.unwrap();
+    // only LIMIT and MARKET default to FULL
+    assert_eq!(resp.status, None);
+    let queried = trade::query_order(&client, "BTCUSDT", &EOrderRef::OrderId(resp.order_id)).await.unwrap();
+    assert!(!queried.is_working);
+    assert_eq!(queried.stop_price, Some(dec("40100")));
+
+    let order = NewOrder::stop_loss("BTCUSDT", ESide::SELL, dec("0.01"), dec("44000"));
+    let err = trade::new_order(&client, &order).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::NewOrderRejected));
+
+    let order = NewOrder::limit_maker("BTCUSDT", ESide::BUY, dec("0.01"), dec("43100"));
+    let err = trade::new_order(&client, &order).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::NewOrderRejected));
+
+    let order = NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.01"), dec("42000")).time_in_force(ETimeInForce::IOC);
+    let resp = trade::new_order(&client, &order).await.unwrap();
+    assert_eq!(resp.status, Some(OrderStatus::Expired));
+}
+
+#[tokio::test]
+async fn server_side_validation() {
+    let (_server, client) = setup().await;
+
+    // not on the 0.01 tick size
+    let order = NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.01"), dec("42000.005"));
+    let err = trade::new_order(&client, &order).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::InvalidMessage));
+
+    // 0.0001 BTC is below the 5 USDT minimum notional
+    let err = trade::test_order(&client, &NewOrder::market("BTCUSDT", ESide::BUY, dec("0.0001"))).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::InvalidMessage));
+
+    let err = trade::new_order(&client, &NewOrder::market("DOGEUSDT", ESide::BUY, dec("1"))).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::BadSymbol));
+
+    let order = NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.01"), dec("42000")).new_client_order_id("dup");
+    trade::new_order(&client, &order).await.unwrap();
+    let err = trade::new_order(&client, &order).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::NewOrderRejected));
+}
+
+#[tokio::test]
+async fn client_side_validation() {
+    let (server, client) = setup().await;
+
+    let invalid = [
+        NewOrder::new("BTCUSDT", ESide::BUY, EOrderType::LIMIT).quantity(dec("1")).price(dec("42000")),
+        NewOrder::market("BTCUSDT", ESide::BUY, dec("1")).price(dec("42000")),
+        NewOrder::market("BTCUSDT", ESide::BUY, dec("1")).quote_order_qty(dec("100")),
+        NewOrder::new("BTCUSDT", ESide::SELL, EOrderType::STOP_LOSS).quantity(dec("1")),
+        NewOrder::limit("BTCUSDT", ESide::BUY, dec("1"), dec("42000")).stop_price(dec("41000")),
+        NewOrder::new("BTCUSDT", ESide::BUY, EOrderType::LIMIT_MAKER).price(dec("42000")),
+    ];
+    for order in invalid.iter() {
+        assert!(matches!(order.validate(), Err(Error::InvalidParameter(_))), "{:?}", order);
+        let err = trade::new_order(&client, order).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidParameter(_)));
+    }
+    assert!(server.requests_to("/api/v3/order").is_empty());
+
+    let trailing = NewOrder::new("BTCUSDT", ESide::SELL, EOrderType::TAKE_PROFIT).quantity(dec("0.01")).trailing_delta(200);
+    assert!(trailing.validate().is_ok());
+}
+
+#[tokio::test]
+async fn test_order_places_nothing() {
+    let (server, client) = setup().await;
+
+    trade::test_order(&client, &NewOrder::limit("BTCUSDT", ESide::SELL, dec("0.01"), dec("44000"))).await.unwrap();
+    assert_eq!(server.requests_to("/api/v3/order/test").len(), 1);
+    assert!(trade::open_orders(&client, &None).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn cancel_all_and_restrictions() {
+    let (_server, client) = setup().await;
+
+    for price in ["41000", "41500", "42000"] {
+        trade::new_order(&client, &NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.01"), dec(price))).await.unwrap();
+    }
+    trade::new_order(&client, &NewOrder::limit("ETHBTC", ESide::BUY, dec("1"), dec("0.05"))).await.unwrap();
+
+    let order = trade::open_orders(&client, &Some("BTCUSDT")).await.unwrap().remove(0);
+    let err = trade::cancel_order(
+        &client, "BTCUSDT", &EOrderRef::OrderId(order.order_id), &None, Some(ECancelRestrictions::ONLY_PARTIALLY_FILLED),
+    ).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::CancelRejected));
+
+    let canceled = trade::cancel_all_open_orders(&client, "BTCUSDT").await.unwrap();
+    assert_eq!(canceled.len(), 3);
+    assert!(canceled.iter().all(|c| matches!(c, CanceledOrder::Order(o) if o.status == OrderStatus::Canceled)));
+    assert_eq!(trade::open_orders(&client, &None).await.unwrap().len(), 1);
+
+    let err = trade::cancel_all_open_orders(&client, "BTCUSDT").await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::CancelRejected));
+}
+
+#[tokio::test]
+async fn new_order_is_not_placed_twice() {
+    let (server, client) = setup().await;
+
+    // executed, but the client only sees a 503: the lookup finds the order
+    server.fail_next("/api/v3/order", Failure::ExecutedThenServerError(503));
+    let resp = trade::new_order(&client, &NewOrder::market("BTCUSDT", ESide::BUY, dec("0.001"))).await.unwrap();
+    assert_eq!(resp.status, Some(OrderStatus::Filled));
+    let orders = trade::all_orders(&client, "BTCUSDT", None, None, None, None).await.unwrap();
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].client_order_id, resp.client_order_id);
+
+    // not executed: the lookup finds nothing and the order is sent again
+    server.fail_next("/api/v3/order", Failure::ServerError(502));
+    trade::new_order(&client, &NewOrder::market("BTCUSDT", ESide::BUY, dec("0.001"))).await.unwrap();
+    assert_eq!(trade::all_orders(&client, "BTCUSDT", None, None, None, None).await.unwrap().len(), 2);
+
+    let placed: Vec<_> = server.requests_to("/api/v3/order").into_iter().filter(|r| r.method == "POST").collect();
+    assert_eq!(placed.len(), 3);
+    assert_eq!(placed[1].param("newClientOrderId"), placed[2].param("newClientOrderId"));
+
+    // a ban is final, nothing is looked up or resent
+    server.fail_next("/api/v3/order", Failure::Banned { retry_after: 0 });
+    let err = trade::new_order(&client, &NewOrder::market("BTCUSDT", ESide::BUY, dec("0.001"))).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::TooManyRequests));
+    assert_eq!(server.requests_to("/api/v3/order").len(), 6);
+}
+
+#[tokio::test]
+async fn order_count_is_tracked() {
+    let (_server, client) = setup().await;
+
+    for _ in 0..3 {
+        trade::new_order(&client, &NewOrder::market("BNBUSDT", ESide::SELL, dec("0.1"))).await.unwrap();
+    }
+    trade::open_orders(&client, &None).await.unwrap();
+
+    let orders: Vec<_> = client.rate_limit_usage().into_iter().filter(|u| u.counter == Counter::Orders).collect();
+    assert_eq!(orders.len(), 2);
+    assert!(orders.iter().all(|u| u.used == 3));
+}
+
+#[tokio::test]
+async fn cancel_replace_outcomes() {
+    let (server, client) = setup().await;
+
+    let quote = |price: &str| NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.01"), dec(price));
+    let placed = trade::new_order(&client, &quote("42000").new_client_order_id("q-1")).await.unwrap();
+
+    let resp = trade::cancel_replace_order(
+        &client, &EOrderRef::ClientOrderId(String::from("q-1")), ECancelReplaceMode::STOP_ON_FAILURE,
+        &quote("42100").new_client_order_id("q-2"), &None, None, None,
+    ).await.unwrap();
+    assert!(resp.is_success());
+    assert_eq!(resp.canceled().unwrap().order_id, placed.order_id);
+    assert_eq!(resp.new_order().unwrap().client_order_id, "q-2");
+    let open = trade::open_orders(&client, &None).await.unwrap();
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0].price, dec("42100"));
+
+    // q-1 is gone: nothing is placed on STOP_ON_FAILURE, the new order is on ALLOW_FAILURE
+    let resp = trade::cancel_replace_order(
+        &client, &EOrderRef::ClientOrderId(String::from("q-1")), ECancelReplaceMode::STOP_ON_FAILURE,
+        &quote("42200"), &None, None, None,
+    ).await.unwrap();
+    assert_eq!(resp.cancel_result, CancelReplaceResult::Failure);
+    assert_eq!(resp.new_order_result, CancelReplaceResult::NotAttempted);
+    assert!(matches!(&resp.cancel_response, Some(CancelReplaceOutcome::Err(err)) if err.error_code() == ErrorCode::CancelRejected));
+    assert!(resp.new_order_response.is_none());
+
+    let resp = trade::cancel_replace_order(
+        &client, &EOrderRef::ClientOrderId(String::from("q-1")), ECancelReplaceMode::ALLOW_FAILURE,
+        &quote("42200"), &None, None, None,
+    ).await.unwrap();
+    assert_eq!(resp.cancel_result, CancelReplaceResult::Failure);
+    assert_eq!(resp.new_order_result, CancelReplaceResult::Success);
+    assert_eq!(trade::open_orders(&client, &None).await.unwrap().len(), 2);
+
+    // canceled, but the new order breaks the tick size
+    let resp = trade::cancel_replace_order(
+        &client, &EOrderRef::ClientOrderId(String::from("q-2")), ECancelReplaceMode::STOP_ON_FAILURE,
+        &quote("42100.001"), &None, None, None,
+    ).await.unwrap();
+    assert!(!resp.is_success());
+    assert_eq!(resp.canceled().unwrap().orig_client_order_id, "q-2");
+    assert!(matches!(&resp.new_order_response, Some(CancelReplaceOutcome::Err(err)) if err.error_code() == ErrorCode::InvalidMessage));
+
+    // other errors stay errors
+    let err = trade::cancel_replace_order(
+        &client, &EOrderRef::OrderId(1), ECancelReplaceMode::ALLOW_FAILURE,
+        &NewOrder::market("DOGEUSDT", ESide::BUY, dec("1")), &None, None, None,
+    ).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::BadSymbol));
+    assert_eq!(server.requests_to("/api/v3/order/cancelReplace").len(), 5);
+}
+
+#[tokio::test]
+async fn lost_cancel_replace_is_looked_up() {
+    let (server, client) = setup().await;
+
+    let quote = |price: &str| NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.01"), dec(price));
+    trade::new_order(&client, &quote("42000").new_client_order_id("q-1")).await.unwrap();
+
+    // executed, but the answer is lost: the new order is found by its generated client order id
+    server.fail_next("/api/v3/order/cancelReplace", Failure::ExecutedThenServerError(503));
+    let resp = trade::cancel_replace_order(
+        &client, &EOrderRef::ClientOrderId(String::from("q-1")), ECancelReplaceMode::ALLOW_FAILURE,
+        &quote("42100"), &None, None, None,
+    ).await.unwrap();
+    assert!(resp.is_success());
+    assert!(resp.cancel_response.is_none());
+    let new_order = resp.new_order().unwrap();
+    let sent = server.requests_to("/api/v3/order/cancelReplace").pop().unwrap();
+    assert_eq!(sent.param("newClientOrderId"), Some(new_order.client_order_id.as_str()));
+    assert_eq!(new_order.price, Some(dec("42100")));
+
+    // not executed: nothing to find, the error stands and nothing is resent
+    server.fail_next("/api/v3/order/cancelReplace", Failure::ServerError(503));
+    let err = trade::cancel_replace_order(
+        &client, &EOrderRef::OrderId(new_order.order_id), ECancelReplaceMode::STOP_ON_FAILURE,
+        &quote("42200"), &None, None, None,
+    ).await.unwrap_err();
+    assert_eq!(err.status().map(|status| status.as_u16()), Some(503));
+    assert_eq!(server.requests_to("/api/v3/order/cancelReplace").len(), 2);
+    assert_eq!(trade::open_orders(&client, &None).await.unwrap()[0].price, dec("42100"));
+}
+
+#[tokio::test]
+async fn amend_keeps_priority() {
+    let (_server, client) = setup().await;
+
+    let order = NewOrder::limit("BTCUSDT", ESide::SELL, dec("0.05"), dec("44000")).new_client_order_id("ask-1");
+    let placed = trade::new_order(&client, &order).await.unwrap();
+
+    let resp = trade::amend_order_keep_priority(&client, "BTCUSDT", &EOrderRef::OrderId(placed.order_id), dec("0.03"), &Some("ask-2"))
+        .await.unwrap();
+    assert_eq!(resp.amended_order.order_id, placed.order_id);
+    assert_eq!(resp.amended_order.orig_client_order_id, "ask-1");
+    assert_eq!(resp.amended_order.client_order_id, "ask-2");
+    assert_eq!(resp.amended_order.qty, dec("0.03"));
+    assert!(resp.list_status.is_none());
+
+    let queried = trade::query_order(&client, "BTCUSDT", &EOrderRef::OrderId(placed.order_id)).await.unwrap();
+    assert_eq!(queried.orig_qty, dec("0.03"));
+    assert_eq!(queried.time, placed.transact_time);
+
+    let err = trade::amend_order_keep_priority(&client, "BTCUSDT", &EOrderRef::OrderId(placed.order_id), dec("0.04"), &None)
+        .await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::AmendQuantityIncrease));
+    let err = trade::amend_order_keep_priority(&client, "BTCUSDT", &EOrderRef::OrderId(placed.order_id), dec("0"), &None)
+        .await.unwrap_err();
+    assert!(matches!(err, Error::InvalidParameter(_)));
+}
@@ -0,0 +1,107 @@
+use std::{sync::Arc, time::Duration};
+
+use binance_sdk_rs::error::ErrorCode;
+use binance_sdk_rs::http::client::Client;
+use binance_sdk_rs::http::user_data::model::ListenKeyEvent;
+use binance_sdk_rs::http::user_data::user_data::{self, EUserDataStream, ListenKeyHandle};
+use binance_sdk_rs::mock::{Failure, MockServer};
+use binance_sdk_rs::Error;
+use tokio::sync::broadcast;
+
+mod common;
+use common::{http_client, no_retries};
+
+async fn setup() -> (MockServer, Arc<Client>) {
+    let server = MockServer::start().await;
+    let client = Arc::new(http_client(&server, no_retries()));
+    (server, client)
+}
+
+async fn next_event(events: &mut broadcast::Receiver<ListenKeyEvent>) -> ListenKeyEvent {
+    tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap()
+}
+
+#[tokio::test]
+async fn listen_key_endpoints() {
+    let (server, client) = setup().await;
+
+    let spot = user_data::create_listen_key(&client, &EUserDataStream::Spot).await.unwrap().listen_key;
+    assert_eq!(spot.len(), 60);
+    assert_eq!(user_data::create_listen_key(&client, &EUserDataStream::Spot).await.unwrap().listen_key, spot);
+    let margin = user_data::create_listen_key(&client, &EUserDataStream::Margin).await.unwrap().listen_key;
+    let isolated = EUserDataStream::IsolatedMargin(String::from("BTCUSDT"));
+    let isolated_key = user_data::create_listen_key(&client, &isolated).await.unwrap().listen_key;
+    assert!(spot != margin && margin != isolated_key);
+
+    user_data::keepalive_listen_key(&client, &EUserDataStream::Spot, &spot).await.unwrap();
+    let err = user_data::keepalive_listen_key(&client, &EUserDataStream::Spot, &margin).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::InvalidListenKey));
+    user_data::close_listen_key(&client, &isolated, &isolated_key).await.unwrap();
+    let err = user_data::keepalive_listen_key(&client, &isolated, &isolated_key).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::InvalidListenKey));
+
+    // api key only, no signature
+    let req = server.requests_to("/api/v3/userDataStream").remove(0);
+    assert!(req.api_key.is_some());
+    assert!(req.param("signature").is_none());
+
+    let unsigned = Client::builder().base_url(server.url()).build().unwrap();
+    let err = user_data::create_listen_key(&unsigned, &EUserDataStream::Spot).await.unwrap_err();
+    assert!(matches!(err, Error::MissingCredentials));
+    let err = user_data::create_listen_key(&client, &EUserDataStream::IsolatedMargin(String::from("DOGEUSDT"))).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::BadSymbol));
+}
+
+#[tokio::test]
+async fn handle_renews_and_rotates() {
+    let (server, client) = setup().await;
+
+    let handle = ListenKeyHandle::start(client.clone(), EUserDataStream::Spot, Duration::from_millis(20)).await.unwrap();
+    let mut events = handle.subscribe();
+    let mut watch = handle.watch();
+    let first = handle.listen_key();
+
+    assert_eq!(next_event(&mut events).await, ListenKeyEvent::Renewed);
+    assert!(server.requests_to("/api/v3/userDataStream").iter().any(|r| r.method == "PUT"));
+
+    server.expire_listen_keys();
+    let second = loop {
+        if let ListenKeyEvent::Rotated { old, new } = next_event(&mut events).await {
+            assert_eq!(old, first);
+            break new;
+        }
+    };
+    assert_eq!(handle.listen_key(), second);
+    assert!(watch.has_changed().unwrap());
+    assert_eq!(*watch.borrow_and_update(), second);
+
+    handle.close().await.unwrap();
+    let err = user_data::keepalive_listen_key(&client, &EUserDataStream::Spot, &second).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::InvalidListenKey));
+}
+
+#[tokio::test]
+async fn reported_expiry_and_failures() {
+    let (server, client) = setup().await;
+
+    let margin = EUserDataStream::Margin;
+    let handle = ListenKeyHandle::start(client.clone(), margin.clone(), Duration::from_secs(3600)).await.unwrap();
+    let mut events = handle.subscribe();
+    let first = handle.listen_key();
+
+    // e.g. a listenKeyExpired event on the websocket, long before the next keepalive
+    server.expire_listen_keys();
+    handle.report_expired();
+    let second = match next_event(&mut events).await {
+        ListenKeyEvent::Rotated { new, .. } => new,
+        event => panic!("unexpected {:?}", event),
+    };
+    assert_ne!(second, first);
+
+    // failures are reported and retried
+    server.fail_next("/sapi/v1/userDataStream", Failure::ServerError(503));
+    server.expire_listen_keys();
+    handle.report_expired();
+    assert!(matches!(next_event(&mut events).await, ListenKeyEvent::Failed(_)));
+    assert!(matches!(next_event(&mut events).await, ListenKeyEvent::Rotated { .. }));
+}
@@ -0,0 +1,346 @@
+use std::time::Duration;
+
+use binance_sdk_rs::error::ErrorCode;
+use binance_sdk_rs::http::client::{Client, Credentials};
+use binance_sdk_rs::http::wallet::model::{AccountSnapshotData, DepositStatus, WithdrawStatus};
+use binance_sdk_rs::http::wallet::wallet::{self, EAssetTransferType};
+use binance_sdk_rs::mock::{Failure, MockServer};
+use binance_sdk_rs::{Decimal, Error};
+
+mod common;
+use common::{dec, http_client, no_retries, setup};
+
+#[tokio::test]
+async fn status_endpoints() {
+    let (_server, client) = setup().await;
+
+    let body = wallet::system_status(&client).await.unwrap();
+    assert_eq!(&body[..], br#"{"msg":"normal","status":0}"#);
+    let body = wallet::account_status(&client).await.unwrap();
+    assert_eq!(&body[..], br#"{"data":"Normal"}"#);
+    let body = wallet::account_api_trading_status(&client).await.unwrap();
+    assert!(std::str::from_utf8(&body).unwrap().contains("\"isLocked\":false"));
+
+    let restrictions = wallet::account_api_restrictions(&client).await.unwrap();
+    assert!(restrictions.enable_reading);
+    assert!(!restrictions.ip_restrict);
+}
+
+#[tokio::test]
+async fn capital_all() {
+    let (_server, client) = setup().await;
+
+    let coins = wallet::capital_all(&client).await.unwrap();
+    let btc = coins.iter().find(|c| c.coin == "BTC").unwrap();
+    assert_eq!(btc.free, dec("0.08074558"));
+    assert_eq!(btc.network_list.len(), 2);
+}
+
+#[tokio::test]
+async fn account_snapshot() {
+    let (_server, client) = setup().await;
+
+    for (account_type, expected) in [("SPOT", "spot"), ("MARGIN", "margin"), ("FUTURES", "futures")] {
+        let snapshot = wallet::account_snapshot(&client, &String::from(account_type), None, None, Some(7)).await.unwrap();
+        assert_eq!(snapshot.code, 200);
+        let kind = match snapshot.snapshot_vos[0].data {
+            AccountSnapshotData::Spot(_) => "spot",
+            AccountSnapshotData::Margin(_) => "margin",
+            AccountSnapshotData::Futures(_) => "futures",
+        };
+        assert_eq!(kind, expected);
+    }
+}
+
+#[tokio::test]
+async fn dust_and_fast_withdraw_switches() {
+    let (_server, client) = setup().await;
+
+    let body = wallet::asset_dust_btc(&client).await.unwrap();
+    assert!(std::str::from_utf8(&body).unwrap().contains("totalTransferBtc"));
+    wallet::enable_fast_withdraw_switch(&client).await.unwrap();
+    wallet::disable_fast_withdraw_switch(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn withdraw_shows_up_in_history() {
+    let (server, client) = setup().await;
+
+    let resp = wallet::capital_withdraw(
+        &client, "BTC", dec("0.01"), "1HPn8Rx2y6nNSfagQBKy27GB99Vbzg89wv", &Some("my-withdraw-1"),
+        &Some("BTC"), &None, &None, &None, &None,
+    ).await.unwrap();
+
+    let history = wallet::capital_withdraw_history(&client, &Some("BTC"), &None, None, None, None, None, None).await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].id, resp.id);
+    assert_eq!(history[0].amount, dec("0.01"));
+    assert_eq!(history[0].withdraw_order_id.as_deref(), Some("my-withdraw-1"));
+    assert_eq!(history[0].status, WithdrawStatus::Processing);
+
+    // an id is generated when none is given, so the request can be reconciled
+    wallet::capital_withdraw(&client, "BTC", dec("0.02"), "1HPn8Rx2y6nNSfagQBKy27GB99Vbzg89wv", &None, &None, &None, &None, &None, &None).await.unwrap();
+    let sent = server.requests_to("/sapi/v1/capital/withdraw/apply");
+    assert!(sent[1].param("withdrawOrderId").is_some());
+}
+
+#[tokio::test]
+async fn deposit_history_and_address() {
+    let (_server, client) = setup().await;
+
+    let deposits = wallet::capital_deposit_hisrec(&client, &None, None, None, None, None, None).await.unwrap();
+    assert_eq!(deposits.len(), 2);
+    let deposits = wallet::capital_deposit_hisrec(&client, &Some("BNB"), Some(DepositStatus::Success), None, None, None, Some(10)).await.unwrap();
+    assert_eq!(deposits.len(), 1);
+    assert_eq!(deposits[0].amount, dec("0.001"));
+
+    let address = wallet::capital_deposit_address(&client, "BTC", &None).await.unwrap();
+    assert_eq!(address.coin, "BTC");
+    assert!(!address.address.is_empty());
+}
+
+#[tokio::test]
+async fn dust_conversion_shows_up_in_dribblet() {
+    let (_server, client) = setup().await;
+
+    let result = wallet::asset_dust(&client, &["ADA", "TRX"]).await.unwrap();
+    assert_eq!(result.transfer_result.len(), 2);
+
+    let log = wallet::asset_dribblet(&client, None, None).await.unwrap();
+    assert_eq!(log.total, 1);
+    assert_eq!(log.user_asset_dribblets[0].trans_id, result.transfer_result[0].tran_id);
+    assert_eq!(log.user_asset_dribblets[0].total_transfered_amount, result.total_transfered);
+}
+
+#[tokio::test]
+async fn asset_queries() {
+    let (_server, client) = setup().await;
+
+    let dividends = wallet::asset_dividend(&client, &Some("BNB"), None, None, Some(10)).await.unwrap();
+    assert_eq!(dividends.total, 1);
+    assert_eq!(dividends.rows[0].asset, "BNB");
+
+    let details = wallet::asset_detail(&client, &None).await.unwrap();
+    assert_eq!(details.len(), 3);
+    let details = wallet::asset_detail(&client, &Some("BTC")).await.unwrap();
+    assert_eq!(details["BTC"].withdraw_fee, dec("0.0002"));
+
+    let fees = wallet::asset_trade_fee(&client, &Some("BTCUSDT")).await.unwrap();
+    assert_eq!(fees[0].maker_commission, dec("0.001"));
+    assert_eq!(wallet::asset_trade_fee(&client, &None).await.unwrap().len(), 3);
+
+    let funding = wallet::get_funding_asset(&client, &None, &Some("true")).await.unwrap();
+    assert_eq!(funding.len(), 2);
+    assert!(funding.iter().all(|asset| asset.btc_valuation > Decimal::ZERO));
+}
+
+#[tokio::test]
+async fn transfer_shows_up_in_history() {
+    let (_server, client) = setup().await;
+
+    let empty = wallet::get_asset_transfer(&client, EAssetTransferType::MAIN_FUNDING, None, None, None, None, &None, &None).await.unwrap();
+    assert_eq!(empty.total, 0);
+
+    let resp = wallet::make_asset_transfer(&client, EAssetTransferType::MAIN_FUNDING, "USDT", dec("25.5"), &None, &None).await.unwrap();
+    let list = wallet::get_asset_transfer(&client, EAssetTransferType::MAIN_FUNDING, None, None, Some(1), Some(10), &None, &None).await.unwrap();
+    assert_eq!(list.total, 1);
+    assert_eq!(list.rows[0].tran_id, resp.tran_id);
+    assert_eq!(list.rows[0].amount, dec("25.5"));
+}
+
+#[tokio::test]
+async fn signature_and_key_are_checked() {
+    let (server, _client) = setup().await;
+
+    let client = Client::builder().base_url(server.url()).build().unwrap();
+    assert!(matches!(wallet::capital_all(&client).await, Err(Error::MissingCredentials)));
+
+    let client = Client::builder()
+        .base_url(server.url())
+        .credentials(Credentials::new("wrong key", binance_sdk_rs::mock::SECRET_KEY))
+        .build()
+        .unwrap();
+    let err = wallet::capital_all(&client).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::RejectedMbxKey));
+
+    let client = Client::builder()
+        .base_url(server.url())
+        .credentials(Credentials::new(binance_sdk_rs::mock::API_KEY, "wrong secret"))
+        .build()
+        .unwrap();
+    let err = wallet::capital_all(&client).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::InvalidSignature));
+}
+
+#[tokio::test]
+async fn recv_window_is_enforced() {
+    let (server, _client) = setup().await;
+
+    // a client that trusts its own clock while the server runs 10s ahead
+    let client = Client::builder()
+        .base_url(server.url())
+        .credentials(MockServer::credentials())
+        .disable_time_sync()
+        .build()
+        .unwrap();
+    server.set_clock_offset(10_000);
+    let err = wallet::capital_all(&client).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::InvalidTimestamp));
+
+    // a syncing client picks the offset up
+    let client = Client::builder()
+        .base_url(server.url())
+        .credentials(MockServer::credentials())
+        .build()
+        .unwrap();
+    wallet::capital_all(&client).await.unwrap();
+    assert!(client.time_offset().unwrap().offset_ms >= 9_000);
+}
+
+#[tokio::test]
+async fn resyncs_on_invalid_timestamp() {
+    let (server, client) = setup().await;
+
+    wallet::capital_all(&client).await.unwrap();
+    server.fail_next("/sapi/v1/capital/config/getall", Failure::InvalidTimestamp);
+    wallet::capital_all(&client).await.unwrap();
+    assert_eq!(server.requests_to("/api/v3/time").len(), 2);
+}
+
+#[tokio::test]
+async fn concurrent_requests_sync_once() {
+    let (server, client) = setup().await;
+
+    let results = futures::future::join_all((0..5).map(|_| wallet::capital_all(&client))).await;
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(server.requests_to("/api/v3/time").len(), 1);
+}
+
+#[tokio::test]
+async fn failed_syncs_back_off() {
+    let server = MockServer::start().await;
+    let client = http_client(&server, no_retries());
+
+    server.fail_next("/api/v3/time", Failure::ServerError(503));
+    wallet::capital_all(&client).await.unwrap();
+    assert!(client.time_offset().is_none());
+    // the next request does not try again right away
+    wallet::capital_all(&client).await.unwrap();
+    assert_eq!(server.requests_to("/api/v3/time").len(), 1);
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    wallet::capital_all(&client).await.unwrap();
+    assert_eq!(server.requests_to("/api/v3/time").len(), 2);
+    assert!(client.time_offset().is_some());
+}
+
+#[tokio::test]
+async fn reads_are_retried() {
+    let (server, client) = setup().await;
+
+    server.fail_next("/sapi/v1/asset/assetDetail", Failure::ServerError(503));
+    server.fail_next("/sapi/v1/asset/assetDetail", Failure::TooManyRequests { retry_after: 0 });
+    wallet::asset_detail(&client, &None).await.unwrap();
+    let sent = server.requests_to("/sapi/v1/asset/assetDetail");
+    assert_eq!(sent.len(), 3);
+    // every attempt is signed with a fresh timestamp
+    assert_ne!(sent[0].param("signature"), sent[2].param("signature"));
+}
+
+#[tokio::test]
+async fn retries_give_up() {
+    let (server, client) = setup().await;
+
+    for _ in 0..4 {
+        server.fail_next("/sapi/v1/asset/assetDetail", Failure::ServerError(500));
+    }
+    let err = wallet::asset_detail(&client, &None).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::Disconnected));
+    assert_eq!(server.requests_to("/sapi/v1/asset/assetDetail").len(), 4);
+}
+
+#[tokio::test]
+async fn ban_is_not_retried() {
+    let (server, client) = setup().await;
+
+    server.fail_next("/sapi/v1/asset/assetDetail", Failure::Banned { retry_after: 0 });
+    let err = wallet::asset_detail(&client, &None).await.unwrap_err();
+    assert_eq!(err.status().map(|s| s.as_u16()), Some(418));
+}
+
+#[tokio::test]
+async fn lost_withdraw_response_is_not_resent() {
+    let (server, client) = setup().await;
+
+    server.fail_next("/sapi/v1/capital/withdraw/apply", Failure::ExecutedThenServerError(503));
+    let resp = wallet::capital_withdraw(&client, "BTC", dec("0.01"), "1HPn8Rx2y6nNSfagQBKy27GB99Vbzg89wv", &None, &None, &None, &None, &None, &None).await.unwrap();
+
+    assert_eq!(server.requests_to("/sapi/v1/capital/withdraw/apply").len(), 1);
+    let history = wallet::capital_withdraw_history(&client, &None, &None, None, None, None, None, None).await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].id, resp.id);
+}
+
+#[tokio::test]
+async fn failed_withdraw_is_not_resent_blindly() {
+    let (server, client) = setup().await;
+
+    // an id the history does not list may only be lagging behind
+    server.fail_next("/sapi/v1/capital/withdraw/apply", Failure::ServerError(503));
+    let err = wallet::capital_withdraw(&client, "BTC", dec("0.01"), "1HPn8Rx2y6nNSfagQBKy27GB99Vbzg89wv", &None, &None, &None, &None, &None, &None).await.unwrap_err();
+    assert_eq!(err.status().map(|status| status.as_u16()), Some(503));
+    assert_eq!(server.requests_to("/sapi/v1/capital/withdraw/apply").len(), 1);
+}
+
+#[tokio::test]
+async fn failed_transfer_is_not_resent_blindly() {
+    let (server, client) = setup().await;
+
+    // not in the history, but that could be lag as well
+    server.fail_next("/sapi/v1/asset/transfer", Failure::ServerError(502));
+    let err = wallet::make_asset_transfer(&client, EAssetTransferType::MAIN_MARGIN, "USDT", dec("10"), &None, &None).await.unwrap_err();
+    assert_eq!(err.status().map(|status| status.as_u16()), Some(502));
+
+    let posted = server.requests_to("/sapi/v1/asset/transfer").into_iter().filter(|r| r.method == hyper::Method::POST).count();
+    assert_eq!(posted, 1);
+    let list = wallet::get_asset_transfer(&client, EAssetTransferType::MAIN_MARGIN, None, None, None, None, &None, &None).await.unwrap();
+    assert_eq!(list.total, 0);
+}
+
+#[tokio::test]
+async fn lost_transfer_response_is_recovered_from_history() {
+    let (server, client) = setup().await;
+
+    // the same amount the other way does not count
+    wallet::make_asset_transfer(&client, EAssetTransferType::MAIN_FUNDING, "USDT", dec("10"), &None, &None).await.unwrap();
+    server.fail_next("/sapi/v1/asset/transfer", Failure::ExecutedThenServerError(503));
+    let resp = wallet::make_asset_transfer(&client, EAssetTransferType::MAIN_MARGIN, "USDT", dec("10"), &None, &None).await.unwrap();
+    let list = wallet::get_asset_transfer(&client, EAssetTransferType::MAIN_MARGIN, None, None, None, None, &None, &None).await.unwrap();
+    assert_eq!((list.total, list.rows[0].tran_id), (1, resp.tran_id));
+
+    // two identical transfers right after each other cannot be told apart
+    server.fail_next("/sapi/v1/asset/transfer", Failure::ExecutedThenServerError(503));
+    let err = wallet::make_asset_transfer(&client, EAssetTransferType::MAIN_MARGIN, "USDT", dec("10"), &None, &None).await.unwrap_err();
+    assert_eq!(err.status().map(|status| status.as_u16()), Some(503));
+    let posted = server.requests_to("/sapi/v1/asset/transfer").into_iter().filter(|r| r.method == hyper::Method::POST).count();
+    assert_eq!(posted, 3);
+}
+
+#[tokio::test]
+async fn lost_dust_response_is_recovered_from_dribblet() {
+    let (server, client) = setup().await;
+
+    // a conversion of other assets as well is not the lost one
+    wallet::asset_dust(&client, &["ADA", "TRX"]).await.unwrap();
+    server.fail_next("/sapi/v1/asset/dust", Failure::ExecutedThenServerError(504));
+    let result = wallet::asset_dust(&client, &["ADA"]).await.unwrap();
+    assert_eq!(result.transfer_result.len(), 1);
+    assert_eq!(result.transfer_result[0].from_asset, "ADA");
+    assert_eq!(server.requests_to("/sapi/v1/asset/dust").len(), 2);
+
+    // nothing in the dribblet, the conversion is not sent again
+    server.fail_next("/sapi/v1/asset/dust", Failure::ServerError(503));
+    let err = wallet::asset_dust(&client, &["XRP"]).await.unwrap_err();
+    assert_eq!(err.status().map(|status| status.as_u16()), Some(503));
+    assert_eq!(server.requests_to("/sapi/v1/asset/dust").len(), 3);
+}
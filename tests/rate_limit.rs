@@ -0,0 +1,56 @@
+use std::time::{Duration, Instant};
+
+use binance_sdk_rs::http::market::model::{RateLimit, RateLimitInterval, RateLimitType};
+use binance_sdk_rs::http::rate_limit::{Counter, RateLimiter, RequestCost};
+use binance_sdk_rs::Error;
+
+fn weight(weight: u32) -> RequestCost {
+    RequestCost { weight, sapi: false, orders: 0 }
+}
+
+// 10 weight a minute, nothing else limited
+fn limiter(max_wait: Duration) -> RateLimiter {
+    let limiter = RateLimiter::new(max_wait);
+    limiter.set_limits(&[RateLimit {
+        rate_limit_type: RateLimitType::RequestWeight,
+        interval: RateLimitInterval::Minute,
+        interval_num: 1,
+        limit: 10,
+    }]);
+    limiter
+}
+
+fn used(limiter: &RateLimiter, now_ms: u64) -> u32 {
+    limiter.usage(now_ms).iter().find(|usage| usage.counter == Counter::RequestWeight).unwrap().used
+}
+
+#[tokio::test]
+async fn waits_for_the_next_window() {
+    let limiter = limiter(Duration::from_secs(1));
+
+    // 20ms before the minute ends, the clock moving on in real time
+    let started = Instant::now();
+    let now_ms = || 59_980 + started.elapsed().as_millis() as u64;
+    limiter.acquire(weight(6), now_ms).await.unwrap();
+    limiter.acquire(weight(4), now_ms).await.unwrap();
+    assert_eq!(used(&limiter, now_ms()), 10);
+
+    limiter.acquire(weight(6), now_ms).await.unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(20));
+    assert_eq!(used(&limiter, now_ms()), 6);
+}
+
+#[tokio::test]
+async fn fails_when_the_wait_is_too_long() {
+    let limiter = limiter(Duration::from_secs(1));
+
+    limiter.acquire(weight(8), || 30_000).await.unwrap();
+    let err = limiter.acquire(weight(3), || 30_000).await.unwrap_err();
+    assert!(matches!(err, Error::RateLimited { retry_after } if retry_after == Duration::from_secs(30)), "{:?}", err);
+    // nothing was reserved for the refused request
+    assert_eq!(used(&limiter, 30_000), 8);
+
+    // a request heavier than the limit goes through on an empty window
+    limiter.acquire(weight(50), || 60_000).await.unwrap();
+    assert_eq!(used(&limiter, 60_000), 50);
+}
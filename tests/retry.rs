@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use binance_sdk_rs::error::{ApiError, Error, ErrorCode};
+use binance_sdk_rs::http::retry::{is_retryable, RetryPolicy};
+use hyper::StatusCode;
+
+fn api_error(status: u16, code: i32) -> Error {
+    Error::Api(ApiError {
+        status: StatusCode::from_u16(status).unwrap(),
+        code: ErrorCode::from(code),
+        msg: String::new(),
+        data: None,
+    })
+}
+
+#[test]
+fn retryable_errors() {
+    assert!(is_retryable(&Error::Timeout));
+    assert!(is_retryable(&api_error(500, -1001)));
+    assert!(is_retryable(&api_error(429, -1003)));
+    assert!(is_retryable(&Error::Http { status: StatusCode::BAD_GATEWAY, body: String::new() }));
+
+    assert!(!is_retryable(&api_error(418, -1003)));
+    assert!(!is_retryable(&api_error(400, -1021)));
+    assert!(!is_retryable(&api_error(400, -2010)));
+    assert!(!is_retryable(&Error::MissingCredentials));
+}
+
+#[test]
+fn delay_is_capped() {
+    let policy = RetryPolicy {
+        max_retries: 10,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(800),
+    };
+    for attempt in 0..10 {
+        let cap = Duration::from_millis(100 * 2u64.pow(attempt)).min(Duration::from_millis(800));
+        assert!(policy.delay(attempt) <= cap);
+    }
+}
+
+#[test]
+fn stops_after_max_retries() {
+    let policy = RetryPolicy::default();
+    assert!(policy.should_retry(&Error::Timeout, 0));
+    assert!(!policy.should_retry(&Error::Timeout, policy.max_retries));
+    assert!(!RetryPolicy::none().should_retry(&Error::Timeout, 0));
+}
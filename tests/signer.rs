@@ -0,0 +1,133 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use binance_sdk_rs::error::Result;
+use binance_sdk_rs::http::client::{Client, Credentials};
+use binance_sdk_rs::http::signer::{Ed25519Signer, HmacSigner, RsaSigner, Signer};
+use binance_sdk_rs::http::trade::trade::{self, ESide, NewOrder};
+use binance_sdk_rs::http::transport::Transport;
+use ed25519_dalek::{pkcs8::DecodePrivateKey as _, SigningKey};
+use hyper::{body::Bytes, Request, Response};
+use rsa::{pkcs1v15, signature::Verifier as _, RsaPrivateKey};
+use sha2::Sha256;
+
+// example from https://binance-docs.github.io/apidocs/spot/en/#signed-trade-user_data-and-margin-endpoint-security
+#[test]
+fn hmac_matches_binance_example() {
+    let signer = HmacSigner::new("NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j");
+    let payload = "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000&timestamp=1499827319559";
+
+    assert_eq!(
+        signer.sign(payload.as_bytes()).unwrap(),
+        "c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71"
+    );
+}
+
+// payload of the rsa and ed25519 examples in the binance docs; the keys in tests/fixtures are
+// throwaway keys and the expected signatures were produced with `openssl pkeyutl`/`openssl dgst`
+const PAYLOAD: &str = "symbol=BTCUSDT&side=SELL&type=LIMIT&timeInForce=GTC&quantity=1.0000000&price=0.20&timestamp=1668481559918";
+
+#[test]
+fn rsa_signature_matches_openssl() {
+    let signer = RsaSigner::from_pkcs8_pem(include_str!("fixtures/rsa_test_key.pem")).unwrap();
+
+    assert_eq!(
+        signer.sign(PAYLOAD.as_bytes()).unwrap(),
+        "K42f9Ga8FKMNKjqznuFfb3CEn7zeeGyVtb8+jwJJ4vhSNGnt5ZHoN19ihsByQAkYU48LwyXmcmsHfyrZ6hKydguIzq1kvRRFY3MhqdrRWmiHYPjL07qoN4i3l9zOHhbEY/SUYYeCq13wvP1V/c0uCm0SpjPpw+1eb6YdJncKx875AmihYURUqkmbqSsEJPVyP54oBt9/gzuTxT79MLoXoi/LCUc09ijra3Sm65S2dKDLfhDkncmHHjvVamLxooJni8LbzHYV5rWbtyZGfH0WJ2dtxqCpS6+zkhGCvTLSi0hnfQm2NZkhdqNJaap4lLb74e7Q416RH23XUDon2bbK5A=="
+    );
+}
+
+#[test]
+fn ed25519_signature_matches_openssl() {
+    let signer = Ed25519Signer::from_pkcs8_pem(include_str!("fixtures/ed25519_test_key.pem")).unwrap();
+
+    assert_eq!(
+        signer.sign(PAYLOAD.as_bytes()).unwrap(),
+        "oT/4qTCJnV+8u9Z5frMMMMBb8Swr13/aOpgS6C5Zsweb4X3wk3pNqoJBimVcce/iyYFd2B09G7FN7VzIO081Bw=="
+    );
+}
+
+// the signatures have to verify with the public half of the keys
+#[test]
+fn rsa_signature_verifies() {
+    let pem = include_str!("fixtures/rsa_test_key.pem");
+    let signature = STANDARD.decode(RsaSigner::from_pkcs8_pem(pem).unwrap().sign(PAYLOAD.as_bytes()).unwrap()).unwrap();
+
+    let key = pkcs1v15::VerifyingKey::<Sha256>::new(RsaPrivateKey::from_pkcs8_pem(pem).unwrap().to_public_key());
+    let signature = pkcs1v15::Signature::try_from(signature.as_slice()).unwrap();
+    key.verify(PAYLOAD.as_bytes(), &signature).unwrap();
+}
+
+#[test]
+fn ed25519_signature_verifies() {
+    let pem = include_str!("fixtures/ed25519_test_key.pem");
+    let signature = STANDARD.decode(Ed25519Signer::from_pkcs8_pem(pem).unwrap().sign(PAYLOAD.as_bytes()).unwrap()).unwrap();
+
+    let key = SigningKey::from_pkcs8_pem(pem).unwrap().verifying_key();
+    let signature = ed25519_dalek::Signature::from_slice(&signature).unwrap();
+    key.verify_strict(PAYLOAD.as_bytes(), &signature).unwrap();
+}
+
+/// Keeps the body of every request and answers `{}`.
+#[derive(Debug, Default)]
+struct Capture {
+    bodies: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl Transport for Capture {
+    async fn send(&self, req: Request<Bytes>) -> Result<Response<Bytes>> {
+        self.bodies.lock().unwrap().push(String::from_utf8_lossy(req.body()).into_owned());
+        Ok(Response::new(Bytes::from("{}")))
+    }
+}
+
+// sends the order of the docs' examples, returns the signed payload and the decoded signature
+async fn sign_example_order(credentials: Credentials) -> (String, Vec<u8>) {
+    let capture = Arc::new(Capture::default());
+    let client = Client::builder().transport(capture.clone()).credentials(credentials).disable_time_sync().build().unwrap();
+    let order = NewOrder::limit("BTCUSDT", ESide::SELL, "1.0000000".parse().unwrap(), "0.20".parse().unwrap());
+    trade::test_order(&client, &order).await.unwrap();
+
+    let body = capture.bodies.lock().unwrap().pop().unwrap();
+    let (payload, signature) = body.split_once("&signature=").unwrap();
+    // base64 signatures carry `+`, `/` and `=`, which have to be percent-encoded
+    assert!(!signature.contains(['+', '/', '=']), "{}", signature);
+    let signature = signature.replace("%2B", "+").replace("%2F", "/").replace("%3D", "=");
+    (payload.to_string(), STANDARD.decode(signature).unwrap())
+}
+
+// the payload is the encoded params in the order they are sent, without the signature, exactly
+// like the examples; the signature has to be the signer's over it
+fn assert_example_payload(payload: &str) {
+    let (params, timestamp) = payload.split_once("&timestamp=").unwrap();
+    assert_eq!(params, "symbol=BTCUSDT&side=SELL&type=LIMIT&timeInForce=GTC&quantity=1.0000000&price=0.20");
+    assert!(timestamp.parse::<u64>().is_ok(), "{}", timestamp);
+}
+
+#[tokio::test]
+async fn rsa_signs_the_request_payload() {
+    let pem = include_str!("fixtures/rsa_test_key.pem");
+    let (payload, signature) = sign_example_order(Credentials::rsa("key", pem).unwrap()).await;
+    assert_example_payload(&payload);
+
+    let expected = RsaSigner::from_pkcs8_pem(pem).unwrap().sign(payload.as_bytes()).unwrap();
+    assert_eq!(signature, STANDARD.decode(expected).unwrap());
+}
+
+#[tokio::test]
+async fn ed25519_signs_the_request_payload() {
+    let pem = include_str!("fixtures/ed25519_test_key.pem");
+    let (payload, signature) = sign_example_order(Credentials::ed25519("key", pem).unwrap()).await;
+    assert_example_payload(&payload);
+
+    let expected = Ed25519Signer::from_pkcs8_pem(pem).unwrap().sign(payload.as_bytes()).unwrap();
+    assert_eq!(signature, STANDARD.decode(expected).unwrap());
+}
+
+#[test]
+fn invalid_pem_is_a_signing_error() {
+    assert!(Ed25519Signer::from_pkcs8_pem("not a key").is_err());
+    assert!(RsaSigner::from_pkcs8_pem(include_str!("fixtures/ed25519_test_key.pem")).is_err());
+}
@@ -0,0 +1,80 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use binance_sdk_rs::error::{ErrorCode, Result};
+use binance_sdk_rs::http::client::{Client, Credentials};
+use binance_sdk_rs::http::market::market;
+use binance_sdk_rs::http::transport::Transport;
+use binance_sdk_rs::http::wallet::wallet;
+use binance_sdk_rs::Error;
+use hyper::{body::Bytes, Request, Response, StatusCode};
+
+/// Answers every request with the same canned response and keeps what was sent.
+#[derive(Debug)]
+struct FakeTransport {
+    status: StatusCode,
+    body: &'static str,
+    requests: Mutex<Vec<Request<Bytes>>>,
+}
+
+impl FakeTransport {
+    fn new(status: StatusCode, body: &'static str) -> Self {
+        FakeTransport { status, body, requests: Mutex::new(vec![]) }
+    }
+}
+
+#[async_trait]
+impl Transport for FakeTransport {
+    async fn send(&self, req: Request<Bytes>) -> Result<Response<Bytes>> {
+        self.requests.lock().unwrap().push(req);
+        let resp = Response::builder()
+            .status(self.status)
+            .header("x-mbx-used-weight-1m", "42")
+            .body(Bytes::from(self.body))?;
+        Ok(resp)
+    }
+}
+
+#[tokio::test]
+async fn decodes_response_from_transport() {
+    let transport = Arc::new(FakeTransport::new(StatusCode::OK, r#"{"serverTime":1499827319559}"#));
+    let client = Client::builder().transport(transport.clone()).build().unwrap();
+
+    let server_time = market::time(&client).await.unwrap();
+    assert_eq!(server_time.server_time, 1499827319559);
+
+    let requests = transport.requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].uri(), "https://api.binance.com/api/v3/time");
+    assert!(client.rate_limit_usage().iter().any(|usage| usage.used == 42));
+}
+
+#[tokio::test]
+async fn signed_request_carries_key_and_signature() {
+    let transport = Arc::new(FakeTransport::new(StatusCode::OK, "[]"));
+    let client = Client::builder()
+        .transport(transport.clone())
+        .credentials(Credentials::new("key", "secret"))
+        .disable_time_sync()
+        .build()
+        .unwrap();
+
+    wallet::capital_all(&client).await.unwrap();
+
+    let requests = transport.requests.lock().unwrap();
+    let req = &requests[0];
+    assert_eq!(req.headers()["X-MBX-APIKEY"], "key");
+    let query = req.uri().query().unwrap();
+    assert!(query.starts_with("timestamp="));
+    assert!(query.contains("&signature="));
+}
+
+#[tokio::test]
+async fn error_status_becomes_api_error() {
+    let transport = Arc::new(FakeTransport::new(StatusCode::BAD_REQUEST, r#"{"code":-1121,"msg":"Invalid symbol."}"#));
+    let client = Client::builder().transport(transport.clone()).build().unwrap();
+
+    let err = market::avg_price(&client, &String::from("NOPE")).await.unwrap_err();
+    assert!(matches!(err, Error::Api(_)));
+    assert_eq!(err.code(), Some(ErrorCode::BadSymbol));
+}
@@ -0,0 +1,166 @@
+use std::time::{Duration, Instant};
+
+use binance_sdk_rs::error::ErrorCode;
+use binance_sdk_rs::http::client::Credentials;
+use binance_sdk_rs::http::market::model::{RateLimitInterval, RateLimitType};
+use binance_sdk_rs::http::trade::model::OrderStatus;
+use binance_sdk_rs::http::trade::trade::{EOrderRef, ESide, NewOrder};
+use binance_sdk_rs::mock::{Failure, MockServer, API_KEY, ED25519_API_KEY};
+use binance_sdk_rs::ws::api::api::{ApiClient, ApiClientBuilder};
+use binance_sdk_rs::Error;
+use serde_json::Value;
+
+mod common;
+use common::{dec, ws_client};
+
+async fn connect(server: &MockServer, credentials: Credentials) -> ApiClient {
+    ApiClientBuilder::new().credentials(credentials).connect(&ws_client(server)).await.unwrap()
+}
+
+fn last_request(server: &MockServer, method: &str) -> Value {
+    server.ws_api_requests().into_iter().rfind(|request| request["method"] == method).unwrap()
+}
+
+#[tokio::test]
+async fn responses_are_matched_by_id() {
+    let server = MockServer::start().await;
+    let api = ApiClient::connect(&ws_client(&server)).await.unwrap();
+
+    api.ping().await.unwrap();
+    assert!(api.time().await.unwrap().server_time > 0);
+    assert_eq!(api.ticker_price("BTCUSDT").await.unwrap().symbol, "BTCUSDT");
+
+    // the slow answer is overtaken by the quick one
+    server.delay_ws_api_method("depth", Duration::from_millis(200));
+    let started = Instant::now();
+    let (depth, quick) = futures::join!(api.depth("BTCUSDT", 5), async {
+        api.ping().await.unwrap();
+        started.elapsed()
+    });
+    assert_eq!(depth.unwrap().bids.len(), 5);
+    assert!(quick < Duration::from_millis(200), "{:?}", quick);
+
+    let requests = server.ws_api_requests();
+    let ids: Vec<u64> = requests.iter().map(|request| request["id"].as_u64().unwrap()).collect();
+    assert_eq!(ids, [1, 2, 3, 4, 5]);
+    assert_eq!(requests[3]["params"]["symbol"], "BTCUSDT");
+
+    // every response reports the request weight used so far
+    let response = api.request("time", &[]).await.unwrap();
+    assert_eq!(api.rate_limits(), response.rate_limits);
+    let rate_limits = &response.rate_limits;
+    assert_eq!(rate_limits.len(), 1);
+    assert_eq!((rate_limits[0].rate_limit_type, rate_limits[0].interval), (RateLimitType::RequestWeight, RateLimitInterval::Minute));
+    assert_eq!(rate_limits[0].limit, 6000);
+    let response = api.request("ping", &[]).await.unwrap();
+    assert_eq!(response.rate_limits[0].count, rate_limits[0].count + 1);
+
+    let err = api.request("order.amend", &[]).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::UnsupportedOperation));
+    api.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn signed_requests() {
+    let server = MockServer::start().await;
+    let api = connect(&server, MockServer::credentials()).await;
+
+    let order = NewOrder::limit("BTCUSDT", ESide::BUY, dec("0.01"), dec("42000.00")).new_client_order_id("bid-1");
+    let placed = api.new_order(&order).await.unwrap();
+    assert_eq!((placed.client_order_id.as_str(), placed.status), ("bid-1", Some(OrderStatus::New)));
+
+    let params = last_request(&server, "order.place")["params"].clone();
+    assert_eq!(params["apiKey"], API_KEY);
+    assert_eq!((params["price"].as_str(), params["quantity"].as_str()), (Some("42000.00"), Some("0.01")));
+    assert!(params["timestamp"].is_u64());
+    assert_eq!(params["signature"].as_str().map(str::len), Some(64));
+
+    // orders count against the order limits too
+    let rate_limits = api.rate_limits();
+    assert!(rate_limits.iter().any(|limit| limit.rate_limit_type == RateLimitType::Orders && limit.count == 1));
+
+    let queried = api.query_order("BTCUSDT", &EOrderRef::ClientOrderId(String::from("bid-1"))).await.unwrap();
+    assert_eq!(queried.order_id, placed.order_id);
+    assert_eq!(api.open_orders(Some("BTCUSDT")).await.unwrap().len(), 1);
+    let canceled = api.cancel_order("BTCUSDT", &EOrderRef::OrderId(placed.order_id)).await.unwrap();
+    assert_eq!(canceled.status, OrderStatus::Canceled);
+    assert!(api.open_orders(None).await.unwrap().is_empty());
+    assert!(!api.account().await.unwrap().balances.is_empty());
+
+    // only Ed25519 keys can log on
+    let err = api.logon().await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::Other(-4056)));
+    assert!(!api.logged_on());
+
+    let api = ApiClient::connect(&ws_client(&server)).await.unwrap();
+    assert!(matches!(api.account().await.unwrap_err(), Error::MissingCredentials));
+    let forged = connect(&server, Credentials::new(API_KEY, "not the secret")).await;
+    assert_eq!(forged.account().await.unwrap_err().code(), Some(ErrorCode::InvalidSignature));
+}
+
+#[tokio::test]
+async fn logon_authenticates_the_session() {
+    let server = MockServer::start().await;
+    let api = connect(&server, MockServer::ed25519_credentials()).await;
+
+    assert_eq!(api.session_status().await.unwrap().api_key, None);
+    let status = api.logon().await.unwrap();
+    assert_eq!(status.api_key.as_deref(), Some(ED25519_API_KEY));
+    assert!(status.authorized_since.is_some());
+    assert!(api.logged_on());
+    let params = last_request(&server, "session.logon")["params"].clone();
+    assert_eq!(params["apiKey"], ED25519_API_KEY);
+    assert!(params["signature"].as_str().unwrap().ends_with('='));
+
+    // no key or signature once logged on, the timestamp still
+    let order = NewOrder::market("BTCUSDT", ESide::SELL, dec("0.01"));
+    api.new_order(&order).await.unwrap();
+    let params = last_request(&server, "order.place")["params"].clone();
+    assert!(params.get("apiKey").is_none() && params.get("signature").is_none());
+    assert!(params["timestamp"].is_u64());
+
+    assert_eq!(api.logout().await.unwrap().api_key, None);
+    assert!(!api.logged_on());
+    api.test_order(&order).await.unwrap();
+    assert_eq!(last_request(&server, "order.test")["params"]["apiKey"], ED25519_API_KEY);
+}
+
+#[tokio::test]
+async fn timeouts_and_errors() {
+    let server = MockServer::start().await;
+    let api = ApiClientBuilder::new()
+        .credentials(MockServer::credentials())
+        .request_timeout(Duration::from_millis(100))
+        .connect(&ws_client(&server))
+        .await
+        .unwrap();
+
+    server.delay_ws_api_method("depth", Duration::from_millis(300));
+    assert!(matches!(api.depth("BTCUSDT", 5).await.unwrap_err(), Error::Timeout));
+    // the late answer is dropped, later requests get their own
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(api.ticker_price("ETHBTC").await.unwrap().symbol, "ETHBTC");
+
+    server.fail_next("order.test", Failure::TooManyRequests { retry_after: 5 });
+    let order = NewOrder::market("BTCUSDT", ESide::BUY, dec("0.01"));
+    let err = api.test_order(&order).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::TooManyRequests));
+    assert_eq!(err.status().map(|status| status.as_u16()), Some(429));
+    match err {
+        Error::Api(err) => assert!(err.data.unwrap()["retryAfter"].is_u64()),
+        err => panic!("unexpected {:?}", err),
+    }
+
+    // a timestamp error resyncs the clock and signs again
+    server.set_clock_offset(20_000);
+    api.test_order(&order).await.unwrap();
+    let methods: Vec<Value> = server.ws_api_requests().iter().map(|request| request["method"].clone()).collect();
+    assert_eq!(methods[methods.len() - 3..], ["order.test", "time", "order.test"]);
+
+    // waiting requests fail with the connection, and so do later ones
+    server.delay_ws_api_method("ping", Duration::from_millis(50));
+    let (waiting, dropped) = futures::join!(api.ping(), async { server.drop_ws_api_connections() });
+    assert_eq!(dropped, 1);
+    assert!(matches!(waiting.unwrap_err(), Error::WebSocket(_)));
+    assert!(matches!(api.ping().await.unwrap_err(), Error::WebSocket(_)));
+}
@@ -0,0 +1,147 @@
+use std::{net::SocketAddr, time::Duration};
+
+use futures::{SinkExt, StreamExt};
+use binance_sdk_rs::http::client::Client as HttpClient;
+use binance_sdk_rs::ws::client::{Client, Connection};
+use binance_sdk_rs::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::{accept_async, WebSocketStream};
+use tungstenite::Message;
+
+type ServerSocket = WebSocketStream<TcpStream>;
+
+// a server accepting one websocket connection and handing it to `script`
+async fn serve<F, Fut>(script: F) -> (SocketAddr, JoinHandle<()>)
+where
+    F: FnOnce(ServerSocket) -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = ()> + Send,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let task = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        script(accept_async(stream).await.unwrap()).await;
+    });
+    (addr, task)
+}
+
+fn client(addr: SocketAddr) -> Client {
+    Client::builder().base_url(format!("ws://{}", addr)).build().unwrap()
+}
+
+async fn next(connection: &mut Connection) -> Option<String> {
+    tokio::time::timeout(Duration::from_secs(5), connection.next()).await.unwrap().map(|m| m.unwrap())
+}
+
+#[tokio::test]
+async fn messages_pings_and_close() {
+    let (addr, server) = serve(|mut socket| async move {
+        socket.send(Message::Text(String::from("first"))).await.unwrap();
+        socket.send(Message::Ping(b"heartbeat".to_vec())).await.unwrap();
+        socket.send(Message::Text(String::from("second"))).await.unwrap();
+
+        assert_eq!(socket.next().await.unwrap().unwrap(), Message::Pong(b"heartbeat".to_vec()));
+        assert_eq!(socket.next().await.unwrap().unwrap(), Message::Text(String::from("hello")));
+        assert!(matches!(socket.next().await.unwrap().unwrap(), Message::Close(_)));
+    }).await;
+
+    let mut connection = client(addr).connect("/ws/btcusdt@trade").await.unwrap();
+    assert_eq!(next(&mut connection).await.unwrap(), "first");
+    assert_eq!(next(&mut connection).await.unwrap(), "second");
+    connection.send("hello").unwrap();
+    connection.close().await.unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn closed_by_the_server() {
+    let (addr, server) = serve(|mut socket| async move {
+        socket.send(Message::Text(String::from("bye"))).await.unwrap();
+        socket.close(None).await.unwrap();
+        while socket.next().await.is_some() {}
+    }).await;
+
+    let mut connection = client(addr).connect("/ws/btcusdt@trade").await.unwrap();
+    assert_eq!(next(&mut connection).await.unwrap(), "bye");
+    assert_eq!(next(&mut connection).await, None);
+    assert!(connection.send("hello").is_err());
+    connection.close().await.unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn dropping_closes() {
+    let (closed_tx, closed) = oneshot::channel();
+    let (addr, _server) = serve(|mut socket| async move {
+        let frame = socket.next().await.unwrap().unwrap();
+        closed_tx.send(frame).unwrap();
+    }).await;
+
+    let connection = client(addr).connect("/ws/btcusdt@trade").await.unwrap();
+    drop(connection);
+    let frame = tokio::time::timeout(Duration::from_secs(5), closed).await.unwrap().unwrap();
+    assert!(matches!(frame, Message::Close(_)));
+}
+
+// an http proxy answering CONNECT with `status`, reporting the request line it got
+async fn proxy(status: &'static str) -> (SocketAddr, oneshot::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (request_tx, request) = oneshot::channel();
+    tokio::spawn(async move {
+        let (mut inbound, _) = listener.accept().await.unwrap();
+        let mut head = vec![];
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(inbound.read_u8().await.unwrap());
+        }
+        let head = String::from_utf8(head).unwrap();
+        let line = head.lines().next().unwrap().to_string();
+        let target = line.split_whitespace().nth(1).unwrap().to_string();
+        request_tx.send(line).unwrap();
+
+        inbound.write_all(format!("HTTP/1.1 {}\r\n\r\n", status).as_bytes()).await.unwrap();
+        if status.starts_with("200") {
+            let mut outbound = TcpStream::connect(target).await.unwrap();
+            tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await.ok();
+        }
+    });
+    (addr, request)
+}
+
+#[tokio::test]
+async fn uses_the_http_client_proxy() {
+    let (addr, server) = serve(|mut socket| async move {
+        socket.send(Message::Text(String::from("tunneled"))).await.unwrap();
+        while socket.next().await.is_some() {}
+    }).await;
+    let (proxy_addr, request) = proxy("200 Connection established").await;
+
+    let http = HttpClient::builder().proxy(format!("http://{}", proxy_addr)).build().unwrap();
+    let ws = Client::builder().base_url(format!("ws://{}", addr)).proxy_from(&http).build().unwrap();
+    let mut connection = ws.connect("/ws/btcusdt@trade").await.unwrap();
+    assert_eq!(next(&mut connection).await.unwrap(), "tunneled");
+    assert_eq!(request.await.unwrap(), format!("CONNECT {} HTTP/1.1", addr));
+    connection.close().await.unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn connect_errors() {
+    let (proxy_addr, _request) = proxy("407 Proxy Authentication Required").await;
+    let ws = Client::builder().base_url("ws://127.0.0.1:9").proxy(format!("http://{}", proxy_addr)).build().unwrap();
+    let err = ws.connect("/ws/btcusdt@trade").await.unwrap_err();
+    assert_eq!(err.status().map(|s| s.as_u16()), Some(407));
+
+    // nothing listens there
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    assert!(matches!(client(addr).connect("/ws/btcusdt@trade").await, Err(Error::WebSocket(_))));
+
+    assert!(matches!(Client::builder().base_url("https://stream.binance.com").build(), Err(Error::InvalidParameter(_))));
+    assert!(matches!(Client::builder().proxy("not a uri").build(), Err(Error::InvalidParameter(_))));
+    assert_eq!(Client::new().base_url(), "wss://stream.binance.com:9443");
+}
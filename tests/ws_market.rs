@@ -0,0 +1,212 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+use binance_sdk_rs::http::market::model::{AggTrade, BookTicker, Kline, OrderBookSnapshot, Ticker24hr, TickerPrice};
+use binance_sdk_rs::mock::MockServer;
+use binance_sdk_rs::ws::client::Client;
+use binance_sdk_rs::ws::market::market::{self, EMarketStream, MarketStream};
+use binance_sdk_rs::ws::market::model::{MarketEvent, MarketMessage};
+use binance_sdk_rs::ws::stream::StreamEvent;
+use binance_sdk_rs::{Decimal, Error};
+use serde_json::json;
+
+mod common;
+use common::{dec, ws_client};
+
+async fn setup() -> (MockServer, Client) {
+    let server = MockServer::start().await;
+    let client = ws_client(&server);
+    (server, client)
+}
+
+async fn next(stream: &mut MarketStream) -> MarketMessage {
+    match tokio::time::timeout(Duration::from_secs(5), stream.next()).await.unwrap().unwrap().unwrap() {
+        StreamEvent::Message(message) => message,
+        event => panic!("unexpected {:?}", event),
+    }
+}
+
+#[tokio::test]
+async fn events_are_routed_by_stream() {
+    let (server, client) = setup().await;
+    let kline = EMarketStream::Kline(String::from("BTCUSDT"), String::from("1m"));
+    let depth = EMarketStream::PartialDepth(String::from("ETHBTC"), 5);
+    let mut stream = market::subscribe(&client, &[
+        EMarketStream::Trade(String::from("BTCUSDT")),
+        kline.clone(),
+        depth.clone(),
+        EMarketStream::BookTicker(String::from("BTCUSDT")),
+    ]).await.unwrap();
+    assert_eq!(stream.streams().count(), 4);
+
+    assert_eq!(server.push_stream_event("btcusdt@kline_1m", json!({
+        "e": "kline", "E": 1700000001000u64, "s": "BTCUSDT",
+        "k": {
+            "t": 1700000000000u64, "T": 1700000059999u64, "s": "BTCUSDT", "i": "1m", "f": 100, "L": 200,
+            "o": "43000.00", "c": "43010.00", "h": "43020.00", "l": "42990.00", "v": "12.5", "n": 101,
+            "x": false, "q": "537625.00", "V": "6.25", "Q": "268812.50", "B": "0"
+        }
+    })), 1);
+    let message = next(&mut stream).await;
+    assert_eq!(message.stream, kline);
+    let event = match message.event {
+        MarketEvent::Kline(event) => event,
+        event => panic!("unexpected {:?}", event),
+    };
+    assert!(!event.kline.is_closed);
+    assert_eq!(event.kline.interval, "1m");
+    // the same kline as GET /api/v3/klines returns
+    let rest: Kline = serde_json::from_value(json!([
+        1700000000000u64, "43000.00", "43020.00", "42990.00", "43010.00", "12.5",
+        1700000059999u64, "537625.00", 101, "6.25", "268812.50", "0"
+    ])).unwrap();
+    assert_eq!(Kline::from(event), rest);
+
+    server.push_stream_event("ethbtc@depth5@100ms", json!({
+        "lastUpdateId": 160, "bids": [["0.05511", "10"]], "asks": [["0.05513", "4.5"], ["0.05514", "1"]]
+    }));
+    let message = next(&mut stream).await;
+    assert_eq!(message.stream, depth);
+    match message.event {
+        MarketEvent::PartialDepth(OrderBookSnapshot { last_update_id, bids, asks }) => {
+            assert_eq!(last_update_id, 160);
+            assert_eq!(bids[0].price, dec("0.05511"));
+            assert_eq!(asks.len(), 2);
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+
+    // book tickers have no event type, only the stream name tells them apart
+    server.push_stream_event("btcusdt@bookTicker", json!({
+        "u": 400900217, "s": "BTCUSDT", "b": "42999.99", "B": "1.5", "a": "43000.01", "A": "0.3"
+    }));
+    match next(&mut stream).await.event {
+        MarketEvent::BookTicker(event) => {
+            assert_eq!(event.update_id, 400900217);
+            assert_eq!(BookTicker::from(event).ask_price, dec("43000.01"));
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+
+    server.push_stream_event("btcusdt@trade", json!({
+        "e": "trade", "E": 1700000002000u64, "s": "BTCUSDT", "t": 12345, "p": "43000.00", "q": "0.002",
+        "T": 1700000002000u64, "m": true, "M": true
+    }));
+    match next(&mut stream).await.event {
+        MarketEvent::Trade(event) => {
+            assert_eq!((event.id, event.price * event.qty), (12345, dec("86")));
+            assert!(event.is_buyer_maker);
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+
+    // not subscribed
+    assert_eq!(server.push_stream_event("ethbtc@trade", json!({})), 0);
+    stream.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn tickers_and_trades_share_rest_models() {
+    let (server, client) = setup().await;
+    let mut stream = market::subscribe(&client, &[
+        EMarketStream::AggTrade(String::from("BNBUSDT")),
+        EMarketStream::MiniTicker(String::from("BNBUSDT")),
+        EMarketStream::Ticker(String::from("BNBUSDT")),
+        EMarketStream::RollingWindowTicker(String::from("BNBUSDT"), String::from("4h")),
+        EMarketStream::DiffDepth(String::from("BNBUSDT")),
+    ]).await.unwrap();
+
+    server.push_stream_event("bnbusdt@aggTrade", json!({
+        "e": "aggTrade", "E": 1700000000100u64, "s": "BNBUSDT", "a": 26129, "p": "310.50", "q": "100",
+        "f": 100, "l": 105, "T": 1700000000000u64, "m": true, "M": true
+    }));
+    match next(&mut stream).await.event {
+        MarketEvent::AggTrade(event) => {
+            assert_eq!(event.symbol, "BNBUSDT");
+            let agg_trade = AggTrade::from(event);
+            assert_eq!((agg_trade.agg_trade_id, agg_trade.first_trade_id, agg_trade.last_trade_id), (26129, 100, 105));
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+
+    server.push_stream_event("bnbusdt@miniTicker", json!({
+        "e": "24hrMiniTicker", "E": 1700000000000u64, "s": "BNBUSDT", "c": "310.50", "o": "300.00",
+        "h": "312.00", "l": "299.00", "v": "10000", "q": "3080000"
+    }));
+    match next(&mut stream).await.event {
+        MarketEvent::MiniTicker(event) => {
+            assert_eq!(TickerPrice::from(event), TickerPrice { symbol: String::from("BNBUSDT"), price: dec("310.50") });
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+
+    server.push_stream_event("bnbusdt@ticker", json!({
+        "e": "24hrTicker", "E": 1700000000000u64, "s": "BNBUSDT", "p": "10.50", "P": "3.500", "w": "305.10",
+        "x": "300.00", "c": "310.50", "Q": "1.5", "b": "310.40", "B": "20", "a": "310.60", "A": "15",
+        "o": "300.00", "h": "312.00", "l": "299.00", "v": "10000", "q": "3051000",
+        "O": 1699913600000u64, "C": 1700000000000u64, "F": 0, "L": 18150, "n": 18151
+    }));
+    match next(&mut stream).await.event {
+        MarketEvent::Ticker(event) => {
+            let ticker = Ticker24hr::from(event);
+            assert_eq!(ticker.prev_close_price, dec("300"));
+            assert_eq!(ticker.count, 18151);
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+
+    server.push_stream_event("bnbusdt@ticker_4h", json!({
+        "e": "4hTicker", "E": 1700000000000u64, "s": "BNBUSDT", "p": "2.50", "P": "0.812", "o": "308.00",
+        "h": "311.00", "l": "307.00", "c": "310.50", "w": "309.20", "v": "2000", "q": "618400",
+        "O": 1699985600000u64, "C": 1700000000000u64, "F": 16000, "L": 18150, "n": 2151
+    }));
+    match next(&mut stream).await.event {
+        MarketEvent::RollingWindowTicker(event) => assert_eq!(event.count, 2151),
+        event => panic!("unexpected {:?}", event),
+    }
+
+    server.push_stream_event("bnbusdt@depth@100ms", json!({
+        "e": "depthUpdate", "E": 1700000000000u64, "s": "BNBUSDT", "U": 157, "u": 160,
+        "b": [["310.40", "20"]], "a": [["310.60", "0"]]
+    }));
+    match next(&mut stream).await.event {
+        MarketEvent::DiffDepth(event) => {
+            assert_eq!((event.first_update_id, event.final_update_id), (157, 160));
+            assert_eq!(event.asks[0].qty, Decimal::ZERO);
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+}
+
+#[tokio::test]
+async fn bad_events_and_subscriptions() {
+    let (server, client) = setup().await;
+
+    let invalid = [
+        vec![],
+        vec![EMarketStream::Kline(String::from("BTCUSDT"), String::from("2m"))],
+        vec![EMarketStream::PartialDepth(String::from("BTCUSDT"), 50)],
+        vec![EMarketStream::RollingWindowTicker(String::from("BTCUSDT"), String::from("1w"))],
+        vec![EMarketStream::Trade(String::new())],
+    ];
+    for streams in invalid.iter() {
+        assert!(matches!(market::subscribe(&client, streams).await, Err(Error::InvalidParameter(_))), "{:?}", streams);
+    }
+    assert_eq!(EMarketStream::Kline(String::from("BTCUSDT"), String::from("1M")).to_string(), "btcusdt@kline_1M");
+
+    // a broken event is an error, the stream goes on
+    let mut stream = market::subscribe(&client, &[EMarketStream::Trade(String::from("BTCUSDT"))]).await.unwrap();
+    server.push_stream_event("btcusdt@trade", json!({"e": "trade", "p": "not a price"}));
+    server.push_stream_event("btcusdt@trade", json!({
+        "e": "trade", "E": 1, "s": "BTCUSDT", "t": 1, "p": "43000", "q": "1", "T": 1, "m": false, "M": true
+    }));
+    let err = tokio::time::timeout(Duration::from_secs(5), stream.next()).await.unwrap().unwrap().unwrap_err();
+    assert!(matches!(err, Error::Deserialize(_)));
+    assert!(matches!(next(&mut stream).await.event, MarketEvent::Trade(_)));
+
+    // the raw connection gets the combined messages as they are
+    let mut raw = market::subscribe_raw(&client, &[EMarketStream::BookTicker(String::from("ETHBTC"))]).await.unwrap();
+    server.push_stream_event("ethbtc@bookTicker", json!({"u": 1}));
+    let text = tokio::time::timeout(Duration::from_secs(5), raw.next()).await.unwrap().unwrap().unwrap();
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&text).unwrap(), json!({"stream": "ethbtc@bookTicker", "data": {"u": 1}}));
+}
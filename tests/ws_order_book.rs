@@ -0,0 +1,191 @@
+use std::{sync::Arc, time::Duration};
+
+use binance_sdk_rs::http::market::model::{OrderBookSnapshot, PriceLevel};
+use binance_sdk_rs::http::trade::trade::ESide;
+use binance_sdk_rs::mock::{Failure, MockServer};
+use binance_sdk_rs::ws::market::model::{DiffDepthEvent, OrderBookEvent};
+use binance_sdk_rs::ws::market::order_book::{ApplyOutcome, LocalBook, OrderBook, MAX_STALE_SNAPSHOTS};
+use binance_sdk_rs::Decimal;
+use serde_json::json;
+use tokio::sync::broadcast;
+
+mod common;
+use common::{dec, http_client, no_retries, ws_client};
+
+// the first depth snapshot of the mock server, every later one is 3 updates further
+const SNAPSHOT_ID: u64 = 1027027;
+
+async fn setup() -> (MockServer, OrderBook) {
+    let server = MockServer::start().await;
+    let http = http_client(&server, no_retries());
+    let ws = ws_client(&server);
+    let book = OrderBook::start(Arc::new(http), &ws, "BTCUSDT").await.unwrap();
+    (server, book)
+}
+
+fn level(price: &str, qty: &str) -> PriceLevel {
+    PriceLevel { price: dec(price), qty: dec(qty) }
+}
+
+fn diff(first_update_id: u64, final_update_id: u64, bids: Vec<PriceLevel>, asks: Vec<PriceLevel>) -> DiffDepthEvent {
+    DiffDepthEvent { event_time: 0, symbol: String::from("BTCUSDT"), first_update_id, final_update_id, bids, asks }
+}
+
+fn push_diff(server: &MockServer, first_update_id: u64, final_update_id: u64, bids: &[[&str; 2]], asks: &[[&str; 2]]) {
+    server.push_stream_event("btcusdt@depth@100ms", json!({
+        "e": "depthUpdate", "E": 1700000000000u64, "s": "BTCUSDT",
+        "U": first_update_id, "u": final_update_id, "b": bids, "a": asks,
+    }));
+}
+
+async fn next_event(events: &mut broadcast::Receiver<OrderBookEvent>) -> OrderBookEvent {
+    tokio::time::timeout(Duration::from_secs(5), events.recv()).await.unwrap().unwrap()
+}
+
+#[test]
+fn local_book_sequencing() {
+    let mut book = LocalBook::new(OrderBookSnapshot {
+        last_update_id: 100,
+        bids: vec![level("9", "1"), level("8", "2"), level("7", "3")],
+        asks: vec![level("10", "1"), level("11", "2"), level("12", "0")],
+    });
+    assert_eq!(book.best_bid(), Some(level("9", "1")));
+    assert_eq!(book.depth(5).asks.len(), 2);
+
+    assert_eq!(book.apply(&diff(90, 100, vec![level("9", "5")], vec![])), ApplyOutcome::Stale);
+    assert_eq!(book.apply(&diff(102, 103, vec![], vec![])), ApplyOutcome::Gap { expected: 101, first_update_id: 102 });
+    // the first diff may overlap the snapshot
+    assert_eq!(book.apply(&diff(95, 105, vec![level("9", "0"), level("8.5", "4")], vec![])), ApplyOutcome::Applied);
+    assert_eq!(book.best_bid(), Some(level("8.5", "4")));
+    // later ones have to follow on exactly
+    assert_eq!(book.apply(&diff(105, 107, vec![], vec![])), ApplyOutcome::Gap { expected: 106, first_update_id: 105 });
+    assert_eq!(book.apply(&diff(106, 107, vec![], vec![level("10", "0")])), ApplyOutcome::Applied);
+    assert_eq!(book.last_update_id(), 107);
+
+    let depth = book.depth(2);
+    assert_eq!(depth.bids, [level("8.5", "4"), level("8", "2")]);
+    assert_eq!(depth.asks, [level("11", "2")]);
+
+    // 2 at 11
+    assert_eq!(book.vwap(ESide::BUY, dec("2")), Some(dec("11")));
+    assert_eq!(book.vwap(ESide::BUY, dec("3")), None);
+    // 4 at 8.5, 2 at 8
+    assert_eq!(book.vwap(ESide::SELL, dec("6")), Some(dec("50") / dec("6")));
+    assert_eq!(book.vwap(ESide::SELL, Decimal::ZERO), None);
+}
+
+#[tokio::test]
+async fn syncs_from_snapshot_and_diffs() {
+    let (server, book) = setup().await;
+    let mut events = book.subscribe();
+    assert!(!book.is_synced());
+    assert_eq!(book.best_bid(), None);
+
+    // the first one is already in the snapshot, the second overlaps it
+    push_diff(&server, SNAPSHOT_ID - 5, SNAPSHOT_ID - 1, &[["42999.99", "0"]], &[]);
+    push_diff(&server, SNAPSHOT_ID - 1, SNAPSHOT_ID + 1, &[["42999.995", "0.5"]], &[]);
+    assert_eq!(next_event(&mut events).await, OrderBookEvent::Synced { last_update_id: SNAPSHOT_ID + 1 });
+    book.wait_synced().await;
+
+    let snapshot = server.requests_to("/api/v3/depth").remove(0);
+    assert_eq!(snapshot.param("limit"), Some("5000"));
+    assert_eq!(book.best_bid(), Some(level("42999.995", "0.5")));
+    // the stale diff did not remove the snapshot's best bid
+    assert_eq!(book.depth(2).unwrap().bids[1].price, dec("42999.99"));
+    assert_eq!(book.best_ask().unwrap().price, dec("43000.01"));
+
+    push_diff(&server, SNAPSHOT_ID + 2, SNAPSHOT_ID + 2, &[], &[["43000.01", "0"], ["43000.005", "0.25"]]);
+    match next_event(&mut events).await {
+        OrderBookEvent::Updated { last_update_id, asks, .. } => {
+            assert_eq!(last_update_id, SNAPSHOT_ID + 2);
+            assert_eq!(asks.len(), 2);
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+    assert_eq!(book.best_ask(), Some(level("43000.005", "0.25")));
+    assert_eq!(book.last_update_id(), Some(SNAPSHOT_ID + 2));
+
+    // takes the whole best ask and the next level
+    let next_ask = book.depth(2).unwrap().asks[1].clone();
+    let qty = dec("0.25") + next_ask.qty;
+    let expected = (dec("43000.005") * dec("0.25") + next_ask.price * next_ask.qty) / qty;
+    assert_eq!(book.vwap(ESide::BUY, qty), Some(expected));
+    assert_eq!(book.depth(5000).unwrap().asks.len(), 5000);
+}
+
+#[tokio::test]
+async fn resyncs_after_a_gap() {
+    let (server, book) = setup().await;
+    let mut events = book.subscribe();
+
+    push_diff(&server, SNAPSHOT_ID, SNAPSHOT_ID + 1, &[], &[]);
+    assert!(matches!(next_event(&mut events).await, OrderBookEvent::Synced { .. }));
+
+    // SNAPSHOT_ID + 2 never came
+    push_diff(&server, SNAPSHOT_ID + 3, SNAPSHOT_ID + 4, &[], &[]);
+    assert!(matches!(next_event(&mut events).await, OrderBookEvent::OutOfSync(_)));
+    assert!(!book.is_synced());
+    assert_eq!(book.best_bid(), None);
+
+    // a new snapshot, at SNAPSHOT_ID + 3
+    push_diff(&server, SNAPSHOT_ID + 3, SNAPSHOT_ID + 5, &[], &[]);
+    assert_eq!(next_event(&mut events).await, OrderBookEvent::Synced { last_update_id: SNAPSHOT_ID + 5 });
+    assert_eq!(server.requests_to("/api/v3/depth").len(), 2);
+
+    // an unreadable diff counts as a missed one
+    push_diff(&server, SNAPSHOT_ID + 6, SNAPSHOT_ID + 6, &[["not a price", "1"]], &[]);
+    assert!(matches!(next_event(&mut events).await, OrderBookEvent::OutOfSync(_)));
+}
+
+#[tokio::test]
+async fn retries_a_failed_snapshot() {
+    let (server, book) = setup().await;
+    let mut events = book.subscribe();
+
+    server.fail_next("/api/v3/depth", Failure::ServerError(503));
+    push_diff(&server, SNAPSHOT_ID, SNAPSHOT_ID + 1, &[], &[]);
+    assert!(matches!(next_event(&mut events).await, OrderBookEvent::OutOfSync(reason) if reason.contains("snapshot")));
+
+    // the next snapshot is still at SNAPSHOT_ID, older than this diff, and fetched again
+    push_diff(&server, SNAPSHOT_ID + 2, SNAPSHOT_ID + 4, &[], &[]);
+    assert_eq!(next_event(&mut events).await, OrderBookEvent::Synced { last_update_id: SNAPSHOT_ID + 4 });
+    assert_eq!(server.requests_to("/api/v3/depth").len(), 3);
+}
+
+#[tokio::test]
+async fn gives_up_on_stale_snapshots() {
+    let (server, book) = setup().await;
+    let mut events = book.subscribe();
+
+    // every snapshot is 3 updates further, none catches up with this diff
+    push_diff(&server, SNAPSHOT_ID + 1000, SNAPSHOT_ID + 1001, &[], &[]);
+    assert!(matches!(next_event(&mut events).await, OrderBookEvent::OutOfSync(reason) if reason.contains("still older")));
+    assert_eq!(server.requests_to("/api/v3/depth").len(), MAX_STALE_SNAPSHOTS as usize + 1);
+
+    // the next diff starts another sync
+    push_diff(&server, SNAPSHOT_ID + 3 * MAX_STALE_SNAPSHOTS as u64 + 3, SNAPSHOT_ID + 1002, &[], &[]);
+    assert_eq!(next_event(&mut events).await, OrderBookEvent::Synced { last_update_id: SNAPSHOT_ID + 1002 });
+}
+
+#[tokio::test]
+async fn resyncs_after_a_reconnect() {
+    let (server, book) = setup().await;
+    let mut events = book.subscribe();
+
+    push_diff(&server, SNAPSHOT_ID, SNAPSHOT_ID + 1, &[], &[]);
+    assert!(matches!(next_event(&mut events).await, OrderBookEvent::Synced { .. }));
+
+    server.drop_stream_connections();
+    assert!(matches!(next_event(&mut events).await, OrderBookEvent::OutOfSync(reason) if reason.contains("disconnected")));
+    // the stream resubscribed by itself
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !server.stream_control_messages().iter().any(|message| message["method"] == "LIST_SUBSCRIPTIONS") {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }).await.unwrap();
+
+    // a new snapshot, at SNAPSHOT_ID + 3
+    push_diff(&server, SNAPSHOT_ID + 3, SNAPSHOT_ID + 5, &[], &[]);
+    assert_eq!(next_event(&mut events).await, OrderBookEvent::Synced { last_update_id: SNAPSHOT_ID + 5 });
+    assert_eq!(server.stream_connections(), 1);
+}
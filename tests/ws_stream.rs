@@ -0,0 +1,215 @@
+use std::time::{Duration, Instant};
+
+use futures::{FutureExt, StreamExt};
+use binance_sdk_rs::error::ErrorCode;
+use binance_sdk_rs::http::retry::RetryPolicy;
+use binance_sdk_rs::mock::MockServer;
+use binance_sdk_rs::ws::client::Client;
+use binance_sdk_rs::ws::market::market::{self, EMarketStream, MarketStream};
+use binance_sdk_rs::ws::market::model::{MarketEvent, MarketMessage};
+use binance_sdk_rs::ws::stream::{CombinedStream, StreamEvent, StreamMessage};
+use binance_sdk_rs::Error;
+use serde_json::json;
+
+mod common;
+use common::{ws_builder, ws_client};
+
+fn strings(streams: &[&str]) -> Vec<String> {
+    streams.iter().map(|stream| stream.to_string()).collect()
+}
+
+async fn next(stream: &mut CombinedStream) -> StreamEvent<StreamMessage> {
+    tokio::time::timeout(Duration::from_secs(5), stream.next()).await.unwrap().unwrap().unwrap()
+}
+
+async fn next_event(stream: &mut MarketStream) -> StreamEvent<MarketMessage> {
+    tokio::time::timeout(Duration::from_secs(5), stream.next()).await.unwrap().unwrap().unwrap()
+}
+
+// the old connection of a rotation closes in the background
+async fn wait_for_one_connection(server: &MockServer) {
+    tokio::time::timeout(Duration::from_secs(1), async {
+        while server.stream_connections() != 1 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }).await.unwrap();
+}
+
+fn methods(server: &MockServer) -> Vec<String> {
+    server.stream_control_messages().iter().map(|message| message["method"].as_str().unwrap_or_default().to_string()).collect()
+}
+
+#[tokio::test]
+async fn subscriptions_are_restored_after_a_reconnect() {
+    let server = MockServer::start().await;
+    let client = ws_client(&server);
+    let trade = [EMarketStream::Trade(String::from("BTCUSDT"))];
+    let book_ticker = [EMarketStream::BookTicker(String::from("ETHBTC"))];
+    let mut stream = market::subscribe(&client, &trade).await.unwrap();
+
+    stream.subscribe(&book_ticker).await.unwrap();
+    stream.unsubscribe(&trade).await.unwrap();
+    assert_eq!(stream.list_subscriptions().await.unwrap(), ["ethbtc@bookTicker"]);
+    assert_eq!(stream.streams().collect::<Vec<_>>(), [&book_ticker[0]]);
+    assert_eq!(methods(&server), ["SUBSCRIBE", "UNSUBSCRIBE", "LIST_SUBSCRIPTIONS"]);
+    assert_eq!(server.stream_control_messages()[0], json!({"method": "SUBSCRIBE", "params": ["ethbtc@bookTicker"], "id": 1}));
+
+    assert_eq!(server.drop_stream_connections(), 1);
+    assert!(matches!(next_event(&mut stream).await, StreamEvent::Disconnected(_)));
+    assert_eq!(next_event(&mut stream).await, StreamEvent::Reconnected);
+
+    // the current subscriptions only, then checked
+    let restored = server.stream_control_messages().split_off(3);
+    assert_eq!(restored[0]["params"], json!(["ethbtc@bookTicker"]));
+    assert_eq!(restored[1]["method"], "LIST_SUBSCRIPTIONS");
+    assert_eq!(server.stream_connections(), 1);
+
+    assert_eq!(server.push_stream_event("btcusdt@trade", json!({})), 0);
+    server.push_stream_event("ethbtc@bookTicker", json!({
+        "u": 1, "s": "ETHBTC", "b": "0.05511", "B": "10", "a": "0.05513", "A": "4.5"
+    }));
+    match next_event(&mut stream).await {
+        StreamEvent::Message(message) => assert!(matches!(message.event, MarketEvent::BookTicker(_))),
+        event => panic!("unexpected {:?}", event),
+    }
+    stream.close().await.unwrap();
+}
+
+#[tokio::test]
+async fn rotates_without_missing_or_repeating_events() {
+    let server = MockServer::start().await;
+    let client = ws_builder(&server)
+        .rotation_interval(Duration::from_millis(300))
+        .rotation_overlap(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    let mut stream = CombinedStream::connect(&client, &strings(&["btcusdt@trade"])).await.unwrap();
+
+    let pusher = async {
+        let mut max_connections = 0;
+        for i in 0..80 {
+            max_connections = max_connections.max(server.stream_connections());
+            assert!(server.push_stream_event("btcusdt@trade", json!({"i": i})) > 0);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        max_connections
+    };
+    let receiver = async {
+        let mut received = vec![];
+        while received.len() < 80 {
+            match next(&mut stream).await {
+                StreamEvent::Message(message) => received.push(message.data["i"].as_u64().unwrap()),
+                event => panic!("unexpected {:?}", event),
+            }
+        }
+        received
+    };
+    let (max_connections, received) = futures::join!(pusher, receiver);
+
+    assert_eq!(received, (0..80).collect::<Vec<u64>>());
+    assert_eq!(max_connections, 2);
+    assert!(methods(&server).contains(&String::from("LIST_SUBSCRIPTIONS")));
+    assert_eq!(stream.subscriptions(), ["btcusdt@trade"]);
+    assert!(stream.next().now_or_never().is_none());
+}
+
+#[tokio::test]
+async fn serves_the_old_connection_while_rotating() {
+    let server = MockServer::start().await;
+    let client = ws_builder(&server)
+        .rotation_interval(Duration::from_millis(400))
+        .rotation_overlap(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    let started = Instant::now();
+    let mut stream = CombinedStream::connect(&client, &strings(&["btcusdt@trade"])).await.unwrap();
+
+    // the new connection is only ready once its subscriptions are listed
+    server.delay_stream_method("LIST_SUBSCRIPTIONS", Duration::from_millis(600));
+    tokio::time::sleep(Duration::from_millis(600).saturating_sub(started.elapsed())).await;
+    assert_eq!(server.stream_connections(), 2);
+
+    server.push_stream_event("btcusdt@trade", json!({"i": 1}));
+    let message = tokio::time::timeout(Duration::from_millis(100), next(&mut stream)).await.unwrap();
+    assert!(matches!(message, StreamEvent::Message(message) if message.data["i"] == 1));
+    let eth = strings(&["ethbtc@trade"]);
+    tokio::time::timeout(Duration::from_millis(100), stream.subscribe(&eth)).await.unwrap().unwrap();
+
+    // the subscription made meanwhile carries over
+    tokio::time::sleep(Duration::from_millis(1150).saturating_sub(started.elapsed())).await;
+    wait_for_one_connection(&server).await;
+    assert_eq!(server.push_stream_event("ethbtc@trade", json!({"i": 2})), 1);
+    assert!(matches!(next(&mut stream).await, StreamEvent::Message(message) if message.stream == "ethbtc@trade"));
+    assert_eq!(stream.subscriptions(), ["btcusdt@trade", "ethbtc@trade"]);
+}
+
+#[tokio::test]
+async fn requests_outlive_the_rotation() {
+    let server = MockServer::start().await;
+    let client = ws_builder(&server)
+        .rotation_interval(Duration::from_millis(600))
+        .rotation_overlap(Duration::from_millis(300))
+        .build()
+        .unwrap();
+    let started = Instant::now();
+    let stream = CombinedStream::connect(&client, &strings(&["btcusdt@trade", "ethbtc@trade"])).await.unwrap();
+
+    // the old connection is gone before it answers, the new one answers instead
+    server.delay_stream_method("UNSUBSCRIBE", Duration::from_millis(400));
+    tokio::time::sleep(Duration::from_millis(700).saturating_sub(started.elapsed())).await;
+    assert_eq!(server.stream_connections(), 2);
+    stream.unsubscribe(&strings(&["ethbtc@trade"])).await.unwrap();
+    assert!(started.elapsed() < Duration::from_millis(1500));
+
+    wait_for_one_connection(&server).await;
+    assert_eq!(stream.subscriptions(), ["btcusdt@trade"]);
+    assert_eq!(server.push_stream_event("ethbtc@trade", json!({})), 0);
+}
+
+#[tokio::test]
+async fn control_messages_stay_under_the_limit() {
+    let server = MockServer::start().await;
+    let client = ws_client(&server);
+    let stream = CombinedStream::connect(&client, &[]).await.unwrap();
+
+    // more than Binance takes in a second, the mock drops connections that send them
+    let started = Instant::now();
+    let names: Vec<Vec<String>> = (0..8).map(|i| vec![format!("s{}@trade", i)]).collect();
+    let results = futures::future::join_all(names.iter().map(|name| stream.subscribe(name))).await;
+    assert!(results.iter().all(Result::is_ok), "{:?}", results);
+    assert!(started.elapsed() >= Duration::from_millis(1400));
+    assert_eq!(stream.list_subscriptions().await.unwrap().len(), 8);
+    assert_eq!(server.stream_connections(), 1);
+
+    let err = stream.subscribe(&[]).await.unwrap_err();
+    assert!(matches!(err, Error::InvalidParameter(_)));
+    // the server's error comes back as it is
+    let err = stream.subscribe(&[String::new()]).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::Other(2)));
+    assert!(err.to_string().contains("Invalid request"), "{}", err);
+    assert_eq!(stream.subscriptions().len(), 8);
+}
+
+#[tokio::test]
+async fn gives_up_after_the_last_retry() {
+    let server = MockServer::start().await;
+    let client = ws_builder(&server)
+        .reconnect_policy(RetryPolicy {
+            max_retries: 1,
+            base_delay: Duration::from_millis(50),
+            max_delay: Duration::from_millis(50),
+        })
+        .build()
+        .unwrap();
+    let mut stream = CombinedStream::connect(&client, &strings(&["btcusdt@trade"])).await.unwrap();
+
+    server.drop_stream_connections();
+    drop(server);
+    assert!(matches!(next(&mut stream).await, StreamEvent::Disconnected(_)));
+    let end = tokio::time::timeout(Duration::from_secs(5), stream.next()).await.unwrap();
+    assert!(matches!(end, Some(Err(_))), "{:?}", end);
+    assert!(stream.next().await.is_none());
+    assert!(stream.list_subscriptions().await.is_err());
+
+    assert!(Client::builder().rotation_overlap(Duration::from_secs(3600)).rotation_interval(Duration::from_secs(60)).build().is_err());
+}
@@ -0,0 +1,181 @@
+use std::{sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use binance_sdk_rs::error::ErrorCode;
+use binance_sdk_rs::http::account::model::Balance;
+use binance_sdk_rs::http::client::Client as HttpClient;
+use binance_sdk_rs::http::trade::model::{ContingencyType, Order, OrderListOrder, OrderStatus};
+use binance_sdk_rs::http::trade::trade::{EOrderType, ESide};
+use binance_sdk_rs::http::user_data::user_data::{self as listen_key, EUserDataStream, ListenKeyHandle, DEFAULT_KEEPALIVE_INTERVAL};
+use binance_sdk_rs::mock::MockServer;
+use binance_sdk_rs::ws::stream::StreamEvent;
+use binance_sdk_rs::ws::user_data::model::{ExecutionType, UserDataEvent};
+use binance_sdk_rs::ws::user_data::user_data::{self, UserDataStream};
+use binance_sdk_rs::Error;
+use serde_json::{json, Value};
+
+mod common;
+use common::{dec, http_client, no_retries, ws_client};
+
+async fn setup() -> (MockServer, Arc<HttpClient>, UserDataStream) {
+    let server = MockServer::start().await;
+    let http = Arc::new(http_client(&server, no_retries()));
+    let ws = ws_client(&server);
+    let handle = ListenKeyHandle::start(http.clone(), EUserDataStream::Spot, DEFAULT_KEEPALIVE_INTERVAL).await.unwrap();
+    let stream = user_data::subscribe(&ws, handle).await.unwrap();
+    (server, http, stream)
+}
+
+async fn next(stream: &mut UserDataStream) -> UserDataEvent {
+    match tokio::time::timeout(Duration::from_secs(5), stream.next()).await.unwrap().unwrap().unwrap() {
+        StreamEvent::Message(event) => event,
+        event => panic!("unexpected {:?}", event),
+    }
+}
+
+fn execution_report(execution_type: &str, status: &str, last_qty: &str, trade_id: i64) -> Value {
+    json!({
+        "e": "executionReport", "E": 1499405658658u64, "s": "ETHBTC", "c": "mUvoqJxFIILMdfAW5iGSOW", "S": "BUY",
+        "o": "LIMIT", "f": "GTC", "q": "1.00000000", "p": "0.10264410", "P": "0.00000000", "F": "0.00000000",
+        "g": -1, "C": "", "x": execution_type, "X": status, "r": "NONE", "i": 4293153, "l": last_qty,
+        "z": last_qty, "L": "0.10264410", "n": "0.00010000", "N": if trade_id < 0 { Value::Null } else { json!("BNB") },
+        "T": 1499405658657u64, "t": trade_id, "I": 8641984, "w": true, "m": false, "M": false,
+        "O": 1499405658657u64, "Z": "0.05132205", "Y": "0.05132205", "Q": "0.00000000", "W": 1499405658657u64,
+        "V": "EXPIRE_MAKER"
+    })
+}
+
+#[tokio::test]
+async fn events_are_decoded() {
+    let (server, _, mut stream) = setup().await;
+    let key = stream.listen_key();
+
+    server.push_stream_event(&key, json!({
+        "e": "outboundAccountPosition", "E": 1564034571105u64, "u": 1564034571073u64,
+        "B": [{"a": "ETH", "f": "10000.000000", "l": "0.000000"}]
+    }));
+    match next(&mut stream).await {
+        UserDataEvent::OutboundAccountPosition(event) => {
+            assert_eq!(event.last_update_time, 1564034571073);
+            let balance = Balance::from(event.balances[0].clone());
+            assert_eq!((balance.asset.as_str(), balance.free), ("ETH", dec("10000")));
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+
+    server.push_stream_event(&key, json!({"e": "balanceUpdate", "E": 1573200697110u64, "a": "BTC", "d": "-100.00000000", "T": 1573200697068u64}));
+    match next(&mut stream).await {
+        UserDataEvent::BalanceUpdate(event) => assert_eq!(event.delta, dec("-100")),
+        event => panic!("unexpected {:?}", event),
+    }
+
+    // events of other kinds are skipped
+    server.push_stream_event(&key, json!({"e": "externalLockUpdate", "E": 1581557507324u64, "a": "NEO", "d": "10.00000000", "T": 1581557507268u64}));
+    server.push_stream_event(&key, execution_report("NEW", "NEW", "0.00000000", -1));
+    let report = match next(&mut stream).await {
+        UserDataEvent::ExecutionReport(report) => report,
+        event => panic!("unexpected {:?}", event),
+    };
+    assert_eq!((report.execution_type, report.order_status), (ExecutionType::New, OrderStatus::New));
+    assert_eq!(report.commission_asset, None);
+    assert_eq!(report.fill(), None);
+    let order = Order::from(*report);
+    assert_eq!((order.order_id, order.side, order.order_type), (4293153, ESide::BUY, EOrderType::LIMIT));
+    assert_eq!(order.client_order_id, "mUvoqJxFIILMdfAW5iGSOW");
+
+    server.push_stream_event(&key, execution_report("TRADE", "PARTIALLY_FILLED", "0.50000000", 283));
+    match next(&mut stream).await {
+        UserDataEvent::ExecutionReport(report) => {
+            assert_eq!(report.order_status, OrderStatus::PartiallyFilled);
+            let fill = report.fill().unwrap();
+            assert_eq!((fill.trade_id, fill.qty, fill.commission_asset.as_str()), (283, dec("0.5"), "BNB"));
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+
+    // the canceled order keeps its original client id
+    let mut canceled = execution_report("CANCELED", "CANCELED", "0.00000000", -1);
+    canceled["c"] = json!("cancelRequest1");
+    canceled["C"] = json!("mUvoqJxFIILMdfAW5iGSOW");
+    server.push_stream_event(&key, canceled);
+    match next(&mut stream).await {
+        UserDataEvent::ExecutionReport(report) => {
+            assert_eq!(report.execution_type, ExecutionType::Canceled);
+            assert_eq!(Order::from(*report).client_order_id, "mUvoqJxFIILMdfAW5iGSOW");
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+
+    // an amended order reports its new quantity and keeps its place
+    let mut amended = execution_report("AMENDMENT", "NEW", "0.00000000", -1);
+    amended["q"] = json!("0.50000000");
+    server.push_stream_event(&key, amended);
+    match next(&mut stream).await {
+        UserDataEvent::ExecutionReport(report) => {
+            assert_eq!((report.execution_type, report.order_status), (ExecutionType::Amendment, OrderStatus::New));
+            assert_eq!(report.fill(), None);
+            assert_eq!(Order::from(*report).orig_qty, dec("0.5"));
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+
+    server.push_stream_event(&key, json!({
+        "e": "listStatus", "E": 1564035303637u64, "s": "ETHBTC", "g": 2, "c": "OCO", "l": "EXEC_STARTED",
+        "L": "EXECUTING", "r": "NONE", "C": "F4QN4G8DlFATFlIUQ0cjdD", "T": 1564035303625u64,
+        "O": [
+            {"s": "ETHBTC", "i": 17, "c": "AJYsMjErWJesZvqlJCTUgL"},
+            {"s": "ETHBTC", "i": 18, "c": "bfYPSQdLoqAJeNrOr9adzq"}
+        ]
+    }));
+    match next(&mut stream).await {
+        UserDataEvent::ListStatus(event) => {
+            assert_eq!((event.order_list_id, event.contingency_type), (2, ContingencyType::Oco));
+            assert_eq!(OrderListOrder::from(event.orders[1].clone()).order_id, 18);
+        },
+        event => panic!("unexpected {:?}", event),
+    }
+
+    // a broken event is an error, the stream goes on
+    server.push_stream_event(&key, json!({"e": "executionReport", "s": "ETHBTC"}));
+    server.push_stream_event(&key, json!({"e": "balanceUpdate", "E": 1, "a": "BTC", "d": "1", "T": 1}));
+    let err = tokio::time::timeout(Duration::from_secs(5), stream.next()).await.unwrap().unwrap().unwrap_err();
+    assert!(matches!(err, Error::Deserialize(_)));
+    assert!(matches!(next(&mut stream).await, UserDataEvent::BalanceUpdate(_)));
+}
+
+#[tokio::test]
+async fn follows_the_listen_key() {
+    let (server, http, mut stream) = setup().await;
+    let old = stream.listen_key();
+
+    server.expire_listen_keys();
+    server.push_stream_event(&old, json!({"e": "listenKeyExpired", "E": 1576653824250u64, "listenKey": old}));
+    match next(&mut stream).await {
+        UserDataEvent::ListenKeyExpired(event) => assert_eq!(event.listen_key, old),
+        event => panic!("unexpected {:?}", event),
+    }
+
+    // the handle replaces the key and the stream moves over
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !server.stream_control_messages().iter().any(|message| message["method"] == "UNSUBSCRIBE") {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }).await.unwrap();
+    let new = stream.listen_key();
+    assert_ne!(new, old);
+    let methods: Vec<Value> = server.stream_control_messages().iter().map(|message| message["method"].clone()).collect();
+    assert_eq!(methods, [json!("SUBSCRIBE"), json!("UNSUBSCRIBE")]);
+    assert_eq!(server.stream_control_messages()[0]["params"], json!([new]));
+
+    assert_eq!(server.push_stream_event(&old, json!({"e": "balanceUpdate", "E": 1, "a": "BTC", "d": "1", "T": 1})), 0);
+    server.push_stream_event(&new, json!({"e": "balanceUpdate", "E": 2, "a": "BTC", "d": "2", "T": 2}));
+    match next(&mut stream).await {
+        UserDataEvent::BalanceUpdate(event) => assert_eq!(event.delta, dec("2")),
+        event => panic!("unexpected {:?}", event),
+    }
+
+    // closing closes the key too
+    stream.close().await.unwrap();
+    let err = listen_key::keepalive_listen_key(&http, &EUserDataStream::Spot, &new).await.unwrap_err();
+    assert_eq!(err.code(), Some(ErrorCode::InvalidListenKey));
+}